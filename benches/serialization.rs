@@ -0,0 +1,137 @@
+//! Tag encoding, deep hashing and item (de)serialization, without the cost of signing.
+//!
+//! Run with `cargo bench --bench serialization`.
+
+use std::pin::Pin;
+
+use bundlr_sdk::{
+    deep_hash::{deep_hash, DeepHashChunk},
+    deep_hash_sync::deep_hash_sync,
+    tags::{AvroDecode, AvroEncode, Tag},
+    BundlrTx, Ed25519Signer,
+};
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use futures::{stream, Stream};
+
+const KEY: &str =
+    "kNykCXNxgePDjFbDWjPNvXQRa8U12Ywc19dFVaQ7tebUj3m7H4sF4KKdJwM7yxxb3rqxchdjezX9Szh8bLcQAjb";
+
+const TAG_COUNTS: [usize; 3] = [1, 32, 128];
+const PAYLOADS: [(&str, usize); 2] = [("1MB", 1024 * 1024), ("64MB", 64 * 1024 * 1024)];
+/// Same as the chunks items are streamed from files in.
+const CHUNK_SIZE: usize = 256 * 1024;
+
+fn tags(count: usize) -> Vec<Tag> {
+    (0..count)
+        .map(|i| Tag::new(&format!("Tag-Name-{}", i), &format!("some tag value {}", i)))
+        .collect()
+}
+
+fn signed_item(data: Vec<u8>, tags: Vec<Tag>) -> Vec<u8> {
+    let signer = Ed25519Signer::from_base58(KEY).unwrap();
+    let mut tx = BundlrTx::new(vec![], data, tags).unwrap();
+    tx.sign_sync(&signer).unwrap();
+    tx.as_bytes().unwrap()
+}
+
+fn tag_encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tags");
+    for count in TAG_COUNTS {
+        let tags = tags(count);
+        let encoded = tags.encode().unwrap().to_vec();
+        group.bench_with_input(BenchmarkId::new("encode", count), &tags, |b, tags| {
+            b.iter(|| tags.encode().unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("decode", count), &encoded, |b, encoded| {
+            b.iter_batched(
+                || encoded.clone(),
+                |mut encoded| encoded.as_mut_slice().decode().unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+type ChunkStream = Pin<Box<dyn Stream<Item = anyhow::Result<Bytes>> + Send>>;
+
+fn chunked(data: &Bytes) -> ChunkStream {
+    let chunks: Vec<_> = (0..data.len())
+        .step_by(CHUNK_SIZE)
+        .map(|start| Ok(data.slice(start..(start + CHUNK_SIZE).min(data.len()))))
+        .collect();
+    Box::pin(stream::iter(chunks))
+}
+
+fn deep_hashing(c: &mut Criterion) {
+    for (name, size) in PAYLOADS {
+        let mut group = c.benchmark_group(format!("deep_hash/{}", name));
+        group.throughput(Throughput::Bytes(size as u64));
+        if size > 1024 * 1024 {
+            group.sample_size(10);
+        }
+        let data = Bytes::from(vec![7u8; size]);
+        group.bench_function("in_memory", |b| {
+            b.iter(|| deep_hash_sync(DeepHashChunk::Chunk(data.clone())).unwrap())
+        });
+        group.bench_function("streaming", |b| {
+            b.iter_batched(
+                || chunked(&data),
+                |mut chunks| {
+                    futures::executor::block_on(deep_hash(DeepHashChunk::Stream(&mut chunks)))
+                        .unwrap()
+                },
+                BatchSize::SmallInput,
+            )
+        });
+        group.finish();
+    }
+}
+
+fn item_bytes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("item");
+    for count in TAG_COUNTS {
+        let item = signed_item(vec![7u8; 1024], tags(count));
+        group.throughput(Throughput::Bytes(item.len() as u64));
+        group.bench_with_input(BenchmarkId::new("from_bytes", count), &item, |b, item| {
+            b.iter_batched(
+                || item.clone(),
+                |item| BundlrTx::from_bytes(item).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("as_bytes", count), &item, |b, item| {
+            b.iter_batched(
+                || BundlrTx::from_bytes(item.clone()).unwrap(),
+                |tx| tx.as_bytes().unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    for (name, size) in PAYLOADS {
+        let item = signed_item(vec![7u8; size], tags(32));
+        group.throughput(Throughput::Bytes(item.len() as u64));
+        if size > 1024 * 1024 {
+            group.sample_size(10);
+        }
+        group.bench_with_input(BenchmarkId::new("from_bytes", name), &item, |b, item| {
+            b.iter_batched(
+                || item.clone(),
+                |item| BundlrTx::from_bytes(item).unwrap(),
+                BatchSize::PerIteration,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("as_bytes", name), &item, |b, item| {
+            b.iter_batched(
+                || BundlrTx::from_bytes(item.clone()).unwrap(),
+                |tx| tx.as_bytes().unwrap(),
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, tag_encoding, deep_hashing, item_bytes);
+criterion_main!(benches);
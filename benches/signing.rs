@@ -0,0 +1,66 @@
+//! Creating and signing items with each signature type, for payloads from 1 KB to 64 MB.
+//!
+//! Run with `cargo bench --bench signing`.
+
+use std::path::PathBuf;
+
+use bundlr_sdk::{tags::Tag, ArweaveSigner, BundlrTx, Ed25519Signer, Secp256k1Signer, Signer};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+/// Fixed keys, so that runs sign the exact same items.
+const KEY: &str =
+    "kNykCXNxgePDjFbDWjPNvXQRa8U12Ywc19dFVaQ7tebUj3m7H4sF4KKdJwM7yxxb3rqxchdjezX9Szh8bLcQAjb";
+const WALLET: &str = "res/test_wallet.json";
+
+const PAYLOADS: [(&str, usize); 3] = [
+    ("1KB", 1024),
+    ("1MB", 1024 * 1024),
+    ("64MB", 64 * 1024 * 1024),
+];
+
+fn signers() -> Vec<(&'static str, Box<dyn Signer>)> {
+    vec![
+        (
+            "rsa_pss",
+            Box::new(ArweaveSigner::from_keypair_path(PathBuf::from(WALLET)).unwrap()),
+        ),
+        (
+            "ed25519",
+            Box::new(Ed25519Signer::from_base58(KEY).unwrap()),
+        ),
+        (
+            "secp256k1",
+            Box::new(Secp256k1Signer::from_base58(KEY).unwrap()),
+        ),
+    ]
+}
+
+fn create_and_sign(c: &mut Criterion) {
+    let tags = vec![Tag::new("Content-Type", "application/octet-stream")];
+    for (name, size) in PAYLOADS {
+        let mut group = c.benchmark_group(format!("create_and_sign/{}", name));
+        group.throughput(Throughput::Bytes(size as u64));
+        if size > 1024 * 1024 {
+            group.sample_size(10);
+        }
+        let data = vec![7u8; size];
+        for (signer_name, signer) in signers() {
+            group.bench_function(BenchmarkId::from_parameter(signer_name), |b| {
+                // Copying the payload in is the caller's, not part of what is measured
+                b.iter_batched(
+                    || data.clone(),
+                    |data| {
+                        let mut tx = BundlrTx::new(vec![], data, tags.clone()).unwrap();
+                        tx.sign_sync(signer.as_ref()).unwrap();
+                        tx
+                    },
+                    BatchSize::PerIteration,
+                )
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, create_and_sign);
+criterion_main!(benches);
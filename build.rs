@@ -0,0 +1,37 @@
+//! Tells the crate the target it is built for and, from a git checkout of it, the commit it is
+//! built from, for `build_info()`.
+
+use std::{env, path::Path, process::Command};
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if let Ok(target) = env::var("TARGET") {
+        println!("cargo:rustc-env=BUNDLR_SDK_TARGET={}", target);
+    }
+
+    // Only the crate's own checkout: built as a dependency, it may sit in another repository
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let git_dir = Path::new(&manifest_dir).join(".git");
+    if !git_dir.exists() {
+        return;
+    }
+    for path in ["HEAD", "refs/heads", "packed-refs"] {
+        if git_dir.join(path).exists() {
+            println!("cargo:rerun-if-changed={}", git_dir.join(path).display());
+        }
+    }
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .current_dir(&manifest_dir)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+    if let Some(hash) = hash
+        .as_deref()
+        .map(str::trim)
+        .filter(|hash| !hash.is_empty())
+    {
+        println!("cargo:rustc-env=BUNDLR_SDK_GIT_HASH={}", hash);
+    }
+}
@@ -10,7 +10,7 @@ async fn main() -> Result<(), BundlrError> {
         "kNykCXNxgePDjFbDWjPNvXQRa8U12Ywc19dFVaQ7tebUj3m7H4sF4KKdJwM7yxxb3rqxchdjezX9Szh8bLcQAjb")
         .build()
         .expect("Could not create Solana instance");
-    let mut bundlr = BundlrBuilder::new()
+    let bundlr = BundlrBuilder::new()
         .url(url)
         .currency(currency)
         .fetch_pub_info()
@@ -20,7 +20,7 @@ async fn main() -> Result<(), BundlrError> {
     let file = PathBuf::from_str("res/test_image.jpg").unwrap();
     let res = bundlr.upload_file(file).await;
     match res {
-        Ok(()) => println!("[ok]"),
+        Ok(id) => println!("[ok] {}", id),
         Err(err) => println!("[err] {}", err),
     }
     Ok(())
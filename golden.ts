@@ -0,0 +1,82 @@
+// Generates the golden data items of res/golden/pending, signed with deterministic keys, with
+// arbundles rather than this crate. See res/golden/README.md.
+import { AlgorandSigner, createData, InjectedAptosSigner, Signer, SolanaSigner } from "arbundles";
+import bs58 from "bs58";
+import crypto from "crypto";
+import fs from "fs/promises";
+
+const PENDING = "res/golden/pending";
+
+// PKCS#8 prefix of a raw Ed25519 private key, for Node's crypto to sign with it
+const ED25519_PKCS8_PREFIX = Buffer.from("302e020100300506032b657004220420", "hex");
+
+const decode = (s: string) => Buffer.from(s, "base64url");
+
+// Aptos wallet, as injected in browsers, signing its messages with `secret`
+const aptosWallet = (secret: Buffer) => {
+    const key = crypto.createPrivateKey({
+        key: Buffer.concat([ED25519_PKCS8_PREFIX, secret.subarray(0, 32)]),
+        format: "der",
+        type: "pkcs8",
+    });
+    return {
+        signMessage: async ({ message, nonce }: { message: Uint8Array | string, nonce: string }) => {
+            const fullMessage = Buffer.concat([
+                Buffer.from("APTOS\nmessage: "),
+                Buffer.from(message),
+                Buffer.from(`\nnonce: ${nonce}`),
+            ]);
+            return { fullMessage, signature: crypto.sign(null, fullMessage, key).toString("hex") };
+        },
+    };
+};
+
+const signer = (key: { kind: string, secret: string }): Signer => {
+    const secret = Buffer.from(bs58.decode(key.secret));
+    switch (key.kind) {
+        case "ed25519":
+            return new AlgorandSigner(secret, secret.subarray(32, 64));
+        case "solana":
+            return new SolanaSigner(key.secret);
+        case "aptos":
+            return new InjectedAptosSigner(aptosWallet(secret), secret.subarray(32, 64));
+        default:
+            throw new Error(`Unknown key kind ${key.kind}`);
+    }
+};
+
+for (const file of (await fs.readdir(PENDING)).filter((file) => file.endsWith(".json"))) {
+    const name = file.slice(0, -".json".length);
+    const path = `res/golden/${name}`;
+    const spec = JSON.parse(await fs.readFile(`${PENDING}/${file}`, "utf8"));
+    const itemSigner = signer(spec.key);
+    const item = createData(decode(spec.data), itemSigner, {
+        target: spec.target || undefined,
+        anchor: spec.anchor ? decode(spec.anchor).toString() : undefined,
+        tags: spec.tags,
+    });
+    await item.sign(itemSigner);
+    if (!(await item.isValid())) {
+        throw new Error(`${name}: arbundles made an invalid item`);
+    }
+    if (item.signatureType !== spec.signature_type) {
+        throw new Error(`${name}: signature type ${item.signatureType}, not ${spec.signature_type}`);
+    }
+
+    const raw = item.getRaw();
+    await fs.writeFile(`${path}.bin`, raw);
+    const golden = {
+        source: "arbundles (golden.ts)",
+        signature_type: spec.signature_type,
+        key: spec.key,
+        owner: Buffer.from(item.rawOwner).toString("base64url"),
+        target: spec.target,
+        anchor: spec.anchor,
+        tags: spec.tags,
+        data: spec.data,
+        signature: Buffer.from(item.rawSignature).toString("base64url"),
+        id: item.id,
+    };
+    await fs.writeFile(`${path}.json`, JSON.stringify(golden, null, 2) + "\n");
+    console.info(`Generated ${path}.bin, ${raw.length} bytes, id ${item.id}`);
+}
@@ -0,0 +1,99 @@
+#ifndef BUNDLR_H
+#define BUNDLR_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of every FFI call.
+typedef enum BundlrStatus {
+  BUNDLR_STATUS_OK = 0,
+  // A pointer was null, a string was not valid UTF-8, or an argument could not be parsed.
+  BUNDLR_STATUS_INVALID_ARGUMENT = 1,
+  // The currency kind is unknown or not compiled in.
+  BUNDLR_STATUS_UNSUPPORTED_CURRENCY = 2,
+  // The request failed, see `bundlr_last_error`.
+  BUNDLR_STATUS_FAILED = 3,
+  // The library panicked. The handle should not be used anymore.
+  BUNDLR_STATUS_PANIC = 4,
+} BundlrStatus;
+
+// Opaque client handle.
+typedef struct BundlrHandle BundlrHandle;
+
+// Creates a client for the node at `url`.
+//
+// `currency_kind` is the numeric value of `CurrencyType` (1 for Arweave, 2 for Solana, 3 for
+// Ethereum). The key is the `key_len` bytes at `key_ptr`: the JWK, as JSON, for Arweave, and
+// for other currencies the 64 bytes of the keypair, raw or as base58 text as the CLI takes it.
+// The node's public info is fetched before returning.
+//
+// On success, `*out_handle` receives a handle to release with `bundlr_client_free`. On failure,
+// call `bundlr_last_error` with a null handle to get the error.
+//
+// # Safety
+//
+// `url` must be null or a valid NUL-terminated string, `key_ptr` must be null or valid for
+// reads of `key_len` bytes, and `out_handle` must be null or valid for writes.
+enum BundlrStatus bundlr_client_new(const char *url,
+                                    uint16_t currency_kind,
+                                    const uint8_t *key_ptr,
+                                    size_t key_len,
+                                    struct BundlrHandle **out_handle);
+
+// Releases a handle created by `bundlr_client_new`. Passing null is a no-op.
+//
+// # Safety
+//
+// `handle` must be null or a handle that hasn't been released yet.
+void bundlr_client_free(struct BundlrHandle *handle);
+
+// Signs and uploads `len` bytes starting at `data_ptr`.
+//
+// `tags_json` is null or a JSON array of `{ "name": ..., "value": ... }` objects. On success,
+// `*out_id` receives the id of the uploaded item, to release with `bundlr_string_free`.
+//
+// # Safety
+//
+// `handle` must be valid, `data_ptr` must be valid for reads of `len` bytes (or null when
+// `len` is 0), `tags_json` must be null or a valid NUL-terminated string, and `out_id` must be
+// valid for writes.
+enum BundlrStatus bundlr_upload(struct BundlrHandle *handle,
+                                const uint8_t *data_ptr,
+                                size_t len,
+                                const char *tags_json,
+                                char **out_id);
+
+// Fetches the balance of `address` in the client's currency, as a decimal string of base
+// units written to `*out_decimal_string`, to release with `bundlr_string_free`.
+//
+// # Safety
+//
+// `handle` must be valid, `address` must be a valid NUL-terminated string, and
+// `out_decimal_string` must be valid for writes.
+enum BundlrStatus bundlr_get_balance(struct BundlrHandle *handle,
+                                     const char *address,
+                                     char **out_decimal_string);
+
+// Returns the error of the last failed call made with `handle`, or null if it succeeded.
+//
+// With a null handle, returns the error of the last failed `bundlr_client_new` on the calling
+// thread. The string is owned by the library and must not be freed.
+//
+// # Safety
+//
+// `handle` must be null or valid.
+const char *bundlr_last_error(const struct BundlrHandle *handle);
+
+// Releases a string returned by the library. Passing null is a no-op.
+//
+// # Safety
+//
+// `s` must be null or a string written to an `out_*` parameter that hasn't been freed yet.
+void bundlr_string_free(char *s);
+
+#endif  /* BUNDLR_H */
@@ -0,0 +1,20 @@
+//! How far along an upload is before [`Bundlr::upload_acked`](crate::Bundlr::upload_acked)
+//! returns, set with [`UploadOptions::ack`](crate::bundlr::UploadOptions::ack).
+
+/// Guarantee an upload is acknowledged with, from the weakest to the strongest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum AckLevel {
+    /// The item was signed and handed to a background task sending it, without waiting for the
+    /// node. The task is tracked like other operations, and cancelled by a shutdown that times
+    /// out.
+    Written,
+    /// The node accepted the item, answering with a `2xx` status.
+    #[default]
+    Accepted,
+    /// The node's receipt of the item was fetched and checked against its public key.
+    #[cfg(feature = "verify")]
+    ReceiptVerified,
+    /// The bundle holding the item is final on Arweave, as reported by the node's status
+    /// endpoint. With `verify`, the receipt is checked first.
+    Settled,
+}
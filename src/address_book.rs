@@ -0,0 +1,39 @@
+use std::{collections::HashMap, fmt};
+
+/// Human readable labels for addresses, to make errors easier to read.
+///
+/// Labels are only used locally, when formatting, and never sent to the node.
+pub trait AddressBook: Send + Sync {
+    fn label(&self, address: &str) -> Option<String>;
+}
+
+impl AddressBook for HashMap<String, String> {
+    fn label(&self, address: &str) -> Option<String> {
+        self.get(address).cloned()
+    }
+}
+
+/// An address, along with its label if it has one. Displays as `address (label)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabeledAddress {
+    pub address: String,
+    pub label: Option<String>,
+}
+
+impl LabeledAddress {
+    pub fn new(address: &str, book: Option<&dyn AddressBook>) -> Self {
+        Self {
+            address: address.to_owned(),
+            label: book.and_then(|book| book.label(address)),
+        }
+    }
+}
+
+impl fmt::Display for LabeledAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{} ({})", self.address, label),
+            None => f.write_str(&self.address),
+        }
+    }
+}
@@ -0,0 +1,560 @@
+//! Files read one at a time out of a tar or zip archive, for
+//! [`Bundlr::upload_archive`](crate::Bundlr::upload_archive), without extracting it to disk.
+//!
+//! Only regular files are read: directories, links and special files are skipped. Each file is
+//! held in memory once read, but never more than one at a time.
+
+use miniz_oxide::{
+    inflate::{
+        self,
+        stream::{inflate, InflateState},
+    },
+    DataFormat, MZError, MZFlush, MZStatus,
+};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::error::BundlrError;
+
+const BLOCK_SIZE: usize = 512;
+const READ_SIZE: usize = 64 * 1024;
+
+const ZIP_LOCAL_HEADER: u32 = 0x04034b50;
+const ZIP_DATA_DESCRIPTOR: u32 = 0x08074b50;
+const ZIP_CENTRAL_HEADER: u32 = 0x02014b50;
+const ZIP_END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+
+/// Format of an archive to upload with [`Bundlr::upload_archive`](crate::Bundlr::upload_archive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// A tar archive, in the ustar, GNU or pax format.
+    Tar,
+    /// A tar archive compressed with gzip, as in `.tar.gz` and `.tgz` files.
+    TarGz,
+    /// A zip archive of stored or deflated files.
+    ///
+    /// It is read from front to back, without its central directory, so files must have their
+    /// sizes in their local headers as most archivers write them, and not only after their data
+    /// as some tools streaming zips do. Zip64, encrypted files and other compression methods
+    /// aren't supported. Symbolic links are only told apart in the central directory, and are
+    /// read as files holding the path they link to.
+    Zip,
+}
+
+/// A regular file of an archive, with its `/`-separated path in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ArchiveFile {
+    pub(crate) path: String,
+    pub(crate) data: Vec<u8>,
+}
+
+/// Reads the files of an archive in the order they are stored.
+pub(crate) struct ArchiveReader<R> {
+    input: Input<R>,
+    format: ArchiveFormat,
+    finished: bool,
+}
+
+impl<R: AsyncRead + Unpin> ArchiveReader<R> {
+    /// Reader of the archive `format` read from `reader`, failing if it doesn't start as one.
+    pub(crate) async fn open(reader: R, format: ArchiveFormat) -> Result<Self, BundlrError> {
+        let mut input = Input { reader, gzip: None };
+        if format == ArchiveFormat::TarGz {
+            input.read_gzip_header().await?;
+        }
+        Ok(Self {
+            input,
+            format,
+            finished: false,
+        })
+    }
+
+    /// The next regular file, or `None` once all were read.
+    pub(crate) async fn next_file(&mut self) -> Result<Option<ArchiveFile>, BundlrError> {
+        if self.finished {
+            return Ok(None);
+        }
+        let file = match self.format {
+            ArchiveFormat::Tar | ArchiveFormat::TarGz => self.next_tar_file().await?,
+            ArchiveFormat::Zip => self.next_zip_file().await?,
+        };
+        self.finished = file.is_none();
+        Ok(file)
+    }
+
+    async fn next_tar_file(&mut self) -> Result<Option<ArchiveFile>, BundlrError> {
+        // Path of the next entry, from the GNU or pax entry before it
+        let mut long_path = None;
+        loop {
+            let mut header = [0; BLOCK_SIZE];
+            // Archives may end without their two empty blocks
+            if !self.input.read_block(&mut header).await? || header.iter().all(|b| *b == 0) {
+                return Ok(None);
+            }
+            if tar_checksum(&header) != parse_octal(&header[148..156])? {
+                return Err(malformed("Invalid tar header checksum"));
+            }
+            let size = parse_octal(&header[124..136])?;
+            let data = self.input.read_vec(size).await?;
+            self.input
+                .skip(size.next_multiple_of(BLOCK_SIZE as u64) - size)
+                .await?;
+
+            match header[156] {
+                // Regular and contiguous files
+                b'0' | 0 | b'7' => {
+                    let path = match long_path.take() {
+                        Some(path) => path,
+                        None => tar_header_path(&header)?,
+                    };
+                    match normalize(&path) {
+                        Some(path) => return Ok(Some(ArchiveFile { path, data })),
+                        None => continue,
+                    }
+                }
+                // GNU long name
+                b'L' => long_path = Some(utf8(trim_nul(&data))?),
+                // pax extended header, which may hold the path
+                b'x' => long_path = pax_path(&data)?.or(long_path),
+                _ => long_path = None,
+            }
+        }
+    }
+
+    async fn next_zip_file(&mut self) -> Result<Option<ArchiveFile>, BundlrError> {
+        loop {
+            let mut signature = [0; 4];
+            if !self.input.read_block(&mut signature).await? {
+                return Err(malformed("Zip archive without central directory"));
+            }
+            match u32::from_le_bytes(signature) {
+                ZIP_LOCAL_HEADER => {}
+                ZIP_CENTRAL_HEADER | ZIP_END_OF_CENTRAL_DIRECTORY => return Ok(None),
+                _ => return Err(malformed("Invalid zip entry signature")),
+            }
+            let mut header = [0; 26];
+            self.input.read_exact(&mut header).await?;
+            let u16_at = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]);
+            let u32_at = |at: usize| u32::from_le_bytes(header[at..at + 4].try_into().unwrap());
+            let (flags, method, crc) = (u16_at(2), u16_at(4), u32_at(10));
+            let (compressed_size, size) = (u32_at(14), u32_at(18));
+            let name = self.input.read_vec(u16_at(22) as u64).await?;
+            self.input.skip(u16_at(24) as u64).await?;
+            let path = utf8(&name)?;
+
+            let unsupported = |what: &str| malformed(&format!("{} {} not supported", what, path));
+            if flags & 1 != 0 {
+                return Err(unsupported("Encrypted zip entry"));
+            }
+            if compressed_size == u32::MAX || size == u32::MAX {
+                return Err(unsupported("Zip64 entry"));
+            }
+            let has_descriptor = flags & 8 != 0;
+            if has_descriptor && compressed_size == 0 && !path.ends_with('/') {
+                return Err(unsupported("Zip entry with its size after its data"));
+            }
+            let compressed = self.input.read_vec(compressed_size as u64).await?;
+            if has_descriptor {
+                let mut descriptor = [0; 12];
+                self.input.read_exact(&mut descriptor).await?;
+                // The descriptor's signature is optional
+                if u32::from_le_bytes(descriptor[..4].try_into().unwrap()) == ZIP_DATA_DESCRIPTOR {
+                    self.input.skip(4).await?;
+                }
+            }
+
+            let path = match normalize(&path) {
+                Some(path) => path,
+                None => continue,
+            };
+            let data = match method {
+                0 => compressed,
+                8 => inflate::decompress_to_vec_with_limit(&compressed, size as usize)
+                    .map_err(|_| malformed(&format!("Invalid deflated data of {}", path)))?,
+                method => return Err(unsupported(&format!("Compression method {} of", method))),
+            };
+            if data.len() != size as usize || crc32(&data) != crc {
+                return Err(malformed(&format!("Invalid checksum of {}", path)));
+            }
+            return Ok(Some(ArchiveFile { path, data }));
+        }
+    }
+}
+
+/// The archive's bytes, decompressed for gzipped ones.
+struct Input<R> {
+    reader: R,
+    gzip: Option<Gzip>,
+}
+
+struct Gzip {
+    state: Box<InflateState>,
+    buf: Vec<u8>,
+    start: usize,
+    end: usize,
+    eof: bool,
+    finished: bool,
+}
+
+impl<R: AsyncRead + Unpin> Input<R> {
+    /// Skips the gzip header, the compressed data following it.
+    async fn read_gzip_header(&mut self) -> Result<(), BundlrError> {
+        let mut header = [0; 10];
+        self.read_exact(&mut header).await?;
+        if header[..3] != [0x1f, 0x8b, 8] {
+            return Err(malformed("Not a gzip stream"));
+        }
+        let flags = header[3];
+        if flags & 4 != 0 {
+            let mut len = [0; 2];
+            self.read_exact(&mut len).await?;
+            self.skip(u16::from_le_bytes(len) as u64).await?;
+        }
+        // File name, then comment
+        for flag in [8, 16] {
+            if flags & flag != 0 {
+                let mut byte = [1];
+                while byte[0] != 0 {
+                    self.read_exact(&mut byte).await?;
+                }
+            }
+        }
+        if flags & 2 != 0 {
+            self.skip(2).await?;
+        }
+        self.gzip = Some(Gzip {
+            state: InflateState::new_boxed(DataFormat::Raw),
+            buf: vec![0; READ_SIZE],
+            start: 0,
+            end: 0,
+            eof: false,
+            finished: false,
+        });
+        Ok(())
+    }
+
+    async fn read(&mut self, out: &mut [u8]) -> Result<usize, BundlrError> {
+        let gzip = match &mut self.gzip {
+            Some(gzip) => gzip,
+            None => return Ok(self.reader.read(out).await?),
+        };
+        loop {
+            if gzip.finished {
+                return Ok(0);
+            }
+            if gzip.start == gzip.end && !gzip.eof {
+                gzip.end = self.reader.read(&mut gzip.buf).await?;
+                gzip.start = 0;
+                gzip.eof = gzip.end == 0;
+            }
+            let input = &gzip.buf[gzip.start..gzip.end];
+            let res = inflate(&mut gzip.state, input, out, MZFlush::None);
+            gzip.start += res.bytes_consumed;
+            match res.status {
+                Ok(MZStatus::StreamEnd) => gzip.finished = true,
+                Ok(_) => {}
+                Err(MZError::Buf) if !gzip.eof => {}
+                Err(MZError::Buf) => return Err(malformed("Truncated gzip stream")),
+                Err(_) => return Err(malformed("Invalid gzip data")),
+            }
+            if res.bytes_written > 0 {
+                return Ok(res.bytes_written);
+            }
+        }
+    }
+
+    /// Fills `block`, returning `false` if the input ended before its first byte.
+    async fn read_block(&mut self, block: &mut [u8]) -> Result<bool, BundlrError> {
+        let mut read = 0;
+        while read < block.len() {
+            match self.read(&mut block[read..]).await? {
+                0 if read == 0 => return Ok(false),
+                0 => return Err(malformed("Truncated archive")),
+                n => read += n,
+            }
+        }
+        Ok(true)
+    }
+
+    async fn read_exact(&mut self, out: &mut [u8]) -> Result<(), BundlrError> {
+        match self.read_block(out).await? || out.is_empty() {
+            true => Ok(()),
+            false => Err(malformed("Truncated archive")),
+        }
+    }
+
+    /// The next `len` bytes, only allocated as they are read.
+    async fn read_vec(&mut self, len: u64) -> Result<Vec<u8>, BundlrError> {
+        let mut data = Vec::new();
+        let mut chunk = vec![0; READ_SIZE];
+        while (data.len() as u64) < len {
+            let size = (len - data.len() as u64).min(READ_SIZE as u64) as usize;
+            self.read_exact(&mut chunk[..size]).await?;
+            data.extend_from_slice(&chunk[..size]);
+        }
+        Ok(data)
+    }
+
+    async fn skip(&mut self, mut len: u64) -> Result<(), BundlrError> {
+        let mut chunk = vec![0; READ_SIZE.min(len as usize)];
+        while len > 0 {
+            let size = len.min(chunk.len() as u64) as usize;
+            self.read_exact(&mut chunk[..size]).await?;
+            len -= size as u64;
+        }
+        Ok(())
+    }
+}
+
+fn malformed(msg: &str) -> BundlrError {
+    BundlrError::MalformedArchive(msg.to_owned())
+}
+
+fn utf8(bytes: &[u8]) -> Result<String, BundlrError> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| malformed("Non UTF-8 path"))
+}
+
+fn trim_nul(bytes: &[u8]) -> &[u8] {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    &bytes[..end]
+}
+
+/// `path` relative to the root of the archive, or `None` for directories.
+fn normalize(path: &str) -> Option<String> {
+    if path.ends_with('/') {
+        return None;
+    }
+    let path = path
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/");
+    (!path.is_empty()).then_some(path)
+}
+
+fn tar_header_path(header: &[u8; BLOCK_SIZE]) -> Result<String, BundlrError> {
+    let name = utf8(trim_nul(&header[..100]))?;
+    // ustar headers split long paths in a prefix and a name
+    match &header[257..262] == b"ustar" {
+        true => match utf8(trim_nul(&header[345..500]))? {
+            prefix if prefix.is_empty() => Ok(name),
+            prefix => Ok(format!("{}/{}", prefix, name)),
+        },
+        false => Ok(name),
+    }
+}
+
+/// Sum of the bytes of `header`, its checksum field counting as spaces.
+fn tar_checksum(header: &[u8; BLOCK_SIZE]) -> u64 {
+    header
+        .iter()
+        .enumerate()
+        .map(|(i, b)| match i {
+            148..=155 => b' ' as u64,
+            _ => *b as u64,
+        })
+        .sum()
+}
+
+/// A number of a tar header, in octal, or in base 256 if its first bit is set as GNU tar
+/// writes large sizes.
+fn parse_octal(field: &[u8]) -> Result<u64, BundlrError> {
+    if field[0] & 0x80 != 0 {
+        return field[1..].iter().try_fold(field[0] as u64 & 0x7f, |n, b| {
+            n.checked_mul(256)
+                .map(|n| n + *b as u64)
+                .ok_or_else(|| malformed("Invalid tar number"))
+        });
+    }
+    let digits = std::str::from_utf8(trim_nul(field))
+        .map_err(|_| malformed("Invalid tar number"))?
+        .trim();
+    match digits {
+        "" => Ok(0),
+        digits => u64::from_str_radix(digits, 8).map_err(|_| malformed("Invalid tar number")),
+    }
+}
+
+/// The `path` record of a pax extended header, each of its records being
+/// `<length> <key>=<value>\n`.
+fn pax_path(data: &[u8]) -> Result<Option<String>, BundlrError> {
+    let mut rest = data;
+    while !rest.is_empty() {
+        let invalid = || malformed("Invalid pax header");
+        let space = rest.iter().position(|b| *b == b' ').ok_or_else(invalid)?;
+        let len: usize = std::str::from_utf8(&rest[..space])
+            .ok()
+            .and_then(|len| len.parse().ok())
+            .filter(|len| *len > space && *len <= rest.len())
+            .ok_or_else(invalid)?;
+        let record = &rest[space + 1..len];
+        let record = record.strip_suffix(b"\n").unwrap_or(record);
+        if let Some(path) = record.strip_prefix(b"path=") {
+            return utf8(path).map(Some);
+        }
+        rest = &rest[len..];
+    }
+    Ok(None)
+}
+
+/// CRC-32 of `data`, as zip and gzip checksum files with.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| match crc & 1 {
+            1 => (crc >> 1) ^ 0xedb88320,
+            _ => crc >> 1,
+        })
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use miniz_oxide::deflate::compress_to_vec;
+
+    use super::{crc32, ArchiveFile, ArchiveFormat, ArchiveReader, BLOCK_SIZE};
+    use crate::error::BundlrError;
+
+    /// A tar entry of `kind`, its header then its data padded to a block.
+    fn tar_entry(path: &str, kind: u8, data: &[u8]) -> Vec<u8> {
+        let mut header = [0; BLOCK_SIZE];
+        let (prefix, name) = match path.len() > 100 {
+            true => path.rsplit_once('/').unwrap(),
+            false => ("", path),
+        };
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+        header[148..156].copy_from_slice(b"        ");
+        let checksum: u64 = header.iter().map(|b| *b as u64).sum();
+        header[148..155].copy_from_slice(format!("{:06o}\0", checksum).as_bytes());
+
+        let mut entry = header.to_vec();
+        entry.extend_from_slice(data);
+        entry.resize(entry.len().next_multiple_of(BLOCK_SIZE), 0);
+        entry
+    }
+
+    /// A tar archive of a site, with a directory, a long path and a link to skip.
+    pub(crate) fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut tar = tar_entry("./", b'5', b"");
+        for (path, data) in files {
+            tar.extend(tar_entry(&format!("./{}", path), b'0', data));
+        }
+        tar.extend(tar_entry("./link", b'2', b""));
+        tar.extend([0; 2 * BLOCK_SIZE]);
+        tar
+    }
+
+    pub(crate) fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut gzip = vec![0x1f, 0x8b, 8, 8, 0, 0, 0, 0, 0, 255];
+        gzip.extend_from_slice(b"site.tar\0");
+        gzip.extend(compress_to_vec(data, 6));
+        gzip.extend(crc32(data).to_le_bytes());
+        gzip.extend((data.len() as u32).to_le_bytes());
+        gzip
+    }
+
+    /// A zip archive of `files`, deflated, with a directory entry first.
+    pub(crate) fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = vec![];
+        let mut central = vec![];
+        for (path, data, method) in [("assets/", &b""[..], 0)]
+            .into_iter()
+            .chain(files.iter().map(|(path, data)| (*path, *data, 8)))
+        {
+            let compressed = match method {
+                8 => compress_to_vec(data, 6),
+                _ => data.to_vec(),
+            };
+            let mut header = vec![];
+            header.extend(20u16.to_le_bytes());
+            header.extend(0u16.to_le_bytes());
+            header.extend((method as u16).to_le_bytes());
+            header.extend([0; 4]);
+            header.extend(crc32(data).to_le_bytes());
+            header.extend((compressed.len() as u32).to_le_bytes());
+            header.extend((data.len() as u32).to_le_bytes());
+            header.extend((path.len() as u16).to_le_bytes());
+
+            let offset = zip.len() as u32;
+            zip.extend(0x04034b50u32.to_le_bytes());
+            zip.extend(&header);
+            zip.extend(0u16.to_le_bytes());
+            zip.extend(path.as_bytes());
+            zip.extend(compressed);
+
+            central.extend(0x02014b50u32.to_le_bytes());
+            central.extend(20u16.to_le_bytes());
+            central.extend(&header);
+            central.extend([0; 12]);
+            central.extend(offset.to_le_bytes());
+            central.extend(path.as_bytes());
+        }
+        let (central_offset, central_size) = (zip.len() as u32, central.len() as u32);
+        zip.extend(central);
+        zip.extend(0x06054b50u32.to_le_bytes());
+        zip.extend([0; 4]);
+        zip.extend(((files.len() + 1) as u16).to_le_bytes());
+        zip.extend(((files.len() + 1) as u16).to_le_bytes());
+        zip.extend(central_size.to_le_bytes());
+        zip.extend(central_offset.to_le_bytes());
+        zip.extend([0; 2]);
+        zip
+    }
+
+    async fn read(archive: &[u8], format: ArchiveFormat) -> Result<Vec<ArchiveFile>, BundlrError> {
+        let mut reader = ArchiveReader::open(archive, format).await?;
+        let mut files = vec![];
+        while let Some(file) = reader.next_file().await? {
+            files.push(file);
+        }
+        Ok(files)
+    }
+
+    #[tokio::test]
+    async fn should_read_the_files_of_archives() {
+        let long_path = format!("{}/page.html", "deep".repeat(30));
+        let files: &[(&str, &[u8])] = &[
+            ("index.html", b"<h1>Hello</h1>"),
+            ("assets/app.js", &[b'x'; 70_000]),
+            (long_path.as_str(), b"deep"),
+        ];
+        let expected: Vec<_> = files
+            .iter()
+            .map(|(path, data)| ArchiveFile {
+                path: path.to_string(),
+                data: data.to_vec(),
+            })
+            .collect();
+
+        let tar = tar(files);
+        assert_eq!(read(&tar, ArchiveFormat::Tar).await.unwrap(), expected);
+        let tar_gz = gzip(&tar);
+        assert_eq!(read(&tar_gz, ArchiveFormat::TarGz).await.unwrap(), expected);
+        let zip = zip(&files[..2]);
+        assert_eq!(read(&zip, ArchiveFormat::Zip).await.unwrap(), expected[..2]);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+
+        // Truncated, corrupted, or of another format
+        let malformed =
+            |res: Result<_, BundlrError>| matches!(res, Err(BundlrError::MalformedArchive(_)));
+        assert!(malformed(read(&tar[..1000], ArchiveFormat::Tar).await));
+        assert!(malformed(
+            read(&tar_gz[..tar_gz.len() / 2], ArchiveFormat::TarGz).await
+        ));
+        assert!(malformed(
+            read(&zip[..zip.len() / 2], ArchiveFormat::Zip).await
+        ));
+        assert!(malformed(read(&tar, ArchiveFormat::TarGz).await));
+        assert!(malformed(read(&tar, ArchiveFormat::Zip).await));
+        let mut tampered = tar.clone();
+        tampered[BLOCK_SIZE] ^= 1;
+        assert!(malformed(read(&tampered, ArchiveFormat::Tar).await));
+        let mut tampered = zip.clone();
+        tampered[30 + "assets/".len() + 30 + "index.html".len()] ^= 1;
+        assert!(malformed(read(&tampered, ArchiveFormat::Zip).await));
+    }
+}
@@ -0,0 +1,100 @@
+//! What the SDK was built from, for support to tell which build produced an upload.
+
+use serde::Serialize;
+
+use crate::tags::Tag;
+
+/// Name of the tag carrying [`BuildInfo::version_tag`].
+pub const SDK_VERSION_TAG: &str = "SDK-Version";
+/// Name of the tag carrying the enabled features, comma-separated.
+pub const SDK_FEATURES_TAG: &str = "SDK-Features";
+
+/// Cargo features of the crate, by name.
+const FEATURES: [(&str, bool); 16] = [
+    ("client", cfg!(feature = "client")),
+    ("verify", cfg!(feature = "verify")),
+    ("arweave", cfg!(feature = "arweave")),
+    ("cosmos", cfg!(feature = "cosmos")),
+    ("erc20", cfg!(feature = "erc20")),
+    ("ethereum", cfg!(feature = "ethereum")),
+    ("weavevm", cfg!(feature = "weavevm")),
+    ("solana", cfg!(feature = "solana")),
+    ("algorand", cfg!(feature = "algorand")),
+    ("aptos", cfg!(feature = "aptos")),
+    ("near", cfg!(feature = "near")),
+    ("starknet", cfg!(feature = "starknet")),
+    ("build-binary", cfg!(feature = "build-binary")),
+    ("ffi", cfg!(feature = "ffi")),
+    ("test-util", cfg!(feature = "test-util")),
+    ("tracing", cfg!(feature = "tracing")),
+];
+
+/// Version, commit, features and target of the SDK, see [`build_info`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    /// Commit built from, only known when built from a git checkout of the crate.
+    pub git_hash: Option<&'static str>,
+    /// Enabled Cargo features.
+    pub features: Vec<&'static str>,
+    /// Target triple, such as `x86_64-unknown-linux-gnu`.
+    pub target: &'static str,
+}
+
+impl BuildInfo {
+    /// The version, followed by the commit if known, as in `0.5.0+1a2b3c4d5e6f`.
+    pub fn version_tag(&self) -> String {
+        match self.git_hash {
+            Some(hash) => format!("{}+{}", self.version, hash),
+            None => self.version.to_owned(),
+        }
+    }
+
+    /// Tags describing the build, added to uploads with
+    /// [`BundlrBuilder::tag_sdk_version`](crate::BundlrBuilder::tag_sdk_version).
+    pub fn tags(&self) -> Vec<Tag> {
+        vec![
+            Tag::new(SDK_VERSION_TAG, &self.version_tag()),
+            Tag::new(SDK_FEATURES_TAG, &self.features.join(",")),
+        ]
+    }
+}
+
+/// The build of the SDK this binary was compiled with.
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        git_hash: option_env!("BUNDLR_SDK_GIT_HASH"),
+        features: FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect(),
+        target: env!("BUNDLR_SDK_TARGET"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_info, SDK_FEATURES_TAG, SDK_VERSION_TAG};
+
+    #[test]
+    fn should_report_compiled_features() {
+        let info = build_info();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.target.is_empty());
+        assert_eq!(info.features.contains(&"client"), cfg!(feature = "client"));
+        assert_eq!(info.features.contains(&"verify"), cfg!(feature = "verify"));
+        assert_eq!(
+            info.features.contains(&"tracing"),
+            cfg!(feature = "tracing")
+        );
+        assert!(!info.features.contains(&"default"));
+
+        let tags = info.tags();
+        assert_eq!(tags[0].name, SDK_VERSION_TAG);
+        assert!(tags[0].value.starts_with(env!("CARGO_PKG_VERSION")));
+        assert_eq!(tags[1].name, SDK_FEATURES_TAG);
+        assert_eq!(tags[1].value, info.features.join(","));
+    }
+}
@@ -0,0 +1,357 @@
+//! ANS-104 bundles packed locally from signed items, to be posted to Arweave as a transaction of
+//! one's own wallet rather than through a bundler node, e.g. with
+//! [`Arweave::post_bundle`](crate::currency::arweave::Arweave::post_bundle), or unpacked from
+//! the data of a bundle transaction with [`Bundle::from_bytes`].
+//!
+//! A bundle is the count of its items, then the size and id of each, then the items themselves,
+//! each number a 32 bytes little endian integer. Gateways index the items of a transaction
+//! tagged with [`Bundle::tags`] as they do those of bundlers.
+
+use data_encoding::BASE64URL_NOPAD;
+use sha2::{Digest, Sha256};
+
+use crate::{error::BundlrError, tags::Tag, BundlrTx};
+
+pub const BUNDLE_FORMAT: &str = "binary";
+pub const BUNDLE_VERSION: &str = "2.0.0";
+
+/// Signed items packed together, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    items: Vec<Entry>,
+}
+
+/// Where an item lies in a serialized bundle, as its header lists it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleEntry {
+    pub id: String,
+    /// Offset of the item from the start of the bundle.
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Size and id of an item, as the header of a serialized bundle lists it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ItemHeader {
+    /// SHA-256 of the item's signature.
+    pub(crate) id: [u8; 32],
+    /// Offset of the item from the start of the bundle.
+    pub(crate) offset: u64,
+    pub(crate) size: u64,
+}
+
+/// What [`parse_header`] read from the start of a bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ParsedHeader {
+    /// The header is this many bytes long, more than were given.
+    Incomplete(usize),
+    Items(Vec<ItemHeader>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    /// SHA-256 of the item's signature.
+    id: [u8; 32],
+    bytes: Vec<u8>,
+}
+
+impl Bundle {
+    /// Bundle of `items`, which must be signed and hold their data in memory. Fails with
+    /// [`BundlrError::MalformedBundle`] for no items, or unsigned ones.
+    pub fn new(items: Vec<BundlrTx>) -> Result<Self, BundlrError> {
+        if items.is_empty() {
+            return Err(BundlrError::MalformedBundle(
+                "A bundle needs at least one item".to_owned(),
+            ));
+        }
+        let items = items
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| {
+                // The id of an unsigned item would be the hash of an empty signature
+                if !item.is_signed() {
+                    return Err(BundlrError::MalformedBundle(format!(
+                        "Item {} is unsigned",
+                        i
+                    )));
+                }
+                let id = Sha256::digest(item.get_signarure()).into();
+                Ok(Entry {
+                    id,
+                    bytes: item.as_bytes()?,
+                })
+            })
+            .collect::<Result<_, BundlrError>>()?;
+        Ok(Self { items })
+    }
+
+    /// Bundle read back from its serialized `bytes`, e.g. the data of a bundle transaction
+    /// downloaded from Arweave. Fails with [`BundlrError::MalformedBundle`] if its header doesn't
+    /// match its length. Items are only parsed when extracted, with [`Bundle::item`] or
+    /// [`Bundle::items`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BundlrError> {
+        let malformed = |msg: &str| BundlrError::MalformedBundle(msg.to_owned());
+        let headers = match parse_header(bytes, Some(bytes.len() as u64)).map_err(malformed)? {
+            ParsedHeader::Items(headers) => headers,
+            // Checked against the length already
+            ParsedHeader::Incomplete(_) => return Err(malformed("Invalid item count")),
+        };
+        let items = headers
+            .into_iter()
+            .map(|header| {
+                let start = header.offset as usize;
+                Entry {
+                    id: header.id,
+                    bytes: bytes[start..start + header.size as usize].to_vec(),
+                }
+            })
+            .collect();
+        Ok(Self { items })
+    }
+
+    /// Where each item lies in the serialized bundle, in order.
+    pub fn entries(&self) -> Vec<BundleEntry> {
+        let mut offset = 32 + 64 * self.items.len() as u64;
+        self.items
+            .iter()
+            .map(|item| {
+                let entry = BundleEntry {
+                    id: BASE64URL_NOPAD.encode(&item.id),
+                    offset,
+                    size: item.bytes.len() as u64,
+                };
+                offset += entry.size;
+                entry
+            })
+            .collect()
+    }
+
+    /// The item `id`, with its tags and data, if the bundle holds it.
+    ///
+    /// Fails with [`BundlrError::MalformedBundle`] if the item can't be parsed, or its signature
+    /// doesn't hash to the id the header lists. The signature itself isn't verified: see
+    /// [`BundlrTx::verify`].
+    pub fn item(&self, id: &str) -> Result<Option<BundlrTx>, BundlrError> {
+        let id = id.trim_end_matches('=');
+        self.items
+            .iter()
+            .find(|item| BASE64URL_NOPAD.encode(&item.id) == id)
+            .map(Entry::parse)
+            .transpose()
+    }
+
+    /// Every item of the bundle, in order, as [`Bundle::item`] extracts them.
+    pub fn items(&self) -> Result<Vec<BundlrTx>, BundlrError> {
+        self.items.iter().map(Entry::parse).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Ids of the items, in order.
+    pub fn ids(&self) -> Vec<String> {
+        self.items
+            .iter()
+            .map(|item| BASE64URL_NOPAD.encode(&item.id))
+            .collect()
+    }
+
+    /// Tags marking a transaction's data as a bundle.
+    pub fn tags() -> Vec<Tag> {
+        vec![
+            Tag::new("Bundle-Format", BUNDLE_FORMAT),
+            Tag::new("Bundle-Version", BUNDLE_VERSION),
+        ]
+    }
+
+    /// Serialized bundle, the data of the transaction to post.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let size: usize = self.items.iter().map(|item| item.bytes.len()).sum();
+        let mut bytes = Vec::with_capacity(32 + 64 * self.items.len() + size);
+        bytes.extend_from_slice(&le_u256(self.items.len() as u64));
+        for item in &self.items {
+            bytes.extend_from_slice(&le_u256(item.bytes.len() as u64));
+            bytes.extend_from_slice(&item.id);
+        }
+        for item in &self.items {
+            bytes.extend_from_slice(&item.bytes);
+        }
+        bytes
+    }
+}
+
+impl Entry {
+    fn parse(&self) -> Result<BundlrTx, BundlrError> {
+        let id = BASE64URL_NOPAD.encode(&self.id);
+        let malformed = |msg: String| BundlrError::MalformedBundle(format!("{}: {}", id, msg));
+        let item =
+            BundlrTx::from_bytes(self.bytes.clone()).map_err(|err| malformed(err.to_string()))?;
+        match Sha256::digest(item.get_signarure()).as_slice() == self.id {
+            true => Ok(item),
+            false => Err(malformed("Signature doesn't match the id".to_owned())),
+        }
+    }
+}
+
+/// Parses the header of a serialized bundle, the count of its items then the size and id of
+/// each, from `bytes`, the start of the bundle.
+///
+/// The header is checked against the `length` of the bundle when known, so that nothing is
+/// allocated for counts and sizes it can't hold, and limited to `u32::MAX` items otherwise. Fails
+/// with the reason the header is malformed.
+pub(crate) fn parse_header(
+    bytes: &[u8],
+    length: Option<u64>,
+) -> Result<ParsedHeader, &'static str> {
+    if bytes.len() < 32 {
+        return match length {
+            Some(length) if length < 32 => Err("Invalid item count"),
+            _ => Ok(ParsedHeader::Incomplete(32)),
+        };
+    }
+    let count = read_u256(bytes, 0).ok_or("Invalid item count")?;
+    let headers_end = count
+        .checked_mul(64)
+        .and_then(|headers| headers.checked_add(32))
+        .filter(|end| match length {
+            Some(length) => *end <= length,
+            None => count <= u32::MAX as u64,
+        })
+        .and_then(|end| usize::try_from(end).ok())
+        .ok_or("Invalid item count")?;
+    if bytes.len() < headers_end {
+        return Ok(ParsedHeader::Incomplete(headers_end));
+    }
+
+    let mut offset = headers_end as u64;
+    let mut items = Vec::with_capacity(count as usize);
+    for header in (32..headers_end).step_by(64) {
+        let size = read_u256(bytes, header).ok_or("Invalid item size")?;
+        let end = offset.checked_add(size).ok_or("Invalid item size")?;
+        if length.is_some_and(|length| end > length) {
+            return Err("Truncated item");
+        }
+        items.push(ItemHeader {
+            id: bytes[header + 32..header + 64].try_into().unwrap(),
+            offset,
+            size,
+        });
+        offset = end;
+    }
+    Ok(ParsedHeader::Items(items))
+}
+
+/// The 32 bytes little endian integer at `offset` of `bytes`, if there and under 2^64.
+fn read_u256(bytes: &[u8], offset: usize) -> Option<u64> {
+    let n = bytes.get(offset..offset.checked_add(32)?)?;
+    match n[8..].iter().all(|byte| *byte == 0) {
+        true => Some(u64::from_le_bytes(n[..8].try_into().unwrap())),
+        false => None,
+    }
+}
+
+fn le_u256(n: u64) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    bytes[..8].copy_from_slice(&n.to_le_bytes());
+    bytes
+}
+
+#[cfg(all(test, feature = "arweave"))]
+mod tests {
+    use std::{fs, path::PathBuf, str::FromStr};
+
+    use super::Bundle;
+    use crate::{
+        error::BundlrError, tags::Tag, verify::file::verify_file_bundle, ArweaveSigner, BundlrTx,
+    };
+
+    fn signed(data: &str) -> BundlrTx {
+        let signer =
+            ArweaveSigner::from_keypair_path(PathBuf::from_str("res/test_wallet.json").unwrap())
+                .unwrap();
+        let mut item = BundlrTx::new(vec![], data.into(), vec![Tag::new("name", data)]).unwrap();
+        item.sign_sync(&signer).unwrap();
+        item
+    }
+
+    #[tokio::test]
+    async fn should_pack_items_readable_as_a_bundle() {
+        let items = vec![signed("Hello"), signed("world")];
+        let bundle = Bundle::new(items).unwrap();
+        assert_eq!(bundle.len(), 2);
+
+        let path = std::env::temp_dir().join(format!("packed_bundle_{}", std::process::id()));
+        fs::write(&path, bundle.to_bytes()).unwrap();
+        let read = verify_file_bundle(path.display().to_string()).await;
+        fs::remove_file(&path).unwrap();
+        let read = read.unwrap();
+        let ids: Vec<_> = read
+            .iter()
+            .map(|item| item.tx_id.trim_end_matches('=').to_owned())
+            .collect();
+        assert_eq!(ids, bundle.ids());
+
+        assert!(matches!(
+            Bundle::new(vec![]),
+            Err(BundlrError::MalformedBundle(_))
+        ));
+        let unsigned = BundlrTx::new(vec![], b"Hello".to_vec(), vec![]).unwrap();
+        assert!(matches!(
+            Bundle::new(vec![unsigned]),
+            Err(BundlrError::MalformedBundle(_))
+        ));
+    }
+
+    #[test]
+    fn should_reject_unsigned_items_among_signed_ones() {
+        let unsigned = BundlrTx::new(vec![], b"world".to_vec(), vec![]).unwrap();
+        match Bundle::new(vec![signed("Hello"), unsigned]) {
+            Err(BundlrError::MalformedBundle(msg)) => assert_eq!(msg, "Item 1 is unsigned"),
+            res => panic!("Expected an unsigned item to be rejected, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn should_extract_items_from_bundle_bytes() {
+        let bundle = Bundle::new(vec![signed("Hello"), signed("world")]).unwrap();
+        let bytes = bundle.to_bytes();
+        let unpacked = Bundle::from_bytes(&bytes).unwrap();
+        assert_eq!(unpacked, bundle);
+
+        let entries = unpacked.entries();
+        assert_eq!(entries[0].offset, 32 + 2 * 64);
+        assert_eq!(entries[1].offset, entries[0].offset + entries[0].size);
+        assert_eq!(entries[1].offset + entries[1].size, bytes.len() as u64);
+        let world = unpacked.item(&entries[1].id).unwrap().unwrap();
+        assert_eq!(world.get_data(), Some(&b"world"[..]));
+        assert_eq!(world.get_tags(), [Tag::new("name", "world")]);
+        assert!(unpacked.item("missing").unwrap().is_none());
+        let items = unpacked.items().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].get_data(), Some(&b"Hello"[..]));
+
+        // Truncated, or with an id not matching its item
+        let malformed = |bytes: &[u8]| {
+            matches!(
+                Bundle::from_bytes(bytes),
+                Err(BundlrError::MalformedBundle(_))
+            )
+        };
+        assert!(malformed(&bytes[..bytes.len() - 1]));
+        assert!(malformed(&bytes[..100]));
+        assert!(malformed(&[]));
+        let mut tampered = bytes.clone();
+        tampered[32 + 32] ^= 1;
+        let tampered = Bundle::from_bytes(&tampered).unwrap();
+        let id = &tampered.entries()[0].id;
+        assert!(matches!(
+            tampered.item(id),
+            Err(BundlrError::MalformedBundle(_))
+        ));
+    }
+}
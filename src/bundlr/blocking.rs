@@ -0,0 +1,208 @@
+//! Synchronous client, for scripts and codebases without an async runtime of their own.
+//!
+//! A [`Bundlr`] wraps an async [`crate::Bundlr`] with a single-threaded Tokio runtime of its
+//! own, blocking the calling thread until each request completes. It must not be used from
+//! inside an async runtime, where calls panic.
+//!
+//! ```no_run
+//! # #[cfg(feature = "arweave")]
+//! # fn main() -> Result<(), bundlr_sdk::error::BundlrError> {
+//! use std::{path::PathBuf, str::FromStr};
+//!
+//! use bundlr_sdk::{bundlr::blocking::Bundlr, currency::arweave::ArweaveBuilder};
+//! use reqwest::Url;
+//!
+//! let currency = ArweaveBuilder::new()
+//!     .keypair_path(PathBuf::from_str("res/test_wallet.json").unwrap())
+//!     .build()?;
+//! let url = Url::parse("https://node1.bundlr.network").unwrap();
+//! let bundlr = Bundlr::try_new(url, currency)?;
+//! let id = bundlr.upload_file("res/test_image.jpg")?;
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "arweave"))]
+//! # fn main() {}
+//! ```
+
+use std::{future::Future, path::Path};
+
+use num::BigUint;
+use reqwest::Url;
+use serde_json::Value;
+use tokio::runtime::Runtime;
+
+use crate::{
+    bundlr::BundlrBuilder,
+    currency,
+    error::{BuilderError, BundlrError},
+    tags::Tag,
+    BundlrTx,
+};
+
+/// Blocking client of a node, see the [module docs](self).
+pub struct Bundlr<Currency> {
+    runtime: Runtime,
+    inner: crate::Bundlr<Currency>,
+}
+
+fn runtime() -> Result<Runtime, BundlrError> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(BundlrError::IoError)
+}
+
+impl<Currency> Bundlr<Currency>
+where
+    Currency: currency::Currency,
+{
+    /// Client of the node at `url`, fetching its public info, as [`crate::Bundlr::try_new`].
+    pub fn try_new(url: Url, currency: Currency) -> Result<Self, BundlrError> {
+        let runtime = runtime()?;
+        let inner = runtime.block_on(crate::Bundlr::try_new(url, currency))?;
+        Ok(Self { runtime, inner })
+    }
+
+    /// Client built by `builder`, which should be given the node's public info with
+    /// [`BundlrBuilder::pub_info`] or [`BundlrBuilder::with_state`], or told to fetch it with
+    /// [`BundlrBuilder::fetch_pub_info_lazily`]. Background tasks of the client, such as pub info
+    /// refreshes, run during later calls.
+    pub fn from_builder(builder: BundlrBuilder<Currency>) -> Result<Self, BuilderError> {
+        let runtime = runtime()?;
+        let inner = {
+            let _entered = runtime.enter();
+            builder.build()?
+        };
+        Ok(Self { runtime, inner })
+    }
+
+    /// The async client, for requests without a blocking equivalent, sent with
+    /// [`Bundlr::block_on`].
+    pub fn inner(&self) -> &crate::Bundlr<Currency> {
+        &self.inner
+    }
+
+    /// Runs `future` to completion on the client's runtime, e.g. a request of
+    /// [`Bundlr::inner`].
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// See [`crate::Bundlr::get_balance`].
+    pub fn get_balance(&self, address: &str) -> Result<BigUint, BundlrError> {
+        self.block_on(self.inner.get_balance(address))
+    }
+
+    /// See [`crate::Bundlr::get_price`].
+    pub fn get_price(&self, byte_amount: u64) -> Result<BigUint, BundlrError> {
+        self.block_on(self.inner.get_price(byte_amount))
+    }
+
+    /// See [`crate::Bundlr::fund`].
+    pub fn fund(&self, amount: u64, multiplier: Option<f64>) -> Result<bool, BundlrError> {
+        self.block_on(self.inner.fund(amount, multiplier))
+    }
+
+    /// See [`crate::Bundlr::withdraw`].
+    pub fn withdraw(&self, amount: u64) -> Result<bool, BundlrError> {
+        self.block_on(self.inner.withdraw(amount))
+    }
+
+    /// See [`crate::Bundlr::create_transaction`].
+    pub fn create_transaction(
+        &self,
+        data: Vec<u8>,
+        additional_tags: Vec<Tag>,
+    ) -> Result<BundlrTx, BundlrError> {
+        self.inner.create_transaction(data, additional_tags)
+    }
+
+    /// See [`crate::Bundlr::sign_transaction`].
+    pub fn sign_transaction(&self, tx: &mut BundlrTx) -> Result<(), BundlrError> {
+        self.block_on(self.inner.sign_transaction(tx))
+    }
+
+    /// See [`crate::Bundlr::send_transaction`].
+    pub fn send_transaction(&self, tx: BundlrTx) -> Result<Value, BundlrError> {
+        self.block_on(self.inner.send_transaction(tx))
+    }
+
+    /// Signs an item of `data` tagged with `additional_tags`, then sends it.
+    pub fn upload(&self, data: Vec<u8>, additional_tags: Vec<Tag>) -> Result<Value, BundlrError> {
+        let mut tx = self.create_transaction(data, additional_tags)?;
+        self.sign_transaction(&mut tx)?;
+        self.send_transaction(tx)
+    }
+
+    /// See [`crate::Bundlr::upload_file`].
+    pub fn upload_file(&self, file_path: impl AsRef<Path>) -> Result<String, BundlrError> {
+        self.block_on(self.inner.upload_file(file_path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, str::FromStr};
+
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use reqwest::Url;
+
+    use super::Bundlr;
+    use crate::{
+        bundlr::{BundlrBuilder, PubInfo},
+        currency::arweave::ArweaveBuilder,
+        tags::Tag,
+    };
+
+    #[test]
+    fn should_upload_and_get_balance_without_a_runtime() {
+        let server = MockServer::start();
+        let info = server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200)
+                .body(r#"{ "version": "0.2.0", "gateway": "arweave.net", "addresses": {} }"#);
+        });
+        let upload = server.mock(|when, then| {
+            when.method(POST).path("/tx/arweave");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(r#"{ "id": "some-id", "timestamp": 1 }"#);
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/account/balance/arweave")
+                .query_param("address", "address");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(r#"{ "balance": "42" }"#);
+        });
+        let wallet = PathBuf::from_str("res/test_wallet.json").unwrap();
+        let currency = || {
+            ArweaveBuilder::new()
+                .keypair_path(wallet.clone())
+                .build()
+                .unwrap()
+        };
+        let url = Url::from_str(&server.url("/")).unwrap();
+
+        let bundlr = Bundlr::try_new(url.clone(), currency()).unwrap();
+        info.assert();
+        let res = bundlr
+            .upload(b"Hello".to_vec(), vec![Tag::new("name", "value")])
+            .unwrap();
+        assert_eq!(res["id"], "some-id");
+        upload.assert();
+        assert_eq!(bundlr.get_balance("address").unwrap(), 42u8.into());
+
+        let builder = BundlrBuilder::new()
+            .url(url)
+            .currency(currency())
+            .pub_info(PubInfo::default());
+        let bundlr = Bundlr::from_builder(builder).unwrap();
+        assert_eq!(bundlr.get_balance("address").unwrap(), 42u8.into());
+        info.assert_hits(1);
+    }
+}
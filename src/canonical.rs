@@ -0,0 +1,141 @@
+//! Hashes identifying the content of an upload, whichever SDK made it.
+//!
+//! An item's id depends on its signature, and its bytes on the order of its tags, so neither can
+//! tell two uploads of the same content apart. The canonical hash only depends on the data and
+//! on the set of tags, following a versioned profile. Version 1 is the SHA-256 of:
+//!
+//! | Field                | Encoding                                             |
+//! |----------------------|------------------------------------------------------|
+//! | version              | 1 byte, `0x01`                                       |
+//! | number of tags       | 8 bytes, little endian                               |
+//! | each tag, sorted     | name length (8 bytes, little endian), name bytes,    |
+//! |                      | value length (8 bytes, little endian), value bytes   |
+//! | data                 | the rest, as is                                      |
+//!
+//! Tags are sorted by the UTF-8 bytes of their name, then of their value. Names are compared
+//! case-sensitively, and neither names nor values are trimmed or otherwise normalized: tags
+//! differing in any byte make different hashes. Duplicate tags are all kept. Everything before
+//! the data is length-prefixed, so the data can be hashed as it streams in.
+//!
+//! A profile never changes once released: `res/canonical` holds its test vectors, which other
+//! SDKs implement against.
+
+use sha2::{Digest, Sha256};
+
+use crate::tags::Tag;
+
+/// Version byte prefixed to everything hashed by the current profile.
+pub const CANONICAL_HASH_VERSION: u8 = 1;
+
+/// Canonical hash of an upload of `data` with `tags`.
+pub fn canonical_upload_hash(data: &[u8], tags: &[Tag]) -> [u8; 32] {
+    let mut hasher = CanonicalHasher::new(tags);
+    hasher.update(data);
+    hasher.finish()
+}
+
+/// Computes a [`canonical_upload_hash`] from data read in chunks.
+#[derive(Clone)]
+pub struct CanonicalHasher(Sha256);
+
+impl CanonicalHasher {
+    pub fn new(tags: &[Tag]) -> Self {
+        let mut sorted: Vec<_> = tags
+            .iter()
+            .map(|tag| (tag.name.as_bytes(), tag.value.as_bytes()))
+            .collect();
+        sorted.sort_unstable();
+
+        let mut hasher = Sha256::new();
+        hasher.update([CANONICAL_HASH_VERSION]);
+        hasher.update((sorted.len() as u64).to_le_bytes());
+        for (name, value) in sorted {
+            hasher.update((name.len() as u64).to_le_bytes());
+            hasher.update(name);
+            hasher.update((value.len() as u64).to_le_bytes());
+            hasher.update(value);
+        }
+        Self(hasher)
+    }
+
+    /// Hashes the next chunk of data.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    pub fn finish(self) -> [u8; 32] {
+        self.0.finalize().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use data_encoding::BASE64URL_NOPAD;
+    use serde::Deserialize;
+
+    use super::{canonical_upload_hash, CanonicalHasher};
+    use crate::tags::Tag;
+
+    #[derive(Deserialize)]
+    struct Vector {
+        name: String,
+        tags: Vec<Tag>,
+        data: String,
+        hash: String,
+    }
+
+    #[test]
+    fn should_match_the_reference_vectors() {
+        let vectors: Vec<Vector> =
+            serde_json::from_slice(&fs::read("res/canonical/v1.json").unwrap()).unwrap();
+        for vector in vectors {
+            let data = BASE64URL_NOPAD.decode(vector.data.as_bytes()).unwrap();
+            let hash = canonical_upload_hash(&data, &vector.tags);
+            assert_eq!(
+                BASE64URL_NOPAD.encode(&hash),
+                vector.hash,
+                "{}",
+                vector.name
+            );
+
+            let mut hasher = CanonicalHasher::new(&vector.tags);
+            for chunk in data.chunks(7) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finish(), hash, "{}: streamed", vector.name);
+        }
+    }
+
+    #[test]
+    fn should_ignore_tag_order_only() {
+        let tags = vec![
+            Tag::new("Content-Type", "text/plain"),
+            Tag::new("App-Name", "app"),
+        ];
+        let reversed: Vec<_> = tags.iter().rev().cloned().collect();
+        let hash = canonical_upload_hash(b"data", &tags);
+        assert_eq!(canonical_upload_hash(b"data", &reversed), hash);
+
+        let differing = [
+            vec![
+                Tag::new("content-type", "text/plain"),
+                Tag::new("App-Name", "app"),
+            ],
+            vec![
+                Tag::new("Content-Type", "text/plain "),
+                Tag::new("App-Name", "app"),
+            ],
+            vec![Tag::new("Content-Type", "text/plain")],
+            vec![
+                Tag::new("Content-Typetext/plain", ""),
+                Tag::new("App-Name", "app"),
+            ],
+        ];
+        for tags in &differing {
+            assert_ne!(canonical_upload_hash(b"data", tags), hash, "{:?}", tags);
+        }
+        assert_ne!(canonical_upload_hash(b"Data", &tags), hash);
+    }
+}
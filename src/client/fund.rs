@@ -1,9 +1,10 @@
 use std::{path::PathBuf, str::FromStr};
 
+use super::unsupported;
 use crate::{
     bundlr::BundlrBuilder,
     consts::USE_JS_SDK,
-    currency::{arweave::ArweaveBuilder, CurrencyType},
+    currency::{aptos::AptosBuilder, arweave::ArweaveBuilder, CurrencyType},
     error::BundlrError,
 };
 use num_traits::Zero;
@@ -19,9 +20,9 @@ pub async fn run_fund(
         return Err(BundlrError::InvalidAmount);
     }
 
-    let wallet = PathBuf::from_str(wallet).expect("Invalid wallet path");
     match currency {
         CurrencyType::Arweave => {
+            let wallet = PathBuf::from_str(wallet).expect("Invalid wallet path");
             let currency = ArweaveBuilder::new().keypair_path(wallet).build()?;
             let bundlr = BundlrBuilder::new()
                 .url(url)
@@ -33,7 +34,19 @@ pub async fn run_fund(
         }
         CurrencyType::Solana => todo!("{}", USE_JS_SDK),
         CurrencyType::Ethereum => todo!("{}", USE_JS_SDK),
-        CurrencyType::Erc20 => todo!("{}", USE_JS_SDK),
-        CurrencyType::Cosmos => todo!("{}", USE_JS_SDK),
+        CurrencyType::Erc20 => Err(unsupported("fund", currency)),
+        CurrencyType::Cosmos => Err(unsupported("fund", currency)),
+        CurrencyType::Aptos => {
+            let currency = AptosBuilder::new().wallet(wallet).build()?;
+            let bundlr = BundlrBuilder::new()
+                .url(url)
+                .currency(currency)
+                .fetch_pub_info()
+                .await?
+                .build()?;
+            bundlr.fund(amount, None).await.map(|res| res.to_string())
+        }
+        CurrencyType::Near => Err(unsupported("fund", currency)),
+        CurrencyType::Starknet => Err(unsupported("fund", currency)),
     }
 }
@@ -4,3 +4,14 @@ pub mod method;
 pub mod price;
 pub mod upload;
 pub mod withdraw;
+
+use crate::{consts::USE_JS_SDK, currency::CurrencyType, error::BundlrError};
+
+/// Error for the currencies the command line can't `operation` with, as they need more than a
+/// wallet, such as the endpoint of their chain.
+pub(crate) fn unsupported(operation: &str, currency: CurrencyType) -> BundlrError {
+    BundlrError::Unsupported(format!(
+        "Can't {} with {} from the command line. {}",
+        operation, currency, USE_JS_SDK
+    ))
+}
@@ -5,11 +5,13 @@ use std::{
     str::FromStr,
 };
 
+use super::unsupported;
 use crate::{
     bundlr::BundlrBuilder,
     consts::VERSION,
     currency::{
-        arweave::ArweaveBuilder, ethereum::EthereumBuilder, solana::SolanaBuilder, CurrencyType,
+        aptos::AptosBuilder, arweave::ArweaveBuilder, ethereum::EthereumBuilder,
+        solana::SolanaBuilder, CurrencyType,
     },
     error::BundlrError,
     tags::Tag,
@@ -82,7 +84,24 @@ pub async fn run_upload(
                 Err(err) => Err(BundlrError::UploadError(err.to_string())),
             }
         }
-        CurrencyType::Erc20 => todo!(),
-        CurrencyType::Cosmos => todo!(),
+        CurrencyType::Erc20 => Err(unsupported("upload", currency)),
+        CurrencyType::Cosmos => Err(unsupported("upload", currency)),
+        CurrencyType::Aptos => {
+            let currency = AptosBuilder::new().wallet(wallet).build()?;
+            let bundlr = BundlrBuilder::new()
+                .url(url)
+                .currency(currency)
+                .fetch_pub_info()
+                .await?
+                .build()?;
+            let mut tx = bundlr.create_transaction(buffer, vec![base_tag])?;
+            bundlr.sign_transaction(&mut tx).await?;
+            match bundlr.send_transaction(tx).await {
+                Ok(res) => Ok(format!("File {} uploaded: {}", file_path, res)),
+                Err(err) => Err(BundlrError::UploadError(err.to_string())),
+            }
+        }
+        CurrencyType::Near => Err(unsupported("upload", currency)),
+        CurrencyType::Starknet => Err(unsupported("upload", currency)),
     }
 }
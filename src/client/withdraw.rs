@@ -1,9 +1,10 @@
 use std::{path::PathBuf, str::FromStr};
 
+use super::unsupported;
 use crate::{
     bundlr::BundlrBuilder,
     consts::USE_JS_SDK,
-    currency::{arweave::ArweaveBuilder, CurrencyType},
+    currency::{aptos::AptosBuilder, arweave::ArweaveBuilder, CurrencyType},
     error::BundlrError,
 };
 use num_traits::Zero;
@@ -29,11 +30,31 @@ pub async fn run_withdraw(
                 .fetch_pub_info()
                 .await?
                 .build()?;
-            bundlr.withdraw(amount).await.map(|res| res.to_string())
+            let withdrawal = bundlr.request_withdrawal(amount).await?;
+            Ok(format!(
+                "Withdrawal of {} (fee {}) sent in transaction {}",
+                withdrawal.final_amount, withdrawal.fee, withdrawal.tx_id
+            ))
         }
         CurrencyType::Solana => todo!("{}", USE_JS_SDK),
         CurrencyType::Ethereum => todo!("{}", USE_JS_SDK),
-        CurrencyType::Erc20 => todo!("{}", USE_JS_SDK),
-        CurrencyType::Cosmos => todo!("{}", USE_JS_SDK),
+        CurrencyType::Erc20 => Err(unsupported("withdraw", currency)),
+        CurrencyType::Cosmos => Err(unsupported("withdraw", currency)),
+        CurrencyType::Aptos => {
+            let currency = AptosBuilder::new().wallet(wallet).build()?;
+            let bundlr = BundlrBuilder::new()
+                .url(url)
+                .currency(currency)
+                .fetch_pub_info()
+                .await?
+                .build()?;
+            let withdrawal = bundlr.request_withdrawal(amount).await?;
+            Ok(format!(
+                "Withdrawal of {} (fee {}) sent in transaction {}",
+                withdrawal.final_amount, withdrawal.fee, withdrawal.tx_id
+            ))
+        }
+        CurrencyType::Near => Err(unsupported("withdraw", currency)),
+        CurrencyType::Starknet => Err(unsupported("withdraw", currency)),
     }
 }
@@ -0,0 +1,120 @@
+//! Time as seen by a client, so that tests can control it.
+
+use std::time::{Duration, SystemTime};
+
+#[cfg(any(test, feature = "test-util"))]
+use std::sync::Arc;
+#[cfg(any(test, feature = "test-util"))]
+use tokio::sync::watch;
+
+/// Where a client reads the time and sleeps: the system clock by default, or a [`MockClock`]
+/// with the `test-util` feature.
+///
+/// Backoffs, cache TTLs, drain pauses and shutdown timeouts go through it. Request timings and
+/// the node's clock measure how long things actually take, and always use the system's.
+#[derive(Debug, Clone, Default)]
+pub struct Clock(Source);
+
+#[derive(Debug, Clone, Default)]
+enum Source {
+    #[default]
+    System,
+    #[cfg(any(test, feature = "test-util"))]
+    Mock(MockClock),
+}
+
+impl Clock {
+    pub fn system() -> Self {
+        Self(Source::System)
+    }
+
+    pub(crate) fn now(&self) -> SystemTime {
+        match &self.0 {
+            Source::System => SystemTime::now(),
+            #[cfg(any(test, feature = "test-util"))]
+            Source::Mock(mock) => mock.now(),
+        }
+    }
+
+    pub(crate) async fn sleep(&self, duration: Duration) {
+        match &self.0 {
+            Source::System => tokio::time::sleep(duration).await,
+            #[cfg(any(test, feature = "test-util"))]
+            Source::Mock(mock) => mock.sleep(duration).await,
+        }
+    }
+}
+
+/// A clock that only moves when told to. Sleeps on it return once it was advanced past their
+/// end, however long that takes in real time.
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug, Clone)]
+pub struct MockClock(Arc<watch::Sender<SystemTime>>);
+
+#[cfg(any(test, feature = "test-util"))]
+impl MockClock {
+    /// A clock stopped at `start`.
+    pub fn new(start: SystemTime) -> Self {
+        Self(Arc::new(watch::channel(start).0))
+    }
+
+    pub fn now(&self) -> SystemTime {
+        *self.0.borrow()
+    }
+
+    /// Moves the clock forward by `duration`, waking the sleeps ending by then.
+    pub fn advance(&self, duration: Duration) {
+        self.0.send_modify(|now| *now += duration);
+    }
+
+    /// Number of sleeps waiting for the clock to be advanced.
+    pub fn sleepers(&self) -> usize {
+        self.0.receiver_count()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        let end = self.now() + duration;
+        let mut now = self.0.subscribe();
+        // The sender lives as long as `self`, so this can't fail
+        now.wait_for(|now| *now >= end).await.ok();
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl From<MockClock> for Clock {
+    fn from(mock: MockClock) -> Self {
+        Self(Source::Mock(mock))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{Clock, MockClock};
+
+    #[tokio::test]
+    async fn should_sleep_until_advanced() {
+        let mock = MockClock::new(SystemTime::UNIX_EPOCH);
+        let clock = Clock::from(mock.clone());
+        clock.sleep(Duration::ZERO).await;
+
+        let sleep = tokio::spawn({
+            let clock = clock.clone();
+            async move { clock.sleep(Duration::from_secs(60)).await }
+        });
+        while mock.sleepers() == 0 {
+            tokio::task::yield_now().await;
+        }
+        mock.advance(Duration::from_secs(59));
+        tokio::task::yield_now().await;
+        assert!(!sleep.is_finished());
+
+        mock.advance(Duration::from_secs(1));
+        sleep.await.unwrap();
+        assert_eq!(
+            clock.now(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(60)
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Signatures of the upstream `bundlr-sdk` crate, kept so that code written against it builds
+//! unchanged while its call sites are migrated.
+//!
+//! Everything here is deprecated, and only wraps the current API: the wrappers panic where the
+//! upstream crate did, instead of returning errors. [`Bundlr`] dereferences to the current
+//! [`crate::Bundlr`], so migrated calls can be mixed with upstream ones on the same client.
+#![allow(deprecated)]
+
+use std::ops::{Deref, DerefMut};
+
+use reqwest::Url;
+
+use crate::{currency::Currency, tags::Tag, BundlrTx};
+
+/// A client borrowing its currency, created like the upstream crate's.
+#[deprecated(
+    since = "0.5.0",
+    note = "Use `BundlrBuilder` to build a `bundlr_sdk::Bundlr`"
+)]
+pub struct Bundlr<'a, C: ?Sized> {
+    inner: crate::Bundlr<&'a C>,
+}
+
+impl<'a, C> Bundlr<'a, C>
+where
+    C: Currency + Send + Sync + ?Sized,
+{
+    /// Client of the node at `url`, fetching its public info.
+    ///
+    /// # Panics
+    ///
+    /// If the public info can't be fetched.
+    #[deprecated(
+        since = "0.5.0",
+        note = "Use `bundlr_sdk::Bundlr::try_new`, failing instead of panicking"
+    )]
+    pub async fn new(url: Url, currency: &'a C) -> Bundlr<'a, C> {
+        let inner = crate::Bundlr::try_new(url.clone(), currency)
+            .await
+            .unwrap_or_else(|err| panic!("Could not create a client of {}: {}", url, err));
+        Bundlr { inner }
+    }
+
+    /// Creates an unsigned transaction.
+    ///
+    /// # Panics
+    ///
+    /// If the transaction can't be created.
+    #[deprecated(since = "0.5.0", note = "Use `Bundlr::create_transaction`")]
+    pub fn create_transaction_with_tags(&self, data: Vec<u8>, tags: Vec<Tag>) -> BundlrTx {
+        self.inner
+            .create_transaction(data, tags)
+            .unwrap_or_else(|err| panic!("Could not create transaction: {}", err))
+    }
+
+    /// The current client, to migrate to.
+    pub fn into_inner(self) -> crate::Bundlr<&'a C> {
+        self.inner
+    }
+}
+
+impl<'a, C: ?Sized> Deref for Bundlr<'a, C> {
+    type Target = crate::Bundlr<&'a C>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a, C: ?Sized> DerefMut for Bundlr<'a, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
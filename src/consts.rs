@@ -2,6 +2,10 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub const BUNDLR_DEFAULT_URL: &str = "https://node1.bundlr.network/";
 pub const CHUNK_SIZE: u64 = 256u64 * 1024;
+/// Maximum number of price queries sent at once when pricing many sizes.
+pub const PRICE_QUERIES_AT_ONCE: usize = 16;
+/// Length of item ids: 32 bytes in base64url, without padding.
+pub const ITEM_ID_LENGTH: usize = 43;
 /// Multiplier applied to the buffer argument from the cli to determine the maximum number
 /// of simultaneous request to the `chunk/ endpoint`.
 pub const CHUNKS_BUFFER_FACTOR: usize = 20;
@@ -18,6 +22,10 @@ pub const RETRY_SLEEP: u64 = 10;
 /// Number of confirmations needed to consider a transaction funded
 pub const CONFIRMATIONS_NEEDED: u64 = 5;
 
+/// Number of times to check whether the node credited a fund made to pay for an upload, every
+/// [`RETRY_SLEEP`] seconds, before sending the upload again regardless.
+pub const AUTO_FUND_BALANCE_POLLS: u32 = 30;
+
 pub const USE_JS_SDK: &str = "Use js-sdk to perform this operation";
 
 pub const LIST_AS_BUFFER: &[u8] = "list".as_bytes();
@@ -0,0 +1,191 @@
+//! Items interacting with SmartWeave contracts, as read by SmartWeave and Warp.
+
+use data_encoding::BASE64URL_NOPAD;
+use serde_json::Value;
+
+use crate::{
+    error::BundlrError,
+    tags::{Tag, MAX_TAG_VALUE_BYTES},
+};
+
+pub const APP_NAME: &str = "SmartWeaveAction";
+pub const APP_VERSION: &str = "0.3.0";
+
+/// Tags set by [`ContractInteraction::tags`], which additional tags may not override.
+pub const INTERACTION_TAGS: [&str; 4] = ["App-Name", "App-Version", "Contract", "Input"];
+
+/// A call to a contract, carried by the tags of an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractInteraction {
+    contract_id: String,
+    /// Canonical JSON of the input.
+    input: String,
+}
+
+impl ContractInteraction {
+    /// Calls the contract deployed in transaction `contract_id` with `input`, which is
+    /// canonicalized: serialized compactly, with object keys sorted.
+    ///
+    /// Fails if `contract_id` is not an Arweave transaction id, or if the input can't fit in a tag.
+    pub fn new(contract_id: &str, input: &Value) -> Result<Self, BundlrError> {
+        check_contract_id(contract_id)?;
+        let mut canonical = String::new();
+        write_canonical(input, &mut canonical);
+        if canonical.len() > MAX_TAG_VALUE_BYTES {
+            return Err(BundlrError::InvalidInteraction(format!(
+                "Input is {} bytes, more than the {} a tag can hold",
+                canonical.len(),
+                MAX_TAG_VALUE_BYTES
+            )));
+        }
+        Ok(Self {
+            contract_id: contract_id.to_owned(),
+            input: canonical,
+        })
+    }
+
+    /// Reads the interaction carried by an item's tags.
+    pub fn from_tags(tags: &[Tag]) -> Result<Self, BundlrError> {
+        let get = |name: &str| {
+            tags.iter()
+                .find(|tag| tag.name == name)
+                .map(|tag| tag.value.as_str())
+                .ok_or_else(|| BundlrError::InvalidInteraction(format!("No {} tag", name)))
+        };
+        if get("App-Name")? != APP_NAME {
+            return Err(BundlrError::InvalidInteraction(
+                "Not a SmartWeave action".to_owned(),
+            ));
+        }
+        let input = serde_json::from_str(get("Input")?)
+            .map_err(|err| BundlrError::InvalidInteraction(err.to_string()))?;
+        Self::new(get("Contract")?, &input)
+    }
+
+    pub fn contract_id(&self) -> &str {
+        &self.contract_id
+    }
+
+    /// Canonical JSON of the input.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Tags of the interaction, in the order SmartWeave expects them, followed by `extra_tags`.
+    ///
+    /// Fails if `extra_tags` sets any of the [`INTERACTION_TAGS`].
+    pub fn tags(&self, extra_tags: Vec<Tag>) -> Result<Vec<Tag>, BundlrError> {
+        if let Some(tag) = extra_tags
+            .iter()
+            .find(|tag| INTERACTION_TAGS.contains(&tag.name.as_str()))
+        {
+            return Err(BundlrError::InvalidInteraction(format!(
+                "Tag {} is set by the interaction",
+                tag.name
+            )));
+        }
+        let mut tags = vec![
+            Tag::new("App-Name", APP_NAME),
+            Tag::new("App-Version", APP_VERSION),
+            Tag::new("Contract", &self.contract_id),
+            Tag::new("Input", &self.input),
+        ];
+        tags.extend(extra_tags);
+        Ok(tags)
+    }
+}
+
+/// Checks `id` is an Arweave transaction id: 32 bytes, base64url encoded without padding.
+fn check_contract_id(id: &str) -> Result<(), BundlrError> {
+    match BASE64URL_NOPAD.decode(id.as_bytes()) {
+        Ok(bytes) if bytes.len() == 32 => Ok(()),
+        _ => Err(BundlrError::InvalidInteraction(format!(
+            "Invalid contract id {:?}",
+            id
+        ))),
+    }
+}
+
+/// Writes `value` as compact JSON, with object keys sorted, whatever the order of `serde_json`'s
+/// maps.
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(entries) => {
+            let mut entries: Vec<_> = entries.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::ContractInteraction;
+    use crate::{error::BundlrError, tags::Tag};
+
+    const CONTRACT: &str = "KT45jaf8n9UwgkEareWxPgLJk4oMWpI5NODgYVIF1fY";
+
+    #[test]
+    fn should_tag_interactions() {
+        let input =
+            json!({ "qty": 10, "function": "transfer", "target": { "b": 1, "a": [2, "x"] } });
+        let interaction = ContractInteraction::new(CONTRACT, &input).unwrap();
+        let canonical = r#"{"function":"transfer","qty":10,"target":{"a":[2,"x"],"b":1}}"#;
+        assert_eq!(interaction.input(), canonical);
+
+        let tags = interaction.tags(vec![Tag::new("Extra", "tag")]).unwrap();
+        assert_eq!(
+            tags,
+            vec![
+                Tag::new("App-Name", "SmartWeaveAction"),
+                Tag::new("App-Version", "0.3.0"),
+                Tag::new("Contract", CONTRACT),
+                Tag::new("Input", canonical),
+                Tag::new("Extra", "tag"),
+            ]
+        );
+        assert_eq!(ContractInteraction::from_tags(&tags).unwrap(), interaction);
+    }
+
+    #[test]
+    fn should_reject_invalid_interactions() {
+        fn invalid<T>(res: Result<T, BundlrError>) -> bool {
+            matches!(res, Err(BundlrError::InvalidInteraction(_)))
+        }
+        let input = json!({ "function": "transfer" });
+        for id in ["", "not-an-id", &CONTRACT[1..], &format!("{}=", CONTRACT)] {
+            assert!(invalid(ContractInteraction::new(id, &input)), "{}", id);
+        }
+
+        let large = json!({ "data": "x".repeat(3072) });
+        assert!(invalid(ContractInteraction::new(CONTRACT, &large)));
+
+        let interaction = ContractInteraction::new(CONTRACT, &input).unwrap();
+        assert!(invalid(interaction.tags(vec![Tag::new("Input", "{}")])));
+        assert!(invalid(ContractInteraction::from_tags(&[Tag::new(
+            "App-Name", "Other"
+        )])));
+    }
+}
@@ -0,0 +1,663 @@
+//! Aptos, funding the node's `aptos` address with `0x1::aptos_account::transfer` transactions
+//! submitted to an Aptos fullnode's REST API.
+//!
+//! Transactions are encoded in BCS and signed with the wallet's ed25519 key locally, the
+//! fullnode only being asked for the gas price, the account's sequence number and the chain id.
+//! Amounts are in octas, 10^-8 APT.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use data_encoding::HEXLOWER_PERMISSIVE;
+use ed25519_dalek::{Keypair, PublicKey, SecretKey};
+use num::ToPrimitive;
+use reqwest::{header::CONTENT_TYPE, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+use sha3::{Digest, Sha3_256};
+
+use crate::{
+    error::{BuilderError, BundlrError},
+    transaction::{Tx, TxStatus},
+    utils::{read_json, RawNumber},
+    AptosSigner, Signer, Verifier,
+};
+
+use super::{units, Currency, CurrencyType, TxResponse};
+
+const APTOS_TICKER: &str = "APT";
+const APTOS_BASE_UNIT: &str = "octa";
+const APTOS_DECIMALS: u8 = 8;
+const APTOS_BASE_URL: &str = "https://fullnode.mainnet.aptoslabs.com/";
+
+/// Gas a transfer may use at most, well above the few hundred units it takes.
+pub const TRANSFER_MAX_GAS: u64 = 2_000;
+/// Seconds a transaction may wait to be included before it expires.
+const EXPIRATION_SECS: u64 = 600;
+const TRANSFER_MODULE: &str = "aptos_account";
+const TRANSFER_FUNCTION: &str = "transfer";
+const BCS_CONTENT_TYPE: &str = "application/x.aptos.signed_transaction+bcs";
+/// Scheme of single ed25519 keys, appended to the public key to derive the address.
+const ED25519_SCHEME: u8 = 0;
+
+pub struct Aptos {
+    signer: Option<AptosSigner>,
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl Default for Aptos {
+    fn default() -> Self {
+        Self {
+            signer: None,
+            client: reqwest::Client::new(),
+            url: Url::parse(APTOS_BASE_URL).unwrap(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct AptosBuilder {
+    base_url: Option<Url>,
+    wallet: Option<String>,
+}
+
+impl AptosBuilder {
+    pub fn new() -> AptosBuilder {
+        Default::default()
+    }
+
+    /// Fullnode to send requests to, whose REST API is under `v1/`.
+    pub fn base_url(mut self, base_url: Url) -> AptosBuilder {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    /// Wallet's keypair in base58, or its private key in hex as exported by the Aptos CLI.
+    pub fn wallet(mut self, wallet: &str) -> AptosBuilder {
+        self.wallet = Some(wallet.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Aptos, BuilderError> {
+        let signer = match self.wallet {
+            Some(wallet) => Some(parse_wallet(&wallet)?),
+            None => None,
+        };
+        Ok(Aptos {
+            signer,
+            url: self
+                .base_url
+                .unwrap_or_else(|| Url::parse(APTOS_BASE_URL).unwrap()),
+            ..Aptos::default()
+        })
+    }
+}
+
+fn parse_wallet(wallet: &str) -> Result<AptosSigner, BundlrError> {
+    let hex = wallet.strip_prefix("0x").unwrap_or(wallet);
+    match hex.len() {
+        64 => {
+            let key = HEXLOWER_PERMISSIVE
+                .decode(hex.as_bytes())
+                .map_err(|err| BundlrError::ParseError(err.to_string()))?;
+            let secret = SecretKey::from_bytes(&key).map_err(BundlrError::ED25519Error)?;
+            let public = PublicKey::from(&secret);
+            Ok(AptosSigner::new(Keypair { secret, public }))
+        }
+        _ => AptosSigner::from_base58(wallet),
+    }
+}
+
+/// Address of the account of an ed25519 `pub_key`, in hex.
+pub fn address_of(pub_key: &[u8]) -> String {
+    let hash = Sha3_256::new()
+        .chain_update(pub_key)
+        .chain_update([ED25519_SCHEME])
+        .finalize();
+    format!("0x{}", data_encoding::HEXLOWER.encode(&hash))
+}
+
+/// `address`, which may be written without its leading zeros such as `0x1`, in bytes.
+fn parse_address(address: &str) -> Result<[u8; 32], BundlrError> {
+    let hex = address.strip_prefix("0x").unwrap_or(address);
+    let invalid = || BundlrError::ParseError(format!("Invalid Aptos address: {}", address));
+    if hex.is_empty() || hex.len() > 64 {
+        return Err(invalid());
+    }
+    let bytes = HEXLOWER_PERMISSIVE
+        .decode(format!("{:0>64}", hex).as_bytes())
+        .map_err(|_| invalid())?;
+    bytes.try_into().map_err(|_| invalid())
+}
+
+fn uleb128(buf: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        buf.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn bcs_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    uleb128(buf, bytes.len());
+    buf.extend_from_slice(bytes);
+}
+
+/// A transaction calling `0x1::aptos_account::transfer`, as signed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawTransfer {
+    pub sender: [u8; 32],
+    pub sequence_number: u64,
+    pub to: [u8; 32],
+    pub amount: u64,
+    pub max_gas_amount: u64,
+    pub gas_unit_price: u64,
+    pub expiration_timestamp_secs: u64,
+    pub chain_id: u8,
+}
+
+impl RawTransfer {
+    /// The transaction in BCS.
+    pub fn to_bcs(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&self.sender);
+        buf.extend_from_slice(&self.sequence_number.to_le_bytes());
+        // TransactionPayload::EntryFunction
+        uleb128(&mut buf, 2);
+        let mut framework = [0; 32];
+        framework[31] = 1;
+        buf.extend_from_slice(&framework);
+        bcs_bytes(&mut buf, TRANSFER_MODULE.as_bytes());
+        bcs_bytes(&mut buf, TRANSFER_FUNCTION.as_bytes());
+        // No type arguments, then the recipient and amount, each BCS-encoded
+        uleb128(&mut buf, 0);
+        uleb128(&mut buf, 2);
+        bcs_bytes(&mut buf, &self.to);
+        bcs_bytes(&mut buf, &self.amount.to_le_bytes());
+        buf.extend_from_slice(&self.max_gas_amount.to_le_bytes());
+        buf.extend_from_slice(&self.gas_unit_price.to_le_bytes());
+        buf.extend_from_slice(&self.expiration_timestamp_secs.to_le_bytes());
+        buf.push(self.chain_id);
+        buf
+    }
+
+    /// What the sender signs: the transaction in BCS, after the hash of its type's name.
+    pub fn signing_message(&self) -> Vec<u8> {
+        let prefix = Sha3_256::digest(b"APTOS::RawTransaction");
+        [prefix.as_slice(), &self.to_bcs()].concat()
+    }
+
+    /// The transaction signed by `pub_key` with `signature`, in BCS.
+    pub fn signed(&self, pub_key: &[u8], signature: &[u8]) -> Vec<u8> {
+        let mut buf = self.to_bcs();
+        // TransactionAuthenticator::Ed25519
+        uleb128(&mut buf, 0);
+        bcs_bytes(&mut buf, pub_key);
+        bcs_bytes(&mut buf, signature);
+        buf
+    }
+}
+
+#[derive(Deserialize)]
+struct GasEstimation {
+    gas_estimate: u64,
+}
+
+#[derive(Deserialize)]
+struct AccountData {
+    sequence_number: RawNumber,
+}
+
+#[derive(Deserialize)]
+struct LedgerInfo {
+    chain_id: u8,
+    block_height: RawNumber,
+}
+
+#[derive(Deserialize)]
+struct Submitted {
+    hash: String,
+}
+
+#[derive(Deserialize)]
+struct Block {
+    block_height: RawNumber,
+    block_hash: String,
+}
+
+#[derive(Deserialize)]
+struct TransferPayload {
+    function: String,
+    arguments: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct AptosTransaction {
+    #[serde(rename = "type")]
+    kind: String,
+    hash: String,
+    sender: String,
+    version: Option<RawNumber>,
+    gas_used: Option<RawNumber>,
+    gas_unit_price: RawNumber,
+    max_gas_amount: RawNumber,
+    success: Option<bool>,
+    payload: TransferPayload,
+}
+
+impl AptosTransaction {
+    fn is_pending(&self) -> bool {
+        self.kind == "pending_transaction"
+    }
+}
+
+fn parse_u64(number: &RawNumber, field: &str) -> Result<u64, BundlrError> {
+    number
+        .parse_atomic(field)?
+        .to_u64()
+        .ok_or(BundlrError::TypeParseError(format!(
+            "{} does not fit in a u64",
+            field
+        )))
+}
+
+impl Aptos {
+    /// `amount` of APT in octas, see [`units::parse_units`].
+    pub fn parse_amount(&self, amount: &str) -> Result<u64, BundlrError> {
+        units::parse_units(amount, APTOS_DECIMALS)
+    }
+
+    /// `amount` of octas in APT, see [`units::format_units`].
+    pub fn format_amount(&self, amount: u64) -> String {
+        units::format_units(amount, APTOS_DECIMALS)
+    }
+
+    fn signer(&self) -> Result<&AptosSigner, BundlrError> {
+        self.signer.as_ref().ok_or(BundlrError::CurrencyError(
+            "No private key present".to_string(),
+        ))
+    }
+
+    fn api(&self, path: &str) -> Result<Url, BundlrError> {
+        self.url
+            .join(&format!("v1/{}", path))
+            .map_err(|err| BundlrError::ParseError(err.to_string()))
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, BundlrError> {
+        read_json(self.client.get(self.api(path)?).send().await).await
+    }
+
+    async fn transaction(&self, tx_id: &str) -> Result<AptosTransaction, BundlrError> {
+        let res = self
+            .client
+            .get(self.api(&format!("transactions/by_hash/{}", tx_id))?)
+            .send()
+            .await;
+        match res {
+            Ok(res) if res.status() == StatusCode::NOT_FOUND => Err(BundlrError::TxNotFound),
+            res => read_json(res).await,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Currency for Aptos {
+    fn get_min_unit_name(&self) -> String {
+        APTOS_BASE_UNIT.to_string()
+    }
+
+    fn get_type(&self) -> CurrencyType {
+        CurrencyType::Aptos
+    }
+
+    fn needs_fee(&self) -> bool {
+        true
+    }
+
+    async fn get_tx(&self, tx_id: String) -> Result<Tx, BundlrError> {
+        let tx = self.transaction(&tx_id).await?;
+        let transfer = format!("0x1::{}::{}", TRANSFER_MODULE, TRANSFER_FUNCTION);
+        let (to, amount) = match (tx.payload.function == transfer, &tx.payload.arguments[..]) {
+            (true, [serde_json::Value::String(to), serde_json::Value::String(amount)]) => {
+                (to.clone(), amount.clone())
+            }
+            _ => {
+                return Err(BundlrError::CurrencyError(format!(
+                    "{} is not a transfer of {}",
+                    tx_id, APTOS_TICKER
+                )))
+            }
+        };
+        let gas = match &tx.gas_used {
+            Some(gas_used) => parse_u64(gas_used, "gas_used")?,
+            None => parse_u64(&tx.max_gas_amount, "max_gas_amount")?,
+        };
+        let pending = tx.is_pending();
+        Ok(Tx {
+            id: tx.hash,
+            from: tx.sender,
+            to,
+            amount: amount.parse().map_err(|_| BundlrError::NumericParse {
+                field: "amount".to_owned(),
+                raw: amount.clone(),
+            })?,
+            fee: gas.saturating_mul(parse_u64(&tx.gas_unit_price, "gas_unit_price")?),
+            block_height: match &tx.version {
+                Some(version) => parse_u64(version, "version")? as u128,
+                None => 0,
+            },
+            pending,
+            confirmed: !pending && tx.success == Some(true),
+        })
+    }
+
+    async fn get_tx_status(
+        &self,
+        tx_id: String,
+    ) -> Result<(StatusCode, Option<TxStatus>), BundlrError> {
+        let tx = self.transaction(&tx_id).await?;
+        let version = match (&tx.version, tx.is_pending()) {
+            (Some(version), false) => version,
+            _ => return Ok((StatusCode::ACCEPTED, None)),
+        };
+        if tx.success == Some(false) {
+            return Err(BundlrError::CurrencyError(format!(
+                "Transaction {} failed",
+                tx_id
+            )));
+        }
+        let version = parse_u64(version, "version")?;
+        let block: Block = self.get(&format!("blocks/by_version/{}", version)).await?;
+        let ledger: LedgerInfo = self.get("").await?;
+        let height = parse_u64(&block.block_height, "block_height")?;
+        let current = parse_u64(&ledger.block_height, "block_height")?;
+        Ok((
+            StatusCode::OK,
+            Some(TxStatus {
+                confirmations: current.saturating_sub(height) + 1,
+                height: height as u128,
+                block_hash: block.block_hash,
+            }),
+        ))
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>, BundlrError> {
+        Ok(self
+            .signer()?
+            .sign(Bytes::copy_from_slice(message))?
+            .to_vec())
+    }
+
+    fn verify(&self, pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), BundlrError> {
+        AptosSigner::verify(
+            Bytes::copy_from_slice(pub_key),
+            Bytes::copy_from_slice(message),
+            Bytes::copy_from_slice(signature),
+        )
+    }
+
+    fn get_pub_key(&self) -> Result<Bytes, BundlrError> {
+        Ok(self.signer()?.pub_key())
+    }
+
+    fn wallet_address(&self) -> Result<String, BundlrError> {
+        Ok(address_of(&self.signer()?.pub_key()))
+    }
+
+    fn get_signer(&self) -> Result<&dyn Signer, BundlrError> {
+        Ok(self.signer()?)
+    }
+
+    async fn get_id(&self, _item: ()) -> Result<String, BundlrError> {
+        Err(BundlrError::Unsupported(format!(
+            "No currency id for {}",
+            self.name()
+        )))
+    }
+
+    async fn price(&self) -> Result<String, BundlrError> {
+        Err(BundlrError::Unsupported(format!(
+            "No USD price source for {}",
+            self.name()
+        )))
+    }
+
+    /// Height of the chain's latest block.
+    async fn get_current_height(&self) -> Result<u128, BundlrError> {
+        let ledger: LedgerInfo = self.get("").await?;
+        Ok(parse_u64(&ledger.block_height, "block_height")? as u128)
+    }
+
+    /// Most a transfer may cost at the estimated gas price, [`TRANSFER_MAX_GAS`] units, in
+    /// octas.
+    async fn get_fee(&self, _amount: u64, to: &str, multiplier: f64) -> Result<u64, BundlrError> {
+        parse_address(to)?;
+        let gas: GasEstimation = self.get("estimate_gas_price").await?;
+        let fee = gas.gas_estimate.saturating_mul(TRANSFER_MAX_GAS);
+        Ok((fee as f64 * multiplier).ceil() as u64)
+    }
+
+    async fn create_tx(&self, amount: u64, to: &str, fee: u64) -> Result<Tx, BundlrError> {
+        parse_address(to)?;
+        Ok(Tx {
+            id: String::new(),
+            from: self.wallet_address()?,
+            to: to.to_owned(),
+            amount,
+            fee,
+            block_height: 0,
+            pending: true,
+            confirmed: false,
+        })
+    }
+
+    /// Submits the transfer, at the gas price its fee was estimated with.
+    async fn send_tx(&self, data: Tx) -> Result<TxResponse, BundlrError> {
+        let signer = self.signer()?;
+        let sender = self.wallet_address()?;
+        let account: AccountData = self.get(&format!("accounts/{}", sender)).await?;
+        let ledger: LedgerInfo = self.get("").await?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let transfer = RawTransfer {
+            sender: parse_address(&sender)?,
+            sequence_number: parse_u64(&account.sequence_number, "sequence_number")?,
+            to: parse_address(&data.to)?,
+            amount: data.amount,
+            max_gas_amount: TRANSFER_MAX_GAS,
+            gas_unit_price: (data.fee / TRANSFER_MAX_GAS).max(1),
+            expiration_timestamp_secs: now + EXPIRATION_SECS,
+            chain_id: ledger.chain_id,
+        };
+        let signature = signer.sign_raw(transfer.signing_message().into())?;
+        let res = self
+            .client
+            .post(self.api("transactions")?)
+            .header(CONTENT_TYPE, BCS_CONTENT_TYPE)
+            .body(transfer.signed(&signer.pub_key(), &signature))
+            .send()
+            .await;
+        let submitted: Submitted = read_json(res).await?;
+        Ok(TxResponse {
+            tx_id: submitted.hash,
+        })
+    }
+
+    fn rpc_url(&self) -> Option<Url> {
+        Some(self.url.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{PublicKey, Signature, Verifier};
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use reqwest::Url;
+
+    use super::{address_of, parse_address, AptosBuilder, RawTransfer, TRANSFER_MAX_GAS};
+    use crate::{currency::Currency, BundlrBuilder};
+
+    const WALLET: &str = "0x3030303030303030303030303030303030303030303030303030303030303030";
+    const NODE_ADDRESS: &str = "0xaa";
+    const TX_HASH: &str = "0x00000000000000000000000000000000000000000000000000000000000000ff";
+
+    #[test]
+    fn should_encode_transfers_in_bcs() {
+        let transfer = RawTransfer {
+            sender: [1; 32],
+            sequence_number: 5,
+            to: parse_address(NODE_ADDRESS).unwrap(),
+            amount: 100,
+            max_gas_amount: 2_000,
+            gas_unit_price: 100,
+            expiration_timestamp_secs: 1_700_000_000,
+            chain_id: 1,
+        };
+        let bcs = data_encoding::HEXLOWER.encode(&transfer.to_bcs());
+        assert_eq!(
+            bcs,
+            [
+                &"01".repeat(32),
+                "0500000000000000",
+                "02",
+                &format!("{:0>64}", "1"),
+                "0d6170746f735f6163636f756e74",
+                "087472616e73666572",
+                "00",
+                "02",
+                &format!("20{:0>64}", "aa"),
+                "086400000000000000",
+                "d007000000000000",
+                "6400000000000000",
+                "00f1536500000000",
+                "01",
+            ]
+            .concat()
+        );
+        assert_eq!(
+            transfer.signing_message().len(),
+            32 + transfer.to_bcs().len()
+        );
+        assert!(parse_address("0xzz").is_err());
+        assert!(parse_address(&format!("0x{}", "1".repeat(65))).is_err());
+    }
+
+    #[test]
+    fn should_sign_transfers_with_the_wallets_key() {
+        let aptos = AptosBuilder::new().wallet(WALLET).build().unwrap();
+        let signer = aptos.signer().unwrap();
+        let transfer = RawTransfer {
+            sender: parse_address(&aptos.wallet_address().unwrap()).unwrap(),
+            sequence_number: 0,
+            to: parse_address(NODE_ADDRESS).unwrap(),
+            amount: 1,
+            max_gas_amount: TRANSFER_MAX_GAS,
+            gas_unit_price: 100,
+            expiration_timestamp_secs: 0,
+            chain_id: 1,
+        };
+        let signature = signer.sign_raw(transfer.signing_message().into()).unwrap();
+        let pub_key = aptos.get_pub_key().unwrap();
+        PublicKey::from_bytes(&pub_key)
+            .unwrap()
+            .verify(
+                &transfer.signing_message(),
+                &Signature::from_bytes(&signature).unwrap(),
+            )
+            .unwrap();
+
+        let signed = transfer.signed(&pub_key, &signature);
+        let authenticator = &signed[transfer.to_bcs().len()..];
+        assert_eq!(authenticator[..2], [0, 32]);
+        assert_eq!(authenticator[2..34], pub_key[..]);
+        assert_eq!(authenticator[34], 64);
+        assert_eq!(authenticator[35..], signature[..]);
+    }
+
+    #[tokio::test]
+    async fn should_read_the_ledger_height() {
+        let server = MockServer::start();
+        let aptos = AptosBuilder::new()
+            .base_url(Url::parse(&server.url("/aptos/")).unwrap())
+            .build()
+            .unwrap();
+        server.mock(|when, then| {
+            when.method(GET).path("/aptos/v1/");
+            then.status(200)
+                .body(r#"{ "chain_id": 2, "block_height": "10", "ledger_version": "20" }"#);
+        });
+        assert_eq!(aptos.get_current_height().await.unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn should_fund_with_signed_transfers() {
+        let server = MockServer::start();
+        let aptos = AptosBuilder::new()
+            .base_url(Url::parse(&server.url("/aptos/")).unwrap())
+            .wallet(WALLET)
+            .build()
+            .unwrap();
+        let sender = aptos.wallet_address().unwrap();
+        assert_eq!(sender, address_of(&aptos.get_pub_key().unwrap()));
+
+        let gas = server.mock(|when, then| {
+            when.method(GET).path("/aptos/v1/estimate_gas_price");
+            then.status(200).body(r#"{ "gas_estimate": 100 }"#);
+        });
+        let account = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/aptos/v1/accounts/{}", sender));
+            then.status(200)
+                .body(r#"{ "sequence_number": "7", "authentication_key": "0x00" }"#);
+        });
+        let ledger = server.mock(|when, then| {
+            when.method(GET).path("/aptos/v1/");
+            then.status(200)
+                .body(r#"{ "chain_id": 2, "block_height": "10", "ledger_version": "20" }"#);
+        });
+        let submitted = server.mock(|when, then| {
+            when.method(POST)
+                .path("/aptos/v1/transactions")
+                .header("content-type", "application/x.aptos.signed_transaction+bcs");
+            then.status(202)
+                .body(format!(r#"{{ "hash": "{}" }}"#, TX_HASH));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(format!(
+                    r#"{{ "version": "0.2.0", "gateway": "arweave.net", "addresses": {{ "aptos": "{}" }} }}"#,
+                    NODE_ADDRESS
+                ));
+        });
+        let credited = server.mock(|when, then| {
+            when.method(POST)
+                .path("/account/balance/aptos")
+                .body_contains(TX_HASH);
+            then.status(200).body("OK");
+        });
+
+        let bundlr = BundlrBuilder::new()
+            .url(Url::parse(&server.url("/")).unwrap())
+            .currency(aptos)
+            .fetch_pub_info()
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(bundlr.fund(1_000, None).await.unwrap());
+
+        gas.assert();
+        account.assert();
+        ledger.assert();
+        submitted.assert();
+        credited.assert();
+    }
+}
@@ -1,10 +1,16 @@
-use arweave_rs::{crypto::base64::Base64, Arweave as ArweaveSdk};
+use arweave_rs::{
+    consts::MAX_TX_DATA,
+    crypto::base64::Base64,
+    transaction::tags::{FromUtf8Strs, Tag as ArweaveTag},
+    Arweave as ArweaveSdk,
+};
 use bytes::Bytes;
 use num::ToPrimitive;
 use reqwest::{StatusCode, Url};
-use std::{ops::Mul, path::PathBuf, str::FromStr};
+use std::{ops::Mul, path::PathBuf, str::FromStr, sync::Arc};
 
 use crate::{
+    bundle::Bundle,
     error::{BuilderError, BundlrError},
     transaction::{Tx, TxStatus},
     ArweaveSigner, Signer, Verifier,
@@ -56,11 +62,9 @@ impl ArweaveBuilder {
             .unwrap_or_else(|| Url::from_str(ARWEAVE_BASE_URL).unwrap());
 
         let sdk = match &self.keypair_path {
-            // With signer
-            Some(keypair_path) => arweave_rs::ArweaveBuilder::new()
-                .base_url(base_url)
-                .keypair_path(keypair_path.clone())
-                .build()?,
+            // With signer. Not through arweave_rs' builder, which ignores the base url for
+            // transactions
+            Some(keypair_path) => ArweaveSdk::from_keypair_path(keypair_path.clone(), base_url)?,
             // Without signer
             None => arweave_rs::ArweaveBuilder::new()
                 .base_url(base_url)
@@ -86,6 +90,55 @@ impl ArweaveBuilder {
     }
 }
 
+impl Arweave {
+    /// Wraps the currency to be shared by several clients, see the [`Currency`] implementation
+    /// for `Arc`.
+    pub fn shared(self) -> Arc<Arweave> {
+        Arc::new(self)
+    }
+
+    /// Posts `bundle` to Arweave as a transaction of the wallet, skipping bundler nodes: the
+    /// wallet pays the network's fee for it. Returns the id of the transaction, which gateways
+    /// index the items of once it is mined.
+    ///
+    /// Bundles are posted whole, so those over 10 MB fail with [`BundlrError::Unsupported`].
+    pub async fn post_bundle(&self, bundle: &Bundle) -> Result<TxResponse, BundlrError> {
+        let data = bundle.to_bytes();
+        if data.len() as u64 > MAX_TX_DATA {
+            return Err(BundlrError::Unsupported(format!(
+                "posting bundles of {} bytes, over {}",
+                data.len(),
+                MAX_TX_DATA
+            )));
+        }
+        let tags = Bundle::tags()
+            .iter()
+            .map(|tag| ArweaveTag::from_utf8_strs(&tag.name, &tag.value))
+            .collect::<Result<_, _>>()
+            .map_err(BundlrError::ArweaveSdkError)?;
+        let fee = self
+            .sdk
+            .get_fee(Base64(vec![]), data.clone())
+            .await
+            .map_err(BundlrError::ArweaveSdkError)?;
+        let tx = self
+            .sdk
+            .create_transaction(Base64(vec![]), tags, data, 0, fee, false)
+            .await
+            .map_err(BundlrError::ArweaveSdkError)?;
+        let signed_tx = self
+            .sdk
+            .sign_transaction(tx)
+            .map_err(BundlrError::ArweaveSdkError)?;
+        let (tx_id, _) = self
+            .sdk
+            .post_transaction(&signed_tx)
+            .await
+            .map_err(BundlrError::ArweaveSdkError)?;
+        Ok(TxResponse { tx_id })
+    }
+}
+
 #[async_trait::async_trait]
 impl Currency for Arweave {
     fn get_min_unit_name(&self) -> String {
@@ -107,9 +160,19 @@ impl Currency for Arweave {
                 Base64::from_str(&tx_id).map_err(|err| BundlrError::ParseError(err.to_string()))?,
             )
             .await
-            .map_err(BundlrError::ArweaveSdkError)?;
+            .map_err(|err| match err {
+                arweave_rs::error::Error::TransactionInfoError(status)
+                    if status.starts_with("404") =>
+                {
+                    BundlrError::TxNotFound
+                }
+                err => BundlrError::ArweaveSdkError(err),
+            })?;
 
-        if status == 200 {
+        if status == StatusCode::ACCEPTED {
+            // Pending, which the gateway doesn't give the details of
+            Err(BundlrError::TxStatusNotConfirmed)
+        } else if status == 200 {
             match tx {
                 Some(tx) => Ok(Tx {
                     id: tx.id.to_string(),
@@ -207,15 +270,15 @@ impl Currency for Arweave {
         }
     }
 
-    async fn get_id(&self, _item: ()) -> String {
+    async fn get_id(&self, _item: ()) -> Result<String, BundlrError> {
         todo!();
     }
 
-    async fn price(&self) -> String {
+    async fn price(&self) -> Result<String, BundlrError> {
         todo!();
     }
 
-    async fn get_current_height(&self) -> u128 {
+    async fn get_current_height(&self) -> Result<u128, BundlrError> {
         todo!();
     }
 
@@ -301,13 +364,29 @@ impl Currency for Arweave {
 
         Ok(TxResponse { tx_id })
     }
+
+    fn rpc_url(&self) -> Option<Url> {
+        Some(self.sdk.base_url.clone())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::{path::PathBuf, str::FromStr};
 
-    use crate::currency::{arweave::ArweaveBuilder, Currency};
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use reqwest::Url;
+
+    use crate::{
+        bundle::Bundle,
+        currency::{arweave::ArweaveBuilder, Currency},
+        error::BundlrError,
+        tags::Tag,
+        BundlrTx,
+    };
 
     #[test]
     fn should_sign_and_verify() {
@@ -330,4 +409,64 @@ mod tests {
 
     #[tokio::test]
     async fn should_get_fee_correctly() {}
+
+    #[tokio::test]
+    async fn should_tell_pending_from_unknown_transactions() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", "A".repeat(43)));
+            then.status(202).body("Pending");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", "E".repeat(43)));
+            then.status(404).body("Not Found");
+        });
+        let c = ArweaveBuilder::new()
+            .base_url(Url::parse(&server.url("/")).unwrap())
+            .keypair_path(PathBuf::from_str("res/test_wallet.json").unwrap())
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            c.get_tx("A".repeat(43)).await,
+            Err(BundlrError::TxStatusNotConfirmed)
+        ));
+        assert!(matches!(
+            c.get_tx("E".repeat(43)).await,
+            Err(BundlrError::TxNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_post_bundles_as_transactions() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body("A".repeat(64));
+        });
+        let price = server.mock(|when, then| {
+            when.method(GET).path_contains("/price/");
+            then.status(200).body("1000");
+        });
+        let post = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .body_contains("QnVuZGxlLUZvcm1hdA")
+                .body_contains(r#""reward":"1000""#);
+            then.status(200);
+        });
+        let c = ArweaveBuilder::new()
+            .base_url(Url::parse(&server.url("/")).unwrap())
+            .keypair_path(PathBuf::from_str("res/test_wallet.json").unwrap())
+            .build()
+            .unwrap();
+        let mut item = BundlrTx::new(vec![], b"Hello".to_vec(), vec![Tag::new("a", "b")]).unwrap();
+        item.sign_sync(c.get_signer().unwrap()).unwrap();
+        let bundle = Bundle::new(vec![item]).unwrap();
+
+        let res = c.post_bundle(&bundle).await.unwrap();
+        assert_eq!(res.tx_id.len(), 43);
+        price.assert();
+        post.assert();
+    }
 }
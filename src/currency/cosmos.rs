@@ -0,0 +1,1001 @@
+//! Cosmos-SDK chains, such as the Cosmos Hub, funding the node's address with `MsgSend`s
+//! broadcast through a node's LCD (REST) endpoint.
+//!
+//! Transactions are encoded in protobuf and signed in `SIGN_MODE_DIRECT` with the wallet's
+//! secp256k1 key locally, the LCD only being asked for the account's number and sequence.
+//! Addresses are in bech32 with the chain's prefix, and amounts are in the chain's base denom,
+//! such as `uatom`, 10^-6 ATOM.
+
+use bech32::{FromBase32, ToBase32, Variant};
+use bytes::Bytes;
+use data_encoding::{BASE64, HEXLOWER_PERMISSIVE, HEXUPPER};
+use num::ToPrimitive;
+use reqwest::{StatusCode, Url};
+use ripemd::Ripemd160;
+use secp256k1::SecretKey;
+use serde::{de::DeserializeOwned, Deserialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{BuilderError, BundlrError},
+    transaction::{Tx, TxStatus},
+    utils::{read_json, RawNumber},
+    CosmosSigner, Signer, Verifier,
+};
+
+use super::{units, Currency, CurrencyType, TxResponse};
+
+/// A Cosmos-SDK chain and its fee token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CosmosChain {
+    pub chain_id: &'static str,
+    /// Name of the currency on the node, as in its `/account/balance/{name}` path.
+    pub name: &'static str,
+    /// Human-readable part of the chain's bech32 addresses.
+    pub prefix: &'static str,
+    /// Base denom amounts are in.
+    pub denom: &'static str,
+    pub ticker: &'static str,
+    pub decimals: u8,
+    /// Price of a unit of gas in the base denom.
+    pub gas_price: f64,
+}
+
+pub const COSMOS_HUB: CosmosChain = CosmosChain {
+    chain_id: "cosmoshub-4",
+    name: "cosmos",
+    prefix: "cosmos",
+    denom: "uatom",
+    ticker: "ATOM",
+    decimals: 6,
+    gas_price: 0.025,
+};
+
+/// Gas a send may use at most, well above the ~80k units it takes.
+pub const SEND_GAS_LIMIT: u64 = 200_000;
+const MSG_SEND_TYPE: &str = "/cosmos.bank.v1beta1.MsgSend";
+const PUB_KEY_TYPE: &str = "/cosmos.crypto.secp256k1.PubKey";
+const SIGN_MODE_DIRECT: u64 = 1;
+const BROADCAST_MODE_SYNC: &str = "BROADCAST_MODE_SYNC";
+
+pub struct Cosmos {
+    signer: Option<CosmosSigner>,
+    client: reqwest::Client,
+    url: Url,
+    chain_id: String,
+    name: String,
+    prefix: String,
+    denom: String,
+    ticker: String,
+    decimals: u8,
+    gas_price: f64,
+}
+
+pub struct CosmosBuilder {
+    lcd_url: Option<Url>,
+    chain_id: String,
+    name: String,
+    prefix: String,
+    denom: String,
+    ticker: String,
+    decimals: u8,
+    gas_price: f64,
+    wallet: Option<String>,
+}
+
+impl Default for CosmosBuilder {
+    fn default() -> Self {
+        CosmosBuilder::from(COSMOS_HUB)
+    }
+}
+
+impl From<CosmosChain> for CosmosBuilder {
+    fn from(chain: CosmosChain) -> Self {
+        CosmosBuilder {
+            lcd_url: None,
+            chain_id: chain.chain_id.to_owned(),
+            name: chain.name.to_owned(),
+            prefix: chain.prefix.to_owned(),
+            denom: chain.denom.to_owned(),
+            ticker: chain.ticker.to_owned(),
+            decimals: chain.decimals,
+            gas_price: chain.gas_price,
+            wallet: None,
+        }
+    }
+}
+
+impl CosmosBuilder {
+    /// Builder for the Cosmos Hub, whose settings may be changed one by one for other chains.
+    pub fn new() -> CosmosBuilder {
+        Default::default()
+    }
+
+    /// Uses all of `chain`'s settings, keeping the LCD endpoint and wallet.
+    pub fn chain(self, chain: CosmosChain) -> CosmosBuilder {
+        CosmosBuilder {
+            lcd_url: self.lcd_url,
+            wallet: self.wallet,
+            ..CosmosBuilder::from(chain)
+        }
+    }
+
+    /// LCD (REST) endpoint of a node of the chain, serving `cosmos/...` paths.
+    pub fn lcd_url(mut self, lcd_url: Url) -> CosmosBuilder {
+        self.lcd_url = Some(lcd_url);
+        self
+    }
+
+    pub fn chain_id(mut self, chain_id: &str) -> CosmosBuilder {
+        self.chain_id = chain_id.to_owned();
+        self
+    }
+
+    /// Name of the currency on the node, as in its `/account/balance/{name}` path.
+    pub fn name(mut self, name: &str) -> CosmosBuilder {
+        self.name = name.to_owned();
+        self
+    }
+
+    /// Human-readable part of the chain's bech32 addresses, such as `osmo`.
+    pub fn prefix(mut self, prefix: &str) -> CosmosBuilder {
+        self.prefix = prefix.to_owned();
+        self
+    }
+
+    /// Base denom to fund and pay fees with, such as `uosmo`.
+    pub fn denom(mut self, denom: &str) -> CosmosBuilder {
+        self.denom = denom.to_owned();
+        self
+    }
+
+    pub fn ticker(mut self, ticker: &str) -> CosmosBuilder {
+        self.ticker = ticker.to_owned();
+        self
+    }
+
+    /// Decimals of the ticker's unit over the base denom, 6 for most chains.
+    pub fn decimals(mut self, decimals: u8) -> CosmosBuilder {
+        self.decimals = decimals;
+        self
+    }
+
+    /// Price of a unit of gas in the base denom, at least the chain's minimum gas price.
+    pub fn gas_price(mut self, gas_price: f64) -> CosmosBuilder {
+        self.gas_price = gas_price;
+        self
+    }
+
+    /// Wallet's key, in base58 as for [`CosmosSigner::from_base58`], or as a hex private key.
+    pub fn wallet(mut self, wallet: &str) -> CosmosBuilder {
+        self.wallet = Some(wallet.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Cosmos, BuilderError> {
+        let url = self.lcd_url.ok_or(BuilderError::MissingField(
+            "lcd_url is required for Cosmos currencies".to_owned(),
+        ))?;
+        if !(self.gas_price.is_finite() && self.gas_price >= 0.0) {
+            return Err(BundlrError::CurrencyError(format!(
+                "Invalid gas price: {}",
+                self.gas_price
+            ))
+            .into());
+        }
+        let signer = match self.wallet {
+            Some(wallet) => Some(parse_wallet(&wallet)?),
+            None => None,
+        };
+        Ok(Cosmos {
+            signer,
+            client: reqwest::Client::new(),
+            url,
+            chain_id: self.chain_id,
+            name: self.name,
+            prefix: self.prefix,
+            denom: self.denom,
+            ticker: self.ticker,
+            decimals: self.decimals,
+            gas_price: self.gas_price,
+        })
+    }
+}
+
+fn parse_wallet(wallet: &str) -> Result<CosmosSigner, BundlrError> {
+    let hex = wallet.strip_prefix("0x").unwrap_or(wallet);
+    match hex.len() {
+        64 => {
+            let key = HEXLOWER_PERMISSIVE
+                .decode(hex.as_bytes())
+                .map_err(|err| BundlrError::ParseError(err.to_string()))?;
+            let key = SecretKey::from_slice(&key).map_err(BundlrError::Secp256k1Error)?;
+            CosmosSigner::new(key)
+        }
+        _ => CosmosSigner::from_base58(wallet),
+    }
+}
+
+/// Address of a compressed secp256k1 `pub_key` on chains whose addresses start with `prefix`.
+pub fn address_of(prefix: &str, pub_key: &[u8]) -> Result<String, BundlrError> {
+    let hash = Ripemd160::digest(Sha256::digest(pub_key));
+    bech32::encode(prefix, hash.to_base32(), Variant::Bech32)
+        .map_err(|err| BundlrError::ParseError(err.to_string()))
+}
+
+/// Checks that `address` is a bech32 address starting with `prefix`.
+fn parse_address(prefix: &str, address: &str) -> Result<(), BundlrError> {
+    let invalid = || BundlrError::ParseError(format!("Invalid {} address: {}", prefix, address));
+    let (hrp, data, variant) = bech32::decode(address).map_err(|_| invalid())?;
+    let bytes = Vec::<u8>::from_base32(&data).map_err(|_| invalid())?;
+    match hrp == prefix && variant == Variant::Bech32 && !bytes.is_empty() {
+        true => Ok(()),
+        false => Err(invalid()),
+    }
+}
+
+fn varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Length-delimited `field`, left out when empty as protobuf does for default values.
+fn proto_bytes(buf: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    if !bytes.is_empty() {
+        varint(buf, field << 3 | 2);
+        varint(buf, bytes.len() as u64);
+        buf.extend_from_slice(bytes);
+    }
+}
+
+/// Varint `field`, left out when zero as protobuf does for default values.
+fn proto_uint(buf: &mut Vec<u8>, field: u64, value: u64) {
+    if value != 0 {
+        varint(buf, field << 3);
+        varint(buf, value);
+    }
+}
+
+fn proto_any(type_url: &str, value: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    proto_bytes(&mut buf, 1, type_url.as_bytes());
+    proto_bytes(&mut buf, 2, value);
+    buf
+}
+
+fn proto_coin(denom: &str, amount: u64) -> Vec<u8> {
+    let mut buf = Vec::new();
+    proto_bytes(&mut buf, 1, denom.as_bytes());
+    proto_bytes(&mut buf, 2, amount.to_string().as_bytes());
+    buf
+}
+
+/// A transaction with a single `MsgSend`, as signed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawSend {
+    pub from: String,
+    pub to: String,
+    pub denom: String,
+    pub amount: u64,
+    pub fee: u64,
+    pub gas_limit: u64,
+    /// Compressed secp256k1 key of the sender.
+    pub pub_key: Vec<u8>,
+    pub account_number: u64,
+    pub sequence: u64,
+    pub chain_id: String,
+}
+
+impl RawSend {
+    /// The `TxBody`, holding the `MsgSend`, in protobuf.
+    pub fn body_bytes(&self) -> Vec<u8> {
+        let mut msg = Vec::new();
+        proto_bytes(&mut msg, 1, self.from.as_bytes());
+        proto_bytes(&mut msg, 2, self.to.as_bytes());
+        proto_bytes(&mut msg, 3, &proto_coin(&self.denom, self.amount));
+        let mut buf = Vec::new();
+        proto_bytes(&mut buf, 1, &proto_any(MSG_SEND_TYPE, &msg));
+        buf
+    }
+
+    /// The `AuthInfo`, holding the signer's key and sequence and the fee, in protobuf.
+    pub fn auth_info_bytes(&self) -> Vec<u8> {
+        let mut pub_key = Vec::new();
+        proto_bytes(&mut pub_key, 1, &self.pub_key);
+        let mut single = Vec::new();
+        proto_uint(&mut single, 1, SIGN_MODE_DIRECT);
+        let mut mode_info = Vec::new();
+        proto_bytes(&mut mode_info, 1, &single);
+        let mut signer_info = Vec::new();
+        proto_bytes(&mut signer_info, 1, &proto_any(PUB_KEY_TYPE, &pub_key));
+        proto_bytes(&mut signer_info, 2, &mode_info);
+        proto_uint(&mut signer_info, 3, self.sequence);
+        let mut fee = Vec::new();
+        proto_bytes(&mut fee, 1, &proto_coin(&self.denom, self.fee));
+        proto_uint(&mut fee, 2, self.gas_limit);
+        let mut buf = Vec::new();
+        proto_bytes(&mut buf, 1, &signer_info);
+        proto_bytes(&mut buf, 2, &fee);
+        buf
+    }
+
+    /// What the sender signs in `SIGN_MODE_DIRECT`: the `SignDoc`, in protobuf.
+    pub fn sign_doc(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        proto_bytes(&mut buf, 1, &self.body_bytes());
+        proto_bytes(&mut buf, 2, &self.auth_info_bytes());
+        proto_bytes(&mut buf, 3, self.chain_id.as_bytes());
+        proto_uint(&mut buf, 4, self.account_number);
+        buf
+    }
+
+    /// The `TxRaw` signed with `signature`, in protobuf, as broadcast.
+    pub fn signed(&self, signature: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        proto_bytes(&mut buf, 1, &self.body_bytes());
+        proto_bytes(&mut buf, 2, &self.auth_info_bytes());
+        proto_bytes(&mut buf, 3, signature);
+        buf
+    }
+}
+
+/// An account, whose number and sequence are nested in the base account of vesting and module
+/// accounts.
+#[derive(Deserialize)]
+struct Account {
+    account_number: Option<RawNumber>,
+    sequence: Option<RawNumber>,
+    base_account: Option<Box<Account>>,
+    base_vesting_account: Option<Box<Account>>,
+}
+
+impl Account {
+    fn base(&self) -> &Account {
+        match (&self.base_account, &self.base_vesting_account) {
+            (Some(base), _) | (None, Some(base)) => base.base(),
+            (None, None) => self,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AccountResponse {
+    account: Account,
+}
+
+#[derive(Deserialize)]
+struct Coin {
+    denom: String,
+    amount: RawNumber,
+}
+
+#[derive(Deserialize)]
+struct TxResult {
+    txhash: String,
+    height: RawNumber,
+    #[serde(default)]
+    code: u32,
+    #[serde(default)]
+    raw_log: String,
+}
+
+#[derive(Deserialize)]
+struct Broadcast {
+    tx_response: TxResult,
+}
+
+#[derive(Deserialize)]
+struct MsgSend {
+    #[serde(rename = "@type")]
+    kind: String,
+    #[serde(default)]
+    from_address: String,
+    #[serde(default)]
+    to_address: String,
+    #[serde(default)]
+    amount: Vec<Coin>,
+}
+
+#[derive(Deserialize)]
+struct TxBody {
+    messages: Vec<MsgSend>,
+}
+
+#[derive(Deserialize)]
+struct Fee {
+    amount: Vec<Coin>,
+}
+
+#[derive(Deserialize)]
+struct AuthInfo {
+    fee: Fee,
+}
+
+#[derive(Deserialize)]
+struct CosmosTx {
+    body: TxBody,
+    auth_info: AuthInfo,
+}
+
+#[derive(Deserialize)]
+struct TxLookup {
+    tx: CosmosTx,
+    tx_response: TxResult,
+}
+
+#[derive(Deserialize)]
+struct BlockId {
+    hash: String,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    height: RawNumber,
+}
+
+#[derive(Deserialize)]
+struct BlockData {
+    header: Header,
+}
+
+#[derive(Deserialize)]
+struct Block {
+    block_id: BlockId,
+    block: BlockData,
+}
+
+fn parse_u64(number: &RawNumber, field: &str) -> Result<u64, BundlrError> {
+    number
+        .parse_atomic(field)?
+        .to_u64()
+        .ok_or(BundlrError::TypeParseError(format!(
+            "{} does not fit in a u64",
+            field
+        )))
+}
+
+impl Cosmos {
+    pub fn chain_id(&self) -> &str {
+        &self.chain_id
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn denom(&self) -> &str {
+        &self.denom
+    }
+
+    pub fn ticker(&self) -> &str {
+        &self.ticker
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// `amount` of the ticker in the base denom, see [`units::parse_units`].
+    pub fn parse_amount(&self, amount: &str) -> Result<u64, BundlrError> {
+        units::parse_units(amount, self.decimals)
+    }
+
+    /// `amount` of the base denom in the ticker, see [`units::format_units`].
+    pub fn format_amount(&self, amount: u64) -> String {
+        units::format_units(amount, self.decimals)
+    }
+
+    fn signer(&self) -> Result<&CosmosSigner, BundlrError> {
+        self.signer.as_ref().ok_or(BundlrError::CurrencyError(
+            "No private key present".to_string(),
+        ))
+    }
+
+    fn api(&self, path: &str) -> Result<Url, BundlrError> {
+        self.url
+            .join(&format!("cosmos/{}", path))
+            .map_err(|err| BundlrError::ParseError(err.to_string()))
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, BundlrError> {
+        read_json(self.client.get(self.api(path)?).send().await).await
+    }
+
+    async fn transaction(&self, tx_id: &str) -> Result<TxLookup, BundlrError> {
+        let res = self
+            .client
+            .get(self.api(&format!("tx/v1beta1/txs/{}", tx_id))?)
+            .send()
+            .await;
+        match res {
+            Ok(res) if res.status() == StatusCode::NOT_FOUND => Err(BundlrError::TxNotFound),
+            res => read_json(res).await,
+        }
+    }
+
+    /// Sum of the `coins` in the chain's denom.
+    fn sum(&self, coins: &[Coin], field: &str) -> Result<u64, BundlrError> {
+        coins
+            .iter()
+            .filter(|coin| coin.denom == self.denom)
+            .try_fold(0u64, |sum, coin| {
+                Ok(sum.saturating_add(parse_u64(&coin.amount, field)?))
+            })
+    }
+}
+
+#[async_trait::async_trait]
+impl Currency for Cosmos {
+    fn get_min_unit_name(&self) -> String {
+        self.denom.clone()
+    }
+
+    fn get_type(&self) -> CurrencyType {
+        CurrencyType::Cosmos
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn needs_fee(&self) -> bool {
+        true
+    }
+
+    async fn get_tx(&self, tx_id: String) -> Result<Tx, BundlrError> {
+        let lookup = self.transaction(&tx_id).await?;
+        let send = lookup
+            .tx
+            .body
+            .messages
+            .iter()
+            .find(|msg| msg.kind == MSG_SEND_TYPE)
+            .ok_or(BundlrError::CurrencyError(format!(
+                "{} is not a send of {}",
+                tx_id, self.denom
+            )))?;
+        Ok(Tx {
+            id: lookup.tx_response.txhash,
+            from: send.from_address.clone(),
+            to: send.to_address.clone(),
+            amount: self.sum(&send.amount, "amount")?,
+            fee: self.sum(&lookup.tx.auth_info.fee.amount, "fee")?,
+            block_height: parse_u64(&lookup.tx_response.height, "height")? as u128,
+            pending: false,
+            confirmed: lookup.tx_response.code == 0,
+        })
+    }
+
+    /// Transactions are only found once included in a block: until then, they are accepted.
+    async fn get_tx_status(
+        &self,
+        tx_id: String,
+    ) -> Result<(StatusCode, Option<TxStatus>), BundlrError> {
+        let result = match self.transaction(&tx_id).await {
+            Ok(lookup) => lookup.tx_response,
+            Err(BundlrError::TxNotFound) => return Ok((StatusCode::ACCEPTED, None)),
+            Err(err) => return Err(err),
+        };
+        if result.code != 0 {
+            return Err(BundlrError::CurrencyError(format!(
+                "Transaction {} failed: {}",
+                tx_id, result.raw_log
+            )));
+        }
+        let height = parse_u64(&result.height, "height")?;
+        let block: Block = self
+            .get(&format!("base/tendermint/v1beta1/blocks/{}", height))
+            .await?;
+        let latest: Block = self.get("base/tendermint/v1beta1/blocks/latest").await?;
+        let current = parse_u64(&latest.block.header.height, "height")?;
+        let block_hash = BASE64
+            .decode(block.block_id.hash.as_bytes())
+            .map_err(|err| BundlrError::Base64Error(err.to_string()))?;
+        Ok((
+            StatusCode::OK,
+            Some(TxStatus {
+                confirmations: current.saturating_sub(height) + 1,
+                height: height as u128,
+                block_hash: HEXUPPER.encode(&block_hash),
+            }),
+        ))
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>, BundlrError> {
+        Ok(self
+            .signer()?
+            .sign(Bytes::copy_from_slice(message))?
+            .to_vec())
+    }
+
+    fn verify(&self, pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), BundlrError> {
+        CosmosSigner::verify(
+            Bytes::copy_from_slice(pub_key),
+            Bytes::copy_from_slice(message),
+            Bytes::copy_from_slice(signature),
+        )
+    }
+
+    fn get_pub_key(&self) -> Result<Bytes, BundlrError> {
+        Ok(self.signer()?.pub_key())
+    }
+
+    fn wallet_address(&self) -> Result<String, BundlrError> {
+        address_of(&self.prefix, &self.signer()?.pub_key())
+    }
+
+    fn get_signer(&self) -> Result<&dyn Signer, BundlrError> {
+        Ok(self.signer()?)
+    }
+
+    async fn get_id(&self, _item: ()) -> Result<String, BundlrError> {
+        Err(BundlrError::Unsupported(format!(
+            "No currency id for {}",
+            self.name()
+        )))
+    }
+
+    async fn price(&self) -> Result<String, BundlrError> {
+        Err(BundlrError::Unsupported(format!(
+            "No USD price source for {}",
+            self.name()
+        )))
+    }
+
+    /// Height of the chain's latest block.
+    async fn get_current_height(&self) -> Result<u128, BundlrError> {
+        let latest: Block = self.get("base/tendermint/v1beta1/blocks/latest").await?;
+        Ok(parse_u64(&latest.block.header.height, "height")? as u128)
+    }
+
+    /// Most a send may cost at the configured gas price, [`SEND_GAS_LIMIT`] units, in the base
+    /// denom.
+    async fn get_fee(&self, _amount: u64, to: &str, multiplier: f64) -> Result<u64, BundlrError> {
+        parse_address(&self.prefix, to)?;
+        Ok((SEND_GAS_LIMIT as f64 * self.gas_price * multiplier).ceil() as u64)
+    }
+
+    async fn create_tx(&self, amount: u64, to: &str, fee: u64) -> Result<Tx, BundlrError> {
+        parse_address(&self.prefix, to)?;
+        Ok(Tx {
+            id: String::new(),
+            from: self.wallet_address()?,
+            to: to.to_owned(),
+            amount,
+            fee,
+            block_height: 0,
+            pending: true,
+            confirmed: false,
+        })
+    }
+
+    /// Broadcasts the send, returning once it passed the node's checks but before it is
+    /// included in a block.
+    async fn send_tx(&self, data: Tx) -> Result<TxResponse, BundlrError> {
+        let signer = self.signer()?;
+        let from = self.wallet_address()?;
+        parse_address(&self.prefix, &data.to)?;
+        let account: AccountResponse = self.get(&format!("auth/v1beta1/accounts/{}", from)).await?;
+        let account = account.account.base();
+        let send = RawSend {
+            from,
+            to: data.to,
+            denom: self.denom.clone(),
+            amount: data.amount,
+            fee: data.fee,
+            gas_limit: SEND_GAS_LIMIT,
+            pub_key: signer.pub_key().to_vec(),
+            account_number: match &account.account_number {
+                Some(number) => parse_u64(number, "account_number")?,
+                None => 0,
+            },
+            sequence: match &account.sequence {
+                Some(sequence) => parse_u64(sequence, "sequence")?,
+                None => 0,
+            },
+            chain_id: self.chain_id.clone(),
+        };
+        let signature = signer.sign(send.sign_doc().into())?;
+        let res = self
+            .client
+            .post(self.api("tx/v1beta1/txs")?)
+            .json(&serde_json::json!({
+                "tx_bytes": BASE64.encode(&send.signed(&signature)),
+                "mode": BROADCAST_MODE_SYNC,
+            }))
+            .send()
+            .await;
+        let broadcast: Broadcast = read_json(res).await?;
+        match broadcast.tx_response.code {
+            0 => Ok(TxResponse {
+                tx_id: broadcast.tx_response.txhash,
+            }),
+            code => Err(BundlrError::CurrencyError(format!(
+                "Send rejected with code {}: {}",
+                code, broadcast.tx_response.raw_log
+            ))),
+        }
+    }
+
+    fn rpc_url(&self) -> Option<Url> {
+        Some(self.url.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use reqwest::{StatusCode, Url};
+
+    use super::{address_of, parse_address, CosmosBuilder, CosmosChain, RawSend, COSMOS_HUB};
+    use crate::{
+        currency::{Currency, CurrencyType},
+        error::BundlrError,
+        BundlrBuilder, CosmosSigner, Verifier,
+    };
+
+    const WALLET: &str = "0x3030303030303030303030303030303030303030303030303030303030303030";
+    const TX_HASH: &str = "00000000000000000000000000000000000000000000000000000000000000FF";
+
+    fn node_address(prefix: &str) -> String {
+        address_of(prefix, &[2; 33]).unwrap()
+    }
+
+    fn hex(text: &str) -> String {
+        data_encoding::HEXLOWER.encode(text.as_bytes())
+    }
+
+    #[test]
+    fn should_encode_sends_in_protobuf() {
+        let send = RawSend {
+            from: "a".to_owned(),
+            to: "b".to_owned(),
+            denom: "uatom".to_owned(),
+            amount: 5,
+            fee: 0,
+            gas_limit: 200_000,
+            pub_key: vec![2; 33],
+            account_number: 0,
+            sequence: 0,
+            chain_id: "c".to_owned(),
+        };
+        let body = data_encoding::HEXLOWER.encode(&send.body_bytes());
+        assert_eq!(
+            body,
+            [
+                "0a32",
+                "0a1c",
+                &hex("/cosmos.bank.v1beta1.MsgSend"),
+                "1212",
+                "0a0161",
+                "120162",
+                "1a0a",
+                "0a05",
+                &hex("uatom"),
+                "120135",
+            ]
+            .concat()
+        );
+        let auth_info = data_encoding::HEXLOWER.encode(&send.auth_info_bytes());
+        assert_eq!(
+            auth_info,
+            [
+                "0a4e",
+                "0a46",
+                "0a1f",
+                &hex("/cosmos.crypto.secp256k1.PubKey"),
+                "1223",
+                "0a21",
+                &"02".repeat(33),
+                "12040a020801",
+                "1210",
+                "0a0a",
+                "0a05",
+                &hex("uatom"),
+                "120130",
+                "10c09a0c",
+            ]
+            .concat()
+        );
+        let sign_doc = send.sign_doc();
+        assert_eq!(sign_doc[sign_doc.len() - 3..], [0x1a, 0x01, b'c']);
+    }
+
+    #[test]
+    fn should_derive_bech32_addresses() {
+        let cosmos = CosmosBuilder::new()
+            .lcd_url(Url::parse("http://localhost:1317/").unwrap())
+            .wallet(WALLET)
+            .build()
+            .unwrap();
+        let address = cosmos.wallet_address().unwrap();
+        assert!(address.starts_with("cosmos1"));
+        assert_eq!(address.len(), 45);
+        assert!(parse_address("cosmos", &address).is_ok());
+        assert!(parse_address("osmo", &address).is_err());
+        assert!(parse_address("cosmos", &address.replace('q', "p")).is_err());
+
+        let osmosis = CosmosChain {
+            chain_id: "osmosis-1",
+            name: "osmosis",
+            prefix: "osmo",
+            denom: "uosmo",
+            ticker: "OSMO",
+            ..COSMOS_HUB
+        };
+        let osmo = CosmosBuilder::new()
+            .chain(osmosis)
+            .lcd_url(Url::parse("http://localhost:1317/").unwrap())
+            .wallet(WALLET)
+            .build()
+            .unwrap();
+        assert!(osmo.wallet_address().unwrap().starts_with("osmo1"));
+        assert_eq!(
+            (osmo.name(), osmo.get_min_unit_name()),
+            ("osmosis".to_owned(), "uosmo".to_owned())
+        );
+        assert_eq!(osmo.get_type(), CurrencyType::Cosmos);
+        assert_eq!(osmo.parse_amount("1.5").unwrap(), 1_500_000);
+        assert!(CosmosBuilder::new().wallet(WALLET).build().is_err());
+    }
+
+    #[tokio::test]
+    async fn should_fund_with_signed_sends() {
+        let server = MockServer::start();
+        let cosmos = CosmosBuilder::new()
+            .lcd_url(Url::parse(&server.url("/lcd/")).unwrap())
+            .wallet(WALLET)
+            .build()
+            .unwrap();
+        let sender = cosmos.wallet_address().unwrap();
+        let to = node_address("cosmos");
+        assert_eq!(cosmos.get_fee(1_000, &to, 1.0).await.unwrap(), 5_000);
+
+        let account = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/lcd/cosmos/auth/v1beta1/accounts/{}", sender));
+            then.status(200).body(format!(
+                r#"{{ "account": {{ "@type": "/cosmos.vesting.v1beta1.DelayedVestingAccount", "base_vesting_account": {{ "base_account": {{ "address": "{}", "account_number": "12", "sequence": "3" }} }} }} }}"#,
+                sender
+            ));
+        });
+        let broadcast = server.mock(|when, then| {
+            when.method(POST)
+                .path("/lcd/cosmos/tx/v1beta1/txs")
+                .body_contains("BROADCAST_MODE_SYNC");
+            then.status(200).body(format!(
+                r#"{{ "tx_response": {{ "height": "0", "txhash": "{}", "code": 0, "raw_log": "" }} }}"#,
+                TX_HASH
+            ));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(format!(
+                    r#"{{ "version": "0.2.0", "gateway": "arweave.net", "addresses": {{ "cosmos": "{}" }} }}"#,
+                    to
+                ));
+        });
+        let credited = server.mock(|when, then| {
+            when.method(POST)
+                .path("/account/balance/cosmos")
+                .body_contains(TX_HASH);
+            then.status(200).body("OK");
+        });
+
+        let bundlr = BundlrBuilder::new()
+            .url(Url::parse(&server.url("/")).unwrap())
+            .currency(cosmos)
+            .fetch_pub_info()
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(bundlr.fund(1_000, None).await.unwrap());
+
+        account.assert();
+        broadcast.assert();
+        credited.assert();
+    }
+
+    #[tokio::test]
+    async fn should_sign_sends_the_lcd_accepts() {
+        let signer = CosmosBuilder::new()
+            .lcd_url(Url::parse("http://localhost:1317/").unwrap())
+            .wallet(WALLET)
+            .build()
+            .unwrap();
+        let send = RawSend {
+            from: signer.wallet_address().unwrap(),
+            to: node_address("cosmos"),
+            denom: "uatom".to_owned(),
+            amount: 1,
+            fee: 5_000,
+            gas_limit: 200_000,
+            pub_key: signer.get_pub_key().unwrap().to_vec(),
+            account_number: 1,
+            sequence: 0,
+            chain_id: "cosmoshub-4".to_owned(),
+        };
+        let signature = signer.sign_message(&send.sign_doc()).unwrap();
+        assert_eq!(signature.len(), 64);
+        CosmosSigner::verify(
+            signer.get_pub_key().unwrap(),
+            send.sign_doc().into(),
+            signature.clone().into(),
+        )
+        .unwrap();
+        let signed = send.signed(&signature);
+        assert_eq!(signed[signed.len() - 66..signed.len() - 64], [0x1a, 64]);
+        assert_eq!(signed[signed.len() - 64..], signature[..]);
+    }
+
+    #[tokio::test]
+    async fn should_read_sends_and_their_confirmations() {
+        let server = MockServer::start();
+        let cosmos = CosmosBuilder::new()
+            .lcd_url(Url::parse(&server.url("/")).unwrap())
+            .build()
+            .unwrap();
+        let to = node_address("cosmos");
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/cosmos/tx/v1beta1/txs/{}", TX_HASH));
+            then.status(200).body(format!(
+                r#"{{
+                    "tx": {{
+                        "body": {{ "messages": [{{ "@type": "/cosmos.bank.v1beta1.MsgSend", "from_address": "cosmos1sender", "to_address": "{}", "amount": [{{ "denom": "uatom", "amount": "1000" }}] }}] }},
+                        "auth_info": {{ "fee": {{ "amount": [{{ "denom": "uatom", "amount": "5000" }}], "gas_limit": "200000" }} }}
+                    }},
+                    "tx_response": {{ "height": "100", "txhash": "{}", "code": 0, "raw_log": "" }}
+                }}"#,
+                to, TX_HASH
+            ));
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/cosmos/base/tendermint/v1beta1/blocks/100");
+            then.status(200).body(
+                r#"{ "block_id": { "hash": "qrs=" }, "block": { "header": { "height": "100" } } }"#,
+            );
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/cosmos/base/tendermint/v1beta1/blocks/latest");
+            then.status(200).body(
+                r#"{ "block_id": { "hash": "AAA=" }, "block": { "header": { "height": "104" } } }"#,
+            );
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/cosmos/tx/v1beta1/txs/UNKNOWN");
+            then.status(404)
+                .body(r#"{ "code": 5, "message": "tx not found" }"#);
+        });
+
+        let tx = cosmos.get_tx(TX_HASH.to_owned()).await.unwrap();
+        assert_eq!((tx.to, tx.amount, tx.fee), (to, 1_000, 5_000));
+        assert_eq!((tx.block_height, tx.confirmed), (100, true));
+
+        let (status, confirmed) = cosmos.get_tx_status(TX_HASH.to_owned()).await.unwrap();
+        let confirmed = confirmed.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((confirmed.confirmations, confirmed.height), (5, 100));
+        assert_eq!(confirmed.block_hash, "AABB");
+
+        let (status, pending) = cosmos.get_tx_status("UNKNOWN".to_owned()).await.unwrap();
+        assert_eq!((status, pending.is_none()), (StatusCode::ACCEPTED, true));
+
+        assert_eq!(cosmos.get_current_height().await.unwrap(), 104);
+        assert!(matches!(
+            cosmos.price().await,
+            Err(BundlrError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,446 @@
+//! ERC-20 tokens, such as USDC, to pay for uploads with.
+//!
+//! Each token has its own name on nodes, such as `usdc-eth` for USDC on Ethereum, set with
+//! [`Erc20Builder::name`]. Funding transfers tokens to the node's address for that name by
+//! calling `transfer` on the token's contract, signed with the wallet's key and sent through the
+//! chain's JSON-RPC endpoint. Amounts are in the token's base units, and are held in `u64`s as
+//! for every currency: 18 decimals tokens can't be funded with more than about 18.4 tokens at
+//! once.
+
+use bytes::Bytes;
+use reqwest::{StatusCode, Url};
+use web3::types::{Address, CallRequest, U256};
+
+use crate::{
+    error::{BuilderError, BundlrError},
+    transaction::{Tx, TxStatus},
+    Secp256k1Signer, Signer, Verifier,
+};
+
+use super::{
+    evm::{self, parse_address, parse_wallet, EvmRpc},
+    Currency, CurrencyType, TxResponse,
+};
+
+pub use super::evm::{format_units, parse_units};
+
+/// USDC on Ethereum mainnet.
+pub const USDC_ETHEREUM: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+const USDC_ETHEREUM_NAME: &str = "usdc-eth";
+const USDC_TICKER: &str = "USDC";
+const USDC_DECIMALS: u8 = 6;
+
+/// Selector of `transfer(address,uint256)`.
+const TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+const TRANSFER_DATA_LENGTH: usize = 4 + 32 + 32;
+
+pub struct Erc20 {
+    signer: Option<Secp256k1Signer>,
+    token: Address,
+    name: String,
+    ticker: String,
+    decimals: u8,
+    rpc: EvmRpc,
+}
+
+#[derive(Default)]
+pub struct Erc20Builder {
+    rpc_url: Option<Url>,
+    chain_id: Option<u64>,
+    token: Option<(String, String, u8)>,
+    name: Option<String>,
+    wallet: Option<String>,
+}
+
+impl Erc20Builder {
+    pub fn new() -> Erc20Builder {
+        Default::default()
+    }
+
+    /// USDC on Ethereum mainnet, leaving the RPC endpoint to be set.
+    pub fn usdc() -> Erc20Builder {
+        Erc20Builder::new()
+            .chain_id(evm::ETHEREUM.chain_id)
+            .token(USDC_ETHEREUM, USDC_TICKER, USDC_DECIMALS)
+            .name(USDC_ETHEREUM_NAME)
+    }
+
+    /// JSON-RPC endpoint of the chain the token is on.
+    pub fn rpc_url(mut self, rpc_url: Url) -> Erc20Builder {
+        self.rpc_url = Some(rpc_url);
+        self
+    }
+
+    pub fn chain_id(mut self, chain_id: u64) -> Erc20Builder {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Token at the `contract` address, in hex.
+    pub fn token(mut self, contract: &str, ticker: &str, decimals: u8) -> Erc20Builder {
+        self.token = Some((contract.to_owned(), ticker.to_owned(), decimals));
+        self
+    }
+
+    /// Name of the token in the node's paths and addresses, such as `usdc-eth`, defaults to the
+    /// ticker in lowercase.
+    pub fn name(mut self, name: &str) -> Erc20Builder {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    /// Wallet's key, in base58 as for [`EthereumBuilder::wallet`](super::ethereum::EthereumBuilder::wallet),
+    /// or as a hex private key.
+    pub fn wallet(mut self, wallet: &str) -> Erc20Builder {
+        self.wallet = Some(wallet.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Erc20, BuilderError> {
+        let url = self.rpc_url.ok_or(BuilderError::MissingField(
+            "rpc_url is required for ERC-20 tokens".to_owned(),
+        ))?;
+        let chain_id = self.chain_id.ok_or(BuilderError::MissingField(
+            "chain_id is required for ERC-20 tokens".to_owned(),
+        ))?;
+        let (contract, ticker, decimals) = self.token.ok_or(BuilderError::MissingField(
+            "token is required for ERC-20 tokens".to_owned(),
+        ))?;
+        let token = parse_address(&contract)?;
+        let signer = match self.wallet {
+            Some(wallet) => Some(parse_wallet(&wallet)?),
+            None => None,
+        };
+        Ok(Erc20 {
+            signer,
+            token,
+            name: self.name.unwrap_or_else(|| ticker.to_lowercase()),
+            ticker,
+            decimals,
+            rpc: EvmRpc::new(url, chain_id),
+        })
+    }
+}
+
+/// Call data of `transfer(to, amount)`.
+pub fn transfer_data(to: Address, amount: U256) -> Vec<u8> {
+    let mut data = Vec::with_capacity(TRANSFER_DATA_LENGTH);
+    data.extend_from_slice(&TRANSFER_SELECTOR);
+    data.extend_from_slice(&[0; 12]);
+    data.extend_from_slice(to.as_bytes());
+    let mut amount_bytes = [0; 32];
+    amount.to_big_endian(&mut amount_bytes);
+    data.extend_from_slice(&amount_bytes);
+    data
+}
+
+/// Recipient and amount of a `transfer` call, if `data` is one.
+fn decode_transfer(data: &[u8]) -> Option<(Address, U256)> {
+    match data.len() == TRANSFER_DATA_LENGTH && data[..4] == TRANSFER_SELECTOR {
+        true => Some((
+            Address::from_slice(&data[16..36]),
+            U256::from_big_endian(&data[36..]),
+        )),
+        false => None,
+    }
+}
+
+impl Erc20 {
+    pub fn ticker(&self) -> &str {
+        &self.ticker
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.rpc.chain_id()
+    }
+
+    /// Address of the token's contract.
+    pub fn token(&self) -> Address {
+        self.token
+    }
+
+    /// `amount` of tokens in base units, see [`parse_units`].
+    pub fn parse_amount(&self, amount: &str) -> Result<u64, BundlrError> {
+        parse_units(amount, self.decimals)
+    }
+
+    /// `amount` of base units in tokens, see [`format_units`].
+    pub fn format_amount(&self, amount: u64) -> String {
+        format_units(amount, self.decimals)
+    }
+
+    fn signer(&self) -> Result<&Secp256k1Signer, BundlrError> {
+        self.signer.as_ref().ok_or(BundlrError::CurrencyError(
+            "No private key present".to_string(),
+        ))
+    }
+
+    /// Call of the token's contract transferring `amount` to `to`.
+    fn transfer(&self, amount: u64, to: Address) -> Result<CallRequest, BundlrError> {
+        Ok(evm::call(
+            self.signer()?.address(),
+            self.token,
+            U256::zero(),
+            transfer_data(to, amount.into()),
+        ))
+    }
+}
+
+#[async_trait::async_trait]
+impl Currency for Erc20 {
+    fn get_min_unit_name(&self) -> String {
+        format!("{} base unit", self.ticker)
+    }
+
+    fn get_type(&self) -> CurrencyType {
+        CurrencyType::Erc20
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn needs_fee(&self) -> bool {
+        true
+    }
+
+    async fn get_tx(&self, tx_id: String) -> Result<Tx, BundlrError> {
+        let tx = self.rpc.transaction(&tx_id).await?;
+        let (to, amount) = match tx.to == Some(self.token) {
+            true => decode_transfer(&tx.input.0),
+            false => None,
+        }
+        .ok_or(BundlrError::CurrencyError(format!(
+            "{} is not a transfer of {}",
+            tx_id, self.ticker
+        )))?;
+        evm::to_tx(tx_id, &tx, to, amount)
+    }
+
+    async fn get_tx_status(
+        &self,
+        tx_id: String,
+    ) -> Result<(StatusCode, Option<TxStatus>), BundlrError> {
+        self.rpc.tx_status(&tx_id).await
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>, BundlrError> {
+        Ok(self
+            .signer()?
+            .sign(Bytes::copy_from_slice(message))?
+            .to_vec())
+    }
+
+    fn verify(&self, pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), BundlrError> {
+        Secp256k1Signer::verify(
+            Bytes::copy_from_slice(pub_key),
+            Bytes::copy_from_slice(message),
+            Bytes::copy_from_slice(signature),
+        )
+    }
+
+    fn get_pub_key(&self) -> Result<Bytes, BundlrError> {
+        Ok(self.signer()?.pub_key())
+    }
+
+    fn wallet_address(&self) -> Result<String, BundlrError> {
+        Ok(format!("{:?}", self.signer()?.address()))
+    }
+
+    fn get_signer(&self) -> Result<&dyn Signer, BundlrError> {
+        Ok(self.signer()?)
+    }
+
+    async fn get_id(&self, _item: ()) -> Result<String, BundlrError> {
+        Err(BundlrError::Unsupported(format!(
+            "No currency id for {}",
+            self.name()
+        )))
+    }
+
+    async fn price(&self) -> Result<String, BundlrError> {
+        Err(BundlrError::Unsupported(format!(
+            "No USD price source for {}",
+            self.name()
+        )))
+    }
+
+    /// Height of the chain's latest block.
+    async fn get_current_height(&self) -> Result<u128, BundlrError> {
+        Ok(self.rpc.block_number().await? as u128)
+    }
+
+    /// Fee of transferring `amount` to `to` at the current gas price, in wei.
+    async fn get_fee(&self, amount: u64, to: &str, multiplier: f64) -> Result<u64, BundlrError> {
+        let call = self.transfer(amount, parse_address(to)?)?;
+        self.rpc.fee(call, multiplier).await
+    }
+
+    async fn create_tx(&self, amount: u64, to: &str, fee: u64) -> Result<Tx, BundlrError> {
+        evm::unsent_tx(self, amount, to, fee)
+    }
+
+    /// Sends the transfer, paying its fee at the gas price it was estimated with.
+    async fn send_tx(&self, data: Tx) -> Result<TxResponse, BundlrError> {
+        let call = self.transfer(data.amount, parse_address(&data.to)?)?;
+        self.rpc.send(self.signer()?, call, data.fee).await
+    }
+
+    fn rpc_url(&self) -> Option<Url> {
+        Some(self.rpc.url().clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use num::BigUint;
+    use reqwest::Url;
+    use web3::types::{Address, U256};
+
+    use super::{decode_transfer, transfer_data, Erc20Builder};
+    use crate::{
+        currency::{
+            evm::tests::{mock_rpc, NODE_ADDRESS, TX_HASH, WALLET},
+            Currency,
+        },
+        BundlrBuilder,
+    };
+
+    #[test]
+    fn should_encode_transfers() {
+        let to = NODE_ADDRESS.parse::<Address>().unwrap();
+        let data = transfer_data(to, U256::from(1_500_000));
+        assert_eq!(
+            data_encoding::HEXLOWER.encode(&data),
+            "a9059cbb\
+             00000000000000000000000000000000000000000000000000000000000000aa\
+             000000000000000000000000000000000000000000000000000000000016e360"
+        );
+        assert_eq!(decode_transfer(&data), Some((to, U256::from(1_500_000))));
+        assert_eq!(decode_transfer(&data[..36]), None);
+    }
+
+    #[test]
+    fn should_require_rpc_chain_and_token() {
+        let url = Url::parse("http://localhost:8545/").unwrap();
+        assert!(Erc20Builder::new().rpc_url(url.clone()).build().is_err());
+        assert!(Erc20Builder::usdc().build().is_err());
+        let usdc = Erc20Builder::usdc()
+            .rpc_url(url)
+            .wallet(WALLET)
+            .build()
+            .unwrap();
+        assert_eq!(
+            (usdc.ticker(), usdc.decimals(), usdc.chain_id()),
+            ("USDC", 6, 1)
+        );
+        assert_eq!(usdc.parse_amount("2.5").unwrap(), 2_500_000);
+        assert!(usdc.wallet_address().unwrap().starts_with("0x"));
+    }
+
+    #[tokio::test]
+    async fn should_use_the_token_name_on_the_node() {
+        let url = Url::parse("http://localhost:8545/").unwrap();
+        let dai = Erc20Builder::new()
+            .rpc_url(url.clone())
+            .chain_id(1)
+            .token("0x6B175474E89094C44Da98b954EedeAC495271d0F", "DAI", 18)
+            .build()
+            .unwrap();
+        assert_eq!(dai.name(), "dai");
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(format!(
+                    r#"{{ "version": "0.2.0", "gateway": "arweave.net", "addresses": {{ "usdc-eth": "{}" }} }}"#,
+                    NODE_ADDRESS
+                ));
+        });
+        let balance = server.mock(|when, then| {
+            when.method(GET)
+                .path("/account/balance/usdc-eth")
+                .query_param("address", NODE_ADDRESS);
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("{ \"balance\": \"5000000\" }");
+        });
+        let price = server.mock(|when, then| {
+            when.method(GET).path("/price/usdc-eth/1024");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("1200");
+        });
+
+        let usdc = Erc20Builder::usdc()
+            .rpc_url(url)
+            .wallet(WALLET)
+            .build()
+            .unwrap();
+        assert_eq!(usdc.name(), "usdc-eth");
+        let bundlr = BundlrBuilder::new()
+            .url(Url::parse(&server.url("/")).unwrap())
+            .currency(usdc)
+            .fetch_pub_info()
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(
+            bundlr.get_balance(NODE_ADDRESS).await.unwrap(),
+            BigUint::from(5_000_000u32)
+        );
+        assert_eq!(
+            bundlr.get_price(1024).await.unwrap(),
+            BigUint::from(1200u32)
+        );
+        balance.assert();
+        price.assert();
+    }
+
+    #[tokio::test]
+    async fn should_fund_with_signed_transfers() {
+        let server = MockServer::start();
+        let estimate = mock_rpc(&server, "eth_estimateGas", r#""0xfde8""#);
+        let gas_price = mock_rpc(&server, "eth_gasPrice", r#""0x3b9aca00""#);
+        let nonce = mock_rpc(&server, "eth_getTransactionCount", r#""0x5""#);
+        let sent = server.mock(|when, then| {
+            when.method(POST)
+                .path("/rpc")
+                .body_contains("eth_sendRawTransaction")
+                .body_contains(
+                    "a9059cbb00000000000000000000000000000000000000000000000000000000000000aa",
+                );
+            then.status(200).body(format!(
+                r#"{{"jsonrpc":"2.0","id":0,"result":"{}"}}"#,
+                TX_HASH
+            ));
+        });
+
+        let usdc = Erc20Builder::usdc()
+            .rpc_url(Url::parse(&server.url("/rpc")).unwrap())
+            .wallet(WALLET)
+            .build()
+            .unwrap();
+        let fee = usdc.get_fee(1_500_000, NODE_ADDRESS, 1.5).await.unwrap();
+        assert_eq!(fee, 65_000 * 1_000_000_000 * 3 / 2);
+        let tx = usdc.create_tx(1_500_000, NODE_ADDRESS, fee).await.unwrap();
+        let res = usdc.send_tx(tx).await.unwrap();
+
+        assert_eq!(res.tx_id, TX_HASH);
+        estimate.assert_hits(2);
+        gas_price.assert();
+        nonce.assert();
+        sent.assert();
+    }
+}
@@ -4,7 +4,7 @@ use reqwest::{StatusCode, Url};
 use crate::{
     error::{BuilderError, BundlrError},
     transaction::{Tx, TxStatus},
-    Ed25519Signer, Secp256k1Signer, Signer, Verifier,
+    Secp256k1Signer, Signer, Verifier,
 };
 
 use super::{Currency, CurrencyType, TxResponse};
@@ -116,7 +116,7 @@ impl Currency for Ethereum {
     }
 
     fn verify(&self, pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), BundlrError> {
-        Ed25519Signer::verify(
+        Secp256k1Signer::verify(
             Bytes::copy_from_slice(pub_key),
             Bytes::copy_from_slice(message),
             Bytes::copy_from_slice(signature),
@@ -146,15 +146,15 @@ impl Currency for Ethereum {
         }
     }
 
-    async fn get_id(&self, _item: ()) -> String {
+    async fn get_id(&self, _item: ()) -> Result<String, BundlrError> {
         todo!();
     }
 
-    async fn price(&self) -> String {
+    async fn price(&self) -> Result<String, BundlrError> {
         todo!();
     }
 
-    async fn get_current_height(&self) -> u128 {
+    async fn get_current_height(&self) -> Result<u128, BundlrError> {
         todo!();
     }
 
@@ -169,4 +169,8 @@ impl Currency for Ethereum {
     async fn send_tx(&self, data: Tx) -> Result<TxResponse, BundlrError> {
         todo!()
     }
+
+    fn rpc_url(&self) -> Option<Url> {
+        Some(self.url.clone())
+    }
 }
@@ -0,0 +1,656 @@
+//! Native currencies of EVM chains, such as MATIC on Polygon or BNB on BSC, configured by chain
+//! id, RPC endpoint, ticker and decimals instead of one module per chain.
+//!
+//! Funding sends the amount to the node's address for the chain's [`Chain::name`], signed with
+//! the wallet's secp256k1 key and sent through the chain's JSON-RPC endpoint. Amounts are held in
+//! `u64`s as for every currency: 18 decimals currencies can't be funded with more than about 18.4
+//! at once.
+
+use std::str::FromStr;
+
+use bytes::Bytes;
+use reqwest::{StatusCode, Url};
+use web3::{
+    transports::Http,
+    types::{
+        Address, BlockNumber, Bytes as CallData, CallRequest, Transaction, TransactionId,
+        TransactionParameters, H256, U256, U64,
+    },
+    Web3,
+};
+
+use crate::{
+    error::{BuilderError, BundlrError},
+    transaction::{Tx, TxStatus},
+    Secp256k1Signer, Signer, Verifier,
+};
+
+use super::{Currency, CurrencyType, TxResponse};
+
+pub use super::units::{format_units, parse_units};
+
+const EVM_BASE_UNIT: &str = "wei";
+const EVM_DECIMALS: u8 = 18;
+
+/// An EVM chain, as named by Bundlr nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chain {
+    pub chain_id: u64,
+    /// Name of the chain's currency in the node's paths and addresses.
+    pub name: &'static str,
+    pub ticker: &'static str,
+    pub decimals: u8,
+}
+
+pub const ETHEREUM: Chain = Chain {
+    chain_id: 1,
+    name: "ethereum",
+    ticker: "ETH",
+    decimals: EVM_DECIMALS,
+};
+pub const POLYGON: Chain = Chain {
+    chain_id: 137,
+    name: "matic",
+    ticker: "MATIC",
+    decimals: EVM_DECIMALS,
+};
+pub const BSC: Chain = Chain {
+    chain_id: 56,
+    name: "bnb",
+    ticker: "BNB",
+    decimals: EVM_DECIMALS,
+};
+pub const AVALANCHE: Chain = Chain {
+    chain_id: 43114,
+    name: "avalanche",
+    ticker: "AVAX",
+    decimals: EVM_DECIMALS,
+};
+pub const BASE: Chain = Chain {
+    chain_id: 8453,
+    name: "base-eth",
+    ticker: "ETH",
+    decimals: EVM_DECIMALS,
+};
+
+pub struct EvmCurrency {
+    signer: Option<Secp256k1Signer>,
+    name: String,
+    ticker: String,
+    decimals: u8,
+    rpc: EvmRpc,
+}
+
+#[derive(Default)]
+pub struct EvmCurrencyBuilder {
+    rpc_url: Option<Url>,
+    chain_id: Option<u64>,
+    name: Option<String>,
+    ticker: Option<String>,
+    decimals: Option<u8>,
+    wallet: Option<String>,
+}
+
+impl EvmCurrencyBuilder {
+    pub fn new() -> EvmCurrencyBuilder {
+        Default::default()
+    }
+
+    /// Chain id, name, ticker and decimals of a known `chain`, leaving the RPC endpoint to be set.
+    pub fn chain(self, chain: Chain) -> EvmCurrencyBuilder {
+        self.chain_id(chain.chain_id)
+            .name(chain.name)
+            .ticker(chain.ticker)
+            .decimals(chain.decimals)
+    }
+
+    /// JSON-RPC endpoint of the chain.
+    pub fn rpc_url(mut self, rpc_url: Url) -> EvmCurrencyBuilder {
+        self.rpc_url = Some(rpc_url);
+        self
+    }
+
+    pub fn chain_id(mut self, chain_id: u64) -> EvmCurrencyBuilder {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Name of the currency in the node's paths and addresses, defaults to the ticker in
+    /// lowercase.
+    pub fn name(mut self, name: &str) -> EvmCurrencyBuilder {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    pub fn ticker(mut self, ticker: &str) -> EvmCurrencyBuilder {
+        self.ticker = Some(ticker.to_owned());
+        self
+    }
+
+    /// Decimals of the currency, defaults to 18.
+    pub fn decimals(mut self, decimals: u8) -> EvmCurrencyBuilder {
+        self.decimals = Some(decimals);
+        self
+    }
+
+    /// Wallet's key, in base58 as for [`EthereumBuilder::wallet`](super::ethereum::EthereumBuilder::wallet),
+    /// or as a hex private key.
+    pub fn wallet(mut self, wallet: &str) -> EvmCurrencyBuilder {
+        self.wallet = Some(wallet.into());
+        self
+    }
+
+    pub fn build(self) -> Result<EvmCurrency, BuilderError> {
+        let url = self.rpc_url.ok_or(BuilderError::MissingField(
+            "rpc_url is required for EVM currencies".to_owned(),
+        ))?;
+        let chain_id = self.chain_id.ok_or(BuilderError::MissingField(
+            "chain_id is required for EVM currencies".to_owned(),
+        ))?;
+        let ticker = self.ticker.ok_or(BuilderError::MissingField(
+            "ticker is required for EVM currencies".to_owned(),
+        ))?;
+        let signer = match self.wallet {
+            Some(wallet) => Some(parse_wallet(&wallet)?),
+            None => None,
+        };
+        Ok(EvmCurrency {
+            signer,
+            name: self.name.unwrap_or_else(|| ticker.to_lowercase()),
+            ticker,
+            decimals: self.decimals.unwrap_or(EVM_DECIMALS),
+            rpc: EvmRpc::new(url, chain_id),
+        })
+    }
+}
+
+/// Signer of a wallet's key, in base58 or as a hex private key.
+pub(crate) fn parse_wallet(wallet: &str) -> Result<Secp256k1Signer, BundlrError> {
+    let hex = wallet.strip_prefix("0x").unwrap_or(wallet);
+    match hex.len() {
+        64 => {
+            let key = data_encoding::HEXLOWER_PERMISSIVE
+                .decode(hex.as_bytes())
+                .map_err(|err| BundlrError::ParseError(err.to_string()))?;
+            let sec_key = secp256k1::SecretKey::from_slice(&key)
+                .map_err(|err| BundlrError::ParseError(err.to_string()))?;
+            Ok(Secp256k1Signer::new(sec_key))
+        }
+        _ => Secp256k1Signer::from_base58(wallet),
+    }
+}
+
+pub(crate) fn parse_address(address: &str) -> Result<Address, BundlrError> {
+    Address::from_str(address)
+        .map_err(|err| BundlrError::ParseError(format!("Invalid address {}: {}", address, err)))
+}
+
+pub(crate) fn to_u64(amount: U256) -> Result<u64, BundlrError> {
+    match amount > U256::from(u64::MAX) {
+        true => Err(BundlrError::TypeParseError(format!(
+            "{} does not fit in a u64",
+            amount
+        ))),
+        false => Ok(amount.as_u64()),
+    }
+}
+
+fn rpc_error(err: web3::Error) -> BundlrError {
+    BundlrError::CurrencyError(format!("RPC request failed: {}", err))
+}
+
+fn parse_hash(tx_id: &str) -> Result<H256, BundlrError> {
+    H256::from_str(tx_id)
+        .map_err(|err| BundlrError::ParseError(format!("Invalid tx id {}: {}", tx_id, err)))
+}
+
+/// Call from `from` to `to`, sending `value` along with `data`.
+pub(crate) fn call(from: Address, to: Address, value: U256, data: Vec<u8>) -> CallRequest {
+    CallRequest {
+        from: Some(from),
+        to: Some(to),
+        value: Some(value),
+        data: Some(CallData(data)),
+        ..Default::default()
+    }
+}
+
+/// JSON-RPC endpoint of an EVM chain, sending transactions signed locally.
+pub(crate) struct EvmRpc {
+    web3: Web3<Http>,
+    chain_id: u64,
+    url: Url,
+}
+
+impl EvmRpc {
+    pub(crate) fn new(url: Url, chain_id: u64) -> Self {
+        Self {
+            web3: Web3::new(Http::with_client(reqwest::Client::new(), url.clone())),
+            chain_id,
+            url,
+        }
+    }
+
+    pub(crate) fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub(crate) fn url(&self) -> &Url {
+        &self.url
+    }
+
+    async fn estimate_gas(&self, call: CallRequest) -> Result<U256, BundlrError> {
+        self.web3
+            .eth()
+            .estimate_gas(call, None)
+            .await
+            .map_err(rpc_error)
+    }
+
+    /// Fee of `call` at the current gas price, in wei.
+    pub(crate) async fn fee(&self, call: CallRequest, multiplier: f64) -> Result<u64, BundlrError> {
+        let gas = self.estimate_gas(call).await?;
+        let gas_price = self.web3.eth().gas_price().await.map_err(rpc_error)?;
+        let fee = to_u64(gas.saturating_mul(gas_price))?;
+        Ok((fee as f64 * multiplier).ceil() as u64)
+    }
+
+    /// Sends `call` signed by `signer`, paying `fee` wei at the gas price it was estimated with.
+    pub(crate) async fn send(
+        &self,
+        signer: &Secp256k1Signer,
+        call: CallRequest,
+        fee: u64,
+    ) -> Result<TxResponse, BundlrError> {
+        let gas = self.estimate_gas(call.clone()).await?;
+        if gas.is_zero() {
+            return Err(BundlrError::CurrencyError(
+                "Transaction needs no gas".to_string(),
+            ));
+        }
+        let nonce = self
+            .web3
+            .eth()
+            .transaction_count(signer.address(), Some(BlockNumber::Pending))
+            .await
+            .map_err(rpc_error)?;
+        let params = TransactionParameters {
+            nonce: Some(nonce),
+            to: call.to,
+            gas,
+            gas_price: Some(U256::from(fee) / gas),
+            value: call.value.unwrap_or_default(),
+            data: call.data.unwrap_or_default(),
+            chain_id: Some(self.chain_id),
+            ..Default::default()
+        };
+        let signed = self
+            .web3
+            .accounts()
+            .sign_transaction(params, signer)
+            .await
+            .map_err(rpc_error)?;
+        let hash = self
+            .web3
+            .eth()
+            .send_raw_transaction(signed.raw_transaction)
+            .await
+            .map_err(rpc_error)?;
+        Ok(TxResponse {
+            tx_id: format!("{:?}", hash),
+        })
+    }
+
+    /// Number of the chain's latest block.
+    pub(crate) async fn block_number(&self) -> Result<u64, BundlrError> {
+        let number = self.web3.eth().block_number().await.map_err(rpc_error)?;
+        Ok(number.as_u64())
+    }
+
+    pub(crate) async fn transaction(&self, tx_id: &str) -> Result<Transaction, BundlrError> {
+        self.web3
+            .eth()
+            .transaction(TransactionId::Hash(parse_hash(tx_id)?))
+            .await
+            .map_err(rpc_error)?
+            .ok_or(BundlrError::TxNotFound)
+    }
+
+    /// Status of a transaction, pending until mined and failing if reverted.
+    pub(crate) async fn tx_status(
+        &self,
+        tx_id: &str,
+    ) -> Result<(StatusCode, Option<TxStatus>), BundlrError> {
+        let receipt = self
+            .web3
+            .eth()
+            .transaction_receipt(parse_hash(tx_id)?)
+            .await
+            .map_err(rpc_error)?;
+        let (receipt, height) = match receipt {
+            Some(receipt) => match receipt.block_number {
+                Some(height) => (receipt, height),
+                None => return Ok((StatusCode::ACCEPTED, None)),
+            },
+            None => return Ok((StatusCode::ACCEPTED, None)),
+        };
+        if receipt.status == Some(U64::zero()) {
+            return Err(BundlrError::CurrencyError(format!(
+                "Transaction {} reverted",
+                tx_id
+            )));
+        }
+        let current = self.block_number().await?;
+        Ok((
+            StatusCode::OK,
+            Some(TxStatus {
+                confirmations: current.saturating_sub(height.as_u64()) + 1,
+                height: height.as_u64() as u128,
+                block_hash: receipt
+                    .block_hash
+                    .map(|hash| format!("{:?}", hash))
+                    .unwrap_or_default(),
+            }),
+        ))
+    }
+}
+
+/// `tx` as sending `amount` to `to`.
+pub(crate) fn to_tx(
+    tx_id: String,
+    tx: &Transaction,
+    to: Address,
+    amount: U256,
+) -> Result<Tx, BundlrError> {
+    let fee = tx.gas.saturating_mul(tx.gas_price.unwrap_or_default());
+    let pending = tx.block_number.is_none();
+    Ok(Tx {
+        id: tx_id,
+        from: tx
+            .from
+            .map(|from| format!("{:?}", from))
+            .unwrap_or_default(),
+        to: format!("{:?}", to),
+        amount: to_u64(amount)?,
+        fee: to_u64(fee)?,
+        block_height: tx.block_number.map(|block| block.as_u64()).unwrap_or(0) as u128,
+        pending,
+        confirmed: !pending,
+    })
+}
+
+/// Unsigned transaction from the wallet of `currency`, checked to be sent to an EVM address.
+pub(crate) fn unsent_tx(
+    currency: &dyn Currency,
+    amount: u64,
+    to: &str,
+    fee: u64,
+) -> Result<Tx, BundlrError> {
+    parse_address(to)?;
+    Ok(Tx {
+        id: String::new(),
+        from: currency.wallet_address()?,
+        to: to.to_owned(),
+        amount,
+        fee,
+        block_height: 0,
+        pending: true,
+        confirmed: false,
+    })
+}
+
+impl EvmCurrency {
+    pub fn ticker(&self) -> &str {
+        &self.ticker
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.rpc.chain_id()
+    }
+
+    /// `amount` of the currency in base units, see [`parse_units`].
+    pub fn parse_amount(&self, amount: &str) -> Result<u64, BundlrError> {
+        parse_units(amount, self.decimals)
+    }
+
+    /// `amount` of base units in the currency, see [`format_units`].
+    pub fn format_amount(&self, amount: u64) -> String {
+        format_units(amount, self.decimals)
+    }
+
+    fn signer(&self) -> Result<&Secp256k1Signer, BundlrError> {
+        self.signer.as_ref().ok_or(BundlrError::CurrencyError(
+            "No private key present".to_string(),
+        ))
+    }
+
+    fn transfer(&self, amount: u64, to: Address) -> Result<CallRequest, BundlrError> {
+        Ok(call(
+            self.signer()?.address(),
+            to,
+            amount.into(),
+            Vec::new(),
+        ))
+    }
+}
+
+#[async_trait::async_trait]
+impl Currency for EvmCurrency {
+    fn get_min_unit_name(&self) -> String {
+        EVM_BASE_UNIT.to_string()
+    }
+
+    /// Items are signed as for Ethereum, whichever the chain.
+    fn get_type(&self) -> CurrencyType {
+        CurrencyType::Ethereum
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn needs_fee(&self) -> bool {
+        true
+    }
+
+    async fn get_tx(&self, tx_id: String) -> Result<Tx, BundlrError> {
+        let tx = self.rpc.transaction(&tx_id).await?;
+        let to = tx.to.ok_or(BundlrError::CurrencyError(format!(
+            "{} creates a contract",
+            tx_id
+        )))?;
+        let value = tx.value;
+        to_tx(tx_id, &tx, to, value)
+    }
+
+    async fn get_tx_status(
+        &self,
+        tx_id: String,
+    ) -> Result<(StatusCode, Option<TxStatus>), BundlrError> {
+        self.rpc.tx_status(&tx_id).await
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>, BundlrError> {
+        Ok(self
+            .signer()?
+            .sign(Bytes::copy_from_slice(message))?
+            .to_vec())
+    }
+
+    fn verify(&self, pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), BundlrError> {
+        Secp256k1Signer::verify(
+            Bytes::copy_from_slice(pub_key),
+            Bytes::copy_from_slice(message),
+            Bytes::copy_from_slice(signature),
+        )
+    }
+
+    fn get_pub_key(&self) -> Result<Bytes, BundlrError> {
+        Ok(self.signer()?.pub_key())
+    }
+
+    fn wallet_address(&self) -> Result<String, BundlrError> {
+        Ok(format!("{:?}", self.signer()?.address()))
+    }
+
+    fn get_signer(&self) -> Result<&dyn Signer, BundlrError> {
+        Ok(self.signer()?)
+    }
+
+    async fn get_id(&self, _item: ()) -> Result<String, BundlrError> {
+        Err(BundlrError::Unsupported(format!(
+            "No currency id for {}",
+            self.name()
+        )))
+    }
+
+    async fn price(&self) -> Result<String, BundlrError> {
+        Err(BundlrError::Unsupported(format!(
+            "No USD price source for {}",
+            self.name()
+        )))
+    }
+
+    /// Height of the chain's latest block.
+    async fn get_current_height(&self) -> Result<u128, BundlrError> {
+        Ok(self.rpc.block_number().await? as u128)
+    }
+
+    /// Fee of sending `amount` to `to` at the current gas price, in wei.
+    async fn get_fee(&self, amount: u64, to: &str, multiplier: f64) -> Result<u64, BundlrError> {
+        let call = self.transfer(amount, parse_address(to)?)?;
+        self.rpc.fee(call, multiplier).await
+    }
+
+    async fn create_tx(&self, amount: u64, to: &str, fee: u64) -> Result<Tx, BundlrError> {
+        unsent_tx(self, amount, to, fee)
+    }
+
+    async fn send_tx(&self, data: Tx) -> Result<TxResponse, BundlrError> {
+        let call = self.transfer(data.amount, parse_address(&data.to)?)?;
+        self.rpc.send(self.signer()?, call, data.fee).await
+    }
+
+    fn rpc_url(&self) -> Option<Url> {
+        Some(self.rpc.url().clone())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use httpmock::{
+        Method::{GET, POST},
+        Mock, MockServer,
+    };
+    use reqwest::Url;
+
+    use super::{EvmCurrencyBuilder, POLYGON};
+    use crate::{currency::Currency, BundlrBuilder};
+
+    pub(crate) const WALLET: &str =
+        "0x3030303030303030303030303030303030303030303030303030303030303030";
+    pub(crate) const NODE_ADDRESS: &str = "0x00000000000000000000000000000000000000aa";
+    pub(crate) const TX_HASH: &str =
+        "0x00000000000000000000000000000000000000000000000000000000000000ff";
+
+    /// Answers calls of `method` on the RPC endpoint at `/rpc` with `result`.
+    pub(crate) fn mock_rpc<'a>(server: &'a MockServer, method: &str, result: &str) -> Mock<'a> {
+        server.mock(|when, then| {
+            when.method(POST).path("/rpc").body_contains(method);
+            then.status(200)
+                .body(format!(r#"{{"jsonrpc":"2.0","id":0,"result":{}}}"#, result));
+        })
+    }
+
+    #[test]
+    fn should_configure_chains() {
+        let url = Url::parse("http://localhost:8545/").unwrap();
+        assert!(EvmCurrencyBuilder::new().chain_id(10).build().is_err());
+        let custom = EvmCurrencyBuilder::new()
+            .rpc_url(url.clone())
+            .chain_id(10)
+            .ticker("OP")
+            .build()
+            .unwrap();
+        assert_eq!((custom.name(), custom.decimals()), ("op".to_owned(), 18));
+        let polygon = EvmCurrencyBuilder::new()
+            .chain(POLYGON)
+            .rpc_url(url.clone())
+            .build()
+            .unwrap();
+        assert_eq!(
+            (polygon.name(), polygon.ticker(), polygon.chain_id()),
+            ("matic".to_owned(), "MATIC", 137)
+        );
+        assert_eq!(polygon.rpc_url(), Some(url));
+        assert_eq!(polygon.format_amount(1_500_000_000_000_000_000), "1.5");
+    }
+
+    #[tokio::test]
+    async fn should_read_the_chain_height() {
+        let server = MockServer::start();
+        let polygon = EvmCurrencyBuilder::new()
+            .chain(POLYGON)
+            .rpc_url(Url::parse(&server.url("/rpc")).unwrap())
+            .build()
+            .unwrap();
+        let block_number = mock_rpc(&server, "eth_blockNumber", r#""0x64""#);
+        assert_eq!(polygon.get_current_height().await.unwrap(), 100);
+        block_number.assert();
+    }
+
+    #[tokio::test]
+    async fn should_fund_from_the_configured_chain() {
+        let server = MockServer::start();
+        let estimate = mock_rpc(&server, "eth_estimateGas", r#""0x5208""#);
+        let gas_price = mock_rpc(&server, "eth_gasPrice", r#""0x3b9aca00""#);
+        let nonce = mock_rpc(&server, "eth_getTransactionCount", r#""0x5""#);
+        let sent = mock_rpc(
+            &server,
+            "eth_sendRawTransaction",
+            &format!(r#""{}""#, TX_HASH),
+        );
+        let info = server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(format!(
+                    r#"{{ "version": "0.2.0", "gateway": "arweave.net", "addresses": {{ "ethereum": "0x00000000000000000000000000000000000000bb", "matic": "{}" }} }}"#,
+                    NODE_ADDRESS
+                ));
+        });
+        let credited = server.mock(|when, then| {
+            when.method(POST)
+                .path("/account/balance/matic")
+                .body_contains(TX_HASH);
+            then.status(200).body("OK");
+        });
+
+        let polygon = EvmCurrencyBuilder::new()
+            .chain(POLYGON)
+            .rpc_url(Url::parse(&server.url("/rpc")).unwrap())
+            .wallet(WALLET)
+            .build()
+            .unwrap();
+        let bundlr = BundlrBuilder::new()
+            .url(Url::parse(&server.url("/")).unwrap())
+            .currency(polygon)
+            .fetch_pub_info()
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(bundlr.fund(1_000_000_000_000_000, None).await.unwrap());
+
+        info.assert();
+        estimate.assert_hits(2);
+        gas_price.assert();
+        nonce.assert();
+        sent.assert();
+        credited.assert();
+    }
+}
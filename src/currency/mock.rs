@@ -0,0 +1,187 @@
+//! Currency for tests, which never reaches any network.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use bytes::Bytes;
+use reqwest::{StatusCode, Url};
+
+use super::{Currency, CurrencyType, TxResponse};
+use crate::{
+    error::BundlrError,
+    transaction::{Tx, TxStatus},
+    Signer,
+};
+
+/// Currency recording its transfers instead of sending them.
+pub(crate) struct MockCurrency {
+    needs_fee: bool,
+    /// Fee to report, `None` for a fee that can't be parsed.
+    fee: Option<u64>,
+    /// Recipient and fee of the transfers sent.
+    sent: Mutex<Vec<(String, u64)>>,
+    /// Amounts of the transfers sent.
+    amounts: Mutex<Vec<u64>>,
+    /// Number of status requests. The first one fails, the next ones report one more
+    /// confirmation each.
+    polls: AtomicU64,
+    /// Number of status requests after which transfers are dropped, no longer found on chain.
+    dropped_after: Option<u64>,
+    rpc_url: Option<Url>,
+}
+
+impl MockCurrency {
+    pub(crate) fn new(needs_fee: bool, fee: Option<u64>) -> Arc<Self> {
+        Arc::new(Self {
+            needs_fee,
+            fee,
+            sent: Mutex::new(vec![]),
+            amounts: Mutex::new(vec![]),
+            polls: AtomicU64::new(0),
+            dropped_after: None,
+            rpc_url: None,
+        })
+    }
+
+    /// Currency claiming to send its transactions to `rpc_url`.
+    pub(crate) fn with_rpc_url(rpc_url: Url) -> Arc<Self> {
+        Arc::new(Self {
+            needs_fee: false,
+            fee: Some(0),
+            sent: Mutex::new(vec![]),
+            amounts: Mutex::new(vec![]),
+            polls: AtomicU64::new(0),
+            dropped_after: None,
+            rpc_url: Some(rpc_url),
+        })
+    }
+
+    /// Currency dropping its transfers after `polls` status requests.
+    pub(crate) fn dropping(polls: u64) -> Arc<Self> {
+        Arc::new(Self {
+            needs_fee: false,
+            fee: Some(0),
+            sent: Mutex::new(vec![]),
+            amounts: Mutex::new(vec![]),
+            polls: AtomicU64::new(0),
+            dropped_after: Some(polls),
+            rpc_url: None,
+        })
+    }
+
+    fn dropped(&self) -> bool {
+        self.dropped_after
+            .is_some_and(|polls| self.polls() >= polls)
+    }
+
+    pub(crate) fn polls(&self) -> u64 {
+        self.polls.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn sent(&self) -> Vec<(String, u64)> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    pub(crate) fn amounts(&self) -> Vec<u64> {
+        self.amounts.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl Currency for MockCurrency {
+    fn get_min_unit_name(&self) -> String {
+        "unit".to_owned()
+    }
+    fn get_type(&self) -> CurrencyType {
+        CurrencyType::Arweave
+    }
+    fn needs_fee(&self) -> bool {
+        self.needs_fee
+    }
+    async fn get_tx(&self, tx_id: String) -> Result<Tx, BundlrError> {
+        if self.dropped() {
+            return Err(BundlrError::TxNotFound);
+        }
+        Ok(Tx {
+            id: tx_id,
+            from: "wallet".to_owned(),
+            to: "node".to_owned(),
+            amount: 0,
+            fee: 0,
+            block_height: 0,
+            pending: true,
+            confirmed: false,
+        })
+    }
+    async fn get_tx_status(
+        &self,
+        _: String,
+    ) -> Result<(StatusCode, Option<TxStatus>), BundlrError> {
+        if self.dropped() {
+            self.polls.fetch_add(1, Ordering::SeqCst);
+            return Ok((StatusCode::ACCEPTED, None));
+        }
+        match self.polls.fetch_add(1, Ordering::SeqCst) {
+            0 => Err(BundlrError::ResponseError("Unavailable".to_owned())),
+            polls => Ok((
+                StatusCode::OK,
+                Some(TxStatus {
+                    confirmations: polls,
+                    height: 1,
+                    block_hash: "block".to_owned(),
+                }),
+            )),
+        }
+    }
+    fn get_pub_key(&self) -> Result<Bytes, BundlrError> {
+        unimplemented!()
+    }
+    fn wallet_address(&self) -> Result<String, BundlrError> {
+        Ok("wallet".to_owned())
+    }
+    fn sign_message(&self, _: &[u8]) -> Result<Vec<u8>, BundlrError> {
+        unimplemented!()
+    }
+    fn verify(&self, _: &[u8], _: &[u8], _: &[u8]) -> Result<(), BundlrError> {
+        unimplemented!()
+    }
+    fn get_signer(&self) -> Result<&dyn Signer, BundlrError> {
+        unimplemented!()
+    }
+    async fn get_id(&self, _: ()) -> Result<String, BundlrError> {
+        unimplemented!()
+    }
+    async fn price(&self) -> Result<String, BundlrError> {
+        unimplemented!()
+    }
+    async fn get_current_height(&self) -> Result<u128, BundlrError> {
+        unimplemented!()
+    }
+    async fn get_fee(&self, _: u64, _: &str, _: f64) -> Result<u64, BundlrError> {
+        self.fee
+            .ok_or_else(|| BundlrError::ParseError("Invalid fee".to_owned()))
+    }
+    async fn create_tx(&self, amount: u64, to: &str, fee: u64) -> Result<Tx, BundlrError> {
+        Ok(Tx {
+            id: "fund-tx".to_owned(),
+            from: "wallet".to_owned(),
+            to: to.to_owned(),
+            amount,
+            fee,
+            block_height: 0,
+            pending: true,
+            confirmed: false,
+        })
+    }
+    async fn send_tx(&self, tx: Tx) -> Result<TxResponse, BundlrError> {
+        self.sent.lock().unwrap().push((tx.to, tx.fee));
+        self.amounts.lock().unwrap().push(tx.amount);
+        Ok(TxResponse { tx_id: tx.id })
+    }
+
+    fn rpc_url(&self) -> Option<Url> {
+        self.rpc_url.clone()
+    }
+}
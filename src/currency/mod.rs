@@ -1,18 +1,35 @@
+#[cfg(feature = "aptos")]
+pub mod aptos;
 #[cfg(feature = "arweave")]
 pub mod arweave;
+#[cfg(feature = "cosmos")]
+pub mod cosmos;
+#[cfg(feature = "near")]
+pub mod near;
 #[cfg(feature = "solana")]
 pub mod solana;
+#[cfg(feature = "starknet")]
+pub mod starknet;
+#[cfg(feature = "weavevm")]
+pub mod weavevm;
 
+#[cfg(feature = "erc20")]
+pub mod erc20;
 #[cfg(feature = "ethereum")]
 pub mod ethereum;
+#[cfg(any(feature = "ethereum", feature = "erc20"))]
+pub mod evm;
+#[cfg(test)]
+pub(crate) mod mock;
+pub mod units;
 
 use core::fmt;
 
 use bytes::Bytes;
 use num_derive::FromPrimitive;
-use reqwest::StatusCode;
+use reqwest::{StatusCode, Url};
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 
 #[cfg(feature = "build-binary")]
 use clap::ValueEnum;
@@ -31,6 +48,9 @@ pub enum CurrencyType {
     Ethereum = 3,
     Erc20 = 4,
     Cosmos = 5,
+    Aptos = 6,
+    Near = 7,
+    Starknet = 8,
 }
 
 #[derive(Deserialize)]
@@ -53,6 +73,9 @@ impl FromStr for CurrencyType {
             "ethereum" => Ok(CurrencyType::Ethereum),
             "erc20" => Ok(CurrencyType::Erc20),
             "cosmos" => Ok(CurrencyType::Cosmos),
+            "aptos" => Ok(CurrencyType::Aptos),
+            "near" => Ok(CurrencyType::Near),
+            "starknet" => Ok(CurrencyType::Starknet),
             _ => Err(anyhow::Error::msg("Invalid or unsupported currency")),
         }
     }
@@ -66,6 +89,12 @@ pub trait Currency {
     /// Gets currency type
     fn get_type(&self) -> CurrencyType;
 
+    /// Name of the currency in the node's paths and addresses, such as "matic" for Polygon.
+    /// Defaults to the currency type.
+    fn name(&self) -> String {
+        self.get_type().to_string()
+    }
+
     /// Returns if the currency needs fee for transacting
     fn needs_fee(&self) -> bool;
 
@@ -94,13 +123,13 @@ pub trait Currency {
     fn get_signer(&self) -> Result<&dyn Signer, BundlrError>;
 
     /// Gets currency Id
-    async fn get_id(&self, item: ()) -> String;
+    async fn get_id(&self, item: ()) -> Result<String, BundlrError>;
 
     /// Get price of currency in USD
-    async fn price(&self) -> String;
+    async fn price(&self) -> Result<String, BundlrError>;
 
     /// Get given currency network's block height
-    async fn get_current_height(&self) -> u128;
+    async fn get_current_height(&self) -> Result<u128, BundlrError>;
 
     /// Get fee for transaction
     async fn get_fee(&self, amount: u64, to: &str, multiplier: f64) -> Result<u64, BundlrError>;
@@ -110,4 +139,113 @@ pub trait Currency {
 
     /// Send a signed transaction
     async fn send_tx(&self, data: Tx) -> Result<TxResponse, BundlrError>;
+
+    /// URL of the currency network's node it sends requests to, if any
+    fn rpc_url(&self) -> Option<Url> {
+        None
+    }
 }
+
+/// Implements [`Currency`] for a pointer to a currency, delegating to it.
+macro_rules! delegate_currency {
+    ($(#[$attr:meta])* impl<$($lifetime:lifetime,)? C> for $pointer:ty) => {
+        $(#[$attr])*
+        #[async_trait::async_trait]
+        impl<$($lifetime,)? C> Currency for $pointer
+        where
+            C: Currency + Send + Sync + ?Sized,
+        {
+            fn get_min_unit_name(&self) -> String {
+                (**self).get_min_unit_name()
+            }
+
+            fn get_type(&self) -> CurrencyType {
+                (**self).get_type()
+            }
+
+            fn name(&self) -> String {
+                (**self).name()
+            }
+
+            fn needs_fee(&self) -> bool {
+                (**self).needs_fee()
+            }
+
+            async fn get_tx(&self, tx_id: String) -> Result<Tx, BundlrError> {
+                (**self).get_tx(tx_id).await
+            }
+
+            async fn get_tx_status(
+                &self,
+                tx_id: String,
+            ) -> Result<(StatusCode, Option<TxStatus>), BundlrError> {
+                (**self).get_tx_status(tx_id).await
+            }
+
+            fn get_pub_key(&self) -> Result<Bytes, BundlrError> {
+                (**self).get_pub_key()
+            }
+
+            fn wallet_address(&self) -> Result<String, BundlrError> {
+                (**self).wallet_address()
+            }
+
+            fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>, BundlrError> {
+                (**self).sign_message(message)
+            }
+
+            fn verify(&self, pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), BundlrError> {
+                (**self).verify(pub_key, message, signature)
+            }
+
+            fn get_signer(&self) -> Result<&dyn Signer, BundlrError> {
+                (**self).get_signer()
+            }
+
+            async fn get_id(&self, item: ()) -> Result<String, BundlrError> {
+                (**self).get_id(item).await
+            }
+
+            async fn price(&self) -> Result<String, BundlrError> {
+                (**self).price().await
+            }
+
+            async fn get_current_height(&self) -> Result<u128, BundlrError> {
+                (**self).get_current_height().await
+            }
+
+            async fn get_fee(&self, amount: u64, to: &str, multiplier: f64) -> Result<u64, BundlrError> {
+                (**self).get_fee(amount, to, multiplier).await
+            }
+
+            async fn create_tx(&self, amount: u64, to: &str, fee: u64) -> Result<Tx, BundlrError> {
+                (**self).create_tx(amount, to, fee).await
+            }
+
+            async fn send_tx(&self, data: Tx) -> Result<TxResponse, BundlrError> {
+                (**self).send_tx(data).await
+            }
+
+            fn rpc_url(&self) -> Option<Url> {
+                (**self).rpc_url()
+            }
+        }
+    };
+}
+
+delegate_currency!(
+    /// Lets several clients share one currency, and so one wallet.
+    ///
+    /// Currencies hold no mutable chain state: anchors, nonces and fees are fetched from the
+    /// network for each transaction, so sharing one between clients, across threads, is safe.
+    ///
+    /// With an `Arc<dyn Currency + Send + Sync>`, the currency can be picked at runtime while
+    /// the client stays `'static`, `Send` and `Sync`, to keep in application state or move into
+    /// spawned tasks.
+    impl<C> for Arc<C>
+);
+
+delegate_currency!(
+    /// Lets a client borrow a currency, as [`compat::Bundlr`](crate::compat::Bundlr) does.
+    impl<'a, C> for &'a C
+);
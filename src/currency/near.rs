@@ -0,0 +1,892 @@
+//! NEAR, funding the node's account with `Transfer` transactions sent through a NEAR JSON-RPC
+//! endpoint, and signing items with the account's ed25519 key.
+//!
+//! Transactions are encoded in Borsh and signed locally, the endpoint only being asked for the
+//! access key's nonce and a recent block hash. Amounts are in yoctoNEAR, 10^-24 NEAR.
+//!
+//! [`Currency`] holds amounts in `u64`s as for every currency, no more than about 0.0000184 NEAR,
+//! and fails with [`BundlrError::AmountOverflow`] on those that don't fit rather than rounding
+//! them. A transfer's fee alone doesn't at usual gas prices: the `u128` methods of [`Near`] look
+//! it up with [`Near::transfer_fee`], send with [`Near::send_transfer`] a transfer to be credited
+//! with [`Bundlr::submit_fund_tx`](crate::Bundlr::submit_fund_tx), and read it back with
+//! [`Near::get_transfer`].
+
+use bytes::Bytes;
+use data_encoding::{BASE64, HEXLOWER};
+use reqwest::{StatusCode, Url};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Deserialize,
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{BuilderError, BundlrError},
+    transaction::{Tx, TxStatus},
+    utils::{read_json, RawNumber},
+    Ed25519Signer, Signer, Verifier,
+};
+
+use super::{units, Currency, CurrencyType, TxResponse};
+
+const NEAR_BASE_UNIT: &str = "yoctoNEAR";
+const NEAR_DECIMALS: u8 = 24;
+
+/// Gas burnt by a transfer, sending and executing both its receipt and its action.
+pub const TRANSFER_GAS: u64 = 446_365_125_000;
+const ED25519_KEY_TYPE: u8 = 0;
+const ED25519_KEY_PREFIX: &str = "ed25519:";
+const TRANSFER_ACTION: u8 = 3;
+const UNKNOWN_TRANSACTION: &str = "UNKNOWN_TRANSACTION";
+
+pub struct Near {
+    signer: Option<Ed25519Signer>,
+    account_id: Option<String>,
+    client: reqwest::Client,
+    url: Url,
+}
+
+/// A transfer of NEAR, its amounts in yoctoNEAR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NearTransfer {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    pub deposit: u128,
+    /// Tokens burnt by the transaction and its receipts, its fee.
+    pub tokens_burnt: u128,
+    pub block_height: u128,
+    pub pending: bool,
+    pub confirmed: bool,
+}
+
+#[derive(Default)]
+pub struct NearBuilder {
+    rpc_url: Option<Url>,
+    account_id: Option<String>,
+    wallet: Option<String>,
+}
+
+impl NearBuilder {
+    pub fn new() -> NearBuilder {
+        Default::default()
+    }
+
+    /// JSON-RPC endpoint of a NEAR node, such as `https://rpc.mainnet.near.org`.
+    pub fn rpc_url(mut self, rpc_url: Url) -> NearBuilder {
+        self.rpc_url = Some(rpc_url);
+        self
+    }
+
+    /// Account the wallet's key is an access key of, defaults to the key's implicit account.
+    pub fn account_id(mut self, account_id: &str) -> NearBuilder {
+        self.account_id = Some(account_id.into());
+        self
+    }
+
+    /// Wallet's keypair in base58, optionally after `ed25519:` as exported by the NEAR CLI.
+    pub fn wallet(mut self, wallet: &str) -> NearBuilder {
+        self.wallet = Some(wallet.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Near, BuilderError> {
+        let url = self.rpc_url.ok_or(BuilderError::MissingField(
+            "rpc_url is required for NEAR".to_owned(),
+        ))?;
+        let signer = match self.wallet {
+            Some(wallet) => Some(Ed25519Signer::from_base58(
+                wallet.strip_prefix(ED25519_KEY_PREFIX).unwrap_or(&wallet),
+            )?),
+            None => None,
+        };
+        let account_id = match (self.account_id, &signer) {
+            (Some(account_id), _) => Some(parse_account_id(&account_id)?.to_owned()),
+            (None, Some(signer)) => Some(implicit_account_of(&signer.pub_key())),
+            (None, None) => None,
+        };
+        Ok(Near {
+            signer,
+            account_id,
+            client: reqwest::Client::new(),
+            url,
+        })
+    }
+}
+
+/// Implicit account of an ed25519 `pub_key`, the key in hex.
+pub fn implicit_account_of(pub_key: &[u8]) -> String {
+    HEXLOWER.encode(pub_key)
+}
+
+/// Checks that `account_id` is a valid NEAR account id, such as `bundlr.near`.
+fn parse_account_id(account_id: &str) -> Result<&str, BundlrError> {
+    let is_separator = |c: u8| matches!(c, b'-' | b'_' | b'.');
+    let bytes = account_id.as_bytes();
+    let valid = (2..=64).contains(&bytes.len())
+        && bytes
+            .iter()
+            .all(|&c| c.is_ascii_lowercase() || c.is_ascii_digit() || is_separator(c))
+        && !is_separator(bytes[0])
+        && !is_separator(bytes[bytes.len() - 1])
+        && !bytes
+            .windows(2)
+            .any(|pair| is_separator(pair[0]) && is_separator(pair[1]));
+    match valid {
+        true => Ok(account_id),
+        false => Err(BundlrError::ParseError(format!(
+            "Invalid NEAR account id: {}",
+            account_id
+        ))),
+    }
+}
+
+fn borsh_string(buf: &mut Vec<u8>, string: &str) {
+    buf.extend_from_slice(&(string.len() as u32).to_le_bytes());
+    buf.extend_from_slice(string.as_bytes());
+}
+
+/// A transaction with a single `Transfer` action, as signed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawTransfer {
+    pub signer_id: String,
+    pub public_key: [u8; 32],
+    pub nonce: u64,
+    pub receiver_id: String,
+    pub block_hash: [u8; 32],
+    /// Amount transferred, in yoctoNEAR.
+    pub deposit: u128,
+}
+
+impl RawTransfer {
+    /// The transaction in Borsh.
+    pub fn to_borsh(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        borsh_string(&mut buf, &self.signer_id);
+        buf.push(ED25519_KEY_TYPE);
+        buf.extend_from_slice(&self.public_key);
+        buf.extend_from_slice(&self.nonce.to_le_bytes());
+        borsh_string(&mut buf, &self.receiver_id);
+        buf.extend_from_slice(&self.block_hash);
+        buf.extend_from_slice(&1u32.to_le_bytes());
+        buf.push(TRANSFER_ACTION);
+        buf.extend_from_slice(&self.deposit.to_le_bytes());
+        buf
+    }
+
+    /// What the signer signs, and the transaction's id once in base58.
+    pub fn hash(&self) -> [u8; 32] {
+        Sha256::digest(self.to_borsh()).into()
+    }
+
+    /// The transaction signed with `signature`, in Borsh.
+    pub fn signed(&self, signature: &[u8]) -> Vec<u8> {
+        let mut buf = self.to_borsh();
+        buf.push(ED25519_KEY_TYPE);
+        buf.extend_from_slice(signature);
+        buf
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcCause {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    message: String,
+    cause: Option<RpcCause>,
+    #[serde(default)]
+    data: Value,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct AccessKey {
+    nonce: u64,
+    block_hash: String,
+}
+
+#[derive(Deserialize)]
+struct GasPrice {
+    gas_price: RawNumber,
+}
+
+#[derive(Deserialize)]
+struct BlockHeader {
+    height: u64,
+    hash: String,
+}
+
+#[derive(Deserialize)]
+struct Block {
+    header: BlockHeader,
+}
+
+#[derive(Deserialize)]
+enum ExecutionStatus {
+    NotStarted,
+    Started,
+    Failure(Value),
+    SuccessValue(IgnoredAny),
+}
+
+#[derive(Deserialize)]
+struct TransactionView {
+    signer_id: String,
+    receiver_id: String,
+    hash: String,
+    actions: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct OutcomeView {
+    tokens_burnt: RawNumber,
+}
+
+#[derive(Deserialize)]
+struct Outcome {
+    block_hash: String,
+    outcome: OutcomeView,
+}
+
+#[derive(Deserialize)]
+struct NearTransaction {
+    status: ExecutionStatus,
+    transaction: TransactionView,
+    transaction_outcome: Outcome,
+    #[serde(default)]
+    receipts_outcome: Vec<Outcome>,
+}
+
+impl NearTransaction {
+    /// Deposit of the transaction's transfer, if it has one.
+    fn deposit(&self) -> Option<&str> {
+        self.transaction
+            .actions
+            .iter()
+            .find_map(|action| action.get("Transfer")?.get("deposit")?.as_str())
+    }
+}
+
+fn parse_u128(number: &RawNumber, field: &str) -> Result<u128, BundlrError> {
+    number
+        .parse_atomic(field)?
+        .try_into()
+        .map_err(|_| BundlrError::TypeParseError(format!("{} does not fit in a u128", field)))
+}
+
+fn to_u64(field: &str, amount: u128) -> Result<u64, BundlrError> {
+    u64::try_from(amount).map_err(|_| BundlrError::AmountOverflow {
+        field: field.to_owned(),
+        amount,
+    })
+}
+
+fn decode_hash(hash: &str) -> Result<[u8; 32], BundlrError> {
+    bs58::decode(hash)
+        .into_vec()
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(BundlrError::ParseError(format!(
+            "Invalid NEAR hash: {}",
+            hash
+        )))
+}
+
+impl Near {
+    /// Account funding and signing, once a wallet or account id is set.
+    pub fn account_id(&self) -> Option<&str> {
+        self.account_id.as_deref()
+    }
+
+    /// `amount` of NEAR in yoctoNEAR, see [`units::parse_units`].
+    pub fn parse_amount(&self, amount: &str) -> Result<u64, BundlrError> {
+        units::parse_units(amount, NEAR_DECIMALS)
+    }
+
+    /// `amount` of yoctoNEAR in NEAR, see [`units::format_units`].
+    pub fn format_amount(&self, amount: u64) -> String {
+        units::format_units(amount, NEAR_DECIMALS)
+    }
+
+    /// Cost of a transfer to `to`'s [`TRANSFER_GAS`] at the current gas price, times
+    /// `multiplier`, in yoctoNEAR.
+    ///
+    /// NEAR transactions don't carry a fee, the gas they use being burnt from the sender's
+    /// balance: this is only an estimate.
+    pub async fn transfer_fee(&self, to: &str, multiplier: f64) -> Result<u128, BundlrError> {
+        parse_account_id(to)?;
+        let gas_price: GasPrice = self.rpc("gas_price", json!([null])).await?;
+        let gas_price = parse_u128(&gas_price.gas_price, "gas_price")?;
+        Ok((TRANSFER_GAS as f64 * gas_price as f64 * multiplier).ceil() as u128)
+    }
+
+    /// Sends `deposit` yoctoNEAR to `to`, returning once the transfer is included in a block.
+    pub async fn send_transfer(&self, to: &str, deposit: u128) -> Result<TxResponse, BundlrError> {
+        let signer = self.signer()?;
+        let public_key = signer.pub_key();
+        let access_key: AccessKey = self
+            .rpc(
+                "query",
+                json!({
+                    "request_type": "view_access_key",
+                    "finality": "final",
+                    "account_id": self.sender()?,
+                    "public_key": format!("{}{}", ED25519_KEY_PREFIX, bs58::encode(&public_key).into_string()),
+                }),
+            )
+            .await?;
+        let transfer = RawTransfer {
+            signer_id: self.sender()?.to_owned(),
+            public_key: public_key[..]
+                .try_into()
+                .map_err(|_| BundlrError::InvalidKey("Invalid ed25519 public key".to_owned()))?,
+            nonce: access_key.nonce + 1,
+            receiver_id: parse_account_id(to)?.to_owned(),
+            block_hash: decode_hash(&access_key.block_hash)?,
+            deposit,
+        };
+        let hash = transfer.hash();
+        let signature = signer.sign(Bytes::copy_from_slice(&hash))?;
+        let _: Value = self
+            .rpc(
+                "send_tx",
+                json!({
+                    "signed_tx_base64": BASE64.encode(&transfer.signed(&signature)),
+                    "wait_until": "INCLUDED",
+                }),
+            )
+            .await?;
+        Ok(TxResponse {
+            tx_id: bs58::encode(hash).into_string(),
+        })
+    }
+
+    /// Transfer `tx_id`, which must have been sent by this currency's account as NEAR looks
+    /// transactions up by hash and sender.
+    pub async fn get_transfer(&self, tx_id: &str) -> Result<NearTransfer, BundlrError> {
+        let tx = self.transaction(tx_id).await?;
+        let deposit = tx.deposit().ok_or(BundlrError::CurrencyError(format!(
+            "{} is not a transfer of NEAR",
+            tx_id
+        )))?;
+        let deposit = deposit
+            .parse::<u128>()
+            .map_err(|_| BundlrError::NumericParse {
+                field: "deposit".to_owned(),
+                raw: deposit.to_owned(),
+            })?;
+        let tokens_burnt = tx
+            .receipts_outcome
+            .iter()
+            .chain([&tx.transaction_outcome])
+            .try_fold(0u128, |sum, outcome| {
+                Ok::<_, BundlrError>(
+                    sum.saturating_add(parse_u128(&outcome.outcome.tokens_burnt, "tokens_burnt")?),
+                )
+            })?;
+        let block = self
+            .block(json!({ "block_id": tx.transaction_outcome.block_hash }))
+            .await?;
+        let (pending, confirmed) = match tx.status {
+            ExecutionStatus::NotStarted | ExecutionStatus::Started => (true, false),
+            ExecutionStatus::Failure(_) => (false, false),
+            ExecutionStatus::SuccessValue(_) => (false, true),
+        };
+        Ok(NearTransfer {
+            id: tx.transaction.hash,
+            from: tx.transaction.signer_id,
+            to: tx.transaction.receiver_id,
+            deposit,
+            tokens_burnt,
+            block_height: block.height as u128,
+            pending,
+            confirmed,
+        })
+    }
+
+    fn signer(&self) -> Result<&Ed25519Signer, BundlrError> {
+        self.signer.as_ref().ok_or(BundlrError::CurrencyError(
+            "No private key present".to_string(),
+        ))
+    }
+
+    fn sender(&self) -> Result<&str, BundlrError> {
+        self.account_id.as_deref().ok_or(BundlrError::CurrencyError(
+            "No account id present".to_string(),
+        ))
+    }
+
+    async fn rpc<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, BundlrError> {
+        let res = self
+            .client
+            .post(self.url.clone())
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": "bundlr",
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await;
+        let res: RpcResponse<T> = read_json(res).await?;
+        match (res.result, res.error) {
+            (_, Some(error))
+                if error.cause.as_ref().map(|cause| cause.name.as_str())
+                    == Some(UNKNOWN_TRANSACTION) =>
+            {
+                Err(BundlrError::TxNotFound)
+            }
+            (_, Some(error)) => Err(BundlrError::CurrencyError(format!(
+                "{} failed: {} {}",
+                method, error.message, error.data
+            ))),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(BundlrError::ResponseError(format!(
+                "{} returned no result",
+                method
+            ))),
+        }
+    }
+
+    async fn transaction(&self, tx_id: &str) -> Result<NearTransaction, BundlrError> {
+        self.rpc("tx", json!([tx_id, self.sender()?])).await
+    }
+
+    async fn block(&self, params: Value) -> Result<BlockHeader, BundlrError> {
+        let block: Block = self.rpc("block", params).await?;
+        Ok(block.header)
+    }
+}
+
+#[async_trait::async_trait]
+impl Currency for Near {
+    fn get_min_unit_name(&self) -> String {
+        NEAR_BASE_UNIT.to_string()
+    }
+
+    fn get_type(&self) -> CurrencyType {
+        CurrencyType::Near
+    }
+
+    fn needs_fee(&self) -> bool {
+        true
+    }
+
+    /// [`Near::get_transfer`], failing with [`BundlrError::AmountOverflow`] if its deposit or
+    /// fee doesn't fit in a `u64`.
+    async fn get_tx(&self, tx_id: String) -> Result<Tx, BundlrError> {
+        let transfer = self.get_transfer(&tx_id).await?;
+        Ok(Tx {
+            id: transfer.id,
+            from: transfer.from,
+            to: transfer.to,
+            amount: to_u64("deposit", transfer.deposit)?,
+            fee: to_u64("tokens_burnt", transfer.tokens_burnt)?,
+            block_height: transfer.block_height,
+            pending: transfer.pending,
+            confirmed: transfer.confirmed,
+        })
+    }
+
+    async fn get_tx_status(
+        &self,
+        tx_id: String,
+    ) -> Result<(StatusCode, Option<TxStatus>), BundlrError> {
+        let tx = match self.transaction(&tx_id).await {
+            Ok(tx) => tx,
+            Err(BundlrError::TxNotFound) => return Ok((StatusCode::ACCEPTED, None)),
+            Err(err) => return Err(err),
+        };
+        match tx.status {
+            ExecutionStatus::NotStarted | ExecutionStatus::Started => {
+                return Ok((StatusCode::ACCEPTED, None))
+            }
+            ExecutionStatus::Failure(failure) => {
+                return Err(BundlrError::CurrencyError(format!(
+                    "Transaction {} failed: {}",
+                    tx_id, failure
+                )))
+            }
+            ExecutionStatus::SuccessValue(_) => {}
+        }
+        let block = self
+            .block(json!({ "block_id": tx.transaction_outcome.block_hash }))
+            .await?;
+        let latest = self.block(json!({ "finality": "final" })).await?;
+        Ok((
+            StatusCode::OK,
+            Some(TxStatus {
+                confirmations: latest.height.saturating_sub(block.height) + 1,
+                height: block.height as u128,
+                block_hash: block.hash,
+            }),
+        ))
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>, BundlrError> {
+        Ok(self
+            .signer()?
+            .sign(Bytes::copy_from_slice(message))?
+            .to_vec())
+    }
+
+    fn verify(&self, pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), BundlrError> {
+        Ed25519Signer::verify(
+            Bytes::copy_from_slice(pub_key),
+            Bytes::copy_from_slice(message),
+            Bytes::copy_from_slice(signature),
+        )
+    }
+
+    fn get_pub_key(&self) -> Result<Bytes, BundlrError> {
+        Ok(self.signer()?.pub_key())
+    }
+
+    fn wallet_address(&self) -> Result<String, BundlrError> {
+        self.signer()?;
+        Ok(self.sender()?.to_owned())
+    }
+
+    fn get_signer(&self) -> Result<&dyn Signer, BundlrError> {
+        Ok(self.signer()?)
+    }
+
+    async fn get_id(&self, _item: ()) -> Result<String, BundlrError> {
+        Err(BundlrError::Unsupported(format!(
+            "No currency id for {}",
+            self.name()
+        )))
+    }
+
+    async fn price(&self) -> Result<String, BundlrError> {
+        Err(BundlrError::Unsupported(format!(
+            "No USD price source for {}",
+            self.name()
+        )))
+    }
+
+    /// Height of the chain's latest block.
+    async fn get_current_height(&self) -> Result<u128, BundlrError> {
+        Ok(self.block(json!({ "finality": "final" })).await?.height as u128)
+    }
+
+    /// [`Near::transfer_fee`], failing with [`BundlrError::AmountOverflow`] if it doesn't fit
+    /// in a `u64`, as at the minimum gas price.
+    async fn get_fee(&self, _amount: u64, to: &str, multiplier: f64) -> Result<u64, BundlrError> {
+        to_u64("fee", self.transfer_fee(to, multiplier).await?)
+    }
+
+    async fn create_tx(&self, amount: u64, to: &str, fee: u64) -> Result<Tx, BundlrError> {
+        parse_account_id(to)?;
+        Ok(Tx {
+            id: String::new(),
+            from: self.wallet_address()?,
+            to: to.to_owned(),
+            amount,
+            fee,
+            block_height: 0,
+            pending: true,
+            confirmed: false,
+        })
+    }
+
+    /// [`Near::send_transfer`] of the transaction's amount.
+    async fn send_tx(&self, data: Tx) -> Result<TxResponse, BundlrError> {
+        self.send_transfer(&data.to, data.amount as u128).await
+    }
+
+    fn rpc_url(&self) -> Option<Url> {
+        Some(self.url.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signature, Verifier};
+    use httpmock::{
+        Method::{GET, POST},
+        Mock, MockServer,
+    };
+    use reqwest::{StatusCode, Url};
+
+    use super::{implicit_account_of, parse_account_id, NearBuilder, RawTransfer};
+    use crate::{
+        currency::{Currency, CurrencyType},
+        error::BundlrError,
+        BundlrBuilder,
+    };
+
+    const NODE_ACCOUNT: &str = "bundlr.near";
+    const BLOCK_HASH: &str = "11111111111111111111111111111111";
+
+    fn wallet() -> String {
+        let secret = SecretKey::from_bytes(&[0x30; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        let keypair = Keypair { secret, public };
+        format!("ed25519:{}", bs58::encode(keypair.to_bytes()).into_string())
+    }
+
+    fn mock_rpc<'a>(server: &'a MockServer, method: &str, result: &str) -> Mock<'a> {
+        let body = format!(r#"{{"jsonrpc":"2.0","id":"bundlr","result":{}}}"#, result);
+        let method = format!(r#""method":"{}""#, method);
+        server.mock(|when, then| {
+            when.method(POST).path("/rpc").body_contains(&method);
+            then.status(200).body(body);
+        })
+    }
+
+    fn near(server: &MockServer) -> super::Near {
+        NearBuilder::new()
+            .rpc_url(Url::parse(&server.url("/rpc")).unwrap())
+            .wallet(&wallet())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn should_encode_transfers_in_borsh() {
+        let transfer = RawTransfer {
+            signer_id: "a.near".to_owned(),
+            public_key: [1; 32],
+            nonce: 7,
+            receiver_id: "b".to_owned(),
+            block_hash: [2; 32],
+            deposit: 1_000,
+        };
+        let borsh = data_encoding::HEXLOWER.encode(&transfer.to_borsh());
+        assert_eq!(
+            borsh,
+            [
+                "06000000",
+                &data_encoding::HEXLOWER.encode(b"a.near"),
+                "00",
+                &"01".repeat(32),
+                "0700000000000000",
+                "0100000062",
+                &"02".repeat(32),
+                "01000000",
+                "03",
+                "e8030000000000000000000000000000",
+            ]
+            .concat()
+        );
+        let signed = transfer.signed(&[3; 64]);
+        assert_eq!(signed[transfer.to_borsh().len()], 0);
+        assert_eq!(signed.len(), transfer.to_borsh().len() + 65);
+    }
+
+    #[test]
+    fn should_validate_account_ids() {
+        for valid in ["bundlr.near", "a1", "app_1-x.testnet", &"f".repeat(64)] {
+            assert!(parse_account_id(valid).is_ok(), "{}", valid);
+        }
+        for invalid in [
+            "a",
+            "Bundlr.near",
+            ".near",
+            "near.",
+            "a..near",
+            &"f".repeat(65),
+        ] {
+            assert!(parse_account_id(invalid).is_err(), "{}", invalid);
+        }
+
+        let url = Url::parse("http://localhost:3030/").unwrap();
+        let near = NearBuilder::new()
+            .rpc_url(url.clone())
+            .wallet(&wallet())
+            .build()
+            .unwrap();
+        let implicit = implicit_account_of(&near.get_pub_key().unwrap());
+        assert_eq!(near.wallet_address().unwrap(), implicit);
+        assert_eq!(near.get_type(), CurrencyType::Near);
+        assert_eq!(
+            near.parse_amount("0.00001").unwrap(),
+            10_000_000_000_000_000_000
+        );
+        let named = NearBuilder::new()
+            .rpc_url(url.clone())
+            .account_id("alice.near")
+            .wallet(&wallet())
+            .build()
+            .unwrap();
+        assert_eq!(named.account_id(), Some("alice.near"));
+        assert!(NearBuilder::new()
+            .rpc_url(url)
+            .account_id("Alice")
+            .build()
+            .is_err());
+        assert!(NearBuilder::new().build().is_err());
+    }
+
+    #[tokio::test]
+    async fn should_fund_with_signed_transfers() {
+        let server = MockServer::start();
+        let near = near(&server);
+        let gas_price = mock_rpc(&server, "gas_price", r#"{ "gas_price": "10000000" }"#);
+        assert_eq!(
+            near.get_fee(1, NODE_ACCOUNT, 1.0).await.unwrap(),
+            446_365_125_000 * 10_000_000
+        );
+        assert!(matches!(
+            near.get_fee(1, NODE_ACCOUNT, 5.0).await,
+            Err(BundlrError::AmountOverflow { field, .. }) if field == "fee"
+        ));
+        let fee = near.transfer_fee(NODE_ACCOUNT, 5.0).await.unwrap();
+        assert!(fee > u64::MAX as u128);
+
+        let access_key = mock_rpc(
+            &server,
+            "query",
+            &format!(
+                r#"{{ "nonce": 41, "block_hash": "{}", "block_height": 10, "permission": "FullAccess" }}"#,
+                BLOCK_HASH
+            ),
+        );
+        let sent = server.mock(|when, then| {
+            when.method(POST)
+                .path("/rpc")
+                .body_contains(r#""method":"send_tx""#)
+                .body_contains("signed_tx_base64");
+            then.status(200).body(
+                r#"{"jsonrpc":"2.0","id":"bundlr","result":{ "final_execution_status": "INCLUDED" }}"#,
+            );
+        });
+        let transfer = RawTransfer {
+            signer_id: near.wallet_address().unwrap(),
+            public_key: near.get_pub_key().unwrap()[..].try_into().unwrap(),
+            nonce: 42,
+            receiver_id: NODE_ACCOUNT.to_owned(),
+            block_hash: [0; 32],
+            deposit: 1_000,
+        };
+        let tx_id = bs58::encode(transfer.hash()).into_string();
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(format!(
+                    r#"{{ "version": "0.2.0", "gateway": "arweave.net", "addresses": {{ "near": "{}" }} }}"#,
+                    NODE_ACCOUNT
+                ));
+        });
+        let credited = server.mock(|when, then| {
+            when.method(POST)
+                .path("/account/balance/near")
+                .body_contains(&tx_id);
+            then.status(200).body("OK");
+        });
+
+        let signature = near.sign_message(&transfer.hash()).unwrap();
+        PublicKey::from_bytes(&near.get_pub_key().unwrap())
+            .unwrap()
+            .verify(
+                &transfer.hash(),
+                &Signature::from_bytes(&signature).unwrap(),
+            )
+            .unwrap();
+        let bundlr = BundlrBuilder::new()
+            .url(Url::parse(&server.url("/")).unwrap())
+            .currency(near)
+            .fetch_pub_info()
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(bundlr.fund(1_000, None).await.unwrap());
+
+        gas_price.assert_hits(4);
+        access_key.assert();
+        sent.assert();
+        credited.assert();
+    }
+
+    #[tokio::test]
+    async fn should_read_transfers_and_their_confirmations() {
+        let server = MockServer::start();
+        let near = near(&server);
+        let tx = server.mock(|when, then| {
+            when.method(POST)
+                .path("/rpc")
+                .body_contains(r#""method":"tx""#)
+                .body_contains(r#""params":["sent","#);
+            then.status(200).body(format!(
+                r#"{{"jsonrpc":"2.0","id":"bundlr","result":{{
+                    "status": {{ "SuccessValue": "" }},
+                    "transaction": {{ "signer_id": "alice.near", "receiver_id": "{}", "hash": "sent", "actions": [{{ "Transfer": {{ "deposit": "1000" }} }}] }},
+                    "transaction_outcome": {{ "block_hash": "{}", "outcome": {{ "tokens_burnt": "223182562500000000000" }} }},
+                    "receipts_outcome": [{{ "block_hash": "{}", "outcome": {{ "tokens_burnt": "0" }} }}]
+                }}}}"#,
+                NODE_ACCOUNT, BLOCK_HASH, BLOCK_HASH
+            ));
+        });
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/rpc")
+                .body_contains(r#""method":"tx""#)
+                .body_contains(r#""params":["unknown","#);
+            then.status(200).body(
+                r#"{"jsonrpc":"2.0","id":"bundlr","error":{ "name": "HANDLER_ERROR", "cause": { "name": "UNKNOWN_TRANSACTION" }, "code": -32000, "message": "Server error", "data": "Transaction doesn't exist" }}"#,
+            );
+        });
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/rpc")
+                .body_contains(r#""method":"block""#)
+                .body_contains("block_id");
+            then.status(200).body(format!(
+                r#"{{"jsonrpc":"2.0","id":"bundlr","result":{{ "header": {{ "height": 100, "hash": "{}" }} }}}}"#,
+                BLOCK_HASH
+            ));
+        });
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/rpc")
+                .body_contains(r#""method":"block""#)
+                .body_contains("finality");
+            then.status(200).body(
+                r#"{"jsonrpc":"2.0","id":"bundlr","result":{ "header": { "height": 102, "hash": "latest" } }}"#,
+            );
+        });
+
+        let transfer = near.get_transfer("sent").await.unwrap();
+        assert_eq!(
+            (transfer.to.as_str(), transfer.deposit),
+            (NODE_ACCOUNT, 1_000)
+        );
+        assert_eq!(
+            (transfer.tokens_burnt, transfer.block_height),
+            (223_182_562_500_000_000_000, 100)
+        );
+        assert!(transfer.confirmed && !transfer.pending);
+        assert!(matches!(
+            near.get_tx("sent".to_owned()).await,
+            Err(BundlrError::AmountOverflow { field, .. }) if field == "tokens_burnt"
+        ));
+
+        let (status, confirmed) = near.get_tx_status("sent".to_owned()).await.unwrap();
+        let confirmed = confirmed.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((confirmed.confirmations, confirmed.height), (3, 100));
+        assert_eq!(confirmed.block_hash, BLOCK_HASH);
+
+        let (status, pending) = near.get_tx_status("unknown".to_owned()).await.unwrap();
+        assert_eq!((status, pending.is_none()), (StatusCode::ACCEPTED, true));
+        tx.assert_hits(3);
+
+        assert_eq!(near.get_current_height().await.unwrap(), 102);
+    }
+}
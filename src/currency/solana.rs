@@ -146,15 +146,15 @@ impl Currency for Solana {
         }
     }
 
-    async fn get_id(&self, _item: ()) -> String {
+    async fn get_id(&self, _item: ()) -> Result<String, BundlrError> {
         todo!();
     }
 
-    async fn price(&self) -> String {
+    async fn price(&self) -> Result<String, BundlrError> {
         todo!();
     }
 
-    async fn get_current_height(&self) -> u128 {
+    async fn get_current_height(&self) -> Result<u128, BundlrError> {
         todo!();
     }
 
@@ -169,4 +169,8 @@ impl Currency for Solana {
     async fn send_tx(&self, data: Tx) -> Result<TxResponse, BundlrError> {
         todo!()
     }
+
+    fn rpc_url(&self) -> Option<Url> {
+        Some(self.url.clone())
+    }
 }
@@ -0,0 +1,947 @@
+//! Starknet, funding the node's address with ERC-20 transfers, STRK by default, invoked through
+//! the wallet's account contract and sent through a Starknet JSON-RPC endpoint.
+//!
+//! Starknet accounts are contracts: the wallet's Stark key signs V3 invoke transactions of the
+//! account at the configured address, paying fees in STRK. Amounts are in the token's base
+//! units, fri for STRK, and are held in `u64`s as for every currency: no more than about 18.4
+//! tokens can be funded at once.
+//!
+//! Stark keys can't sign items, so uploads have to be signed with another currency's signer.
+
+use bytes::Bytes;
+use reqwest::{StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+use starknet_crypto::{get_public_key, poseidon_hash_many, rfc6979_generate_k, sign, Felt};
+
+use crate::{
+    error::{BuilderError, BundlrError},
+    transaction::{Tx, TxStatus},
+    utils::read_json,
+    Signer,
+};
+
+use super::{units, Currency, CurrencyType, TxResponse};
+
+/// STRK, at the same address on mainnet and Sepolia.
+pub const STRK_TOKEN: &str = "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+/// ETH, at the same address on mainnet and Sepolia.
+pub const ETH_TOKEN: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+pub const SN_MAIN: &str = "SN_MAIN";
+pub const SN_SEPOLIA: &str = "SN_SEPOLIA";
+const STRK_TICKER: &str = "STRK";
+const TOKEN_DECIMALS: u8 = 18;
+
+/// Most L1 gas a transfer may use: transfers only publish their state diff as blob data.
+pub const TRANSFER_MAX_L1_GAS: u64 = 0;
+/// Most L2 gas a transfer may use, well above the ~1M units it takes.
+pub const TRANSFER_MAX_L2_GAS: u64 = 3_000_000;
+/// Most L1 data gas a transfer may use, well above the ~200 units it takes.
+pub const TRANSFER_MAX_L1_DATA_GAS: u64 = 1_000;
+
+const INVOKE_PREFIX: &str = "invoke";
+const TRANSACTION_VERSION: u64 = 3;
+const L1_GAS: &str = "L1_GAS";
+const L2_GAS: &str = "L2_GAS";
+const L1_DATA_GAS: &str = "L1_DATA";
+/// JSON-RPC error code of unknown transaction hashes.
+const TXN_HASH_NOT_FOUND: i64 = 29;
+
+pub struct Starknet {
+    private_key: Option<Felt>,
+    account: Option<Felt>,
+    token: Felt,
+    ticker: String,
+    name: String,
+    chain_id: Felt,
+    client: reqwest::Client,
+    url: Url,
+}
+
+#[derive(Default)]
+pub struct StarknetBuilder {
+    rpc_url: Option<Url>,
+    chain_id: Option<String>,
+    token: Option<(String, String)>,
+    name: Option<String>,
+    account_address: Option<String>,
+    wallet: Option<String>,
+}
+
+impl StarknetBuilder {
+    pub fn new() -> StarknetBuilder {
+        Default::default()
+    }
+
+    /// JSON-RPC endpoint of a Starknet node, serving version 0.8 of the API or later.
+    pub fn rpc_url(mut self, rpc_url: Url) -> StarknetBuilder {
+        self.rpc_url = Some(rpc_url);
+        self
+    }
+
+    /// Chain id as a short string, [`SN_MAIN`] by default.
+    pub fn chain_id(mut self, chain_id: &str) -> StarknetBuilder {
+        self.chain_id = Some(chain_id.to_owned());
+        self
+    }
+
+    /// Token at the `contract` address to fund with, in hex, [`STRK_TOKEN`] by default.
+    pub fn token(mut self, contract: &str, ticker: &str) -> StarknetBuilder {
+        self.token = Some((contract.to_owned(), ticker.to_owned()));
+        self
+    }
+
+    /// Name of the currency on the node, as in its `/account/balance/{name}` path, `starknet`
+    /// by default.
+    pub fn name(mut self, name: &str) -> StarknetBuilder {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    /// Address of the account contract the wallet's key signs for, in hex.
+    pub fn account_address(mut self, account_address: &str) -> StarknetBuilder {
+        self.account_address = Some(account_address.to_owned());
+        self
+    }
+
+    /// Wallet's Stark private key, in hex.
+    pub fn wallet(mut self, wallet: &str) -> StarknetBuilder {
+        self.wallet = Some(wallet.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Starknet, BuilderError> {
+        let url = self.rpc_url.ok_or(BuilderError::MissingField(
+            "rpc_url is required for Starknet".to_owned(),
+        ))?;
+        let private_key = match self.wallet {
+            Some(wallet) => Some(parse_felt(&wallet, "private key")?),
+            None => None,
+        };
+        let account = match (self.account_address, &private_key) {
+            (Some(address), _) => Some(parse_felt(&address, "address")?),
+            (None, Some(_)) => {
+                return Err(BuilderError::MissingField(
+                    "account_address is required with a Starknet wallet".to_owned(),
+                ))
+            }
+            (None, None) => None,
+        };
+        let (token, ticker) = self
+            .token
+            .unwrap_or_else(|| (STRK_TOKEN.to_owned(), STRK_TICKER.to_owned()));
+        Ok(Starknet {
+            private_key,
+            account,
+            token: parse_felt(&token, "token address")?,
+            ticker,
+            name: self
+                .name
+                .unwrap_or_else(|| CurrencyType::Starknet.to_string()),
+            chain_id: short_string(self.chain_id.as_deref().unwrap_or(SN_MAIN))?,
+            client: reqwest::Client::new(),
+            url,
+        })
+    }
+}
+
+fn parse_felt(hex: &str, field: &str) -> Result<Felt, BundlrError> {
+    Felt::from_hex(hex)
+        .map_err(|_| BundlrError::ParseError(format!("Invalid Starknet {}: {}", field, hex)))
+}
+
+fn to_hex(felt: &Felt) -> String {
+    format!("{:#x}", felt)
+}
+
+/// `text`, of at most 31 ASCII characters, as a felt.
+pub fn short_string(text: &str) -> Result<Felt, BundlrError> {
+    match text.len() <= 31 && text.is_ascii() {
+        true => Ok(Felt::from_bytes_be_slice(text.as_bytes())),
+        false => Err(BundlrError::ParseError(format!(
+            "Invalid Starknet short string: {}",
+            text
+        ))),
+    }
+}
+
+/// Selector of the entry point `name`, its Keccak-256 truncated to 250 bits.
+pub fn selector(name: &str) -> Felt {
+    let mut hash: [u8; 32] = Keccak256::digest(name.as_bytes()).into();
+    hash[0] &= 0x03;
+    Felt::from_bytes_be(&hash)
+}
+
+/// Resource bound `name`, as hashed: the name, then the amount and price, packed in a felt.
+fn resource_bound(name: &str, max_amount: u64, max_price_per_unit: u128) -> Felt {
+    let mut bytes = [0; 32];
+    bytes[8 - name.len()..8].copy_from_slice(name.as_bytes());
+    bytes[8..16].copy_from_slice(&max_amount.to_be_bytes());
+    bytes[16..].copy_from_slice(&max_price_per_unit.to_be_bytes());
+    Felt::from_bytes_be(&bytes)
+}
+
+/// Prices of a unit of each kind of gas, in fri.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasPrices {
+    pub l1_gas: u128,
+    pub l2_gas: u128,
+    pub l1_data_gas: u128,
+}
+
+impl GasPrices {
+    /// Most a transfer may cost at these prices.
+    pub fn transfer_fee(&self) -> u128 {
+        (TRANSFER_MAX_L1_GAS as u128 * self.l1_gas)
+            .saturating_add(TRANSFER_MAX_L2_GAS as u128 * self.l2_gas)
+            .saturating_add(TRANSFER_MAX_L1_DATA_GAS as u128 * self.l1_data_gas)
+    }
+
+    /// These prices raised so that a transfer may cost up to `fee`.
+    fn up_to(&self, fee: u64) -> GasPrices {
+        let base = self.transfer_fee();
+        let scale = |price: u128| match base {
+            0 => price,
+            base => price.saturating_mul(fee as u128).div_ceil(base).max(price),
+        };
+        GasPrices {
+            l1_gas: scale(self.l1_gas),
+            l2_gas: scale(self.l2_gas),
+            l1_data_gas: scale(self.l1_data_gas),
+        }
+    }
+}
+
+/// A V3 invoke transaction of an account, executing a single ERC-20 `transfer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvokeTransfer {
+    pub sender: Felt,
+    pub token: Felt,
+    pub to: Felt,
+    pub amount: u64,
+    pub nonce: Felt,
+    pub chain_id: Felt,
+    pub gas_prices: GasPrices,
+}
+
+impl InvokeTransfer {
+    /// Calldata of the account's `__execute__`, calling `transfer(to, amount)` on the token.
+    pub fn calldata(&self) -> Vec<Felt> {
+        vec![
+            Felt::ONE,
+            self.token,
+            selector("transfer"),
+            Felt::THREE,
+            self.to,
+            Felt::from(self.amount),
+            Felt::ZERO,
+        ]
+    }
+
+    /// What the account's key signs, and the transaction's hash.
+    pub fn hash(&self) -> Felt {
+        let fee_fields = [
+            Felt::ZERO,
+            resource_bound(L1_GAS, TRANSFER_MAX_L1_GAS, self.gas_prices.l1_gas),
+            resource_bound(L2_GAS, TRANSFER_MAX_L2_GAS, self.gas_prices.l2_gas),
+            resource_bound(
+                L1_DATA_GAS,
+                TRANSFER_MAX_L1_DATA_GAS,
+                self.gas_prices.l1_data_gas,
+            ),
+        ];
+        poseidon_hash_many(&[
+            Felt::from_bytes_be_slice(INVOKE_PREFIX.as_bytes()),
+            Felt::from(TRANSACTION_VERSION),
+            self.sender,
+            poseidon_hash_many(&fee_fields),
+            poseidon_hash_many(&[]),
+            self.chain_id,
+            self.nonce,
+            Felt::ZERO,
+            poseidon_hash_many(&[]),
+            poseidon_hash_many(&self.calldata()),
+        ])
+    }
+
+    /// The transaction signed with `signature`, as `starknet_addInvokeTransaction` takes it.
+    pub fn to_json(&self, signature: &[Felt]) -> Value {
+        let bound = |max_amount: u64, price: u128| {
+            json!({
+                "max_amount": format!("{:#x}", max_amount),
+                "max_price_per_unit": format!("{:#x}", price),
+            })
+        };
+        json!({
+            "type": "INVOKE",
+            "version": format!("{:#x}", TRANSACTION_VERSION),
+            "sender_address": to_hex(&self.sender),
+            "calldata": self.calldata().iter().map(to_hex).collect::<Vec<_>>(),
+            "signature": signature.iter().map(to_hex).collect::<Vec<_>>(),
+            "nonce": to_hex(&self.nonce),
+            "resource_bounds": {
+                "l1_gas": bound(TRANSFER_MAX_L1_GAS, self.gas_prices.l1_gas),
+                "l2_gas": bound(TRANSFER_MAX_L2_GAS, self.gas_prices.l2_gas),
+                "l1_data_gas": bound(TRANSFER_MAX_L1_DATA_GAS, self.gas_prices.l1_data_gas),
+            },
+            "tip": "0x0",
+            "paymaster_data": [],
+            "account_deployment_data": [],
+            "nonce_data_availability_mode": "L1",
+            "fee_data_availability_mode": "L1",
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+    #[serde(default)]
+    data: Value,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct ResourcePrice {
+    price_in_fri: String,
+}
+
+#[derive(Deserialize)]
+struct BlockHeader {
+    l1_gas_price: ResourcePrice,
+    l2_gas_price: ResourcePrice,
+    l1_data_gas_price: ResourcePrice,
+}
+
+#[derive(Deserialize)]
+struct Submitted {
+    transaction_hash: String,
+}
+
+#[derive(Deserialize)]
+struct InvokeTransaction {
+    transaction_hash: String,
+    sender_address: String,
+    calldata: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct FeePayment {
+    amount: String,
+}
+
+#[derive(Deserialize)]
+struct Receipt {
+    execution_status: String,
+    #[serde(default)]
+    revert_reason: Option<String>,
+    block_hash: Option<String>,
+    block_number: Option<u64>,
+    actual_fee: FeePayment,
+}
+
+impl Receipt {
+    fn succeeded(&self) -> bool {
+        self.execution_status == "SUCCEEDED"
+    }
+}
+
+fn felt_to_u64(hex: &str, field: &str) -> Result<u64, BundlrError> {
+    parse_felt(hex, field)?
+        .try_into()
+        .map_err(|_| BundlrError::TypeParseError(format!("{} does not fit in a u64", field)))
+}
+
+fn felt_to_u128(hex: &str, field: &str) -> Result<u128, BundlrError> {
+    parse_felt(hex, field)?
+        .try_into()
+        .map_err(|_| BundlrError::TypeParseError(format!("{} does not fit in a u128", field)))
+}
+
+impl Starknet {
+    pub fn ticker(&self) -> &str {
+        &self.ticker
+    }
+
+    /// Address of the token's contract.
+    pub fn token(&self) -> Felt {
+        self.token
+    }
+
+    /// `amount` of tokens in base units, see [`units::parse_units`].
+    pub fn parse_amount(&self, amount: &str) -> Result<u64, BundlrError> {
+        units::parse_units(amount, TOKEN_DECIMALS)
+    }
+
+    /// `amount` of base units in tokens, see [`units::format_units`].
+    pub fn format_amount(&self, amount: u64) -> String {
+        units::format_units(amount, TOKEN_DECIMALS)
+    }
+
+    fn private_key(&self) -> Result<&Felt, BundlrError> {
+        self.private_key.as_ref().ok_or(BundlrError::CurrencyError(
+            "No private key present".to_string(),
+        ))
+    }
+
+    fn account(&self) -> Result<&Felt, BundlrError> {
+        self.private_key()?;
+        self.account.as_ref().ok_or(BundlrError::CurrencyError(
+            "No account address present".to_string(),
+        ))
+    }
+
+    /// `hash` signed with the wallet's key, as `[r, s]`.
+    fn sign_hash(&self, hash: &Felt) -> Result<[Felt; 2], BundlrError> {
+        let private_key = self.private_key()?;
+        let k = rfc6979_generate_k(hash, private_key, None);
+        let signature = sign(private_key, hash, &k)
+            .map_err(|err| BundlrError::CurrencyError(err.to_string()))?;
+        Ok([signature.r, signature.s])
+    }
+
+    async fn rpc<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, BundlrError> {
+        let res = self
+            .client
+            .post(self.url.clone())
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await;
+        let res: RpcResponse<T> = read_json(res).await?;
+        match (res.result, res.error) {
+            (_, Some(error)) if error.code == TXN_HASH_NOT_FOUND => Err(BundlrError::TxNotFound),
+            (_, Some(error)) => Err(BundlrError::CurrencyError(format!(
+                "{} failed: {} {}",
+                method, error.message, error.data
+            ))),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(BundlrError::ResponseError(format!(
+                "{} returned no result",
+                method
+            ))),
+        }
+    }
+
+    async fn gas_prices(&self) -> Result<GasPrices, BundlrError> {
+        let block: BlockHeader = self
+            .rpc(
+                "starknet_getBlockWithTxHashes",
+                json!({ "block_id": "latest" }),
+            )
+            .await?;
+        Ok(GasPrices {
+            l1_gas: felt_to_u128(&block.l1_gas_price.price_in_fri, "l1_gas_price")?,
+            l2_gas: felt_to_u128(&block.l2_gas_price.price_in_fri, "l2_gas_price")?,
+            l1_data_gas: felt_to_u128(&block.l1_data_gas_price.price_in_fri, "l1_data_gas_price")?,
+        })
+    }
+
+    async fn receipt(&self, tx_id: &str) -> Result<Receipt, BundlrError> {
+        self.rpc(
+            "starknet_getTransactionReceipt",
+            json!({ "transaction_hash": tx_id }),
+        )
+        .await
+    }
+
+    /// Recipient and amount of `calldata`, if it is a single `transfer` of the token.
+    fn decode_transfer(&self, calldata: &[String]) -> Option<(String, u64)> {
+        let calldata = calldata
+            .iter()
+            .map(|felt| Felt::from_hex(felt).ok())
+            .collect::<Option<Vec<_>>>()?;
+        match calldata[..] {
+            [calls, token, entry_point, len, to, low, high]
+                if calls == Felt::ONE
+                    && token == self.token
+                    && entry_point == selector("transfer")
+                    && len == Felt::THREE
+                    && high == Felt::ZERO =>
+            {
+                Some((to_hex(&to), low.try_into().ok()?))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Currency for Starknet {
+    fn get_min_unit_name(&self) -> String {
+        format!("{} base unit", self.ticker)
+    }
+
+    fn get_type(&self) -> CurrencyType {
+        CurrencyType::Starknet
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn needs_fee(&self) -> bool {
+        true
+    }
+
+    async fn get_tx(&self, tx_id: String) -> Result<Tx, BundlrError> {
+        let tx: InvokeTransaction = self
+            .rpc(
+                "starknet_getTransactionByHash",
+                json!({ "transaction_hash": tx_id }),
+            )
+            .await?;
+        let (to, amount) = self
+            .decode_transfer(&tx.calldata)
+            .ok_or(BundlrError::CurrencyError(format!(
+                "{} is not a transfer of {}",
+                tx_id, self.ticker
+            )))?;
+        let receipt = self.receipt(&tx_id).await?;
+        Ok(Tx {
+            id: tx.transaction_hash,
+            from: tx.sender_address,
+            to,
+            amount,
+            fee: felt_to_u64(&receipt.actual_fee.amount, "actual_fee")?,
+            block_height: receipt.block_number.unwrap_or_default() as u128,
+            pending: receipt.block_number.is_none(),
+            confirmed: receipt.block_number.is_some() && receipt.succeeded(),
+        })
+    }
+
+    async fn get_tx_status(
+        &self,
+        tx_id: String,
+    ) -> Result<(StatusCode, Option<TxStatus>), BundlrError> {
+        let receipt = match self.receipt(&tx_id).await {
+            Ok(receipt) => receipt,
+            Err(BundlrError::TxNotFound) => return Ok((StatusCode::ACCEPTED, None)),
+            Err(err) => return Err(err),
+        };
+        if !receipt.succeeded() {
+            return Err(BundlrError::CurrencyError(format!(
+                "Transaction {} reverted: {}",
+                tx_id,
+                receipt.revert_reason.unwrap_or_default()
+            )));
+        }
+        let (height, block_hash) = match (receipt.block_number, receipt.block_hash) {
+            (Some(height), Some(block_hash)) => (height, block_hash),
+            _ => return Ok((StatusCode::ACCEPTED, None)),
+        };
+        let current: u64 = self.rpc("starknet_blockNumber", json!([])).await?;
+        Ok((
+            StatusCode::OK,
+            Some(TxStatus {
+                confirmations: current.saturating_sub(height) + 1,
+                height: height as u128,
+                block_hash,
+            }),
+        ))
+    }
+
+    fn sign_message(&self, _message: &[u8]) -> Result<Vec<u8>, BundlrError> {
+        Err(BundlrError::Unsupported(
+            "Stark keys can't sign items".to_owned(),
+        ))
+    }
+
+    fn verify(
+        &self,
+        _pub_key: &[u8],
+        _message: &[u8],
+        _signature: &[u8],
+    ) -> Result<(), BundlrError> {
+        Err(BundlrError::Unsupported(
+            "Stark signatures of items can't be verified".to_owned(),
+        ))
+    }
+
+    fn get_pub_key(&self) -> Result<Bytes, BundlrError> {
+        let public_key = get_public_key(self.private_key()?);
+        Ok(Bytes::copy_from_slice(&public_key.to_bytes_be()))
+    }
+
+    fn wallet_address(&self) -> Result<String, BundlrError> {
+        Ok(to_hex(self.account()?))
+    }
+
+    fn get_signer(&self) -> Result<&dyn Signer, BundlrError> {
+        Err(BundlrError::Unsupported(
+            "Stark keys can't sign items".to_owned(),
+        ))
+    }
+
+    async fn get_id(&self, _item: ()) -> Result<String, BundlrError> {
+        Err(BundlrError::Unsupported(format!(
+            "No currency id for {}",
+            self.name()
+        )))
+    }
+
+    async fn price(&self) -> Result<String, BundlrError> {
+        Err(BundlrError::Unsupported(format!(
+            "No USD price source for {}",
+            self.name()
+        )))
+    }
+
+    /// Height of the chain's latest block.
+    async fn get_current_height(&self) -> Result<u128, BundlrError> {
+        let current: u64 = self.rpc("starknet_blockNumber", json!([])).await?;
+        Ok(current as u128)
+    }
+
+    /// Most a transfer may cost at the latest block's gas prices, in fri.
+    async fn get_fee(&self, _amount: u64, to: &str, multiplier: f64) -> Result<u64, BundlrError> {
+        parse_felt(to, "address")?;
+        let fee = self.gas_prices().await?.transfer_fee();
+        Ok((fee as f64 * multiplier).ceil().min(u64::MAX as f64) as u64)
+    }
+
+    async fn create_tx(&self, amount: u64, to: &str, fee: u64) -> Result<Tx, BundlrError> {
+        parse_felt(to, "address")?;
+        Ok(Tx {
+            id: String::new(),
+            from: self.wallet_address()?,
+            to: to.to_owned(),
+            amount,
+            fee,
+            block_height: 0,
+            pending: true,
+            confirmed: false,
+        })
+    }
+
+    /// Sends the transfer at the latest block's gas prices, raised so that it may cost up to
+    /// its fee.
+    async fn send_tx(&self, data: Tx) -> Result<TxResponse, BundlrError> {
+        let sender = *self.account()?;
+        let nonce: String = self
+            .rpc(
+                "starknet_getNonce",
+                json!({ "block_id": "latest", "contract_address": to_hex(&sender) }),
+            )
+            .await?;
+        let transfer = InvokeTransfer {
+            sender,
+            token: self.token,
+            to: parse_felt(&data.to, "address")?,
+            amount: data.amount,
+            nonce: parse_felt(&nonce, "nonce")?,
+            chain_id: self.chain_id,
+            gas_prices: self.gas_prices().await?.up_to(data.fee),
+        };
+        let signature = self.sign_hash(&transfer.hash())?;
+        let submitted: Submitted = self
+            .rpc(
+                "starknet_addInvokeTransaction",
+                json!({ "invoke_transaction": transfer.to_json(&signature) }),
+            )
+            .await?;
+        Ok(TxResponse {
+            tx_id: submitted.transaction_hash,
+        })
+    }
+
+    fn rpc_url(&self) -> Option<Url> {
+        Some(self.url.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{
+        Method::{GET, POST},
+        Mock, MockServer,
+    };
+    use reqwest::{StatusCode, Url};
+    use starknet_crypto::{get_public_key, verify, Felt};
+
+    use super::{
+        resource_bound, selector, short_string, GasPrices, InvokeTransfer, StarknetBuilder,
+        STRK_TOKEN, TRANSFER_MAX_L2_GAS,
+    };
+    use crate::{
+        currency::{Currency, CurrencyType},
+        error::BundlrError,
+        BundlrBuilder,
+    };
+
+    const WALLET: &str = "0x1234567890abcdef";
+    const ACCOUNT: &str = "0x5a";
+    const NODE_ADDRESS: &str = "0xaa";
+    const TX_HASH: &str = "0xff";
+    const BLOCK: &str = r#"{ "block_number": 100, "l1_gas_price": { "price_in_fri": "0x10", "price_in_wei": "0x1" }, "l2_gas_price": { "price_in_fri": "0x2", "price_in_wei": "0x1" }, "l1_data_gas_price": { "price_in_fri": "0x3", "price_in_wei": "0x1" }, "transactions": [] }"#;
+
+    fn mock_rpc<'a>(server: &'a MockServer, method: &str, result: &str) -> Mock<'a> {
+        let body = format!(r#"{{"jsonrpc":"2.0","id":0,"result":{}}}"#, result);
+        let method = format!(r#""method":"{}""#, method);
+        server.mock(|when, then| {
+            when.method(POST).path("/rpc").body_contains(&method);
+            then.status(200).body(body);
+        })
+    }
+
+    fn starknet(server: &MockServer) -> super::Starknet {
+        StarknetBuilder::new()
+            .rpc_url(Url::parse(&server.url("/rpc")).unwrap())
+            .account_address(ACCOUNT)
+            .wallet(WALLET)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn should_encode_transfers() {
+        assert_eq!(
+            selector("transfer"),
+            Felt::from_hex("0x83afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e")
+                .unwrap()
+        );
+        assert_eq!(
+            short_string("SN_MAIN").unwrap(),
+            Felt::from_hex("0x534e5f4d41494e").unwrap()
+        );
+        assert!(short_string(&"a".repeat(32)).is_err());
+        assert_eq!(
+            format!("{:#x}", resource_bound("L2_GAS", 0x10, 0x20)),
+            "0x4c325f474153000000000000001000000000000000000000000000000020"
+        );
+
+        let transfer = InvokeTransfer {
+            sender: Felt::from_hex(ACCOUNT).unwrap(),
+            token: Felt::from_hex(STRK_TOKEN).unwrap(),
+            to: Felt::from_hex(NODE_ADDRESS).unwrap(),
+            amount: 1_000,
+            nonce: Felt::ONE,
+            chain_id: short_string("SN_SEPOLIA").unwrap(),
+            gas_prices: GasPrices {
+                l1_gas: 1,
+                l2_gas: 2,
+                l1_data_gas: 3,
+            },
+        };
+        let calldata = transfer.calldata();
+        assert_eq!(calldata.len(), 7);
+        assert_eq!(
+            calldata[4..],
+            [Felt::from(0xaa), Felt::from(1_000), Felt::ZERO]
+        );
+        let json = transfer.to_json(&[Felt::ONE, Felt::TWO]);
+        assert_eq!(json["version"], "0x3");
+        assert_eq!(json["calldata"][5], "0x3e8");
+        assert_eq!(json["resource_bounds"]["l2_gas"]["max_amount"], "0x2dc6c0");
+        // Hash of the same transfer by starknet-rs: `ExecutionV3::transaction_hash` of
+        // starknet-accounts 0.16.0, for a `SingleOwnerAccount` with the new encoding and no tip
+        assert_eq!(
+            transfer.hash(),
+            Felt::from_hex("0x4a454a0a7dd46401895f082f1c949aee8a677855d7e00e4674e0a2d2a65e77d")
+                .unwrap()
+        );
+        assert_ne!(
+            transfer.hash(),
+            InvokeTransfer {
+                nonce: Felt::TWO,
+                ..transfer.clone()
+            }
+            .hash()
+        );
+    }
+
+    #[test]
+    fn should_raise_gas_prices_up_to_the_fee() {
+        let prices = GasPrices {
+            l1_gas: 10,
+            l2_gas: 2,
+            l1_data_gas: 3,
+        };
+        let fee = prices.transfer_fee();
+        assert_eq!(fee, TRANSFER_MAX_L2_GAS as u128 * 2 + 3_000);
+        let raised = prices.up_to((fee * 3 / 2) as u64);
+        assert_eq!(
+            (raised.l1_gas, raised.l2_gas, raised.l1_data_gas),
+            (15, 3, 5)
+        );
+        assert!(raised.transfer_fee() >= fee * 3 / 2);
+        assert_eq!(prices.up_to(1), prices);
+    }
+
+    #[test]
+    fn should_require_an_account_for_wallets() {
+        let url = Url::parse("http://localhost:5050/").unwrap();
+        assert!(StarknetBuilder::new().build().is_err());
+        assert!(StarknetBuilder::new()
+            .rpc_url(url.clone())
+            .wallet(WALLET)
+            .build()
+            .is_err());
+        let starknet = StarknetBuilder::new()
+            .rpc_url(url)
+            .account_address(ACCOUNT)
+            .wallet(WALLET)
+            .build()
+            .unwrap();
+        assert_eq!(starknet.wallet_address().unwrap(), ACCOUNT);
+        assert_eq!(
+            (starknet.name(), starknet.get_type()),
+            ("starknet".to_owned(), CurrencyType::Starknet)
+        );
+        assert_eq!(
+            starknet.parse_amount("1.5").unwrap(),
+            1_500_000_000_000_000_000
+        );
+        assert!(starknet.get_signer().is_err());
+    }
+
+    #[tokio::test]
+    async fn should_fund_with_signed_invokes() {
+        let server = MockServer::start();
+        let starknet = starknet(&server);
+        let block = mock_rpc(&server, "starknet_getBlockWithTxHashes", BLOCK);
+        let nonce = mock_rpc(&server, "starknet_getNonce", r#""0x7""#);
+        let public_key = get_public_key(&Felt::from_hex(WALLET).unwrap());
+        let expected = InvokeTransfer {
+            sender: Felt::from_hex(ACCOUNT).unwrap(),
+            token: Felt::from_hex(STRK_TOKEN).unwrap(),
+            to: Felt::from_hex(NODE_ADDRESS).unwrap(),
+            amount: 1_000,
+            nonce: Felt::from(7),
+            chain_id: short_string("SN_MAIN").unwrap(),
+            gas_prices: GasPrices {
+                l1_gas: 16,
+                l2_gas: 2,
+                l1_data_gas: 3,
+            },
+        };
+        let signature = starknet.sign_hash(&expected.hash()).unwrap();
+        assert!(verify(&public_key, &expected.hash(), &signature[0], &signature[1]).unwrap());
+        let invoke = expected.to_json(&signature).to_string();
+        let sent = server.mock(|when, then| {
+            when.method(POST)
+                .path("/rpc")
+                .body_contains(r#""method":"starknet_addInvokeTransaction""#)
+                .body_contains(&invoke);
+            then.status(200).body(format!(
+                r#"{{"jsonrpc":"2.0","id":0,"result":{{ "transaction_hash": "{}" }}}}"#,
+                TX_HASH
+            ));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(format!(
+                    r#"{{ "version": "0.2.0", "gateway": "arweave.net", "addresses": {{ "starknet": "{}" }} }}"#,
+                    NODE_ADDRESS
+                ));
+        });
+        let credited = server.mock(|when, then| {
+            when.method(POST)
+                .path("/account/balance/starknet")
+                .body_contains(TX_HASH);
+            then.status(200).body("OK");
+        });
+
+        assert_eq!(
+            starknet.get_fee(1_000, NODE_ADDRESS, 1.0).await.unwrap(),
+            TRANSFER_MAX_L2_GAS * 2 + 3_000
+        );
+        let bundlr = BundlrBuilder::new()
+            .url(Url::parse(&server.url("/")).unwrap())
+            .currency(starknet)
+            .fetch_pub_info()
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(bundlr.fund(1_000, None).await.unwrap());
+
+        block.assert_hits(3);
+        nonce.assert();
+        sent.assert();
+        credited.assert();
+    }
+
+    #[tokio::test]
+    async fn should_read_transfers_and_their_confirmations() {
+        let server = MockServer::start();
+        let starknet = starknet(&server);
+        let calldata = [
+            "0x1",
+            STRK_TOKEN,
+            "0x83afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e",
+            "0x3",
+            NODE_ADDRESS,
+            "0x3e8",
+            "0x0",
+        ];
+        mock_rpc(
+            &server,
+            "starknet_getTransactionByHash",
+            &format!(
+                r#"{{ "transaction_hash": "{}", "type": "INVOKE", "version": "0x3", "sender_address": "{}", "calldata": {:?} }}"#,
+                TX_HASH, ACCOUNT, calldata
+            ),
+        );
+        mock_rpc(
+            &server,
+            "starknet_getTransactionReceipt",
+            r#"{ "execution_status": "SUCCEEDED", "finality_status": "ACCEPTED_ON_L2", "block_hash": "0xb10c", "block_number": 100, "actual_fee": { "amount": "0x2710", "unit": "FRI" } }"#,
+        );
+        mock_rpc(&server, "starknet_blockNumber", "104");
+
+        let tx = starknet.get_tx(TX_HASH.to_owned()).await.unwrap();
+        assert_eq!(
+            (tx.to.as_str(), tx.amount, tx.fee),
+            (NODE_ADDRESS, 1_000, 10_000)
+        );
+        assert_eq!(
+            (tx.block_height, tx.pending, tx.confirmed),
+            (100, false, true)
+        );
+
+        let (status, confirmed) = starknet.get_tx_status(TX_HASH.to_owned()).await.unwrap();
+        let confirmed = confirmed.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((confirmed.confirmations, confirmed.height), (5, 100));
+        assert_eq!(confirmed.block_hash, "0xb10c");
+
+        assert_eq!(starknet.get_current_height().await.unwrap(), 104);
+        assert!(matches!(
+            starknet.get_id(()).await,
+            Err(BundlrError::Unsupported(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_accept_unknown_transactions_as_pending() {
+        let server = MockServer::start();
+        let starknet = starknet(&server);
+        server.mock(|when, then| {
+            when.method(POST).path("/rpc");
+            then.status(200).body(
+                r#"{"jsonrpc":"2.0","id":0,"error":{ "code": 29, "message": "Transaction hash not found" }}"#,
+            );
+        });
+        let (status, pending) = starknet.get_tx_status(TX_HASH.to_owned()).await.unwrap();
+        assert_eq!((status, pending.is_none()), (StatusCode::ACCEPTED, true));
+        assert!(starknet.get_tx(TX_HASH.to_owned()).await.is_err());
+    }
+}
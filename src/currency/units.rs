@@ -0,0 +1,83 @@
+//! Amounts of currencies in their base units, such as wei or octas, converted from and to
+//! decimal strings.
+
+use num::BigUint;
+
+use crate::error::BundlrError;
+
+/// `amount` in base units, e.g. 1_500_000 for `"1.5"` with 6 decimals.
+pub fn parse_units(amount: &str, decimals: u8) -> Result<u64, BundlrError> {
+    let amount = amount.trim();
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+        return Err(BundlrError::ParseError(format!(
+            "Invalid amount: {}",
+            amount
+        )));
+    }
+    if fraction.len() > decimals as usize {
+        return Err(BundlrError::ParseError(format!(
+            "{} has more than {} decimals",
+            amount, decimals
+        )));
+    }
+    format!("{}{:0<width$}", whole, fraction, width = decimals as usize)
+        .parse()
+        .map_err(|_| BundlrError::ParseError(format!("{} is too large", amount)))
+}
+
+/// `amount` of base units, without trailing zeros, e.g. `"1.5"` for 1_500_000 with 6 decimals.
+pub fn format_units(amount: u64, decimals: u8) -> String {
+    format_digits(amount.to_string(), decimals)
+}
+
+/// Same as [`format_units`], for amounts that may not fit in a `u64`.
+pub fn format_big_units(amount: &BigUint, decimals: u8) -> String {
+    format_digits(amount.to_string(), decimals)
+}
+
+fn format_digits(digits: String, decimals: u8) -> String {
+    let digits = format!("{:0>width$}", digits, width = decimals as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+    match fraction.trim_end_matches('0') {
+        "" => whole.to_owned(),
+        fraction => format!("{}.{}", whole, fraction),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::BigUint;
+
+    use super::{format_big_units, format_units, parse_units};
+    use crate::error::BundlrError;
+
+    #[test]
+    fn should_convert_amounts_with_decimals() {
+        assert_eq!(parse_units("1.5", 6).unwrap(), 1_500_000);
+        assert_eq!(parse_units("0.000001", 6).unwrap(), 1);
+        assert_eq!(parse_units(".25", 2).unwrap(), 25);
+        assert_eq!(parse_units("42", 0).unwrap(), 42);
+        assert_eq!(parse_units("18.4", 18).unwrap(), 18_400_000_000_000_000_000);
+        for invalid in ["", ".", "1.2.3", "-1", "1e6", "0.0000001", "1,5"] {
+            assert!(
+                matches!(parse_units(invalid, 6), Err(BundlrError::ParseError(_))),
+                "{}",
+                invalid
+            );
+        }
+        assert!(matches!(
+            parse_units("19", 18),
+            Err(BundlrError::ParseError(_))
+        ));
+
+        assert_eq!(format_units(1_500_000, 6), "1.5");
+        assert_eq!(format_units(1, 6), "0.000001");
+        assert_eq!(format_units(42, 0), "42");
+        assert_eq!(format_units(3_000_000, 6), "3");
+        assert_eq!(format_units(0, 6), "0");
+        let wei = BigUint::from(u64::MAX) * 10u8;
+        assert_eq!(format_big_units(&wei, 18), "184.46744073709551615");
+    }
+}
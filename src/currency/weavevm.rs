@@ -0,0 +1,139 @@
+//! WeaveVM's native token, funding the node's `wvm` address through a WeaveVM RPC endpoint.
+//!
+//! WeaveVM is an EVM chain, so its currency is an [`EvmCurrency`] set up for it: items are
+//! signed as for Ethereum, and fees are estimated from gas as on other EVM chains.
+
+use reqwest::Url;
+
+use crate::error::BuilderError;
+
+use super::evm::{Chain, EvmCurrency, EvmCurrencyBuilder};
+
+pub const WEAVEVM: Chain = Chain {
+    chain_id: 9496,
+    name: "wvm",
+    ticker: "tWVM",
+    decimals: 18,
+};
+pub const WEAVEVM_RPC_URL: &str = crate::transport::WEAVEVM_TESTNET_RPC_URL;
+
+pub type WeaveVm = EvmCurrency;
+
+#[derive(Default)]
+pub struct WeaveVmBuilder {
+    rpc_url: Option<Url>,
+    wallet: Option<String>,
+}
+
+impl WeaveVmBuilder {
+    pub fn new() -> WeaveVmBuilder {
+        Default::default()
+    }
+
+    /// JSON-RPC endpoint of WeaveVM, defaults to [`WEAVEVM_RPC_URL`].
+    pub fn rpc_url(mut self, rpc_url: Url) -> WeaveVmBuilder {
+        self.rpc_url = Some(rpc_url);
+        self
+    }
+
+    /// Wallet's key, in base58 or as a hex private key.
+    pub fn wallet(mut self, wallet: &str) -> WeaveVmBuilder {
+        self.wallet = Some(wallet.into());
+        self
+    }
+
+    pub fn build(self) -> Result<WeaveVm, BuilderError> {
+        let builder = EvmCurrencyBuilder::new().chain(WEAVEVM).rpc_url(
+            self.rpc_url
+                .unwrap_or_else(|| Url::parse(WEAVEVM_RPC_URL).unwrap()),
+        );
+        match self.wallet {
+            Some(wallet) => builder.wallet(&wallet),
+            None => builder,
+        }
+        .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use reqwest::Url;
+
+    use super::{WeaveVmBuilder, WEAVEVM_RPC_URL};
+    use crate::{
+        currency::{
+            evm::tests::{mock_rpc, NODE_ADDRESS, TX_HASH, WALLET},
+            Currency, CurrencyType,
+        },
+        BundlrBuilder,
+    };
+
+    #[test]
+    fn should_default_to_the_weavevm_rpc() {
+        let wvm = WeaveVmBuilder::new().build().unwrap();
+        assert_eq!(wvm.rpc_url(), Some(Url::parse(WEAVEVM_RPC_URL).unwrap()));
+        assert_eq!((wvm.name(), wvm.chain_id()), ("wvm".to_owned(), 9496));
+        assert_eq!(wvm.get_type(), CurrencyType::Ethereum);
+    }
+
+    #[tokio::test]
+    async fn should_fund_the_nodes_wvm_address() {
+        let server = MockServer::start();
+        let estimate = mock_rpc(&server, "eth_estimateGas", r#""0x5208""#);
+        let gas_price = mock_rpc(&server, "eth_gasPrice", r#""0x3b9aca00""#);
+        let nonce = mock_rpc(&server, "eth_getTransactionCount", r#""0x0""#);
+        let sent = server.mock(|when, then| {
+            when.method(POST)
+                .path("/rpc")
+                .body_contains("eth_sendRawTransaction");
+            then.status(200).body(format!(
+                r#"{{"jsonrpc":"2.0","id":0,"result":"{}"}}"#,
+                TX_HASH
+            ));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(format!(
+                    r#"{{ "version": "0.2.0", "gateway": "arweave.net", "addresses": {{ "wvm": "{}" }} }}"#,
+                    NODE_ADDRESS
+                ));
+        });
+        let credited = server.mock(|when, then| {
+            when.method(POST)
+                .path("/account/balance/wvm")
+                .body_contains(TX_HASH);
+            then.status(200).body("OK");
+        });
+
+        let wvm = WeaveVmBuilder::new()
+            .rpc_url(Url::parse(&server.url("/rpc")).unwrap())
+            .wallet(WALLET)
+            .build()
+            .unwrap();
+        assert_eq!(
+            wvm.get_fee(1, NODE_ADDRESS, 1.0).await.unwrap(),
+            21_000 * 1_000_000_000
+        );
+        let bundlr = BundlrBuilder::new()
+            .url(Url::parse(&server.url("/")).unwrap())
+            .currency(wvm)
+            .fetch_pub_info()
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(bundlr.fund(1_000_000_000_000_000, None).await.unwrap());
+
+        estimate.assert_hits(3);
+        gas_price.assert_hits(2);
+        nonce.assert();
+        sent.assert();
+        credited.assert();
+    }
+}
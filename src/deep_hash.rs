@@ -8,7 +8,7 @@ use crate::{
     consts::{BLOB_AS_BUFFER, LIST_AS_BUFFER},
     error::BundlrError,
 };
-use futures::{Stream, TryStream, TryStreamExt};
+use futures::{Stream, TryStreamExt};
 
 pub enum DeepHashChunk<'a> {
     Chunk(Bytes),
@@ -16,8 +16,6 @@ pub enum DeepHashChunk<'a> {
     Chunks(Vec<DeepHashChunk<'a>>),
 }
 
-trait Foo: Stream<Item = anyhow::Result<Bytes>> + TryStream {}
-
 pub async fn deep_hash(chunk: DeepHashChunk<'_>) -> Result<Bytes, BundlrError> {
     match chunk {
         DeepHashChunk::Chunk(b) => {
@@ -0,0 +1,587 @@
+//! Uploads of whole directories, and the state that lets later runs upload only what changed.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use data_encoding::BASE64URL_NOPAD;
+use num::BigUint;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::{io::AsyncReadExt, sync::mpsc};
+use tokio_util::sync::CancellationToken;
+
+use crate::{error::BundlrError, glob::Pattern, manifest::Manifest};
+
+/// What earlier syncs of a directory uploaded, saved in a local file, so that
+/// [`Bundlr::sync_directory`](crate::Bundlr::sync_directory) only uploads new and changed files.
+///
+/// Files are known by the SHA-256 of their content and their content type, not by their path:
+/// a file moved or copied elsewhere is not uploaded again. Entries are never dropped, as the
+/// items they point to stay on the network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryState {
+    path: PathBuf,
+    state: SyncedState,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncedState {
+    items: BTreeMap<String, SyncedItem>,
+    manifest: Option<SyncedManifest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncedItem {
+    id: String,
+    content_type: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SyncedManifest {
+    id: String,
+    manifest: Manifest,
+}
+
+impl DirectoryState {
+    /// The state saved at `path`, or a new one saving there if there is none, with which
+    /// everything is uploaded.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, BundlrError> {
+        let path = path.into();
+        let state = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|err| BundlrError::InvalidDirectoryState(err.to_string()))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => SyncedState::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self { path, state })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Id of the item uploaded with content of SHA-256 `digest`, in base64url, and
+    /// `content_type`.
+    pub fn item_id(&self, digest: &str, content_type: Option<&str>) -> Option<&str> {
+        self.state
+            .items
+            .get(digest)
+            .filter(|item| item.content_type.as_deref() == content_type)
+            .map(|item| item.id.as_str())
+    }
+
+    /// Id of the manifest last uploaded, if it is `manifest`.
+    pub fn manifest_id(&self, manifest: &Manifest) -> Option<&str> {
+        self.state
+            .manifest
+            .as_ref()
+            .filter(|synced| synced.manifest == *manifest)
+            .map(|synced| synced.id.as_str())
+    }
+
+    pub(crate) fn record_item(
+        &mut self,
+        digest: String,
+        content_type: Option<String>,
+        id: String,
+    ) -> Result<(), BundlrError> {
+        self.state
+            .items
+            .insert(digest, SyncedItem { id, content_type });
+        self.save()
+    }
+
+    pub(crate) fn record_manifest(
+        &mut self,
+        manifest: Manifest,
+        id: String,
+    ) -> Result<(), BundlrError> {
+        self.state.manifest = Some(SyncedManifest { id, manifest });
+        self.save()
+    }
+
+    /// Writes the state next to the file before moving it in place, so that an interruption
+    /// never leaves it half written.
+    fn save(&self) -> Result<(), BundlrError> {
+        let bytes = serde_json::to_vec(&self.state)
+            .map_err(|err| BundlrError::InvalidDirectoryState(err.to_string()))?;
+        let mut partial = self.path.clone().into_os_string();
+        partial.push(".partial");
+        fs::write(&partial, bytes)?;
+        fs::rename(&partial, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Outcome of [`Bundlr::sync_directory`](crate::Bundlr::sync_directory).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectorySync {
+    /// Id of the manifest of the directory, under which gateways serve it.
+    pub manifest_id: String,
+    pub manifest: Manifest,
+    /// Whether the manifest was uploaded, as opposed to the one of the last sync being reused.
+    pub manifest_uploaded: bool,
+    /// Paths of the files uploaded, ordered.
+    pub uploaded: Vec<String>,
+    /// Paths of the files already uploaded by earlier syncs, ordered.
+    pub unchanged: Vec<String>,
+}
+
+/// What happened to a directory upload, in [`DirectoryProgress::event`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirectoryEvent {
+    /// The file at `path` started uploading.
+    FileStarted { path: String, bytes: u64 },
+    /// The file at `path` was uploaded as `id`.
+    FileUploaded {
+        path: String,
+        id: String,
+        bytes: u64,
+    },
+    /// The file at `path` was found uploaded by an earlier sync, as `id`.
+    FileUnchanged {
+        path: String,
+        id: String,
+        bytes: u64,
+    },
+    /// The file at `path` failed to upload, failing the whole directory.
+    FileFailed { path: String, error: String },
+    /// The manifest of the directory was uploaded as `id`, once every file was done.
+    ManifestUploaded { id: String },
+}
+
+/// Progress of a directory upload, sent to [`DirectoryHooks::events`] as each file starts and
+/// finishes. Files are done once uploaded or found unchanged, and so are their bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryProgress {
+    pub event: DirectoryEvent,
+    pub files_done: usize,
+    pub total_files: usize,
+    pub bytes_done: u64,
+    pub total_bytes: u64,
+}
+
+/// Hooks into a directory upload, to follow it and cancel it, see
+/// [`Bundlr::sync_directory_watched`](crate::Bundlr::sync_directory_watched).
+#[derive(Debug, Clone, Default)]
+pub struct DirectoryHooks {
+    events: Option<mpsc::UnboundedSender<DirectoryProgress>>,
+    cancel: Option<CancellationToken>,
+}
+
+impl DirectoryHooks {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sends the progress of the upload to `events`, in order. A file's last event is sent
+    /// before the upload of the manifest listing it starts.
+    pub fn events(mut self, events: mpsc::UnboundedSender<DirectoryProgress>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Stops the upload once `cancel` is cancelled, dropping the file being uploaded. Files
+    /// uploaded before are kept in the sync's state, if any, for the next sync to resume from.
+    pub fn cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    pub(crate) fn cancellation(&self) -> Option<&CancellationToken> {
+        self.cancel.as_ref()
+    }
+}
+
+/// Counts of a directory upload, reported to its hooks.
+pub(crate) struct DirectoryTally<'a> {
+    hooks: &'a DirectoryHooks,
+    files_done: usize,
+    total_files: usize,
+    bytes_done: u64,
+    total_bytes: u64,
+}
+
+impl<'a> DirectoryTally<'a> {
+    pub(crate) fn new(hooks: &'a DirectoryHooks, total_files: usize, total_bytes: u64) -> Self {
+        Self {
+            hooks,
+            files_done: 0,
+            total_files,
+            bytes_done: 0,
+            total_bytes,
+        }
+    }
+
+    /// Counts `event` in, and sends it with the updated counts.
+    pub(crate) fn report(&mut self, event: DirectoryEvent) {
+        match &event {
+            DirectoryEvent::FileUploaded { bytes, .. }
+            | DirectoryEvent::FileUnchanged { bytes, .. } => {
+                self.files_done += 1;
+                self.bytes_done += bytes;
+            }
+            _ => {}
+        }
+        if let Some(events) = &self.hooks.events {
+            // Nobody listening any more isn't the upload's concern
+            let _ = events.send(DirectoryProgress {
+                event,
+                files_done: self.files_done,
+                total_files: self.total_files,
+                bytes_done: self.bytes_done,
+                total_bytes: self.total_bytes,
+            });
+        }
+    }
+}
+
+/// Estimated cost of uploading a directory, from
+/// [`Bundlr::estimate_directory_cost`](crate::Bundlr::estimate_directory_cost).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryCost {
+    /// Files that would be uploaded, ordered by path.
+    pub files: Vec<FileCost>,
+    /// Cost of the manifest listing the files.
+    pub manifest: BigUint,
+    /// Total cost, files and manifest, in the currency's base units.
+    pub total: BigUint,
+    /// Balance of the currency's wallet on the node.
+    pub balance: BigUint,
+    /// Amount missing from the balance to pay for the upload, zero if it is enough.
+    pub shortfall: BigUint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileCost {
+    pub path: String,
+    /// Size of the file, which its price is for.
+    pub bytes: u64,
+    /// Cost of the file, in the currency's base units.
+    pub cost: BigUint,
+}
+
+/// Which files of a directory to upload, all of them by default.
+///
+/// # Example
+///
+/// ```
+/// # use bundlr_sdk::directory::DirectoryOptions;
+/// // A built site, without the source maps, dotfiles and anything its .gitignore files exclude
+/// let options = DirectoryOptions::new()
+///     .exclude("*.map")
+///     .exclude(".*")
+///     .ignore_files(".gitignore");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirectoryOptions {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    ignore_files: Vec<String>,
+}
+
+impl DirectoryOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Only uploads the files `pattern`, or another included pattern, matches, or that are in
+    /// a directory it matches. Patterns are gitignore-style, matched against paths relative to
+    /// the directory: `*.html`, `/assets/`, `docs/**/*.md`.
+    pub fn include(mut self, pattern: &str) -> Self {
+        self.include.extend(Pattern::new(pattern));
+        self
+    }
+
+    /// Skips the files and directories `pattern` matches, whatever else includes them, e.g.
+    /// `node_modules/` or `.*` for dotfiles.
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.exclude.extend(Pattern::new(pattern));
+        self
+    }
+
+    /// Also skips what the files named `name` in the directory and its subdirectories exclude,
+    /// as git does with `.gitignore` files: each holds a pattern per line, for paths relative to
+    /// the directory it is in, `!` in front of a pattern includes back what the patterns before
+    /// it excluded, and lines starting with `#` are comments. The files themselves are uploaded
+    /// unless excluded too.
+    pub fn ignore_files(mut self, name: &str) -> Self {
+        self.ignore_files.push(name.to_owned());
+        self
+    }
+
+    fn excludes(&self, rules: &[IgnoreRule], path: &str, is_dir: bool) -> bool {
+        if self
+            .exclude
+            .iter()
+            .any(|pattern| pattern.matches(path, is_dir))
+        {
+            return true;
+        }
+        // As with git, the last rule matching decides
+        rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(path, is_dir))
+            .is_some_and(|rule| !rule.negated)
+    }
+
+    /// Whether the file at the relative `path` is selected, none of the directories it is in
+    /// being excluded either. Ignore files aren't read.
+    #[cfg(feature = "archive")]
+    pub(crate) fn selects(&self, path: &str) -> bool {
+        let dirs = path.match_indices('/').map(|(end, _)| &path[..end]);
+        !dirs
+            .map(|dir| (dir, true))
+            .chain([(path, false)])
+            .any(|(path, is_dir)| self.excludes(&[], path, is_dir))
+            && self.includes(path)
+    }
+
+    fn includes(&self, path: &str) -> bool {
+        self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| pattern.matches_within(path))
+    }
+}
+
+/// A line of an ignore file.
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    /// Path of the directory of the ignore file, empty for the root.
+    base: String,
+    pattern: Pattern,
+    negated: bool,
+}
+
+impl IgnoreRule {
+    fn parse(file: &str, base: &str) -> Vec<Self> {
+        file.lines()
+            .map(|line| line.trim_end())
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let (line, negated) = match line.strip_prefix('!') {
+                    Some(line) => (line, true),
+                    None => (line, false),
+                };
+                Some(Self {
+                    base: base.to_owned(),
+                    pattern: Pattern::new(line)?,
+                    negated,
+                })
+            })
+            .collect()
+    }
+
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        let path = match self.base.as_str() {
+            "" => Some(path),
+            base => path
+                .strip_prefix(base)
+                .and_then(|path| path.strip_prefix('/')),
+        };
+        path.is_some_and(|path| self.pattern.matches(path, is_dir))
+    }
+}
+
+/// Files under the directory `root` that `options` selects, with their `/`-separated paths
+/// relative to it, ordered by path. Symbolic links to files count as files, others are
+/// skipped, and so are the directories excluded, whatever they hold.
+pub(crate) async fn files(
+    root: &Path,
+    options: &DirectoryOptions,
+) -> Result<Vec<(String, PathBuf)>, BundlrError> {
+    let mut files = vec![];
+    // Directories left to list, with their relative path and the ignore rules applying inside
+    let mut dirs = vec![(root.to_path_buf(), String::new(), vec![])];
+    while let Some((dir, dir_path, mut rules)) = dirs.pop() {
+        for name in &options.ignore_files {
+            match tokio::fs::read_to_string(dir.join(name)).await {
+                Ok(file) => rules.extend(IgnoreRule::parse(&file, &dir_path)),
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_str().ok_or_else(|| {
+                BundlrError::UploadError(format!("Non UTF-8 path {}", path.display()))
+            })?;
+            let relative = match dir_path.as_str() {
+                "" => name.to_owned(),
+                dir_path => format!("{}/{}", dir_path, name),
+            };
+            let is_dir = entry.file_type().await?.is_dir();
+            if options.excludes(&rules, &relative, is_dir) {
+                continue;
+            }
+            if is_dir {
+                dirs.push((path, relative, rules.clone()));
+                continue;
+            }
+            // Follows symbolic links, but not to directories, which could loop
+            let is_file = tokio::fs::metadata(&path)
+                .await
+                .is_ok_and(|metadata| metadata.is_file());
+            if is_file && options.includes(&relative) {
+                files.push((relative, path));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// SHA-256 of the content of the file at `path`, in base64url, read a piece at a time.
+pub(crate) async fn file_digest(path: &Path) -> Result<String, BundlrError> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        match file.read(&mut buf).await? {
+            0 => break,
+            read => hasher.update(&buf[..read]),
+        }
+    }
+    Ok(BASE64URL_NOPAD.encode(&hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{file_digest, files, DirectoryOptions, DirectoryState};
+    use crate::{error::BundlrError, manifest::Manifest};
+
+    /// Path of a state file for `test`, removed if left by an earlier run.
+    fn state_path(test: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "bundlr-directory-state-{}-{}.json",
+            test,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn should_select_files_with_patterns_and_ignore_files() {
+        let dir = std::env::temp_dir().join(format!("bundlr-select-dir-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for file in [
+            ".env",
+            ".gitignore",
+            "index.html",
+            "app.js",
+            "app.js.map",
+            "build/out.html",
+            "docs/.gitignore",
+            "docs/guide.md",
+            "docs/draft.md",
+            "docs/notes.txt",
+            "node_modules/lib/index.js",
+        ] {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, file).unwrap();
+        }
+        std::fs::write(dir.join(".gitignore"), "# Built\n/build/\n*.md\n").unwrap();
+        std::fs::write(dir.join("docs/.gitignore"), "!guide.md\n*.txt\n").unwrap();
+
+        let paths = |options| {
+            let dir = dir.clone();
+            async move {
+                let files = files(&dir, &options).await.unwrap();
+                files.into_iter().map(|(path, _)| path).collect::<Vec<_>>()
+            }
+        };
+        let all = paths(DirectoryOptions::new()).await;
+        let ignored = paths(
+            DirectoryOptions::new()
+                .exclude(".*")
+                .exclude("*.map")
+                .exclude("node_modules/")
+                .ignore_files(".gitignore"),
+        )
+        .await;
+        let included = paths(
+            DirectoryOptions::new()
+                .include("*.html")
+                .include("/docs/")
+                .exclude("draft.md"),
+        )
+        .await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(all.len(), 11);
+        assert_eq!(ignored, ["app.js", "docs/guide.md", "index.html"]);
+        assert_eq!(
+            included,
+            [
+                "build/out.html",
+                "docs/.gitignore",
+                "docs/guide.md",
+                "docs/notes.txt",
+                "index.html"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_save_what_was_synced() {
+        let path = state_path("save");
+        let mut state = DirectoryState::open(&path).unwrap();
+        assert_eq!(state.item_id("digest", None), None);
+        state
+            .record_item(
+                "digest".to_owned(),
+                Some("text/html".to_owned()),
+                "id".to_owned(),
+            )
+            .unwrap();
+        let manifest = Manifest::new().path("index.html", "id");
+        state
+            .record_manifest(manifest.clone(), "manifest-id".to_owned())
+            .unwrap();
+
+        let state = DirectoryState::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(state.item_id("digest", Some("text/html")), Some("id"));
+        // The same content with another type is another item
+        assert_eq!(state.item_id("digest", Some("text/plain")), None);
+        assert_eq!(state.manifest_id(&manifest), Some("manifest-id"));
+        assert_eq!(state.manifest_id(&manifest.path("404.html", "id")), None);
+    }
+
+    #[test]
+    fn should_reject_invalid_state_files() {
+        let path = state_path("invalid");
+        std::fs::write(&path, "not json").unwrap();
+        let res = DirectoryState::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(res, Err(BundlrError::InvalidDirectoryState(_))));
+    }
+
+    #[tokio::test]
+    async fn should_hash_file_contents() {
+        let path = state_path("digest");
+        std::fs::write(&path, "Hello").unwrap();
+        let digest = file_digest(&path).await;
+        std::fs::remove_file(&path).unwrap();
+        // SHA-256 of "Hello"
+        assert_eq!(
+            digest.unwrap(),
+            "GF-NsyJx_iX1Yab8k4suJkMG7DBO2lGAB9F2SCY4GWk"
+        );
+    }
+}
@@ -0,0 +1,473 @@
+//! Downloads of item data from a gateway, in concurrent ranges when it serves them, whole or
+//! from a byte range of it.
+//!
+//! Clients given fallback gateways with
+//! [`BundlrBuilder::fallback_gateway_urls`](crate::BundlrBuilder::fallback_gateway_urls) try
+//! them in order when a gateway can't be reached, times out before answering, or answers `404`
+//! or `5xx`. Once a gateway started sending the data, failures are returned as they are.
+
+use std::io::Write;
+
+use futures::{stream, StreamExt, TryStreamExt};
+use reqwest::{
+    header::{CONTENT_RANGE, RANGE},
+    RequestBuilder, Response, StatusCode, Url,
+};
+
+use crate::{
+    error::BundlrError,
+    redirect::{Redirects, RequestKind},
+    tags::Tag,
+};
+
+/// How to download an item's data, all of it in a single request by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DownloadOptions {
+    parallel: Option<(usize, u64)>,
+    range: Option<(u64, u64)>,
+}
+
+impl DownloadOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Downloads the data in ranges of `segment_size` bytes, `segments` of them at a time, if
+    /// the gateway serves ranges of it. Segments are written in order, so at most `segments`
+    /// of them are held in memory.
+    ///
+    /// Falls back to a single request when the gateway ignores ranges or doesn't tell the
+    /// length of the data.
+    pub fn parallel(mut self, segments: usize, segment_size: u64) -> Self {
+        self.parallel = Some((segments.max(1), segment_size.max(1)));
+        self
+    }
+
+    /// Downloads only `length` bytes of the data from `offset`, e.g. for a media player to
+    /// seek, fewer when the data ends first. Gateways that ignore ranges send all the data, of
+    /// which only the range is kept.
+    pub fn range(mut self, offset: u64, length: u64) -> Self {
+        self.range = Some((offset, length));
+        self
+    }
+
+    pub(crate) fn has_range(&self) -> bool {
+        self.range.is_some()
+    }
+
+    /// Bytes `from..to` of the data to download.
+    fn window(&self) -> (u64, u64) {
+        match self.range {
+            Some((offset, length)) => (offset, offset.saturating_add(length)),
+            None => (0, u64::MAX),
+        }
+    }
+}
+
+/// Data of an item downloaded with [`Bundlr::download`](crate::Bundlr::download), checked
+/// against its signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadedItem {
+    pub id: String,
+    pub data: Vec<u8>,
+    pub tags: Vec<Tag>,
+    /// Public key that signed the item.
+    pub owner: Vec<u8>,
+    /// Root URL of the gateway that served the data.
+    pub gateway: Url,
+}
+
+/// Which gateway served a download, see the [module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadSource {
+    /// Root URL of the gateway.
+    pub gateway: Url,
+    /// Length of the data written.
+    pub length: u64,
+}
+
+/// Writes the data of the item `id`, or the range of it `options` ask for, to `writer`, from the
+/// first of `gateways` serving it.
+pub(crate) async fn download<W: Write>(
+    client: &reqwest::Client,
+    redirects: &Redirects,
+    gateways: &[Url],
+    id: &str,
+    writer: &mut W,
+    options: DownloadOptions,
+) -> Result<DownloadSource, BundlrError> {
+    let (from, to) = options.window();
+    let mut failure = None;
+    for gateway in gateways {
+        if from == to {
+            return Ok(DownloadSource {
+                gateway: gateway.clone(),
+                length: 0,
+            });
+        }
+        let url = gateway
+            .join(id)
+            .map_err(|err| BundlrError::ParseError(err.to_string()))?;
+        // A gateway serving ranges answers a probe with the first byte, and the full length
+        let req = match options.parallel {
+            Some(_) => client
+                .get(url.clone())
+                .header(RANGE, format!("bytes={}-{}", from, from)),
+            None => whole(client, url.clone(), options),
+        };
+        match redirects.send(RequestKind::Read, req).await {
+            Ok((res, _)) if !falls_back(res.status()) => {
+                let length = read(client, redirects, url, res, writer, options).await?;
+                return Ok(DownloadSource {
+                    gateway: gateway.clone(),
+                    length,
+                });
+            }
+            Ok((res, _)) => failure = Some(status_error(res).await),
+            Err(err) => failure = Some(err),
+        }
+    }
+    Err(failure.unwrap_or_else(|| BundlrError::Unknown("No gateway to download from".to_owned())))
+}
+
+/// Whether a gateway answering with `status` may lack data another one serves.
+fn falls_back(status: StatusCode) -> bool {
+    status == StatusCode::NOT_FOUND
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status.is_server_error()
+}
+
+/// Request of all the data at `url` `options` ask for.
+fn whole(client: &reqwest::Client, url: Url, options: DownloadOptions) -> RequestBuilder {
+    let (from, to) = options.window();
+    match options.range {
+        Some(_) => client
+            .get(url)
+            .header(RANGE, format!("bytes={}-{}", from, to - 1)),
+        None => client.get(url),
+    }
+}
+
+/// Writes the data at `url` to `writer` out of `res`, the answer to the first request of it:
+/// a probe when downloading in parallel.
+async fn read<W: Write>(
+    client: &reqwest::Client,
+    redirects: &Redirects,
+    url: Url,
+    res: Response,
+    writer: &mut W,
+    options: DownloadOptions,
+) -> Result<u64, BundlrError> {
+    let (from, to) = options.window();
+    let (segments, segment_size) = match options.parallel {
+        Some(parallel) => parallel,
+        None => return write_window(res, writer, from, to).await,
+    };
+    let probe = res;
+    let length = match probe.status() {
+        StatusCode::PARTIAL_CONTENT => probe.headers().get(CONTENT_RANGE).and_then(|range| {
+            let (_, length) = range.to_str().ok()?.rsplit_once('/')?;
+            length.parse::<u64>().ok()
+        }),
+        _ => return write_window(probe, writer, from, to).await,
+    };
+    let to = match length {
+        Some(length) => to.min(length),
+        None => {
+            let req = whole(client, url, options);
+            let (res, _) = redirects.send(RequestKind::Read, req).await?;
+            return write_window(res, writer, from, to).await;
+        }
+    };
+
+    let ranges = (from..to)
+        .step_by(segment_size as usize)
+        .map(|start| (start, (start + segment_size).min(to) - 1));
+    let mut segments = stream::iter(ranges)
+        .map(|(start, end)| {
+            let req = client
+                .get(url.clone())
+                .header(RANGE, format!("bytes={}-{}", start, end));
+            async move {
+                let (res, _) = redirects.send(RequestKind::Read, req).await?;
+                if res.status() != StatusCode::PARTIAL_CONTENT {
+                    return Err(BundlrError::ResponseError(format!(
+                        "Status: {} for bytes {}-{}",
+                        res.status(),
+                        start,
+                        end
+                    )));
+                }
+                let segment = res
+                    .bytes()
+                    .await
+                    .map_err(|err| BundlrError::ResponseError(err.to_string()))?;
+                match segment.len() as u64 == end - start + 1 {
+                    true => Ok(segment),
+                    false => Err(BundlrError::ResponseError(format!(
+                        "Got {} bytes for bytes {}-{}",
+                        segment.len(),
+                        start,
+                        end
+                    ))),
+                }
+            }
+        })
+        .buffered(segments);
+    while let Some(segment) = segments.try_next().await? {
+        writer.write_all(&segment)?;
+    }
+    Ok(to - from)
+}
+
+async fn status_error(res: Response) -> BundlrError {
+    let status = res.status();
+    let text = res.text().await.unwrap_or_default();
+    BundlrError::ResponseError(format!("Status: {}:{:?}", status, text))
+}
+
+/// Writes bytes `from..to` of the data to `writer` out of `res`, the answer to a request of
+/// them: a partial one starts at `from`, while the whole data is cut to the range.
+async fn write_window<W: Write>(
+    res: Response,
+    writer: &mut W,
+    from: u64,
+    to: u64,
+) -> Result<u64, BundlrError> {
+    let status = res.status();
+    if !status.is_success() {
+        return Err(status_error(res).await);
+    }
+    let (mut skip, mut left) = match status {
+        StatusCode::PARTIAL_CONTENT => (0, to - from),
+        _ => (from, to - from),
+    };
+    let mut body = res.bytes_stream();
+    let mut length = 0;
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|err| BundlrError::ResponseError(err.to_string()))?;
+        let start = skip.min(chunk.len() as u64);
+        skip -= start;
+        let end = (start + left).min(chunk.len() as u64);
+        writer.write_all(&chunk[start as usize..end as usize])?;
+        left -= end - start;
+        length += end - start;
+        if left == 0 {
+            break;
+        }
+    }
+    Ok(length)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use httpmock::{Method::GET, MockServer};
+    use reqwest::Url;
+
+    use super::{download, DownloadOptions};
+    use crate::{error::BundlrError, redirect::Redirects};
+
+    fn data() -> Vec<u8> {
+        (0..1000u32).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    fn gateway(server: &MockServer) -> Url {
+        Url::parse(&server.url("/")).unwrap()
+    }
+
+    async fn get(server: &MockServer, options: DownloadOptions) -> Vec<u8> {
+        let mut out = vec![];
+        let source = download(
+            &reqwest::Client::new(),
+            &Redirects::default(),
+            &[gateway(server)],
+            "item-id",
+            &mut out,
+            options,
+        )
+        .await
+        .unwrap();
+        assert_eq!(source.length, out.len() as u64);
+        out
+    }
+
+    #[tokio::test]
+    async fn should_download_ranges_concurrently() {
+        let data = data();
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/item-id")
+                .header("range", "bytes=0-0");
+            then.status(206)
+                .header("content-range", "bytes 0-0/1000")
+                .body(&data[..1]);
+        });
+        let segments: Vec<_> = (0..1000)
+            .step_by(125)
+            .map(|start| {
+                let end = start + 124;
+                server.mock(|when, then| {
+                    when.method(GET)
+                        .path("/item-id")
+                        .header("range", format!("bytes={}-{}", start, end));
+                    then.status(206)
+                        .header("content-range", format!("bytes {}-{}/1000", start, end))
+                        .delay(Duration::from_millis(300))
+                        .body(&data[start..=end]);
+                })
+            })
+            .collect();
+
+        let started = Instant::now();
+        let out = get(&server, DownloadOptions::new().parallel(4, 125)).await;
+        let elapsed = started.elapsed();
+        assert!(out == data, "Downloaded data differs");
+        for segment in &segments {
+            segment.assert();
+        }
+        // 8 segments of 300 ms each, 4 at a time, take about 600 ms
+        assert!(elapsed < Duration::from_millis(1500), "Took {:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn should_fall_back_to_a_single_request() {
+        let data = data();
+        let server = MockServer::start();
+        let whole = server.mock(|when, then| {
+            when.method(GET).path("/item-id");
+            then.status(200).body(&data);
+        });
+        assert!(get(&server, DownloadOptions::new().parallel(4, 100)).await == data);
+        assert!(get(&server, DownloadOptions::new()).await == data);
+        whole.assert_hits(2);
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/item-id")
+                .header("range", "bytes=0-0");
+            then.status(206)
+                .header("content-range", "bytes 0-0/*")
+                .body(&data[..1]);
+        });
+        let whole = server.mock(|when, then| {
+            when.method(GET).path("/item-id").matches(|req| {
+                let headers = req.headers.as_deref().unwrap_or_default();
+                !headers
+                    .iter()
+                    .any(|(name, _)| name.eq_ignore_ascii_case("range"))
+            });
+            then.status(200).body(&data);
+        });
+        assert!(get(&server, DownloadOptions::new().parallel(4, 100)).await == data);
+        whole.assert();
+    }
+
+    #[tokio::test]
+    async fn should_download_byte_ranges() {
+        let data = data();
+        let server = MockServer::start();
+        let range = |start: usize, end: usize| {
+            server.mock(|when, then| {
+                when.method(GET)
+                    .path("/item-id")
+                    .header("range", format!("bytes={}-{}", start, end));
+                then.status(206)
+                    .header("content-range", format!("bytes {}-{}/1000", start, end))
+                    .body(&data[start..=end.min(999)]);
+            })
+        };
+        let single = range(100, 199);
+        let options = DownloadOptions::new().range(100, 100);
+        assert!(get(&server, options).await == data[100..200]);
+        single.assert();
+
+        // In segments, up to the end of the data
+        let probes = [range(900, 900), range(900, 949), range(950, 999)];
+        let options = DownloadOptions::new().range(900, 500).parallel(2, 50);
+        assert!(get(&server, options).await == data[900..]);
+        for probe in &probes {
+            probe.assert();
+        }
+
+        // Cut out of the whole data by gateways ignoring ranges
+        let server = MockServer::start();
+        let whole = server.mock(|when, then| {
+            when.method(GET).path("/item-id");
+            then.status(200).body(&data);
+        });
+        let options = DownloadOptions::new().range(100, 100);
+        assert!(get(&server, options).await == data[100..200]);
+        assert!(get(&server, options.parallel(4, 10)).await == data[100..200]);
+        assert!(get(&server, DownloadOptions::new().range(990, 100)).await == data[990..]);
+        assert!(get(&server, DownloadOptions::new().range(0, 0))
+            .await
+            .is_empty());
+        whole.assert_hits(3);
+    }
+
+    #[tokio::test]
+    async fn should_fall_back_to_other_gateways() {
+        let data = data();
+        let (missing, slow, serving) = (
+            MockServer::start(),
+            MockServer::start(),
+            MockServer::start(),
+        );
+        let missed = missing.mock(|when, then| {
+            when.method(GET).path("/item-id");
+            then.status(404);
+        });
+        slow.mock(|when, then| {
+            when.method(GET).path("/item-id");
+            then.status(200).delay(Duration::from_secs(2)).body(&data);
+        });
+        let served = serving.mock(|when, then| {
+            when.method(GET).path("/item-id");
+            then.status(200).body(&data);
+        });
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+        let gateways = [gateway(&missing), gateway(&slow), gateway(&serving)];
+        let fetch = |gateways: Vec<Url>| {
+            let client = client.clone();
+            async move {
+                let mut out = vec![];
+                let options = DownloadOptions::new();
+                download(
+                    &client,
+                    &Redirects::default(),
+                    &gateways,
+                    "item-id",
+                    &mut out,
+                    options,
+                )
+                .await
+                .map(|source| (source, out))
+            }
+        };
+
+        let (source, out) = fetch(gateways.to_vec()).await.unwrap();
+        assert_eq!(source.gateway, gateways[2]);
+        assert!(out == data);
+        missed.assert();
+        served.assert();
+
+        // Only a missing item or an unavailable gateway moves on to the next one
+        let refusing = MockServer::start();
+        refusing.mock(|when, then| {
+            when.method(GET).path("/item-id");
+            then.status(403);
+        });
+        let res = fetch(vec![gateway(&refusing), gateways[2].clone()]).await;
+        assert!(matches!(res, Err(BundlrError::ResponseError(_))));
+        served.assert_hits(1);
+        let res = fetch(gateways[..2].to_vec()).await;
+        assert!(matches!(res, Err(BundlrError::ResponseError(_))));
+    }
+}
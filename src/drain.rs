@@ -0,0 +1,282 @@
+use std::time::{Duration, SystemTime};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, RETRY_AFTER},
+    StatusCode,
+};
+use tokio::sync::watch;
+
+/// Whether the node accepts uploads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DrainState {
+    #[default]
+    Serving,
+    /// The node is in maintenance: it rejects uploads, but still serves reads.
+    Draining { retry_after: Option<Duration> },
+}
+
+/// Recognizes the responses of a draining node.
+///
+/// A response matches if it has the given status, and the body and header if set.
+#[derive(Debug, Clone)]
+pub struct DrainMatcher {
+    status: StatusCode,
+    body_contains: Option<String>,
+    header: Option<(HeaderName, Option<String>)>,
+}
+
+impl DrainMatcher {
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            body_contains: None,
+            header: None,
+        }
+    }
+
+    /// Requires the body to contain `needle`, ignoring case.
+    pub fn body_contains(mut self, needle: &str) -> Self {
+        self.body_contains = Some(needle.to_lowercase());
+        self
+    }
+
+    /// Requires a `name` header, with the given value if any.
+    pub fn header(mut self, name: HeaderName, value: Option<&str>) -> Self {
+        self.header = Some((name, value.map(str::to_owned)));
+        self
+    }
+
+    fn matches(&self, status: StatusCode, headers: &HeaderMap, body: &[u8]) -> bool {
+        let body_matches = self.body_contains.as_ref().is_none_or(|needle| {
+            String::from_utf8_lossy(body)
+                .to_lowercase()
+                .contains(needle.as_str())
+        });
+        let header_matches = self.header.as_ref().is_none_or(|(name, value)| {
+            headers.get(name).is_some_and(|actual| {
+                value
+                    .as_ref()
+                    .is_none_or(|value| actual.as_bytes() == value.as_bytes())
+            })
+        });
+        status == self.status && body_matches && header_matches
+    }
+}
+
+impl Default for DrainMatcher {
+    /// A `503 Service Unavailable` mentioning `draining` in its body.
+    fn default() -> Self {
+        Self::new(StatusCode::SERVICE_UNAVAILABLE).body_contains("draining")
+    }
+}
+
+/// How the client reacts to a draining node.
+///
+/// By default, uploads fail right away with
+/// [`BundlrError::NodeDraining`](crate::error::BundlrError::NodeDraining). With
+/// [`DrainPolicy::pause_writes`], they wait for the node to recover instead.
+#[derive(Debug, Clone)]
+pub struct DrainPolicy {
+    matchers: Vec<DrainMatcher>,
+    max_pause: Duration,
+    default_retry_after: Duration,
+}
+
+impl Default for DrainPolicy {
+    fn default() -> Self {
+        Self {
+            matchers: vec![DrainMatcher::default()],
+            max_pause: Duration::ZERO,
+            default_retry_after: Duration::from_secs(5),
+        }
+    }
+}
+
+impl DrainPolicy {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Recognizes draining nodes with `matchers` instead of the default one.
+    pub fn matchers(mut self, matchers: Vec<DrainMatcher>) -> Self {
+        self.matchers = matchers;
+        self
+    }
+
+    /// Holds uploads while the node drains, for at most `max_pause` in total, retrying them
+    /// when it says it may be back. Reads are never held.
+    pub fn pause_writes(mut self, max_pause: Duration) -> Self {
+        self.max_pause = max_pause;
+        self
+    }
+
+    /// How long to wait before retrying when the node doesn't send a `Retry-After` header.
+    pub fn default_retry_after(mut self, retry_after: Duration) -> Self {
+        self.default_retry_after = retry_after;
+        self
+    }
+
+    /// State of the node that sent a response, at `now`.
+    pub(crate) fn detect(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+        now: SystemTime,
+    ) -> DrainState {
+        match self
+            .matchers
+            .iter()
+            .any(|matcher| matcher.matches(status, headers, body))
+        {
+            true => DrainState::Draining {
+                retry_after: retry_after(headers, now),
+            },
+            false => DrainState::Serving,
+        }
+    }
+
+    /// How long to wait before trying again, if allowed after already waiting for `paused`.
+    pub(crate) fn pause(
+        &self,
+        retry_after: Option<Duration>,
+        paused: Duration,
+    ) -> Option<Duration> {
+        let left = self
+            .max_pause
+            .checked_sub(paused)
+            .filter(|left| !left.is_zero())?;
+        Some(retry_after.unwrap_or(self.default_retry_after).min(left))
+    }
+}
+
+/// Drain state of a client's node, along with the policy to detect it.
+pub(crate) struct Drain {
+    pub(crate) policy: DrainPolicy,
+    state: watch::Sender<DrainState>,
+}
+
+impl Drain {
+    pub(crate) fn new(policy: DrainPolicy) -> Self {
+        Self {
+            policy,
+            state: watch::channel(DrainState::Serving).0,
+        }
+    }
+
+    pub(crate) fn state(&self) -> DrainState {
+        *self.state.borrow()
+    }
+
+    pub(crate) fn subscribe(&self) -> watch::Receiver<DrainState> {
+        self.state.subscribe()
+    }
+
+    pub(crate) fn set(&self, state: DrainState) {
+        self.state.send_if_modified(|current| {
+            let changed = *current != state;
+            *current = state;
+            changed
+        });
+    }
+}
+
+/// Reads a `Retry-After` header, given either in seconds or as a date, counting from `now`.
+pub(crate) fn retry_after(headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => {
+            let date = httpdate::parse_http_date(value).ok()?;
+            Some(date.duration_since(now).unwrap_or_default())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use reqwest::{
+        header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER},
+        StatusCode,
+    };
+
+    use super::{retry_after, DrainMatcher, DrainPolicy, DrainState};
+
+    #[test]
+    fn should_detect_draining_responses() {
+        let now = SystemTime::now();
+        let policy = DrainPolicy::new();
+        let mut headers = HeaderMap::new();
+        let unavailable = StatusCode::SERVICE_UNAVAILABLE;
+        assert_eq!(
+            policy.detect(unavailable, &headers, b"Node is DRAINING", now),
+            DrainState::Draining { retry_after: None }
+        );
+        assert_eq!(
+            policy.detect(unavailable, &headers, b"Overloaded", now),
+            DrainState::Serving
+        );
+        assert_eq!(
+            policy.detect(StatusCode::OK, &headers, b"draining", now),
+            DrainState::Serving
+        );
+
+        let maintenance = HeaderName::from_static("x-maintenance");
+        let policy = policy.matchers(vec![
+            DrainMatcher::new(unavailable).header(maintenance.clone(), Some("drain"))
+        ]);
+        headers.insert(maintenance, HeaderValue::from_static("drain"));
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("12"));
+        assert_eq!(
+            policy.detect(unavailable, &headers, b"", now),
+            DrainState::Draining {
+                retry_after: Some(Duration::from_secs(12))
+            }
+        );
+    }
+
+    #[test]
+    fn should_parse_retry_after() {
+        let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers, now), None);
+
+        let cases = [
+            ("12", Some(Duration::from_secs(12))),
+            (
+                "Wed, 21 Oct 2015 07:30:00 GMT",
+                Some(Duration::from_secs(120)),
+            ),
+            ("Wed, 21 Oct 2015 07:00:00 GMT", Some(Duration::ZERO)),
+            ("soon", None),
+        ];
+        for (value, expected) in cases {
+            headers.insert(RETRY_AFTER, HeaderValue::from_static(value));
+            assert_eq!(retry_after(&headers, now), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn should_bound_pauses() {
+        let policy = DrainPolicy::new();
+        assert_eq!(
+            policy.pause(Some(Duration::from_secs(1)), Duration::ZERO),
+            None
+        );
+
+        let policy = policy
+            .pause_writes(Duration::from_secs(10))
+            .default_retry_after(Duration::from_secs(4));
+        assert_eq!(
+            policy.pause(None, Duration::ZERO),
+            Some(Duration::from_secs(4))
+        );
+        assert_eq!(
+            policy.pause(Some(Duration::from_secs(30)), Duration::from_secs(8)),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(policy.pause(None, Duration::from_secs(10)), None);
+    }
+}
@@ -1,7 +1,12 @@
+use std::{fmt, ops::RangeInclusive, time::Duration};
+
 use thiserror::Error;
+#[cfg(any(feature = "ethereum", feature = "erc20"))]
 use web3::signing::RecoveryError;
 
+#[cfg(any(feature = "ethereum", feature = "erc20"))]
 use crate::utils::Eip712Error;
+use crate::{ack::AckLevel, address_book::LabeledAddress, schema::SchemaDiff};
 
 #[derive(Debug, Error)]
 pub enum BundlrError {
@@ -20,6 +25,12 @@ pub enum BundlrError {
     #[error("Invalid tag encoding.")]
     InvalidTagEncoding,
 
+    #[error("Invalid chunked tag: {0}")]
+    InvalidChunkedTag(String),
+
+    #[error("Could not convert tags: {0}")]
+    TagSerialization(String),
+
     #[error("File system error: {0}")]
     FsError(String),
 
@@ -65,6 +76,7 @@ pub enum BundlrError {
     #[error("Cannot convert file stream to known bytes. Try using another method")]
     InvalidDataType,
 
+    #[cfg(feature = "arweave")]
     #[error("Arweave Sdk error: {0}")]
     ArweaveSdkError(arweave_rs::error::Error),
 
@@ -77,6 +89,9 @@ pub enum BundlrError {
     #[error("Error converting type: {0}")]
     TypeParseError(String),
 
+    #[error("Invalid number for {field}: {raw:?}")]
+    NumericParse { field: String, raw: String },
+
     #[error("Parse error error: {0}")]
     ParseError(String),
 
@@ -89,9 +104,16 @@ pub enum BundlrError {
     #[error("Unsupported: {0}")]
     Unsupported(String),
 
+    #[cfg(any(
+        feature = "solana",
+        feature = "algorand",
+        feature = "aptos",
+        feature = "near"
+    ))]
     #[error("ED25519 error: {0}")]
     ED25519Error(ed25519_dalek::ed25519::Error),
 
+    #[cfg(any(feature = "ethereum", feature = "erc20", feature = "cosmos"))]
     #[error("Secp256k1 error: {0}")]
     Secp256k1Error(secp256k1::Error),
 
@@ -104,11 +126,265 @@ pub enum BundlrError {
     #[error("Builder error: {0}")]
     BuilderError(BuilderError),
 
+    #[cfg(any(feature = "ethereum", feature = "erc20"))]
     #[error("Eip712 error: {0}")]
     Eip712Error(Eip712Error),
 
+    #[cfg(any(feature = "ethereum", feature = "erc20"))]
     #[error("RecoveryError")]
     RecoveryError(RecoveryError),
+
+    #[error("Unverified response: {0}")]
+    UnverifiedResponse(String),
+
+    #[error("Client is shutting down.")]
+    ShuttingDown,
+
+    #[error("Upload queue is closed.")]
+    QueueClosed,
+
+    #[error("Could not fund {to}: {source}")]
+    FundingFailed {
+        to: LabeledAddress,
+        source: Box<BundlrError>,
+    },
+
+    #[error("Upload redirected to {location}, and not sent there")]
+    RedirectedUpload {
+        location: String,
+        /// Locations redirected to, the last one included.
+        chain: Vec<String>,
+    },
+
+    #[error("Redirect to {location} not followed")]
+    RedirectNotFollowed {
+        location: String,
+        /// Locations redirected to, the last one included.
+        chain: Vec<String>,
+    },
+
+    #[error("Refusing to send requests to {url} without TLS")]
+    InsecureTransport { url: String },
+
+    #[error("Invalid contract interaction: {0}")]
+    InvalidInteraction(String),
+
+    #[error("Invalid tombstone: {0}")]
+    InvalidTombstone(String),
+
+    #[error("Invalid manifest: {0}")]
+    InvalidManifest(String),
+
+    #[error("Invalid directory state: {0}")]
+    InvalidDirectoryState(String),
+
+    #[error("Rejected before signing: {0}")]
+    QuotaExceeded(QuotaExceeded),
+
+    #[error("Not the owner of {target_id}, which only its owner can tombstone")]
+    TombstoneNotOwner { target_id: String },
+
+    #[error("Aborted before sending anything: {0}")]
+    FundAborted(FundCheck),
+
+    #[error("Aborted before sending anything: the node's address changed from {old} to {new}")]
+    FundTargetChanged { old: String, new: String },
+
+    #[error(
+        "Sent {tx_id}, but the node did not credit it, submit it again with `Bundlr::submit_fund_tx`: {source}"
+    )]
+    FundNotCredited {
+        tx_id: String,
+        source: Box<BundlrError>,
+    },
+
+    #[error("Not enough balance to pay for the upload: {0}")]
+    InsufficientBalance(String),
+
+    #[error("Signed by {signer}, not the node's key")]
+    UntrustedSigner { signer: String },
+
+    #[error("Transaction {tx_id} was dropped from the chain")]
+    TxDropped {
+        tx_id: String,
+        /// Confirmations it last had, if it made it into a block before being reorged out.
+        confirmations: Option<u64>,
+    },
+
+    #[error("Transaction {tx_id} not confirmed after {attempts} polls")]
+    ConfirmationTimeout {
+        tx_id: String,
+        attempts: u32,
+        confirmations: Option<u64>,
+    },
+
+    #[error("Unexpected response schema: {0}")]
+    SchemaMismatch(SchemaDiff),
+
+    #[error("Publish aborted with {} orphaned items: {source}", orphans.len())]
+    PublishAborted {
+        orphans: Vec<String>,
+        source: Box<BundlrError>,
+    },
+
+    #[error("Invalid resume token: {0}")]
+    InvalidResumeToken(String),
+
+    #[error("Invalid upload session: {0}")]
+    InvalidUploadSession(String),
+
+    #[error("Unexpected {kind} response with status {status}: {snippet:?}")]
+    UnexpectedResponseFormat {
+        kind: ResponseFormatKind,
+        status: u16,
+        /// Sanitized excerpt of the body.
+        snippet: String,
+    },
+
+    #[error("Item {item_id} is not in bundle {bundle_id}")]
+    ItemNotInBundle { item_id: String, bundle_id: String },
+
+    #[error("Malformed bundle {0}")]
+    MalformedBundle(String),
+
+    #[error("Node is draining for maintenance, retry after {retry_after:?}")]
+    NodeDraining { retry_after: Option<Duration> },
+
+    #[error("Rate limited by the node, retry after {retry_after:?}")]
+    RateLimited { retry_after: Option<Duration> },
+
+    #[error("Timestamp {timestamp} is outside of the allowed window {allowed_window:?}")]
+    TimestampOutOfRange {
+        timestamp: u64,
+        allowed_window: RangeInclusive<u64>,
+    },
+
+    #[error("Currency RPC {rpc_url} belongs to another network than {network}")]
+    NetworkMismatch { network: String, rpc_url: String },
+
+    #[error("Downloaded data of {id} isn't the data it was signed for")]
+    DataMismatch { id: String },
+
+    #[error("{field} of {amount} doesn't fit in a u64")]
+    AmountOverflow { field: String, amount: u128 },
+
+    #[error("Upload {id} not acknowledged as {wanted:?} in time, only as {achieved:?}")]
+    AckTimeout {
+        id: String,
+        wanted: AckLevel,
+        /// Highest level reached before the deadline, if any.
+        achieved: Option<AckLevel>,
+    },
+
+    #[error("Malformed archive: {0}")]
+    MalformedArchive(String),
+
+    #[error(
+        "Directory upload cancelled with {} files done and {} left",
+        done.len(),
+        remaining.len()
+    )]
+    DirectoryCancelled {
+        /// Paths of the files uploaded, or found unchanged, before the upload was cancelled.
+        done: Vec<String>,
+        /// Paths of the files left to upload.
+        remaining: Vec<String>,
+    },
+}
+
+impl BundlrError {
+    /// Whether the request may succeed if sent again as is, e.g. a node in maintenance behind a
+    /// CDN serving an error page, or a client rate limited by the node.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            BundlrError::UnexpectedResponseFormat {
+                kind: ResponseFormatKind::Html,
+                status: 500..=599,
+                ..
+            } | BundlrError::RateLimited { .. }
+        )
+    }
+}
+
+/// Kind of non JSON body received in place of a node response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormatKind {
+    /// An HTML page, such as a CDN's maintenance or error page.
+    Html,
+    /// A Cloudflare challenge page: a WAF is blocking the client from reaching the node.
+    CloudflareChallenge,
+    /// No body at all.
+    Empty,
+}
+
+impl fmt::Display for ResponseFormatKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ResponseFormatKind::Html => "HTML",
+            ResponseFormatKind::CloudflareChallenge => "Cloudflare challenge",
+            ResponseFormatKind::Empty => "empty",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Ambiguity stopping a fund before any money moves. Each can be let through with
+/// [`FundOptions`](crate::bundlr::FundOptions).
+#[derive(Debug, Clone, PartialEq)]
+pub enum FundCheck {
+    /// The currency reports no fee, or a zero one.
+    ZeroFee,
+    /// The node lists no address under the currency's exact key, but has some under keys only
+    /// differing in case, or lists different addresses under such keys.
+    AmbiguousAddress(Vec<String>),
+    /// The fee multiplier is not a finite, positive number.
+    InvalidMultiplier(f64),
+    /// The node's address is empty or all zeros.
+    ZeroAddress,
+}
+
+impl fmt::Display for FundCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FundCheck::ZeroFee => f.write_str("no fee for the transfer"),
+            FundCheck::AmbiguousAddress(addresses) => {
+                write!(f, "ambiguous node addresses {}", addresses.join(", "))
+            }
+            FundCheck::InvalidMultiplier(multiplier) => {
+                write!(f, "invalid fee multiplier {}", multiplier)
+            }
+            FundCheck::ZeroAddress => f.write_str("zero node address"),
+        }
+    }
+}
+
+/// Kind of limit of a [`QuotaExceeded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaKind {
+    BytesPerDay,
+    ItemsPerDay,
+}
+
+impl fmt::Display for QuotaKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotaKind::BytesPerDay => f.write_str("bytes per day"),
+            QuotaKind::ItemsPerDay => f.write_str("items per day"),
+        }
+    }
+}
+
+/// Refusal of a [`QuotaManager`](crate::quota::QuotaManager) to reserve an upload.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Tenant {tenant} is over its quota of {limit} {kind}: {used} used, {requested} requested")]
+pub struct QuotaExceeded {
+    pub tenant: String,
+    pub kind: QuotaKind,
+    pub limit: u64,
+    /// Used or reserved so far.
+    pub used: u64,
+    pub requested: u64,
 }
 
 impl From<BuilderError> for BundlrError {
@@ -117,6 +393,7 @@ impl From<BuilderError> for BundlrError {
     }
 }
 
+#[cfg(feature = "arweave")]
 impl From<arweave_rs::error::Error> for BundlrError {
     fn from(value: arweave_rs::error::Error) -> Self {
         Self::ArweaveSdkError(value)
@@ -134,10 +411,15 @@ pub enum BuilderError {
     #[error("Fetch pub info error: {0}")]
     FetchPubInfoError(String),
 
+    #[error("Refusing to send requests to {url} without TLS")]
+    InsecureTransport { url: String },
+
+    #[cfg(feature = "arweave")]
     #[error("Arweave Sdk error: {0}")]
     ArweaveSdkError(arweave_rs::error::Error),
 }
 
+#[cfg(feature = "arweave")]
 impl From<arweave_rs::error::Error> for BuilderError {
     fn from(value: arweave_rs::error::Error) -> Self {
         Self::ArweaveSdkError(value)
@@ -146,6 +428,36 @@ impl From<arweave_rs::error::Error> for BuilderError {
 
 impl From<BundlrError> for BuilderError {
     fn from(value: BundlrError) -> Self {
-        Self::BundlrError(value.to_string())
+        match value {
+            BundlrError::InsecureTransport { url } => Self::InsecureTransport { url },
+            value => Self::BundlrError(value.to_string()),
+        }
+    }
+}
+
+/// Errors of the standalone verification functions of [`crate::verify`].
+#[derive(Debug, Error)]
+pub enum VerifyError {
+    #[error("Malformed input: {0}")]
+    Malformed(String),
+
+    #[error("Unsupported signer type.")]
+    UnsupportedSigner,
+
+    #[error("Invalid signature.")]
+    InvalidSignature,
+
+    #[error("Signed by a key that is not trusted.")]
+    UntrustedSigner,
+}
+
+impl From<BundlrError> for VerifyError {
+    fn from(value: BundlrError) -> Self {
+        match value {
+            BundlrError::InvalidSignature => Self::InvalidSignature,
+            BundlrError::InvalidSignerType => Self::UnsupportedSigner,
+            BundlrError::UntrustedSigner { .. } => Self::UntrustedSigner,
+            err => Self::Malformed(err.to_string()),
+        }
     }
 }
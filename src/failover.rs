@@ -0,0 +1,222 @@
+//! Failover between replicas of a node, for clients to keep working while one is down.
+//!
+//! A client given more nodes with
+//! [`BundlrBuilder::failover_urls`](crate::BundlrBuilder::failover_urls) sends its requests to
+//! the active node, its own at first. A request the active node fails to answer, with a network
+//! error or a `5xx` response, is sent to the other nodes in order until one answers. Once the
+//! active node failed [`FailoverPolicy::max_failures`] times in a row, the next healthy one
+//! takes over. Health checks with [`Bundlr::check_nodes`](crate::Bundlr::check_nodes) make the
+//! first healthy node active again, e.g. the client's own once it recovered.
+//!
+//! The nodes are expected to be replicas of one bundler, sharing its balances and address:
+//! the client keeps the public info of its own. Chunked uploads stay on the node they started
+//! on.
+
+use std::sync::Mutex;
+
+use reqwest::Url;
+use tokio::sync::watch;
+
+/// When a client changes its active node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FailoverPolicy {
+    max_failures: u32,
+}
+
+impl Default for FailoverPolicy {
+    fn default() -> Self {
+        Self { max_failures: 3 }
+    }
+}
+
+impl FailoverPolicy {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Fails over once the active node failed `failures` times in a row, 3 by default.
+    pub fn max_failures(mut self, failures: u32) -> Self {
+        self.max_failures = failures.max(1);
+        self
+    }
+}
+
+/// Health of a node, as checked by [`Bundlr::check_nodes`](crate::Bundlr::check_nodes).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeHealth {
+    pub url: Url,
+    pub healthy: bool,
+    /// Why the node is unhealthy.
+    pub error: Option<String>,
+}
+
+/// Nodes of a client in priority order, and which of them is active.
+#[derive(Debug)]
+pub(crate) struct Nodes {
+    urls: Vec<Url>,
+    policy: FailoverPolicy,
+    state: Mutex<State>,
+    active: watch::Sender<Url>,
+}
+
+#[derive(Debug)]
+struct State {
+    active: usize,
+    /// Failures in a row of each node.
+    failures: Vec<u32>,
+}
+
+impl State {
+    fn activate(&mut self, index: usize, urls: &[Url], active: &watch::Sender<Url>) {
+        if self.active != index {
+            self.active = index;
+            active.send_replace(urls[index].clone());
+        }
+    }
+}
+
+impl Nodes {
+    /// Nodes at `urls`, the first one active.
+    pub(crate) fn new(urls: Vec<Url>, policy: FailoverPolicy) -> Self {
+        Self {
+            state: Mutex::new(State {
+                active: 0,
+                failures: vec![0; urls.len()],
+            }),
+            active: watch::channel(urls[0].clone()).0,
+            urls,
+            policy,
+        }
+    }
+
+    pub(crate) fn urls(&self) -> &[Url] {
+        &self.urls
+    }
+
+    pub(crate) fn active(&self) -> Url {
+        self.active.borrow().clone()
+    }
+
+    pub(crate) fn subscribe(&self) -> watch::Receiver<Url> {
+        self.active.subscribe()
+    }
+
+    /// Where to send a request to `url`, in order: `url` moved to the active node, then to each
+    /// other node. Empty if `url` isn't on any node, for the request to be sent as is.
+    pub(crate) fn candidates(&self, url: &Url) -> Vec<(usize, Url)> {
+        let path = match self
+            .urls
+            .iter()
+            .find_map(|node| url.as_str().strip_prefix(node.as_str()))
+        {
+            Some(path) => path,
+            None => return vec![],
+        };
+        let active = self.state.lock().unwrap().active;
+        let others = (0..self.urls.len()).filter(|index| *index != active);
+        std::iter::once(active)
+            .chain(others)
+            .filter_map(|index| Some((index, self.urls[index].join(path).ok()?)))
+            .collect()
+    }
+
+    /// Counts a request answered by node `index`, or that it failed to answer. Fails over to the
+    /// next node with fewer failures in a row once the active one failed too many times.
+    pub(crate) fn record(&self, index: usize, answered: bool) {
+        let mut state = self.state.lock().unwrap();
+        if answered {
+            state.failures[index] = 0;
+            return;
+        }
+        state.failures[index] = state.failures[index].saturating_add(1);
+        let max = self.policy.max_failures;
+        if index != state.active || state.failures[index] < max {
+            return;
+        }
+        let len = self.urls.len();
+        let next = (1..len)
+            .map(|offset| (index + offset) % len)
+            .find(|next| state.failures[*next] < max);
+        if let Some(next) = next {
+            state.activate(next, &self.urls, &self.active);
+        }
+    }
+
+    /// Takes the outcome of health checks of every node, in order, making the first healthy one
+    /// active.
+    pub(crate) fn set_health(&self, healthy: &[bool]) {
+        let mut state = self.state.lock().unwrap();
+        for (failures, healthy) in state.failures.iter_mut().zip(healthy) {
+            *failures = match healthy {
+                true => 0,
+                false => self.policy.max_failures,
+            };
+        }
+        if let Some(first) = healthy.iter().position(|healthy| *healthy) {
+            state.activate(first, &self.urls, &self.active);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Url;
+
+    use super::{FailoverPolicy, Nodes};
+
+    fn nodes() -> Nodes {
+        let urls = ["http://primary/", "http://second/", "http://third/api/"]
+            .map(|url| Url::parse(url).unwrap());
+        Nodes::new(urls.to_vec(), FailoverPolicy::new().max_failures(2))
+    }
+
+    #[test]
+    fn should_move_requests_to_the_active_node_first() {
+        let nodes = nodes();
+        let url = Url::parse("http://primary/tx/arweave?x=1").unwrap();
+        let candidates: Vec<_> = nodes
+            .candidates(&url)
+            .into_iter()
+            .map(|(index, url)| (index, url.to_string()))
+            .collect();
+        assert_eq!(
+            candidates,
+            [
+                (0, "http://primary/tx/arweave?x=1".to_owned()),
+                (1, "http://second/tx/arweave?x=1".to_owned()),
+                (2, "http://third/api/tx/arweave?x=1".to_owned()),
+            ]
+        );
+        let elsewhere = Url::parse("http://gateway/tx").unwrap();
+        assert!(nodes.candidates(&elsewhere).is_empty());
+    }
+
+    #[test]
+    fn should_fail_over_after_failures_in_a_row() {
+        let nodes = nodes();
+        let active = nodes.subscribe();
+        nodes.record(0, false);
+        nodes.record(0, true);
+        nodes.record(0, false);
+        assert_eq!(active.borrow().as_str(), "http://primary/");
+
+        // Failures of other nodes don't count against the active one
+        nodes.record(1, false);
+        nodes.record(1, false);
+        nodes.record(0, false);
+        assert!(active.has_changed().unwrap());
+        assert_eq!(active.borrow().as_str(), "http://third/api/");
+        let url = Url::parse("http://second/info").unwrap();
+        assert_eq!(
+            nodes.candidates(&url)[0].1.as_str(),
+            "http://third/api/info"
+        );
+
+        nodes.set_health(&[true, false, true]);
+        assert_eq!(nodes.active().as_str(), "http://primary/");
+        // Unhealthy nodes are skipped
+        nodes.record(0, false);
+        nodes.record(0, false);
+        assert_eq!(nodes.active().as_str(), "http://third/api/");
+    }
+}
@@ -0,0 +1,608 @@
+//! Minimal C interface for uploading data and checking balances.
+//!
+//! Every function returns a [`BundlrStatus`]. On failure, a description of the error can be
+//! read with [`bundlr_last_error`].
+//!
+//! Ownership rules:
+//! - Handles created by [`bundlr_client_new`] must be released with [`bundlr_client_free`].
+//! - Strings written to `out_*` parameters are owned by the caller and must be released with
+//!   [`bundlr_string_free`].
+//! - The string returned by [`bundlr_last_error`] is owned by the library. It stays valid until
+//!   the next call made with the same handle.
+//!
+//! Each handle owns its own single-threaded runtime and blocks the calling thread until the
+//! request completes. It must not be used from more than one thread at a time, nor from inside
+//! an async runtime.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+    str::FromStr,
+};
+
+use num_traits::FromPrimitive;
+use reqwest::Url;
+use tokio::runtime::Runtime;
+
+#[cfg(feature = "arweave")]
+use crate::currency::arweave::{Arweave, ArweaveBuilder};
+#[cfg(feature = "ethereum")]
+use crate::currency::ethereum::{Ethereum, EthereumBuilder};
+#[cfg(feature = "solana")]
+use crate::currency::solana::{Solana, SolanaBuilder};
+use crate::{currency::CurrencyType, error::BundlrError, tags::Tag, Bundlr, BundlrBuilder};
+
+/// Result of every FFI call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundlrStatus {
+    Ok = 0,
+    /// A pointer was null, a string was not valid UTF-8, or an argument could not be parsed.
+    InvalidArgument = 1,
+    /// The currency kind is unknown or not compiled in.
+    UnsupportedCurrency = 2,
+    /// The request failed, see `bundlr_last_error`.
+    Failed = 3,
+    /// The library panicked. The handle should not be used anymore.
+    Panic = 4,
+}
+
+enum Client {
+    #[cfg(feature = "arweave")]
+    Arweave(Bundlr<Arweave>),
+    #[cfg(feature = "solana")]
+    Solana(Bundlr<Solana>),
+    #[cfg(feature = "ethereum")]
+    Ethereum(Bundlr<Ethereum>),
+}
+
+/// Runs `$body` with `$bundlr` bound to the client, whatever its currency.
+macro_rules! with_client {
+    ($client:expr, $bundlr:ident => $body:expr) => {
+        match $client {
+            #[cfg(feature = "arweave")]
+            Client::Arweave($bundlr) => $body,
+            #[cfg(feature = "solana")]
+            Client::Solana($bundlr) => $body,
+            #[cfg(feature = "ethereum")]
+            Client::Ethereum($bundlr) => $body,
+        }
+    };
+}
+
+/// Opaque client handle.
+pub struct BundlrHandle {
+    runtime: Runtime,
+    client: Client,
+    last_error: Option<CString>,
+}
+
+enum FfiError {
+    InvalidArgument(String),
+    UnsupportedCurrency(u16),
+    Failed(BundlrError),
+}
+
+impl FfiError {
+    fn status(&self) -> BundlrStatus {
+        match self {
+            FfiError::InvalidArgument(_) => BundlrStatus::InvalidArgument,
+            FfiError::UnsupportedCurrency(_) => BundlrStatus::UnsupportedCurrency,
+            FfiError::Failed(_) => BundlrStatus::Failed,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            FfiError::InvalidArgument(msg) => format!("Invalid argument: {}", msg),
+            FfiError::UnsupportedCurrency(kind) => format!("Unsupported currency kind: {}", kind),
+            FfiError::Failed(err) => err.to_string(),
+        }
+    }
+}
+
+impl<T: Into<BundlrError>> From<T> for FfiError {
+    fn from(err: T) -> Self {
+        FfiError::Failed(err.into())
+    }
+}
+
+thread_local! {
+    /// Error of the last failed `bundlr_client_new` on this thread, for which no handle exists.
+    static CONSTRUCTOR_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn to_c_string(msg: String) -> CString {
+    CString::new(msg.replace('\0', "")).unwrap_or_default()
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    let msg = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown cause".to_owned());
+    format!("Panic: {}", msg)
+}
+
+/// Runs `f`, catching panics, and records any error with `record`.
+fn guard<F>(record: impl FnOnce(CString), f: F) -> BundlrStatus
+where
+    F: FnOnce() -> Result<(), FfiError>,
+{
+    let (status, msg) = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => return BundlrStatus::Ok,
+        Ok(Err(err)) => (err.status(), err.message()),
+        Err(payload) => (BundlrStatus::Panic, panic_message(payload)),
+    };
+    record(to_c_string(msg));
+    status
+}
+
+/// Same as [`guard`], for calls made on an existing handle.
+fn guard_handle<F>(handle: *mut BundlrHandle, f: F) -> BundlrStatus
+where
+    F: FnOnce(&mut BundlrHandle) -> Result<(), FfiError>,
+{
+    // SAFETY: the caller guarantees the handle comes from `bundlr_client_new` and isn't used
+    // concurrently.
+    let handle = match unsafe { handle.as_mut() } {
+        Some(handle) => handle,
+        None => return BundlrStatus::InvalidArgument,
+    };
+    handle.last_error = None;
+    let handle_ptr: *mut BundlrHandle = handle;
+    guard(
+        // SAFETY: `f` has returned or unwound by the time the error is recorded.
+        |msg| unsafe { (*handle_ptr).last_error = Some(msg) },
+        || f(unsafe { &mut *handle_ptr }),
+    )
+}
+
+unsafe fn read_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError::InvalidArgument(format!("{} is null", name)));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| FfiError::InvalidArgument(format!("{} is not valid UTF-8", name)))
+}
+
+unsafe fn write_out<T>(out: *mut T, value: T, name: &str) -> Result<(), FfiError> {
+    if out.is_null() {
+        return Err(FfiError::InvalidArgument(format!("{} is null", name)));
+    }
+    out.write(value);
+    Ok(())
+}
+
+/// Reads the `len` bytes of the key at `ptr`.
+unsafe fn read_key<'a>(ptr: *const u8, len: usize) -> Result<&'a [u8], FfiError> {
+    if ptr.is_null() || len == 0 {
+        return Err(FfiError::InvalidArgument("key is null or empty".to_owned()));
+    }
+    Ok(slice::from_raw_parts(ptr, len))
+}
+
+/// Length of the raw keypairs of currencies other than Arweave, a secret key then a public key.
+#[cfg(any(feature = "solana", feature = "ethereum"))]
+const KEYPAIR_LENGTH: usize = 64;
+
+/// `key` as the base58 text the currencies' builders take: raw keypairs are encoded, other keys
+/// must already be base58 text.
+#[cfg(any(feature = "solana", feature = "ethereum"))]
+fn base58_key(key: &[u8]) -> Result<String, FfiError> {
+    if key.len() == KEYPAIR_LENGTH {
+        return Ok(bs58::encode(key).into_string());
+    }
+    std::str::from_utf8(key)
+        .map(|key| key.trim().to_owned())
+        .map_err(|_| {
+            FfiError::InvalidArgument(format!(
+                "key is neither a raw keypair of {} bytes nor base58 text",
+                KEYPAIR_LENGTH
+            ))
+        })
+}
+
+/// Builds the Arweave currency with the JWK `jwk`. `arweave_rs` only reads keys from files, so
+/// it is written to one only the user can read, removed once read.
+#[cfg(feature = "arweave")]
+fn arweave_from_jwk(jwk: &[u8]) -> Result<Arweave, FfiError> {
+    use std::{
+        fs::{self, OpenOptions},
+        io::Write,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    static FILES: AtomicUsize = AtomicUsize::new(0);
+    serde_json::from_slice::<serde_json::Value>(jwk)
+        .ok()
+        .filter(serde_json::Value::is_object)
+        .ok_or_else(|| FfiError::InvalidArgument("key is not a JWK".to_owned()))?;
+    let path = std::env::temp_dir().join(format!(
+        "bundlr-ffi-{}-{}.json",
+        std::process::id(),
+        FILES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let written = options.open(&path).and_then(|mut file| file.write_all(jwk));
+    let currency = written
+        .map_err(FfiError::from)
+        .and_then(|_| Ok(ArweaveBuilder::new().keypair_path(path.clone()).build()?));
+    let _ = fs::remove_file(&path);
+    currency
+}
+
+fn new_client(
+    runtime: &Runtime,
+    url: Url,
+    currency_kind: u16,
+    key: &[u8],
+) -> Result<Client, FfiError> {
+    let kind = CurrencyType::from_u16(currency_kind)
+        .ok_or(FfiError::UnsupportedCurrency(currency_kind))?;
+    let builder = BundlrBuilder::new().url(url);
+    runtime.block_on(async {
+        let client = match kind {
+            #[cfg(feature = "arweave")]
+            CurrencyType::Arweave => {
+                let currency = arweave_from_jwk(key)?;
+                Client::Arweave(builder.currency(currency).fetch_pub_info().await?.build()?)
+            }
+            #[cfg(feature = "solana")]
+            CurrencyType::Solana => {
+                let currency = SolanaBuilder::new().wallet(&base58_key(key)?).build()?;
+                Client::Solana(builder.currency(currency).fetch_pub_info().await?.build()?)
+            }
+            #[cfg(feature = "ethereum")]
+            CurrencyType::Ethereum => {
+                let currency = EthereumBuilder::new().wallet(&base58_key(key)?).build()?;
+                Client::Ethereum(builder.currency(currency).fetch_pub_info().await?.build()?)
+            }
+            #[allow(unreachable_patterns)]
+            _ => return Err(FfiError::UnsupportedCurrency(currency_kind)),
+        };
+        Ok(client)
+    })
+}
+
+/// Creates a client for the node at `url`.
+///
+/// `currency_kind` is the numeric value of `CurrencyType` (1 for Arweave, 2 for Solana, 3 for
+/// Ethereum). The key is the `key_len` bytes at `key_ptr`: the JWK, as JSON, for Arweave, and
+/// for other currencies the 64 bytes of the keypair, raw or as base58 text as the CLI takes it.
+/// The node's public info is fetched before returning.
+///
+/// On success, `*out_handle` receives a handle to release with `bundlr_client_free`. On failure,
+/// call `bundlr_last_error` with a null handle to get the error.
+///
+/// # Safety
+///
+/// `url` must be null or a valid NUL-terminated string, `key_ptr` must be null or valid for
+/// reads of `key_len` bytes, and `out_handle` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bundlr_client_new(
+    url: *const c_char,
+    currency_kind: u16,
+    key_ptr: *const u8,
+    key_len: usize,
+    out_handle: *mut *mut BundlrHandle,
+) -> BundlrStatus {
+    CONSTRUCTOR_ERROR.with(|err| err.borrow_mut().take());
+    guard(
+        |msg| CONSTRUCTOR_ERROR.with(|err| *err.borrow_mut() = Some(msg)),
+        || {
+            if out_handle.is_null() {
+                return Err(FfiError::InvalidArgument("out_handle is null".to_owned()));
+            }
+            let url = Url::from_str(read_str(url, "url")?)
+                .map_err(|err| FfiError::InvalidArgument(err.to_string()))?;
+            let key = read_key(key_ptr, key_len)?;
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            let client = new_client(&runtime, url, currency_kind, key)?;
+            let handle = Box::new(BundlrHandle {
+                runtime,
+                client,
+                last_error: None,
+            });
+            write_out(out_handle, Box::into_raw(handle), "out_handle")
+        },
+    )
+}
+
+/// Releases a handle created by `bundlr_client_new`. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `handle` must be null or a handle that hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn bundlr_client_free(handle: *mut BundlrHandle) {
+    if !handle.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(handle))));
+    }
+}
+
+/// Signs and uploads `len` bytes starting at `data_ptr`.
+///
+/// `tags_json` is null or a JSON array of `{ "name": ..., "value": ... }` objects. On success,
+/// `*out_id` receives the id of the uploaded item, to release with `bundlr_string_free`.
+///
+/// # Safety
+///
+/// `handle` must be valid, `data_ptr` must be valid for reads of `len` bytes (or null when
+/// `len` is 0), `tags_json` must be null or a valid NUL-terminated string, and `out_id` must be
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bundlr_upload(
+    handle: *mut BundlrHandle,
+    data_ptr: *const u8,
+    len: usize,
+    tags_json: *const c_char,
+    out_id: *mut *mut c_char,
+) -> BundlrStatus {
+    guard_handle(handle, |handle| {
+        if out_id.is_null() {
+            return Err(FfiError::InvalidArgument("out_id is null".to_owned()));
+        }
+        let data = match (data_ptr.is_null(), len) {
+            (_, 0) => Vec::new(),
+            (true, _) => return Err(FfiError::InvalidArgument("data_ptr is null".to_owned())),
+            (false, _) => slice::from_raw_parts(data_ptr, len).to_vec(),
+        };
+        let tags: Vec<Tag> = match tags_json.is_null() {
+            true => Vec::new(),
+            false => serde_json::from_str(read_str(tags_json, "tags_json")?)
+                .map_err(|err| FfiError::InvalidArgument(format!("tags_json: {}", err)))?,
+        };
+
+        let res = with_client!(&handle.client, bundlr => handle.runtime.block_on(async {
+            let mut tx = bundlr.create_transaction(data, tags)?;
+            bundlr.sign_transaction(&mut tx).await?;
+            bundlr.send_transaction(tx).await
+        }))?;
+        let id = res["id"]
+            .as_str()
+            .ok_or_else(|| BundlrError::ResponseError(format!("Missing id in {}", res)))?;
+        write_out(out_id, to_c_string(id.to_owned()).into_raw(), "out_id")
+    })
+}
+
+/// Fetches the balance of `address` in the client's currency, as a decimal string of base
+/// units written to `*out_decimal_string`, to release with `bundlr_string_free`.
+///
+/// # Safety
+///
+/// `handle` must be valid, `address` must be a valid NUL-terminated string, and
+/// `out_decimal_string` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bundlr_get_balance(
+    handle: *mut BundlrHandle,
+    address: *const c_char,
+    out_decimal_string: *mut *mut c_char,
+) -> BundlrStatus {
+    guard_handle(handle, |handle| {
+        if out_decimal_string.is_null() {
+            return Err(FfiError::InvalidArgument(
+                "out_decimal_string is null".to_owned(),
+            ));
+        }
+        let address = read_str(address, "address")?;
+        let balance = with_client!(&handle.client, bundlr => {
+            handle.runtime.block_on(bundlr.get_balance(address))
+        })?;
+        write_out(
+            out_decimal_string,
+            to_c_string(balance.to_string()).into_raw(),
+            "out_decimal_string",
+        )
+    })
+}
+
+/// Returns the error of the last failed call made with `handle`, or null if it succeeded.
+///
+/// With a null handle, returns the error of the last failed `bundlr_client_new` on the calling
+/// thread. The string is owned by the library and must not be freed.
+///
+/// # Safety
+///
+/// `handle` must be null or valid.
+#[no_mangle]
+pub unsafe extern "C" fn bundlr_last_error(handle: *const BundlrHandle) -> *const c_char {
+    match handle.as_ref() {
+        Some(handle) => handle
+            .last_error
+            .as_ref()
+            .map_or(ptr::null(), |e| e.as_ptr()),
+        // The string lives in the thread local until the next `bundlr_client_new`.
+        None => {
+            CONSTRUCTOR_ERROR.with(|err| err.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+        }
+    }
+}
+
+/// Releases a string returned by the library. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `s` must be null or a string written to an `out_*` parameter that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn bundlr_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::{CStr, CString},
+        ptr,
+    };
+
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+
+    use super::{
+        bundlr_client_free, bundlr_client_new, bundlr_get_balance, bundlr_last_error,
+        bundlr_string_free, bundlr_upload, BundlrHandle, BundlrStatus,
+    };
+
+    fn mock_info(server: &MockServer) {
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(r#"{ "version": "0.2.0", "gateway": "arweave.net", "addresses": {} }"#);
+        });
+    }
+
+    fn new_client(server: &MockServer, kind: u16, key: &[u8]) -> (BundlrStatus, *mut BundlrHandle) {
+        let url = CString::new(server.url("/")).unwrap();
+        let mut handle = ptr::null_mut();
+        let status =
+            unsafe { bundlr_client_new(url.as_ptr(), kind, key.as_ptr(), key.len(), &mut handle) };
+        (status, handle)
+    }
+
+    fn new_handle(server: &MockServer) -> *mut BundlrHandle {
+        let jwk = std::fs::read("res/test_wallet.json").unwrap();
+        let (status, handle) = new_client(server, 1, &jwk);
+        assert_eq!(status, BundlrStatus::Ok);
+        assert!(!handle.is_null());
+        handle
+    }
+
+    #[test]
+    fn should_upload_and_get_balance() {
+        let server = MockServer::start();
+        mock_info(&server);
+        let upload = server.mock(|when, then| {
+            when.method(POST).path("/tx/arweave");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(r#"{ "id": "some-id", "timestamp": 1 }"#);
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/account/balance/arweave")
+                .query_param("address", "some-address");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(r#"{ "balance": "123456789012345678901234567890" }"#);
+        });
+        let handle = new_handle(&server);
+
+        let data = b"Hello";
+        let tags = CString::new(r#"[{ "name": "Content-Type", "value": "text/plain" }]"#).unwrap();
+        let mut id = ptr::null_mut();
+        let status =
+            unsafe { bundlr_upload(handle, data.as_ptr(), data.len(), tags.as_ptr(), &mut id) };
+        assert_eq!(status, BundlrStatus::Ok);
+        assert_eq!(unsafe { CStr::from_ptr(id) }.to_str().unwrap(), "some-id");
+        upload.assert();
+
+        let address = CString::new("some-address").unwrap();
+        let mut balance = ptr::null_mut();
+        let status = unsafe { bundlr_get_balance(handle, address.as_ptr(), &mut balance) };
+        assert_eq!(status, BundlrStatus::Ok);
+        assert_eq!(
+            unsafe { CStr::from_ptr(balance) }.to_str().unwrap(),
+            "123456789012345678901234567890"
+        );
+        assert!(unsafe { bundlr_last_error(handle) }.is_null());
+
+        unsafe {
+            bundlr_string_free(id);
+            bundlr_string_free(balance);
+            bundlr_client_free(handle);
+        }
+    }
+
+    #[test]
+    fn should_report_errors() {
+        let server = MockServer::start();
+        mock_info(&server);
+        server.mock(|when, then| {
+            when.method(GET).path("/account/balance/arweave");
+            then.status(500);
+        });
+
+        let jwk = std::fs::read("res/test_wallet.json").unwrap();
+        let (status, handle) = new_client(&server, 42, &jwk);
+        assert_eq!(status, BundlrStatus::UnsupportedCurrency);
+        assert!(handle.is_null());
+        let err = unsafe { CStr::from_ptr(bundlr_last_error(ptr::null())) };
+        assert_eq!(err.to_str().unwrap(), "Unsupported currency kind: 42");
+
+        let handle = new_handle(&server);
+        assert!(unsafe { bundlr_last_error(ptr::null()) }.is_null());
+
+        let mut out = ptr::null_mut();
+        let tags = CString::new("not json").unwrap();
+        let status = unsafe { bundlr_upload(handle, ptr::null(), 0, tags.as_ptr(), &mut out) };
+        assert_eq!(status, BundlrStatus::InvalidArgument);
+        assert!(out.is_null());
+
+        let address = CString::new("some-address").unwrap();
+        let status = unsafe { bundlr_get_balance(handle, address.as_ptr(), &mut out) };
+        assert_eq!(status, BundlrStatus::Failed);
+        assert!(out.is_null());
+        let err = unsafe { CStr::from_ptr(bundlr_last_error(handle)) };
+        assert!(err.to_str().unwrap().contains("500"));
+
+        let status = unsafe { bundlr_get_balance(handle, ptr::null(), &mut out) };
+        assert_eq!(status, BundlrStatus::InvalidArgument);
+        assert!(
+            unsafe { bundlr_get_balance(ptr::null_mut(), address.as_ptr(), &mut out) }
+                == BundlrStatus::InvalidArgument
+        );
+
+        unsafe { bundlr_client_free(handle) };
+    }
+
+    #[test]
+    fn should_take_raw_and_base58_keys() {
+        const SOLANA_KEY: &str =
+            "kNykCXNxgePDjFbDWjPNvXQRa8U12Ywc19dFVaQ7tebUj3m7H4sF4KKdJwM7yxxb3rqxchdjezX9Szh8bLcQAjb";
+        let server = MockServer::start();
+        mock_info(&server);
+
+        let raw = bs58::decode(SOLANA_KEY).into_vec().unwrap();
+        for key in [SOLANA_KEY.as_bytes(), &raw] {
+            let (status, handle) = new_client(&server, 2, key);
+            assert_eq!(status, BundlrStatus::Ok);
+            unsafe { bundlr_client_free(handle) };
+        }
+
+        // Arweave keys are the JWK itself, not the path to it
+        let (status, handle) = new_client(&server, 1, b"res/test_wallet.json");
+        assert_eq!(status, BundlrStatus::InvalidArgument);
+        assert!(handle.is_null());
+        let (status, _) = new_client(&server, 2, &[]);
+        assert_eq!(status, BundlrStatus::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn should_not_unwind_across_boundary() {
+        let server = MockServer::start_async().await;
+        mock_info(&server);
+        let jwk = std::fs::read("res/test_wallet.json").unwrap();
+
+        // Blocking on a runtime from inside another one panics
+        let (status, handle) = new_client(&server, 1, &jwk);
+        assert_eq!(status, BundlrStatus::Panic);
+        assert!(handle.is_null());
+        let err = unsafe { CStr::from_ptr(bundlr_last_error(ptr::null())) };
+        assert!(err.to_str().unwrap().starts_with("Panic: "));
+    }
+}
@@ -0,0 +1,179 @@
+/// A gitignore-style pattern, matched against `/`-separated paths relative to a directory.
+///
+/// `*` matches anything but `/`, `?` any one character but `/`, `[a-z]` and `[!a-z]` a
+/// character in or out of a set, `**` any number of directories, and `\` escapes the next
+/// character. A pattern with a `/` before its end matches whole paths from the directory,
+/// others match the names of files and directories at any depth. A trailing `/` only matches
+/// directories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Pattern {
+    segments: Vec<Vec<char>>,
+    dir_only: bool,
+}
+
+impl Pattern {
+    /// `None` for an empty pattern, which matches nothing.
+    pub(crate) fn new(pattern: &str) -> Option<Self> {
+        let (pattern, dir_only) = match pattern.strip_suffix('/') {
+            Some(pattern) => (pattern, true),
+            None => (pattern, false),
+        };
+        let anchored = pattern.contains('/');
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+        if pattern.is_empty() {
+            return None;
+        }
+        let mut segments: Vec<Vec<char>> = pattern
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| segment.chars().collect())
+            .collect();
+        if !anchored {
+            segments.insert(0, vec!['*', '*']);
+        }
+        Some(Self { segments, dir_only })
+    }
+
+    /// Whether the file, or directory if `is_dir`, at `path` matches.
+    pub(crate) fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let path: Vec<&str> = path.split('/').collect();
+        match_segments(&self.segments, &path)
+    }
+
+    /// Whether the file at `path` or one of the directories it is in matches.
+    pub(crate) fn matches_within(&self, path: &str) -> bool {
+        path.match_indices('/')
+            .any(|(end, _)| self.matches(&path[..end], true))
+            || self.matches(path, false)
+    }
+}
+
+fn match_segments(pattern: &[Vec<char>], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((segment, rest)) if segment[..] == ['*', '*'] => {
+            (0..=path.len()).any(|skipped| match_segments(rest, &path[skipped..]))
+        }
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, path)) => {
+                let name: Vec<char> = name.chars().collect();
+                match_name(segment, &name) && match_segments(rest, path)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Whether `name`, a single path segment, matches `pattern`.
+fn match_name(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|taken| match_name(rest, &name[taken..])),
+        Some(('?', rest)) => !name.is_empty() && match_name(rest, &name[1..]),
+        Some(('[', rest)) => match (class_end(rest), name.split_first()) {
+            (Some(end), Some((c, name))) => {
+                in_class(&rest[..end], *c) && match_name(&rest[end + 1..], name)
+            }
+            (Some(_), None) => false,
+            // Without a closing bracket, the bracket is just a character
+            (None, _) => name.first() == Some(&'[') && match_name(rest, &name[1..]),
+        },
+        Some(('\\', [escaped, rest @ ..])) => {
+            name.first() == Some(escaped) && match_name(rest, &name[1..])
+        }
+        Some((c, rest)) => name.first() == Some(c) && match_name(rest, &name[1..]),
+    }
+}
+
+/// Index of the `]` closing a class whose content starts `class`. A `]` first in the class is
+/// part of it.
+fn class_end(class: &[char]) -> Option<usize> {
+    let start = match class.first() {
+        Some('!' | '^') => 1,
+        _ => 0,
+    };
+    class
+        .iter()
+        .skip(start + 1)
+        .position(|c| *c == ']')
+        .map(|end| end + start + 1)
+}
+
+fn in_class(class: &[char], c: char) -> bool {
+    let (negated, mut class) = match class.split_first() {
+        Some(('!' | '^', rest)) => (true, rest),
+        _ => (false, class),
+    };
+    let mut found = false;
+    while let Some((first, rest)) = class.split_first() {
+        match rest {
+            ['-', last, rest @ ..] => {
+                found |= (*first..=*last).contains(&c);
+                class = rest;
+            }
+            _ => {
+                found |= *first == c;
+                class = rest;
+            }
+        }
+    }
+    found != negated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pattern;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        Pattern::new(pattern).unwrap().matches(path, false)
+    }
+
+    #[test]
+    fn should_match_names_at_any_depth() {
+        assert!(matches("*.map", "app.js.map"));
+        assert!(matches("*.map", "assets/js/app.js.map"));
+        assert!(!matches("*.map", "app.js"));
+        assert!(matches(".*", ".env"));
+        assert!(matches(".*", "config/.secrets"));
+        assert!(!matches(".*", "config/public"));
+        assert!(matches("file?.txt", "file1.txt"));
+        assert!(!matches("file?.txt", "file10.txt"));
+    }
+
+    #[test]
+    fn should_match_paths_from_the_root() {
+        assert!(matches("/build", "build"));
+        assert!(!matches("/build", "src/build"));
+        assert!(matches("docs/*.md", "docs/index.md"));
+        assert!(!matches("docs/*.md", "docs/guide/index.md"));
+        assert!(!matches("docs/*.md", "other/docs/index.md"));
+        assert!(matches("docs/**/*.md", "docs/index.md"));
+        assert!(matches("docs/**/*.md", "docs/guide/index.md"));
+        assert!(matches("**/tmp/*", "a/b/tmp/file"));
+    }
+
+    #[test]
+    fn should_match_classes_and_escapes() {
+        assert!(matches("img[0-9].png", "img3.png"));
+        assert!(!matches("img[!0-9].png", "img3.png"));
+        assert!(matches("img[!0-9].png", "imgx.png"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("a[b", "a[b"));
+        assert!(matches("\\*.txt", "*.txt"));
+        assert!(!matches("\\*.txt", "a.txt"));
+    }
+
+    #[test]
+    fn should_match_directories_and_their_content() {
+        let pattern = Pattern::new("node_modules/").unwrap();
+        assert!(pattern.matches("web/node_modules", true));
+        assert!(!pattern.matches("web/node_modules", false));
+        assert!(pattern.matches_within("web/node_modules/lib/index.js"));
+        assert!(!pattern.matches_within("web/src/index.js"));
+        assert_eq!(Pattern::new(""), None);
+        assert_eq!(Pattern::new("/"), None);
+    }
+}
@@ -0,0 +1,547 @@
+//! Searches of uploaded transactions by id, owner, tags and block range, on the node or on its
+//! gateway.
+
+use std::ops::RangeInclusive;
+
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    contracts,
+    error::BundlrError,
+    pagination::{Cursor, Page, Paginated},
+    redirect::{Redirects, RequestKind},
+    tags::Tag,
+    tombstone,
+    utils::check_and_return,
+};
+
+const TRANSACTIONS_QUERY: &str =
+    "query($ids: [String!], $owners: [String!], $tags: [TagFilter!], $first: Int, $after: String, $order: SortOrder) {
+  transactions(ids: $ids, owners: $owners, tags: $tags, first: $first, after: $after, order: $order) {
+    edges { cursor node { id address timestamp tags { name value } } }
+    pageInfo { hasNextPage }
+  }
+}";
+
+const GATEWAY_TRANSACTIONS_QUERY: &str =
+    "query($ids: [ID!], $owners: [String!], $tags: [TagFilter!], $block: BlockFilter, $first: Int, $after: String, $sort: SortOrder) {
+  transactions(ids: $ids, owners: $owners, tags: $tags, block: $block, first: $first, after: $after, sort: $sort) {
+    edges { cursor node { id owner { address } tags { name value } block { height timestamp } } }
+    pageInfo { hasNextPage }
+  }
+}";
+
+/// Matches transactions having a tag `name` with any of `values`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagFilter {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+/// Order of the transactions, by time of upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SortOrder {
+    #[serde(rename = "ASC")]
+    Oldest,
+    #[serde(rename = "DESC")]
+    Newest,
+}
+
+/// Transactions to list, all of them by default.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TransactionQuery {
+    pub ids: Vec<String>,
+    pub owners: Vec<String>,
+    pub tags: Vec<TagFilter>,
+    /// Transactions per page, up to the node's own limit. The node's default if `None`.
+    pub page_size: Option<u32>,
+    /// The node's default if `None`.
+    pub order: Option<SortOrder>,
+    /// Heights of the blocks the transactions are in, for gateway searches only: nodes don't
+    /// know about blocks.
+    pub block_range: Option<RangeInclusive<u64>>,
+}
+
+impl TransactionQuery {
+    /// Interactions with the contract deployed in `contract_id`, oldest first, as they are to be
+    /// evaluated. Their tags are read with
+    /// [`ContractInteraction::from_tags`](crate::contracts::ContractInteraction::from_tags).
+    pub fn interactions_for(contract_id: &str) -> Self {
+        Self {
+            tags: vec![
+                tag_filter("App-Name", contracts::APP_NAME),
+                tag_filter("Contract", contract_id),
+            ],
+            order: Some(SortOrder::Oldest),
+            ..Default::default()
+        }
+    }
+
+    /// Tombstones for the item `target_id` signed by `owner`, the address of its owner, newest
+    /// first. Their tags are read with
+    /// [`Tombstone::from_tags`](crate::tombstone::Tombstone::from_tags).
+    pub fn tombstones_for(target_id: &str, owner: &str) -> Self {
+        Self {
+            owners: vec![owner.to_owned()],
+            tags: vec![
+                tag_filter("App-Name", tombstone::APP_NAME),
+                tag_filter(tombstone::TOMBSTONE_FOR_TAG, target_id),
+            ],
+            order: Some(SortOrder::Newest),
+            ..Default::default()
+        }
+    }
+}
+
+fn tag_filter(name: &str, value: &str) -> TagFilter {
+    TagFilter {
+        name: name.to_owned(),
+        values: vec![value.to_owned()],
+    }
+}
+
+/// A transaction found by a [`TransactionSearch`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TransactionSummary {
+    pub id: String,
+    pub address: String,
+    /// Milliseconds since the Unix epoch, according to the node.
+    pub timestamp: u64,
+    pub tags: Vec<Tag>,
+}
+
+/// A transaction found by a [`GatewaySearch`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct GatewayTransaction {
+    pub id: String,
+    pub owner: Owner,
+    pub tags: Vec<Tag>,
+    /// Block the transaction is in, `None` while pending.
+    pub block: Option<Block>,
+}
+
+impl GatewayTransaction {
+    /// Milliseconds since the Unix epoch of the block the transaction is in, as for
+    /// [`TransactionSummary::timestamp`].
+    pub fn timestamp(&self) -> Option<u64> {
+        self.block.as_ref().map(|block| block.timestamp * 1000)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Owner {
+    pub address: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Block {
+    pub height: u64,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize)]
+struct Edge<T> {
+    cursor: String,
+    node: T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Transactions<T> {
+    edges: Vec<Edge<T>>,
+    page_info: PageInfo,
+}
+
+/// `null` rather than an empty filter, which would match nothing.
+fn non_empty(values: Value) -> Value {
+    match values {
+        Value::Array(values) if values.is_empty() => Value::Null,
+        values => values,
+    }
+}
+
+/// Fetches a page of the transactions queried by `body` from the GraphQL endpoint of `url`.
+async fn fetch_transactions<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &Url,
+    redirects: &Redirects,
+    body: Value,
+) -> Result<Page<T>, BundlrError> {
+    let url = url
+        .join("graphql")
+        .map_err(|err| BundlrError::ParseError(err.to_string()))?;
+    let req = client.post(url).json(&body);
+    let (res, _) = redirects.send(RequestKind::Read, req).await?;
+    let mut res: Value = check_and_return(Ok(res)).await?;
+
+    if let Some(errors) = res.get("errors") {
+        return Err(BundlrError::ResponseError(errors.to_string()));
+    }
+    let transactions: Transactions<T> = serde_json::from_value(res["data"]["transactions"].take())
+        .map_err(|err| BundlrError::ParseError(err.to_string()))?;
+
+    let next_cursor = match transactions.edges.last() {
+        Some(edge) if transactions.page_info.has_next_page => {
+            Some(Cursor::from(edge.cursor.clone()))
+        }
+        _ => None,
+    };
+    Ok(Page {
+        items: transactions
+            .edges
+            .into_iter()
+            .map(|edge| edge.node)
+            .collect(),
+        next_cursor,
+        total: None,
+    })
+}
+
+/// Listing of the transactions matching a [`TransactionQuery`], from the node's GraphQL
+/// endpoint.
+pub struct TransactionSearch {
+    client: reqwest::Client,
+    url: Url,
+    query: TransactionQuery,
+    redirects: Redirects,
+}
+
+impl TransactionSearch {
+    pub(crate) fn new(
+        client: reqwest::Client,
+        url: Url,
+        query: TransactionQuery,
+        redirects: Redirects,
+    ) -> Self {
+        Self {
+            client,
+            url,
+            query,
+            redirects,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Paginated for TransactionSearch {
+    type Item = TransactionSummary;
+
+    async fn fetch_page(
+        &self,
+        cursor: Option<&Cursor>,
+    ) -> Result<Page<TransactionSummary>, BundlrError> {
+        if self.query.block_range.is_some() {
+            return Err(BundlrError::Unsupported(
+                "block ranges are only searchable on gateways".to_owned(),
+            ));
+        }
+        let body = json!({
+            "query": TRANSACTIONS_QUERY,
+            "variables": {
+                "ids": non_empty(json!(self.query.ids)),
+                "owners": non_empty(json!(self.query.owners)),
+                "tags": non_empty(json!(self.query.tags)),
+                "first": self.query.page_size,
+                "after": cursor.map(Cursor::as_str),
+                "order": self.query.order,
+            },
+        });
+        fetch_transactions(&self.client, &self.url, &self.redirects, body).await
+    }
+}
+
+/// Listing of the transactions matching a [`TransactionQuery`], from the GraphQL endpoint of an
+/// Arweave gateway, which knows the blocks they were settled in.
+pub struct GatewaySearch {
+    client: reqwest::Client,
+    url: Url,
+    query: TransactionQuery,
+    redirects: Redirects,
+}
+
+impl GatewaySearch {
+    pub(crate) fn new(
+        client: reqwest::Client,
+        url: Url,
+        query: TransactionQuery,
+        redirects: Redirects,
+    ) -> Self {
+        Self {
+            client,
+            url,
+            query,
+            redirects,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Paginated for GatewaySearch {
+    type Item = GatewayTransaction;
+
+    async fn fetch_page(
+        &self,
+        cursor: Option<&Cursor>,
+    ) -> Result<Page<GatewayTransaction>, BundlrError> {
+        let block = self
+            .query
+            .block_range
+            .as_ref()
+            .map(|range| json!({ "min": range.start(), "max": range.end() }));
+        let sort = self.query.order.map(|order| match order {
+            SortOrder::Oldest => "HEIGHT_ASC",
+            SortOrder::Newest => "HEIGHT_DESC",
+        });
+        let body = json!({
+            "query": GATEWAY_TRANSACTIONS_QUERY,
+            "variables": {
+                "ids": non_empty(json!(self.query.ids)),
+                "owners": non_empty(json!(self.query.owners)),
+                "tags": non_empty(json!(self.query.tags)),
+                "block": block,
+                "first": self.query.page_size,
+                "after": cursor.map(Cursor::as_str),
+                "sort": sort,
+            },
+        });
+        fetch_transactions(&self.client, &self.url, &self.redirects, body).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{Method::POST, MockServer};
+    use reqwest::Url;
+    use serde_json::{json, Value};
+
+    use super::{
+        Block, GatewaySearch, GatewayTransaction, Owner, SortOrder, TagFilter, TransactionQuery,
+        TransactionSearch, TransactionSummary,
+    };
+    use crate::{
+        error::BundlrError,
+        pagination::{harness, Paginated},
+        redirect::Redirects,
+        tags::Tag,
+    };
+
+    fn transaction(i: u64) -> TransactionSummary {
+        TransactionSummary {
+            id: format!("id-{}", i),
+            address: "owner".to_owned(),
+            timestamp: 1683731921178 + i,
+            tags: vec![Tag::new("App-Name", "app")],
+        }
+    }
+
+    /// Serves `count` transactions, two per page, the cursor being the index of the item.
+    fn graphql_mock(server: &MockServer, count: u64) {
+        for after in (0..count.max(1)).step_by(2) {
+            let end = (after + 2).min(count);
+            let edges: Vec<_> = (after..end)
+                .map(|i| {
+                    let tx = transaction(i);
+                    json!({
+                        "cursor": (i + 1).to_string(),
+                        "node": {
+                            "id": tx.id,
+                            "address": tx.address,
+                            "timestamp": tx.timestamp,
+                            "tags": [{ "name": "App-Name", "value": "app" }],
+                        },
+                    })
+                })
+                .collect();
+            let cursor = match after {
+                0 => Value::Null,
+                after => Value::String(after.to_string()),
+            };
+            let body = json!({ "data": { "transactions": {
+                "edges": edges,
+                "pageInfo": { "hasNextPage": end < count },
+            } } });
+            server.mock(|when, then| {
+                when.method(POST).path("/graphql").json_body_partial(
+                    json!({ "variables": { "after": cursor, "tags": [
+                            { "name": "App-Name", "values": ["app"] }
+                        ] } })
+                    .to_string(),
+                );
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(body.to_string());
+            });
+        }
+    }
+
+    /// Serves `count` settled transactions from a gateway, two per page.
+    fn gateway_mock(server: &MockServer, count: u64) {
+        for after in (0..count.max(1)).step_by(2) {
+            let end = (after + 2).min(count);
+            let edges: Vec<_> = (after..end)
+                .map(|i| {
+                    json!({
+                        "cursor": (i + 1).to_string(),
+                        "node": {
+                            "id": format!("id-{}", i),
+                            "owner": { "address": "owner" },
+                            "tags": [],
+                            "block": { "height": i, "timestamp": 1683731921 },
+                        },
+                    })
+                })
+                .collect();
+            let cursor = match after {
+                0 => Value::Null,
+                after => Value::String(after.to_string()),
+            };
+            let body = json!({ "data": { "transactions": {
+                "edges": edges,
+                "pageInfo": { "hasNextPage": end < count },
+            } } });
+            server.mock(|when, then| {
+                when.method(POST)
+                    .path("/graphql")
+                    .json_body_partial(json!({ "variables": { "after": cursor } }).to_string());
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(body.to_string());
+            });
+        }
+    }
+
+    fn gateway_transaction(i: u64) -> GatewayTransaction {
+        GatewayTransaction {
+            id: format!("id-{}", i),
+            owner: Owner {
+                address: "owner".to_owned(),
+            },
+            tags: vec![],
+            block: Some(Block {
+                height: i,
+                timestamp: 1683731921,
+            }),
+        }
+    }
+
+    fn gateway_search(server: &MockServer) -> GatewaySearch {
+        GatewaySearch::new(
+            reqwest::Client::new(),
+            Url::parse(&server.url("/")).unwrap(),
+            TransactionQuery::default(),
+            Redirects::default(),
+        )
+    }
+
+    fn search(server: &MockServer) -> TransactionSearch {
+        let query = TransactionQuery {
+            tags: vec![TagFilter {
+                name: "App-Name".to_owned(),
+                values: vec!["app".to_owned()],
+            }],
+            ..Default::default()
+        };
+        TransactionSearch::new(
+            reqwest::Client::new(),
+            Url::parse(&server.url("/")).unwrap(),
+            query,
+            Redirects::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn should_page_through_transactions() {
+        let server = MockServer::start();
+        graphql_mock(&server, 5);
+        let expected: Vec<_> = (0..5).map(transaction).collect();
+        harness::check_multi_page(search(&server), || search(&server), &expected).await;
+    }
+
+    #[tokio::test]
+    async fn should_list_no_transactions() {
+        let server = MockServer::start();
+        graphql_mock(&server, 0);
+        harness::check_empty(search(&server)).await;
+    }
+
+    #[tokio::test]
+    async fn should_page_through_gateway_transactions() {
+        let server = MockServer::start();
+        gateway_mock(&server, 5);
+        let expected: Vec<_> = (0..5).map(gateway_transaction).collect();
+        let restart = || gateway_search(&server);
+        harness::check_multi_page(gateway_search(&server), restart, &expected).await;
+    }
+
+    #[tokio::test]
+    async fn should_search_gateways_by_block_range() {
+        let server = MockServer::start();
+        let body = json!({ "data": { "transactions": {
+            "edges": [
+                { "cursor": "1", "node": {
+                    "id": "settled",
+                    "owner": { "address": "owner" },
+                    "tags": [{ "name": "App-Name", "value": "app" }],
+                    "block": { "height": 1200, "timestamp": 1683731921 },
+                } },
+                { "cursor": "2", "node": {
+                    "id": "pending",
+                    "owner": { "address": "owner" },
+                    "tags": [],
+                    "block": null,
+                } },
+            ],
+            "pageInfo": { "hasNextPage": false },
+        } } });
+        let gateway = server.mock(|when, then| {
+            when.method(POST).path("/graphql").json_body_partial(
+                json!({ "variables": {
+                    "owners": ["owner"],
+                    "block": { "min": 1000, "max": 2000 },
+                    "sort": "HEIGHT_ASC",
+                } })
+                .to_string(),
+            );
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(body.to_string());
+        });
+        let query = TransactionQuery {
+            owners: vec!["owner".to_owned()],
+            order: Some(SortOrder::Oldest),
+            block_range: Some(1000..=2000),
+            ..Default::default()
+        };
+        let url = Url::parse(&server.url("/")).unwrap();
+        let search = GatewaySearch::new(
+            reqwest::Client::new(),
+            url.clone(),
+            query.clone(),
+            Redirects::default(),
+        );
+
+        let page = search.first_page().await.unwrap();
+        gateway.assert();
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.items[0].owner.address, "owner");
+        assert_eq!(page.items[0].block.as_ref().unwrap().height, 1200);
+        assert_eq!(page.items[0].timestamp(), Some(1683731921000));
+        assert_eq!(page.items[1].timestamp(), None);
+        assert!(page.next_cursor.is_none());
+
+        // Nodes don't know about blocks
+        let search =
+            TransactionSearch::new(reqwest::Client::new(), url, query, Redirects::default());
+        let res = search.first_page().await;
+        assert!(matches!(res, Err(BundlrError::Unsupported(_))));
+    }
+}
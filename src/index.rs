@@ -5,10 +5,10 @@ use std::panic;
 
 use crate::Verifier;
 
-#[cfg(feature = "arweave")]
-use crate::ArweaveSigner;
+#[cfg(feature = "verify")]
+use crate::RsaPssVerifier;
 
-#[cfg(any(feature = "solana", feature = "algorand"))]
+#[cfg(any(feature = "solana", feature = "algorand", feature = "near"))]
 use crate::Ed25519Signer;
 
 #[cfg(any(feature = "ethereum", feature = "erc20"))]
@@ -24,6 +24,7 @@ use crate::AptosSigner;
 use crate::MultiAptosSigner;
 
 use crate::error::BundlrError;
+#[cfg(any(feature = "ethereum", feature = "erc20"))]
 use crate::signers::typed_ethereum::TypedEthereumSigner;
 
 #[derive(FromPrimitive, Display, PartialEq, Eq, Debug, Clone)]
@@ -82,69 +83,76 @@ impl SignerMap {
     }
 
     pub fn get_config(&self) -> Config {
+        self.try_get_config()
+            .unwrap_or_else(|| panic!("{:?} get_config has no", self))
+    }
+
+    /// Lengths of the signature and owner, or `None` for unknown signers and signers whose
+    /// feature is disabled.
+    pub fn try_get_config(&self) -> Option<Config> {
         match *self {
-            #[cfg(feature = "arweave")]
-            SignerMap::Arweave => Config {
+            #[cfg(feature = "verify")]
+            SignerMap::Arweave => Some(Config {
                 sig_length: 512,
                 pub_length: 512,
                 sig_name: "arweave".to_owned(),
-            },
-            #[cfg(feature = "algorand")]
-            SignerMap::ED25519 => Config {
+            }),
+            #[cfg(any(feature = "algorand", feature = "near"))]
+            SignerMap::ED25519 => Some(Config {
                 sig_length: ed25519_dalek::SIGNATURE_LENGTH,
                 pub_length: ed25519_dalek::PUBLIC_KEY_LENGTH,
                 sig_name: "ed25519".to_owned(),
-            },
+            }),
             #[cfg(any(feature = "ethereum", feature = "erc20"))]
-            SignerMap::Ethereum => Config {
+            SignerMap::Ethereum => Some(Config {
                 sig_length: secp256k1::constants::COMPACT_SIGNATURE_SIZE + 1,
                 pub_length: secp256k1::constants::UNCOMPRESSED_PUBLIC_KEY_SIZE,
                 sig_name: "ethereum".to_owned(),
-            },
+            }),
             #[cfg(feature = "solana")]
-            SignerMap::Solana => Config {
+            SignerMap::Solana => Some(Config {
                 sig_length: ed25519_dalek::SIGNATURE_LENGTH,
                 pub_length: ed25519_dalek::PUBLIC_KEY_LENGTH,
                 sig_name: "solana".to_owned(),
-            },
+            }),
             #[cfg(feature = "aptos")]
-            SignerMap::InjectedAptos => Config {
+            SignerMap::InjectedAptos => Some(Config {
                 sig_length: ed25519_dalek::SIGNATURE_LENGTH,
                 pub_length: ed25519_dalek::PUBLIC_KEY_LENGTH,
                 sig_name: "injectedAptos".to_owned(),
-            },
+            }),
             #[cfg(feature = "aptos")]
-            SignerMap::MultiAptos => Config {
+            SignerMap::MultiAptos => Some(Config {
                 sig_length: ed25519_dalek::SIGNATURE_LENGTH * 32 + 4, // max 32 64 byte signatures, +4 for 32-bit bitmap
                 pub_length: ed25519_dalek::PUBLIC_KEY_LENGTH * 32 + 1, // max 64 32 byte keys, +1 for 8-bit threshold value
                 sig_name: "multiAptos".to_owned(),
-            },
+            }),
             #[cfg(feature = "cosmos")]
-            SignerMap::Cosmos => Config {
+            SignerMap::Cosmos => Some(Config {
                 sig_length: secp256k1::constants::COMPACT_SIGNATURE_SIZE,
                 pub_length: secp256k1::constants::PUBLIC_KEY_SIZE,
                 sig_name: "cosmos".to_owned(),
-            },
+            }),
             #[cfg(any(feature = "ethereum", feature = "erc20"))]
-            SignerMap::TypedEthereum => Config {
+            SignerMap::TypedEthereum => Some(Config {
                 sig_length: secp256k1::constants::COMPACT_SIGNATURE_SIZE + 1,
                 pub_length: 42,
                 sig_name: "typedEthereum".to_owned(),
-            },
+            }),
             #[allow(unreachable_patterns)]
-            _ => panic!("{:?} get_config has no", self),
+            _ => None,
         }
     }
 
     pub fn verify(&self, pk: &[u8], message: &[u8], signature: &[u8]) -> Result<(), BundlrError> {
         match *self {
-            #[cfg(feature = "arweave")]
-            SignerMap::Arweave => ArweaveSigner::verify(
+            #[cfg(feature = "verify")]
+            SignerMap::Arweave => RsaPssVerifier::verify(
                 Bytes::copy_from_slice(pk),
                 Bytes::copy_from_slice(message),
                 Bytes::copy_from_slice(signature),
             ),
-            #[cfg(feature = "algorand")]
+            #[cfg(any(feature = "algorand", feature = "near"))]
             SignerMap::ED25519 => Ed25519Signer::verify(
                 Bytes::copy_from_slice(pk),
                 Bytes::copy_from_slice(message),
@@ -187,7 +195,7 @@ impl SignerMap {
                 Bytes::copy_from_slice(signature),
             ),
             #[allow(unreachable_patterns)]
-            _ => panic!("{:?} verify not implemented in SignerMap yet", self),
+            _ => Err(BundlrError::InvalidSignerType),
         }
     }
 }
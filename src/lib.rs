@@ -1,32 +1,132 @@
 extern crate derive_builder;
 
+#[cfg(all(
+    feature = "client",
+    not(any(feature = "rustls", feature = "native-tls"))
+))]
+compile_error!("The `client` feature needs a TLS backend: enable `rustls` or `native-tls`");
+
+// The client relies on Tokio's file system, timers and background tasks, which browsers lack
+#[cfg(all(feature = "client", target_arch = "wasm32", target_os = "unknown"))]
+compile_error!(
+    "The `client` feature isn't supported on `wasm32-unknown-unknown`: build with \
+     `--no-default-features`, `web` and the features of the signers needed, then upload the \
+     signed items with `web::upload`"
+);
+
 mod signers;
 mod transaction;
 
 #[cfg(feature = "build-binary")]
 pub mod client;
 
+pub mod ack;
+pub mod address_book;
+#[cfg(feature = "archive")]
+pub mod archive;
+pub mod build_info;
+pub mod bundle;
+#[cfg(feature = "client")]
 pub mod bundlr;
+pub mod canonical;
+#[cfg(feature = "client")]
+pub mod clock;
+#[cfg(feature = "client")]
+pub mod compat;
 pub mod consts;
+pub mod contracts;
+#[cfg(feature = "client")]
 pub mod currency;
 pub mod deep_hash;
 pub mod deep_hash_sync;
+#[cfg(feature = "client")]
+pub mod diagnostics;
+#[cfg(feature = "client")]
+pub mod directory;
+#[cfg(feature = "client")]
+pub mod download;
+#[cfg(feature = "client")]
+pub mod drain;
 pub mod error;
+#[cfg(feature = "client")]
+pub mod failover;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "client")]
+mod glob;
+#[cfg(feature = "client")]
+pub mod graphql;
 pub mod index;
+pub mod manifest;
+pub mod pagination;
+#[cfg(feature = "client")]
+pub mod pricing;
+#[cfg(feature = "client")]
+pub mod progress;
+#[cfg(feature = "client")]
+pub mod publish;
+#[cfg(feature = "client")]
+pub mod queue;
+#[cfg(feature = "client")]
+pub mod quota;
+#[cfg(feature = "client")]
+pub mod redirect;
+#[cfg(feature = "client")]
+pub mod resume;
+#[cfg(feature = "client")]
+pub mod retry;
+#[cfg(feature = "client")]
+pub mod routing;
+pub mod schema;
+#[cfg(feature = "client")]
+pub mod shutdown;
+#[cfg(feature = "client")]
+mod spool;
+#[cfg(feature = "client")]
+pub mod state;
 pub mod tags;
+#[cfg(feature = "client")]
+mod task;
+#[cfg(feature = "client")]
+mod throttle;
+pub mod tombstone;
+#[cfg(feature = "client")]
+pub mod top_up;
+#[cfg(feature = "client")]
+pub mod transport;
+#[cfg(feature = "client")]
 pub mod upload;
 pub mod utils;
 pub mod verify;
+#[cfg(feature = "web")]
+pub mod web;
+#[cfg(feature = "client")]
+pub mod withdrawals;
 
+pub use build_info::{build_info, BuildInfo};
+#[cfg(feature = "client")]
 pub use bundlr::{Bundlr, BundlrBuilder};
 pub use signers::Signer;
 pub use transaction::bundlr::BundlrTx;
+#[cfg(feature = "client")]
+pub use transaction::{
+    poll::{ConfirmationPoll, PollOptions},
+    TxStatus,
+};
 pub use verify::Verifier;
 
 #[cfg(feature = "arweave")]
 pub use signers::arweave::ArweaveSigner;
 
-#[cfg(any(feature = "solana", feature = "algorand"))]
+#[cfg(feature = "verify")]
+pub use signers::rsa_pss::RsaPssVerifier;
+
+#[cfg(any(
+    feature = "solana",
+    feature = "algorand",
+    feature = "aptos",
+    feature = "near"
+))]
 pub use signers::ed25519::Ed25519Signer;
 
 #[cfg(any(feature = "ethereum", feature = "erc20"))]
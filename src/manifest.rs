@@ -0,0 +1,210 @@
+//! Path manifests: items mapping paths to the ids of other items, by which gateways serve a
+//! folder of uploaded files as a website.
+//!
+//! Gateways resolve `<manifest id>/<path>` to the item listed for `path`, the manifest id alone
+//! to its index, and paths it doesn't list to its fallback, if it has them.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::BundlrError, tags::Tag};
+
+pub const MANIFEST_TYPE: &str = "arweave/paths";
+pub const MANIFEST_VERSION: &str = "0.1.0";
+/// Version of manifests with a fallback, which older gateways don't know.
+pub const FALLBACK_MANIFEST_VERSION: &str = "0.2.0";
+/// Content type gateways recognize manifests by.
+pub const MANIFEST_CONTENT_TYPE: &str = "application/x.arweave-manifest+json";
+/// Path [`Bundlr::upload_directory`](crate::Bundlr::upload_directory) makes the index.
+pub const INDEX_PATH: &str = "index.html";
+
+/// A manifest mapping relative paths, `/`-separated, to item ids.
+///
+/// # Example
+///
+/// ```
+/// # use bundlr_sdk::manifest::Manifest;
+/// let manifest = Manifest::new()
+///     .path("index.html", "KT45jaf8n9UwgkEareWxPgLJk4oMWpI5NODgYVIF1fY")
+///     .path("404.html", "QFvXNwPJLgrqDkwVRaSovGdD6vUeKCGLrDKVutGQr3c")
+///     .index("index.html")
+///     .fallback("QFvXNwPJLgrqDkwVRaSovGdD6vUeKCGLrDKVutGQr3c");
+/// let json = manifest.to_json().unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    manifest: String,
+    version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    index: Option<ManifestIndex>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fallback: Option<ManifestEntry>,
+    paths: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ManifestIndex {
+    path: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ManifestEntry {
+    id: String,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            manifest: MANIFEST_TYPE.to_owned(),
+            version: MANIFEST_VERSION.to_owned(),
+            index: None,
+            fallback: None,
+            paths: BTreeMap::new(),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lists the item `id` at `path`, replacing any item listed there before.
+    pub fn path(mut self, path: &str, id: &str) -> Self {
+        self.paths
+            .insert(path.to_owned(), ManifestEntry { id: id.to_owned() });
+        self
+    }
+
+    /// Serves the item at `path` for the manifest id alone. The path must be listed by the time
+    /// the manifest is serialized.
+    pub fn index(mut self, path: &str) -> Self {
+        self.index = Some(ManifestIndex {
+            path: path.to_owned(),
+        });
+        self
+    }
+
+    /// Serves the item `id` for the paths the manifest doesn't list, e.g. a not found page.
+    pub fn fallback(mut self, id: &str) -> Self {
+        self.version = FALLBACK_MANIFEST_VERSION.to_owned();
+        self.fallback = Some(ManifestEntry { id: id.to_owned() });
+        self
+    }
+
+    /// Id of the item at `path`.
+    pub fn get(&self, path: &str) -> Option<&str> {
+        self.paths.get(path).map(|entry| entry.id.as_str())
+    }
+
+    /// Path of the index, if the manifest has one.
+    pub fn index_path(&self) -> Option<&str> {
+        self.index.as_ref().map(|index| index.path.as_str())
+    }
+
+    /// Id of the fallback item, if the manifest has one.
+    pub fn fallback_id(&self) -> Option<&str> {
+        self.fallback.as_ref().map(|fallback| fallback.id.as_str())
+    }
+
+    /// Paths and the ids of their items, ordered by path.
+    pub fn paths(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.paths
+            .iter()
+            .map(|(path, entry)| (path.as_str(), entry.id.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Tags with which gateways recognize the item holding the manifest.
+    pub fn tags() -> Vec<Tag> {
+        vec![
+            Tag::new("Type", "manifest"),
+            Tag::new("Content-Type", MANIFEST_CONTENT_TYPE),
+        ]
+    }
+
+    /// The manifest as the JSON data of its item.
+    ///
+    /// Fails with [`BundlrError::InvalidManifest`] if its index is not one of its paths.
+    pub fn to_json(&self) -> Result<Vec<u8>, BundlrError> {
+        if let Some(index) = self.index_path() {
+            if !self.paths.contains_key(index) {
+                return Err(BundlrError::InvalidManifest(format!(
+                    "Index {:?} is not listed",
+                    index
+                )));
+            }
+        }
+        serde_json::to_vec(self).map_err(|err| BundlrError::ParseError(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::Manifest;
+    use crate::error::BundlrError;
+
+    #[test]
+    fn should_serialize_manifests() {
+        let manifest = Manifest::new()
+            .path("css/site.css", "css-id")
+            .path("index.html", "index-id")
+            .index("index.html");
+
+        let json: serde_json::Value = serde_json::from_slice(&manifest.to_json().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "manifest": "arweave/paths",
+                "version": "0.1.0",
+                "index": { "path": "index.html" },
+                "paths": {
+                    "css/site.css": { "id": "css-id" },
+                    "index.html": { "id": "index-id" },
+                },
+            })
+        );
+        let parsed: Manifest = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, manifest);
+        assert_eq!(parsed.get("css/site.css"), Some("css-id"));
+    }
+
+    #[test]
+    fn should_serialize_fallbacks_in_newer_manifests() {
+        let manifest = Manifest::new()
+            .path("404.html", "not-found-id")
+            .fallback("not-found-id");
+
+        let json: serde_json::Value = serde_json::from_slice(&manifest.to_json().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "manifest": "arweave/paths",
+                "version": "0.2.0",
+                "fallback": { "id": "not-found-id" },
+                "paths": { "404.html": { "id": "not-found-id" } },
+            })
+        );
+        assert_eq!(manifest.index_path(), None);
+        assert_eq!(manifest.fallback_id(), Some("not-found-id"));
+    }
+
+    #[test]
+    fn should_reject_indexes_not_listed() {
+        let manifest = Manifest::new().path("home.html", "id").index("index.html");
+        assert!(matches!(
+            manifest.to_json(),
+            Err(BundlrError::InvalidManifest(_))
+        ));
+    }
+}
@@ -0,0 +1,223 @@
+use std::pin::Pin;
+
+use futures::{stream, Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::error::BundlrError;
+
+/// Position in a listing, as handed out by the node.
+///
+/// Cursors are opaque: they can only be passed back to the listing they come from. They
+/// serialize as plain strings, so they can be persisted to resume a listing in a later run.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Cursor(String);
+
+impl Cursor {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Cursor {
+    fn from(cursor: String) -> Self {
+        Self(cursor)
+    }
+}
+
+/// One page of a listing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Where the next page starts, `None` on the last page.
+    pub next_cursor: Option<Cursor>,
+    /// Number of items in the whole listing, if the node reports it.
+    pub total: Option<u64>,
+}
+
+/// Items stream of a [`Paginated`] listing.
+pub type PageStream<T> = Pin<Box<dyn Stream<Item = Result<T, BundlrError>> + Send>>;
+
+/// A listing served one page at a time.
+///
+/// Implementations only fetch a page at a given cursor, walking the listing is the same for all
+/// of them.
+#[async_trait::async_trait]
+pub trait Paginated: Send + Sync {
+    type Item: Send + Sync;
+
+    /// Fetches the page starting at `cursor`, or the first page if `None`.
+    async fn fetch_page(&self, cursor: Option<&Cursor>) -> Result<Page<Self::Item>, BundlrError>;
+
+    async fn first_page(&self) -> Result<Page<Self::Item>, BundlrError> {
+        self.fetch_page(None).await
+    }
+
+    /// Fetches the page following `page`, or returns `None` if it was the last one.
+    async fn next_page(
+        &self,
+        page: &Page<Self::Item>,
+    ) -> Result<Option<Page<Self::Item>>, BundlrError> {
+        match &page.next_cursor {
+            Some(cursor) => self.fetch_page(Some(cursor)).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Every item from `start` on, or from the beginning if `None`. Pages are only fetched as
+    /// the stream is consumed.
+    ///
+    /// Fails if the node hands out the same cursor twice in a row, rather than looping forever.
+    fn into_stream(self, start: Option<Cursor>) -> PageStream<Self::Item>
+    where
+        Self: Sized + 'static,
+        Self::Item: 'static,
+    {
+        // `None` once the last page was fetched
+        let next: Option<Option<Cursor>> = Some(start);
+        let pages = stream::try_unfold((self, next), |(source, next)| async move {
+            let cursor = match next {
+                Some(cursor) => cursor,
+                None => return Ok(None),
+            };
+            let page = source.fetch_page(cursor.as_ref()).await?;
+            if page.next_cursor.is_some() && page.next_cursor == cursor {
+                return Err(BundlrError::ResponseError(
+                    "Listing cursor did not advance".to_owned(),
+                ));
+            }
+            let items = stream::iter(page.items.into_iter().map(Ok));
+            Ok(Some((items, (source, page.next_cursor.map(Some)))))
+        });
+        Box::pin(pages.try_flatten())
+    }
+}
+
+/// Checks shared by the tests of every [`Paginated`] implementation.
+#[cfg(test)]
+pub(crate) mod harness {
+    use std::fmt::Debug;
+
+    use futures::TryStreamExt;
+
+    use super::{Cursor, Paginated};
+
+    /// Checks `source` lists `expected`, in order, over at least two pages: walking it page by
+    /// page, as a stream, and resuming from a persisted cursor with a new source built by
+    /// `restart`, as a restarted client would.
+    pub(crate) async fn check_multi_page<P>(
+        source: P,
+        restart: impl Fn() -> P,
+        expected: &[P::Item],
+    ) where
+        P: Paginated + 'static,
+        P::Item: PartialEq + Debug + Clone + 'static,
+    {
+        let first = source.first_page().await.unwrap();
+        let resume_at = first.items.len();
+        let cursor = first
+            .next_cursor
+            .clone()
+            .expect("Listing should span several pages");
+
+        let mut walked = first.items.clone();
+        let mut page = first;
+        while let Some(next) = source.next_page(&page).await.unwrap() {
+            walked.extend(next.items.iter().cloned());
+            page = next;
+        }
+        assert_eq!(walked, expected);
+
+        let streamed: Vec<_> = source.into_stream(None).try_collect().await.unwrap();
+        assert_eq!(streamed, expected);
+
+        let persisted = serde_json::to_string(&cursor).unwrap();
+        let cursor: Cursor = serde_json::from_str(&persisted).unwrap();
+        let resumed: Vec<_> = restart()
+            .into_stream(Some(cursor))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(resumed, expected[resume_at..]);
+    }
+
+    /// Checks `source` lists nothing.
+    pub(crate) async fn check_empty<P>(source: P)
+    where
+        P: Paginated + 'static,
+        P::Item: Debug + 'static,
+    {
+        let page = source.first_page().await.unwrap();
+        assert!(page.items.is_empty());
+        assert_eq!(page.next_cursor, None);
+        assert!(source.next_page(&page).await.unwrap().is_none());
+
+        let streamed: Vec<_> = source.into_stream(None).try_collect().await.unwrap();
+        assert!(streamed.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::{harness, Cursor, Page, Paginated};
+    use crate::error::BundlrError;
+
+    /// Listing of `items`, `per_page` at a time, the cursor being the index of the next item.
+    struct Fixed {
+        items: Vec<u32>,
+        per_page: usize,
+    }
+
+    #[async_trait::async_trait]
+    impl Paginated for Fixed {
+        type Item = u32;
+
+        async fn fetch_page(&self, cursor: Option<&Cursor>) -> Result<Page<u32>, BundlrError> {
+            let start: usize = cursor.map_or(0, |cursor| cursor.as_str().parse().unwrap());
+            let end = (start + self.per_page).min(self.items.len());
+            Ok(Page {
+                items: self.items[start..end].to_vec(),
+                next_cursor: (end < self.items.len()).then(|| Cursor::from(end.to_string())),
+                total: Some(self.items.len() as u64),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn should_walk_listing() {
+        let items: Vec<u32> = (0..10).collect();
+        let fixed = || Fixed {
+            items: items.clone(),
+            per_page: 3,
+        };
+        harness::check_multi_page(fixed(), fixed, &items).await;
+        harness::check_empty(Fixed {
+            items: vec![],
+            per_page: 3,
+        })
+        .await;
+    }
+
+    struct Stuck;
+
+    #[async_trait::async_trait]
+    impl Paginated for Stuck {
+        type Item = u32;
+
+        async fn fetch_page(&self, _: Option<&Cursor>) -> Result<Page<u32>, BundlrError> {
+            Ok(Page {
+                items: vec![1],
+                next_cursor: Some(Cursor::from("same".to_owned())),
+                total: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn should_stop_on_stuck_cursor() {
+        let res: Result<Vec<_>, _> = Stuck.into_stream(None).try_collect().await;
+        assert!(matches!(res, Err(BundlrError::ResponseError(_))));
+    }
+}
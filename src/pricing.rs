@@ -0,0 +1,175 @@
+//! Prices of an upload in several currencies, to pick the one to fund with.
+//!
+//! Nodes price uploads in the base units of each currency, which can't be compared as such. A
+//! [`PriceComparison`] converts them to whole tokens, and ranks the currencies given a rate to
+//! a common unit, such as US dollars, which the SDK doesn't know.
+
+use num::BigUint;
+
+use crate::{currency::units::format_big_units, error::BundlrError};
+
+/// A currency to price an upload in, see
+/// [`Bundlr::compare_prices`](crate::Bundlr::compare_prices).
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotedCurrency {
+    name: String,
+    decimals: u8,
+    rate: Option<f64>,
+}
+
+impl QuotedCurrency {
+    /// The currency the node names `name`, see [`Currency::name`](crate::currency::Currency::name),
+    /// whose whole tokens are worth 10^`decimals` base units.
+    pub fn new(name: &str, decimals: u8) -> Self {
+        Self {
+            name: name.to_owned(),
+            decimals,
+            rate: None,
+        }
+    }
+
+    /// Worth of a whole token in the unit of comparison, e.g. its price in US dollars, to rank
+    /// the currency by. Currencies without a rate are quoted but not ranked.
+    pub fn rate(mut self, rate: f64) -> Self {
+        self.rate = Some(rate);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Price of an upload in one currency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceQuote {
+    pub currency: String,
+    /// Price in the currency's base units.
+    pub price: BigUint,
+    /// Price in whole tokens, as a decimal string.
+    pub amount: String,
+    /// Price in the unit of comparison, for currencies with a rate.
+    pub value: Option<f64>,
+}
+
+/// Prices of an upload of `bytes` bytes, from
+/// [`Bundlr::compare_prices`](crate::Bundlr::compare_prices).
+#[derive(Debug)]
+pub struct PriceComparison {
+    pub bytes: u64,
+    /// Quotes of the currencies with a rate, cheapest first, followed by those of the others, in
+    /// the order they were given.
+    pub quotes: Vec<PriceQuote>,
+    /// Currencies the node gave no price for, e.g. as it doesn't accept them, with why.
+    pub unavailable: Vec<(String, BundlrError)>,
+}
+
+impl PriceComparison {
+    /// Compares the prices `prices` fetched for `currencies`, in the same order.
+    pub(crate) fn new(
+        bytes: u64,
+        currencies: &[QuotedCurrency],
+        prices: Vec<Result<BigUint, BundlrError>>,
+    ) -> Self {
+        let mut quotes = vec![];
+        let mut unavailable = vec![];
+        for (currency, price) in currencies.iter().zip(prices) {
+            match price {
+                Ok(price) => {
+                    let amount = format_big_units(&price, currency.decimals);
+                    let value = currency
+                        .rate
+                        .and_then(|rate| Some(amount.parse::<f64>().ok()? * rate));
+                    quotes.push(PriceQuote {
+                        currency: currency.name.clone(),
+                        price,
+                        amount,
+                        value,
+                    });
+                }
+                Err(err) => unavailable.push((currency.name.clone(), err)),
+            }
+        }
+        // Stable, so that unranked quotes keep their order
+        quotes.sort_by(|a, b| match (a.value, b.value) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+        Self {
+            bytes,
+            quotes,
+            unavailable,
+        }
+    }
+
+    /// Quote of the cheapest currency among those with a rate.
+    pub fn cheapest(&self) -> Option<&PriceQuote> {
+        self.quotes.first().filter(|quote| quote.value.is_some())
+    }
+
+    /// Quote of the currency the node names `name`.
+    pub fn quote(&self, name: &str) -> Option<&PriceQuote> {
+        self.quotes.iter().find(|quote| quote.currency == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::BigUint;
+
+    use super::{PriceComparison, QuotedCurrency};
+    use crate::error::BundlrError;
+
+    #[test]
+    fn should_rank_currencies_with_a_rate() {
+        let currencies = [
+            QuotedCurrency::new("arweave", 12).rate(20.0),
+            QuotedCurrency::new("ethereum", 18).rate(2000.0),
+            QuotedCurrency::new("osmosis", 6),
+            QuotedCurrency::new("solana", 9).rate(100.0),
+            QuotedCurrency::new("aptos", 8).rate(10.0),
+        ];
+        let prices = vec![
+            // 0.5 AR, worth 10
+            Ok(BigUint::from(500_000_000_000u64)),
+            // 0.001 ETH, worth 2
+            Ok(BigUint::from(1_000_000_000_000_000u64)),
+            Ok(BigUint::from(1_250_000u64)),
+            // 0.05 SOL, worth 5
+            Ok(BigUint::from(50_000_000u64)),
+            Err(BundlrError::RequestError("404".to_owned())),
+        ];
+        let comparison = PriceComparison::new(1024, &currencies, prices);
+
+        let ranked: Vec<_> = comparison
+            .quotes
+            .iter()
+            .map(|quote| (quote.currency.as_str(), quote.amount.as_str(), quote.value))
+            .collect();
+        assert_eq!(
+            ranked,
+            [
+                ("ethereum", "0.001", Some(2.0)),
+                ("solana", "0.05", Some(5.0)),
+                ("arweave", "0.5", Some(10.0)),
+                ("osmosis", "1.25", None),
+            ]
+        );
+        assert_eq!(comparison.cheapest().unwrap().currency, "ethereum");
+        assert_eq!(
+            comparison.quote("osmosis").unwrap().price,
+            BigUint::from(1_250_000u32)
+        );
+        assert_eq!(comparison.unavailable.len(), 1);
+        assert_eq!(comparison.unavailable[0].0, "aptos");
+
+        let unranked = PriceComparison::new(
+            1024,
+            &[QuotedCurrency::new("osmosis", 6)],
+            vec![Ok(BigUint::from(1u8))],
+        );
+        assert!(unranked.cheapest().is_none());
+    }
+}
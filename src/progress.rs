@@ -0,0 +1,189 @@
+//! Progress of uploads, to render progress bars and estimate when they finish.
+
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use tokio::sync::broadcast;
+
+/// Number of progress updates kept for receivers that fall behind, older ones being dropped.
+pub(crate) const PROGRESS_CAPACITY: usize = 1024;
+
+/// Progress of an upload, sent to the receivers of
+/// [`Bundlr::watch_upload_progress`](crate::Bundlr::watch_upload_progress) each time it moves.
+///
+/// Items sent in one request go out as their header followed by chunks of
+/// [`CHUNK_SIZE`](crate::consts::CHUNK_SIZE) bytes of data, which are counted as its chunks. Bytes
+/// are sent once handed to the connection, not once the node got them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UploadProgress {
+    /// Id of the item being uploaded.
+    pub item_id: String,
+    pub bytes_sent: u64,
+    pub total_bytes: u64,
+    pub chunks_completed: u64,
+    pub total_chunks: u64,
+    /// Number of times the item, or one of its chunks, was sent again after failing. Items sent
+    /// in one request start over from their first byte.
+    pub retries: u32,
+    /// Whether the node accepted the item, the last update of a successful upload.
+    pub accepted: bool,
+}
+
+impl UploadProgress {
+    /// Share of the item sent, between 0 and 1.
+    pub fn fraction(&self) -> f64 {
+        match self.total_bytes {
+            0 => 1.0,
+            total => self.bytes_sent as f64 / total as f64,
+        }
+    }
+}
+
+/// Updates the progress of one upload, sending it to the client's receivers.
+#[derive(Clone)]
+pub(crate) struct ProgressReporter {
+    sender: broadcast::Sender<UploadProgress>,
+    progress: Arc<Mutex<UploadProgress>>,
+}
+
+impl ProgressReporter {
+    pub(crate) fn new(
+        sender: &broadcast::Sender<UploadProgress>,
+        item_id: String,
+        total_bytes: u64,
+        total_chunks: u64,
+    ) -> Self {
+        let progress = UploadProgress {
+            item_id,
+            total_bytes,
+            total_chunks,
+            ..Default::default()
+        };
+        Self {
+            sender: sender.clone(),
+            progress: Arc::new(Mutex::new(progress)),
+        }
+    }
+
+    /// Progress so far.
+    #[cfg(test)]
+    pub(crate) fn get(&self) -> UploadProgress {
+        self.progress
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut UploadProgress)) {
+        let mut progress = self.progress.lock().unwrap_or_else(|err| err.into_inner());
+        change(&mut progress);
+        // Nobody listening is not an error
+        let _ = self.sender.send(progress.clone());
+    }
+
+    /// Records `chunks` chunks of `bytes` bytes in total as sent.
+    pub(crate) fn sent(&self, bytes: u64, chunks: u64) {
+        self.update(|progress| {
+            progress.bytes_sent += bytes;
+            progress.chunks_completed += chunks;
+        });
+    }
+
+    /// Sets the item as sent in `total_chunks` chunks, of which `chunks` chunks of `bytes` bytes
+    /// in total were sent before, e.g. by an interrupted upload.
+    pub(crate) fn split(&self, total_chunks: u64, bytes: u64, chunks: u64) {
+        self.update(|progress| {
+            progress.total_chunks = total_chunks;
+            progress.bytes_sent = bytes;
+            progress.chunks_completed = chunks;
+        });
+    }
+
+    pub(crate) fn retried(&self) {
+        self.update(|progress| progress.retries += 1);
+    }
+
+    pub(crate) fn accepted(&self) {
+        self.update(|progress| progress.accepted = true);
+    }
+
+    /// Counts the chunks of `body`, the whole item, as they are handed to the connection. The
+    /// item is sent anew from its first byte.
+    pub(crate) fn body<S, E>(&self, body: S) -> impl Stream<Item = Result<Bytes, E>>
+    where
+        S: Stream<Item = Result<Bytes, E>>,
+    {
+        self.update(|progress| {
+            progress.bytes_sent = 0;
+            progress.chunks_completed = 0;
+        });
+        let reporter = self.clone();
+        body.inspect(move |chunk| {
+            if let Ok(chunk) = chunk {
+                reporter.sent(chunk.len() as u64, 1);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures::{stream, TryStreamExt};
+    use tokio::sync::broadcast;
+
+    use super::{ProgressReporter, UploadProgress};
+
+    #[tokio::test]
+    async fn should_count_bodies_from_their_first_byte() {
+        let (sender, mut receiver) = broadcast::channel(16);
+        let reporter = ProgressReporter::new(&sender, "id".to_owned(), 8, 2);
+        let chunks = || {
+            stream::iter([
+                Ok::<_, ()>(Bytes::from_static(b"head")),
+                Ok(Bytes::from_static(b"data")),
+            ])
+        };
+
+        let _: Vec<Bytes> = reporter.body(chunks()).try_collect().await.unwrap();
+        reporter.retried();
+        let _: Vec<Bytes> = reporter.body(chunks()).try_collect().await.unwrap();
+        reporter.accepted();
+
+        let mut updates = vec![];
+        while let Ok(progress) = receiver.try_recv() {
+            updates.push((
+                progress.bytes_sent,
+                progress.chunks_completed,
+                progress.retries,
+            ));
+        }
+        assert_eq!(
+            updates,
+            [
+                (0, 0, 0),
+                (4, 1, 0),
+                (8, 2, 0),
+                (8, 2, 1),
+                (0, 0, 1),
+                (4, 1, 1),
+                (8, 2, 1),
+                (8, 2, 1)
+            ]
+        );
+        assert_eq!(
+            reporter.get(),
+            UploadProgress {
+                item_id: "id".to_owned(),
+                bytes_sent: 8,
+                total_bytes: 8,
+                chunks_completed: 2,
+                total_chunks: 2,
+                retries: 1,
+                accepted: true,
+            }
+        );
+        assert_eq!(reporter.get().fraction(), 1.0);
+    }
+}
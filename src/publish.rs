@@ -0,0 +1,219 @@
+use serde_json::{json, Value};
+
+use crate::{
+    currency::Currency,
+    error::BundlrError,
+    quota::RequestContext,
+    resume::{ResumableOperation, ResumeToken},
+    tags::Tag,
+    Bundlr,
+};
+
+/// Tag marking items uploaded as part of a publish that may not have been committed.
+pub const STAGED_TAG: &str = "Staged";
+
+/// All-or-nothing publish of several items.
+///
+/// Items are uploaded with a `Staged: true` tag and none of the discovery tags. Once all of
+/// them succeeded, [`Publish::commit`] uploads a single item carrying the discovery tags and
+/// the ids of its members, so that a partially uploaded publish is never discoverable.
+///
+/// If an item or the commit fails, the error is [`BundlrError::PublishAborted`], listing the
+/// staged items left without a commit.
+pub struct Publish<'a, C> {
+    bundlr: &'a Bundlr<C>,
+    staged: Vec<String>,
+    aborted: bool,
+    context: RequestContext,
+}
+
+impl<'a, C> Publish<'a, C>
+where
+    C: Currency,
+{
+    pub fn begin(bundlr: &'a Bundlr<C>) -> Self {
+        Self {
+            bundlr,
+            staged: vec![],
+            aborted: false,
+            context: RequestContext::new(),
+        }
+    }
+
+    /// Continues a publish that already staged the items `staged`.
+    pub(crate) fn resume(bundlr: &'a Bundlr<C>, staged: Vec<String>) -> Self {
+        Self {
+            bundlr,
+            staged,
+            aborted: false,
+            context: RequestContext::new(),
+        }
+    }
+
+    /// Uploads the items and commit on behalf of the tenant of `context`, accounting each of
+    /// them to its quota. See [`Bundlr::upload_with_context`].
+    pub fn context(mut self, context: RequestContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Token to continue this publish with [`Bundlr::resume`], possibly in another process.
+    /// `None` once an item failed, since the publish can't be committed anymore.
+    pub fn resume_token(&self) -> Option<ResumeToken> {
+        match self.aborted {
+            true => None,
+            false => Some(self.bundlr.resume_token(ResumableOperation::Publish {
+                staged: self.staged.clone(),
+            })),
+        }
+    }
+
+    /// Ids of the items staged so far.
+    pub fn staged(&self) -> &[String] {
+        &self.staged
+    }
+
+    /// Uploads a member item, returning its id. Fails without uploading anything once a previous
+    /// item failed.
+    pub async fn add_item(&mut self, data: Vec<u8>, tags: Vec<Tag>) -> Result<String, BundlrError> {
+        if self.aborted {
+            return Err(self.abort(BundlrError::Unknown(
+                "A previous item of this publish failed".to_owned(),
+            )));
+        }
+
+        let mut tags = tags;
+        tags.push(Tag::new(STAGED_TAG, "true"));
+        match self.upload(data, tags).await {
+            Ok(id) => {
+                self.staged.push(id.clone());
+                Ok(id)
+            }
+            Err(err) => {
+                self.aborted = true;
+                Err(self.abort(err))
+            }
+        }
+    }
+
+    /// Uploads the commit item with the discovery `tags`, returning its id.
+    ///
+    /// Its data is a JSON object listing the member ids, as `{ "items": [..] }`.
+    pub async fn commit(self, tags: Vec<Tag>) -> Result<String, BundlrError> {
+        if self.aborted {
+            return Err(self.abort(BundlrError::Unknown(
+                "An item of this publish failed".to_owned(),
+            )));
+        }
+
+        let data = json!({ "items": self.staged }).to_string().into_bytes();
+        let mut tags = tags;
+        tags.push(Tag::new("Content-Type", "application/json"));
+        match self.upload(data, tags).await {
+            Ok(id) => Ok(id),
+            Err(err) => Err(self.abort(err)),
+        }
+    }
+
+    async fn upload(&self, data: Vec<u8>, tags: Vec<Tag>) -> Result<String, BundlrError> {
+        let reservation = self
+            .bundlr
+            .reserve(&self.context, data.len() as u64)
+            .await?;
+        let res = self.sign_and_send(data, tags).await;
+        self.bundlr.settle(reservation, &res).await;
+        res
+    }
+
+    async fn sign_and_send(&self, data: Vec<u8>, tags: Vec<Tag>) -> Result<String, BundlrError> {
+        let mut tx = self.bundlr.create_transaction(data, tags)?;
+        self.bundlr.sign_transaction(&mut tx).await?;
+        let res = self.bundlr.send_transaction(tx).await?;
+        match res.get("id") {
+            Some(Value::String(id)) => Ok(id.clone()),
+            _ => Err(BundlrError::ResponseError(
+                "Upload response has no id".to_owned(),
+            )),
+        }
+    }
+
+    fn abort(&self, err: BundlrError) -> BundlrError {
+        BundlrError::PublishAborted {
+            orphans: self.staged.clone(),
+            source: Box::new(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, str::FromStr};
+
+    use httpmock::{Method::POST, MockServer};
+    use reqwest::Url;
+
+    use super::Publish;
+    use crate::{
+        bundlr::PubInfo, currency::arweave::ArweaveBuilder, error::BundlrError, tags::Tag,
+        BundlrBuilder,
+    };
+
+    fn contains(body: &Option<Vec<u8>>, needle: &[u8]) -> bool {
+        body.as_deref()
+            .unwrap_or_default()
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    #[tokio::test]
+    async fn should_not_commit_after_failed_item() {
+        let server = MockServer::start();
+        let failing = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx/arweave")
+                .matches(|req| contains(&req.body, b"item-3"));
+            then.status(500).body("Internal error");
+        });
+        let staged = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx/arweave")
+                .matches(|req| contains(&req.body, b"Staged"));
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("{ \"id\": \"staged-id\", \"timestamp\": 1 }");
+        });
+        let commit = server.mock(|when, then| {
+            when.method(POST).path("/tx/arweave");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("{ \"id\": \"commit-id\", \"timestamp\": 1 }");
+        });
+
+        let wallet = PathBuf::from_str("res/test_wallet.json").unwrap();
+        let bundlr = BundlrBuilder::new()
+            .url(Url::from_str(&server.url("/")).unwrap())
+            .currency(ArweaveBuilder::new().keypair_path(wallet).build().unwrap())
+            .pub_info(PubInfo::default())
+            .build()
+            .unwrap();
+
+        let mut publish = Publish::begin(&bundlr);
+        for i in 1..=2 {
+            let data = format!("item-{}", i).into_bytes();
+            assert_eq!(publish.add_item(data, vec![]).await.unwrap(), "staged-id");
+        }
+        let res = publish.add_item(b"item-3".to_vec(), vec![]).await;
+        match res {
+            Err(BundlrError::PublishAborted { orphans, .. }) => {
+                assert_eq!(orphans, vec!["staged-id", "staged-id"])
+            }
+            res => panic!("Expected the publish to abort, got {:?}", res),
+        }
+
+        let res = publish.commit(vec![Tag::new("App-Name", "release")]).await;
+        assert!(matches!(res, Err(BundlrError::PublishAborted { .. })));
+        failing.assert_hits(1);
+        staged.assert_hits(2);
+        commit.assert_hits(0);
+    }
+}
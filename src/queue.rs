@@ -0,0 +1,567 @@
+//! Uploads queued by priority, for user-facing items not to wait behind bulk ones.
+//!
+//! An [`UploadQueue`] serves [`Priority::Interactive`] items first, then
+//! [`Priority::Normal`] ones, while guaranteeing [`Priority::Background`] items a minimum share
+//! of the uploads started as long as some of them are waiting, so that they keep progressing
+//! under a steady flow of higher priority ones.
+
+use std::{
+    collections::VecDeque,
+    pin::pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use futures::future;
+use serde_json::Value;
+use tokio::sync::{oneshot, Notify};
+
+use crate::{
+    bundlr::{OffloadSigning, Upload, UploadOptions},
+    currency,
+    error::BundlrError,
+    quota::RequestContext,
+    Bundlr,
+};
+
+/// How urgently an item should be uploaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Priority {
+    /// Waited for by a user.
+    Interactive,
+    #[default]
+    Normal,
+    /// Bulk uploads, only guaranteed their
+    /// [`QueueOptions::min_background_share`].
+    Background,
+}
+
+impl Priority {
+    /// From highest to lowest.
+    pub const ALL: [Priority; 3] = [
+        Priority::Interactive,
+        Priority::Normal,
+        Priority::Background,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How many times an upload is attempted, and how long to wait between attempts.
+///
+/// Only failures that may go away are retried, see
+/// [`BundlrError::is_retryable`]. Waits double after each retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetrySettings {
+    attempts: u32,
+    backoff: Duration,
+}
+
+impl RetrySettings {
+    /// A single attempt, as outside of queues.
+    pub const ONCE: RetrySettings = RetrySettings {
+        attempts: 1,
+        backoff: Duration::ZERO,
+    };
+
+    /// Up to `attempts` attempts, the first retry after `backoff`.
+    pub fn new(attempts: u32, backoff: Duration) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            backoff,
+        }
+    }
+
+    /// Defaults for `priority`: interactive items retry sooner and give up earlier, not to keep
+    /// users waiting, background ones are more patient.
+    pub fn for_priority(priority: Priority) -> Self {
+        match priority {
+            Priority::Interactive => Self::new(2, Duration::from_millis(200)),
+            Priority::Normal => Self::new(3, Duration::from_secs(1)),
+            Priority::Background => Self::new(5, Duration::from_secs(2)),
+        }
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Wait before attempt `attempt`, counted from 1, if it should be made after `err`.
+    pub(crate) fn retry_after(&self, attempt: u32, err: &BundlrError) -> Option<Duration> {
+        match attempt > 1 && attempt <= self.attempts && err.is_retryable() {
+            true => Some(self.backoff * 2u32.saturating_pow(attempt - 2)),
+            false => None,
+        }
+    }
+}
+
+/// An item to upload through an [`UploadQueue`].
+pub struct UploadRequest {
+    upload: Upload,
+    priority: Priority,
+    context: RequestContext,
+}
+
+impl UploadRequest {
+    pub fn new(upload: impl Into<Upload>) -> Self {
+        Self {
+            upload: upload.into(),
+            priority: Priority::default(),
+            context: RequestContext::new(),
+        }
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Uploads on behalf of the tenant of `context`, as with
+    /// [`Bundlr::upload_with_context`].
+    pub fn context(mut self, context: RequestContext) -> Self {
+        self.context = context;
+        self
+    }
+}
+
+/// How an [`UploadQueue`] schedules its uploads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueueOptions {
+    concurrency: usize,
+    min_background_share: f64,
+    offload: OffloadSigning,
+    retry: [RetrySettings; 3],
+}
+
+impl QueueOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Uploads at most `concurrency` items at a time.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Fraction of the uploads started while background items are waiting that go to them,
+    /// between 0 and 1. Background items only get what's left by higher priorities at 0.
+    pub fn min_background_share(mut self, share: f64) -> Self {
+        self.min_background_share = share.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Where items are signed, as with [`Bundlr::upload`].
+    pub fn offload(mut self, offload: OffloadSigning) -> Self {
+        self.offload = offload;
+        self
+    }
+
+    /// Retries uploads of `priority` with `retry`, instead of
+    /// [`RetrySettings::for_priority`].
+    pub fn retry(mut self, priority: Priority, retry: RetrySettings) -> Self {
+        self.retry[priority.index()] = retry;
+        self
+    }
+}
+
+impl Default for QueueOptions {
+    /// 4 uploads at a time, a tenth of them for background items.
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            min_background_share: 0.1,
+            offload: OffloadSigning::default(),
+            retry: Priority::ALL.map(RetrySettings::for_priority),
+        }
+    }
+}
+
+/// Activity of a queue for a priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PriorityMetrics {
+    /// Items waiting.
+    pub depth: usize,
+    /// Items taken out of the queue to be uploaded.
+    pub served: u64,
+    /// Time served items waited in the queue, in total.
+    pub total_wait: Duration,
+    pub max_wait: Duration,
+}
+
+impl PriorityMetrics {
+    pub fn mean_wait(&self) -> Duration {
+        match self.served {
+            0 => Duration::ZERO,
+            served => self.total_wait / served as u32,
+        }
+    }
+}
+
+/// Activity of a queue, by priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QueueMetrics {
+    by_priority: [PriorityMetrics; 3],
+}
+
+impl QueueMetrics {
+    pub fn priority(&self, priority: Priority) -> &PriorityMetrics {
+        &self.by_priority[priority.index()]
+    }
+}
+
+struct Waiting<T> {
+    item: T,
+    since: Instant,
+}
+
+/// Picks the next item to serve, highest priority first, but background items at least their
+/// share of the picks made while they wait.
+pub(crate) struct Scheduler<T> {
+    queues: [VecDeque<Waiting<T>>; 3],
+    min_background_share: f64,
+    /// Picks made while background items were waiting, and how many of them were theirs. Reset
+    /// when none are left, not to make up for idle times with bursts.
+    contended: (u64, u64),
+    metrics: QueueMetrics,
+}
+
+impl<T> Scheduler<T> {
+    pub(crate) fn new(min_background_share: f64) -> Self {
+        Self {
+            queues: Default::default(),
+            min_background_share,
+            contended: (0, 0),
+            metrics: Default::default(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, priority: Priority, item: T, now: Instant) {
+        self.queues[priority.index()].push_back(Waiting { item, since: now });
+        self.metrics.by_priority[priority.index()].depth += 1;
+    }
+
+    pub(crate) fn pop(&mut self, now: Instant) -> Option<(Priority, T)> {
+        let background = Priority::Background.index();
+        let contended = !self.queues[background].is_empty();
+        let (picks, background_picks) = self.contended;
+        let starved =
+            contended && (background_picks as f64) < self.min_background_share * picks as f64;
+        let priority = match starved {
+            true => Priority::Background,
+            false => *Priority::ALL
+                .iter()
+                .find(|priority| !self.queues[priority.index()].is_empty())?,
+        };
+        let waiting = self.queues[priority.index()].pop_front()?;
+
+        if contended {
+            self.contended.0 += 1;
+            self.contended.1 += (priority == Priority::Background) as u64;
+        }
+        if self.queues[background].is_empty() {
+            self.contended = (0, 0);
+        }
+        let metrics = &mut self.metrics.by_priority[priority.index()];
+        let wait = now.saturating_duration_since(waiting.since);
+        metrics.depth -= 1;
+        metrics.served += 1;
+        metrics.total_wait += wait;
+        metrics.max_wait = metrics.max_wait.max(wait);
+        Some((priority, waiting.item))
+    }
+
+    /// Takes every waiting item out, highest priority first, without counting them as served.
+    pub(crate) fn drain(&mut self) -> Vec<T> {
+        self.contended = (0, 0);
+        Priority::ALL
+            .iter()
+            .flat_map(|priority| {
+                self.metrics.by_priority[priority.index()].depth = 0;
+                std::mem::take(&mut self.queues[priority.index()])
+            })
+            .map(|waiting| waiting.item)
+            .collect()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.queues.iter().all(VecDeque::is_empty)
+    }
+
+    pub(crate) fn metrics(&self) -> QueueMetrics {
+        self.metrics
+    }
+}
+
+type Job = (UploadRequest, oneshot::Sender<Result<Value, BundlrError>>);
+
+/// Outcome of [`UploadQueue::shutdown`].
+pub struct QueueShutdown {
+    /// Items still being uploaded at the timeout. They finish, unless cancelled by a shutdown
+    /// of the client.
+    pub uploading: usize,
+    /// Items still queued at the timeout, never started, highest priority first. Queue them
+    /// again, on this client or another, to upload them.
+    pub unsent: Vec<UploadRequest>,
+}
+
+/// Uploads of a client, scheduled by priority. Create one with [`Bundlr::upload_queue`].
+///
+/// Items are uploaded while [`UploadQueue::run`] is being awaited, alongside the calls to
+/// [`UploadQueue::enqueue`]:
+///
+/// ```ignore
+/// let queue = bundlr.upload_queue(QueueOptions::new());
+/// let (_, uploaded) = futures::join!(queue.run(), async {
+///     let uploaded = queue.enqueue(UploadRequest::new(tx).priority(Priority::Interactive)).await;
+///     queue.close();
+///     uploaded
+/// });
+/// ```
+pub struct UploadQueue<'a, Currency> {
+    bundlr: &'a Bundlr<Currency>,
+    options: QueueOptions,
+    scheduler: Mutex<Scheduler<Job>>,
+    ready: Notify,
+    closed: AtomicBool,
+    /// Items taken from the queue and not uploaded yet. Changed with the scheduler locked.
+    uploading: AtomicUsize,
+    /// Notified whenever an item finishes uploading.
+    progressed: Notify,
+}
+
+impl<'a, Currency> UploadQueue<'a, Currency>
+where
+    Currency: currency::Currency + Clone + Send + 'static,
+{
+    pub(crate) fn new(bundlr: &'a Bundlr<Currency>, options: QueueOptions) -> Self {
+        Self {
+            bundlr,
+            options,
+            scheduler: Mutex::new(Scheduler::new(options.min_background_share)),
+            ready: Notify::new(),
+            closed: AtomicBool::new(false),
+            uploading: AtomicUsize::new(0),
+            progressed: Notify::new(),
+        }
+    }
+
+    /// Queues `request`, returning what the node answered once it was uploaded.
+    ///
+    /// Fails with [`BundlrError::QueueClosed`] if the queue was closed before the item was
+    /// queued, or with [`BundlrError::ShuttingDown`] if [`UploadQueue::shutdown`] took it out
+    /// unsent. Dropping the returned future doesn't take the item out of the queue.
+    pub async fn enqueue(&self, request: UploadRequest) -> Result<Value, BundlrError> {
+        let (done, uploaded) = oneshot::channel();
+        {
+            let mut scheduler = self.scheduler.lock().unwrap();
+            if self.closed.load(Ordering::SeqCst) {
+                return Err(BundlrError::QueueClosed);
+            }
+            scheduler.push(request.priority, (request, done), Instant::now());
+        }
+        self.ready.notify_one();
+        uploaded.await.map_err(|_| BundlrError::QueueClosed)?
+    }
+
+    /// Refuses new items. [`UploadQueue::run`] returns once the queued ones are uploaded.
+    pub fn close(&self) {
+        let _scheduler = self.scheduler.lock().unwrap();
+        self.closed.store(true, Ordering::SeqCst);
+        self.ready.notify_waiters();
+    }
+
+    /// Closes the queue, and waits up to `timeout` for the queued items to be uploaded by
+    /// [`UploadQueue::run`]. Items not started by then are taken out of the queue and returned,
+    /// rather than lost to a shutdown of the client: shut the queue down first.
+    pub async fn shutdown(&self, timeout: Duration) -> QueueShutdown {
+        self.close();
+        let drained = async {
+            loop {
+                // Registered before looking at the queue, not to miss items finishing meanwhile
+                let mut progressed = pin!(self.progressed.notified());
+                progressed.as_mut().enable();
+                {
+                    let scheduler = self.scheduler.lock().unwrap();
+                    if scheduler.is_empty() && self.uploading.load(Ordering::SeqCst) == 0 {
+                        return;
+                    }
+                }
+                progressed.await;
+            }
+        };
+        let _ = tokio::time::timeout(timeout, drained).await;
+
+        let (unsent, uploading) = {
+            let mut scheduler = self.scheduler.lock().unwrap();
+            (scheduler.drain(), self.uploading.load(Ordering::SeqCst))
+        };
+        let unsent = unsent
+            .into_iter()
+            .map(|(request, done)| {
+                let _ = done.send(Err(BundlrError::ShuttingDown));
+                request
+            })
+            .collect();
+        QueueShutdown { uploading, unsent }
+    }
+
+    /// Uploads queued items, [`QueueOptions::concurrency`] at a time, until the queue is closed
+    /// and empty.
+    pub async fn run(&self) {
+        future::join_all((0..self.options.concurrency).map(|_| self.work())).await;
+    }
+
+    pub fn metrics(&self) -> QueueMetrics {
+        self.scheduler.lock().unwrap().metrics()
+    }
+
+    async fn work(&self) {
+        loop {
+            // Registered before looking at the queue, not to miss items queued meanwhile
+            let mut ready = pin!(self.ready.notified());
+            ready.as_mut().enable();
+            let next = {
+                let mut scheduler = self.scheduler.lock().unwrap();
+                match scheduler.pop(Instant::now()) {
+                    Some(next) => {
+                        self.uploading.fetch_add(1, Ordering::SeqCst);
+                        Some(next)
+                    }
+                    None if self.closed.load(Ordering::SeqCst) => return,
+                    None => None,
+                }
+            };
+            let (priority, (request, done)) = match next {
+                Some(next) => next,
+                None => {
+                    ready.await;
+                    continue;
+                }
+            };
+            let res = self
+                .bundlr
+                .upload_retrying(
+                    &request.context,
+                    request.upload,
+                    &UploadOptions::new().offload(self.options.offload),
+                    self.options.retry[priority.index()],
+                )
+                .await;
+            let _ = done.send(res);
+            self.uploading.fetch_sub(1, Ordering::SeqCst);
+            self.progressed.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{Priority, RetrySettings, Scheduler};
+    use crate::error::{BundlrError, ResponseFormatKind};
+
+    fn drain(scheduler: &mut Scheduler<u32>, now: Instant) -> Vec<(Priority, u32)> {
+        std::iter::from_fn(|| scheduler.pop(now)).collect()
+    }
+
+    #[test]
+    fn should_serve_higher_priorities_first() {
+        let mut scheduler = Scheduler::new(0.0);
+        let now = Instant::now();
+        scheduler.push(Priority::Background, 1, now);
+        scheduler.push(Priority::Normal, 2, now);
+        scheduler.push(Priority::Interactive, 3, now);
+        scheduler.push(Priority::Normal, 4, now);
+        let served: Vec<_> = drain(&mut scheduler, now)
+            .iter()
+            .map(|(_, item)| *item)
+            .collect();
+        assert_eq!(served, vec![3, 2, 4, 1]);
+        assert!(scheduler.pop(now).is_none());
+    }
+
+    #[test]
+    fn should_guarantee_background_share() {
+        let mut scheduler = Scheduler::new(0.25);
+        let start = Instant::now();
+        for item in 0..4 {
+            scheduler.push(Priority::Background, item, start);
+        }
+        for item in 10..22 {
+            scheduler.push(Priority::Interactive, item, start);
+        }
+        let served = drain(&mut scheduler, start + Duration::from_secs(1));
+        // A background item every 4 picks, while they wait
+        let background: Vec<_> = served
+            .iter()
+            .enumerate()
+            .filter(|(_, (priority, _))| *priority == Priority::Background)
+            .map(|(position, _)| position)
+            .collect();
+        assert_eq!(background, vec![1, 5, 9, 13]);
+
+        let metrics = scheduler.metrics();
+        let background = metrics.priority(Priority::Background);
+        assert_eq!((background.depth, background.served), (0, 4));
+        assert_eq!(background.mean_wait(), Duration::from_secs(1));
+        assert_eq!(metrics.priority(Priority::Interactive).served, 12);
+
+        // No catching up with the picks made while no background item waited
+        for item in 30..40 {
+            scheduler.push(Priority::Interactive, item, start);
+        }
+        for _ in 0..8 {
+            scheduler.pop(start);
+        }
+        scheduler.push(Priority::Background, 4, start);
+        scheduler.push(Priority::Background, 5, start);
+        let served = drain(&mut scheduler, start);
+        let priorities: Vec<_> = served.iter().map(|(priority, _)| *priority).collect();
+        assert_eq!(
+            priorities,
+            vec![
+                Priority::Interactive,
+                Priority::Background,
+                Priority::Interactive,
+                Priority::Background
+            ]
+        );
+        assert_eq!(scheduler.metrics().priority(Priority::Interactive).depth, 0);
+    }
+
+    #[test]
+    fn should_retry_retryable_errors_with_backoff() {
+        let retry = RetrySettings::new(3, Duration::from_millis(100));
+        let unavailable = BundlrError::UnexpectedResponseFormat {
+            kind: ResponseFormatKind::Html,
+            status: 503,
+            snippet: String::new(),
+        };
+        assert_eq!(
+            retry.retry_after(2, &unavailable),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            retry.retry_after(3, &unavailable),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(retry.retry_after(4, &unavailable), None);
+        assert_eq!(
+            retry.retry_after(2, &BundlrError::ResponseError("Status: 400".to_owned())),
+            None
+        );
+        assert_eq!(RetrySettings::ONCE.retry_after(2, &unavailable), None);
+
+        let interactive = RetrySettings::for_priority(Priority::Interactive);
+        let background = RetrySettings::for_priority(Priority::Background);
+        assert!(interactive.attempts() < background.attempts());
+        assert!(interactive.retry_after(2, &unavailable) < background.retry_after(2, &unavailable));
+    }
+}
@@ -0,0 +1,279 @@
+//! Per-tenant quotas, for clients uploading on behalf of others out of their own balance.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::SystemTime,
+};
+
+use crate::{
+    clock::Clock,
+    error::{QuotaExceeded, QuotaKind},
+};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Who an operation is made for.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RequestContext {
+    tenant: Option<String>,
+}
+
+impl RequestContext {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Accounts the operation to `tenant`'s quota.
+    pub fn tenant(mut self, tenant: &str) -> Self {
+        self.tenant = Some(tenant.to_owned());
+        self
+    }
+
+    pub fn tenant_name(&self) -> Option<&str> {
+        self.tenant.as_deref()
+    }
+}
+
+/// Bytes set aside for an upload by a [`QuotaManager`], until it is committed or rolled back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reservation {
+    /// Identifies the reservation to the manager that made it.
+    pub id: u64,
+    pub tenant: String,
+    pub bytes: u64,
+}
+
+/// Accounts uploads to tenants, set with
+/// [`BundlrBuilder::quota_manager`](crate::BundlrBuilder::quota_manager).
+///
+/// Each upload made with a tenant in its [`RequestContext`] is reserved before it is signed and
+/// anything is sent, then committed once the node accepted it, or rolled back if it failed.
+/// Uploads without a tenant are not accounted.
+#[async_trait::async_trait]
+pub trait QuotaManager: Send + Sync {
+    async fn check_and_reserve(
+        &self,
+        tenant: &str,
+        bytes: u64,
+    ) -> Result<Reservation, QuotaExceeded>;
+
+    /// Counts a reserved upload as done.
+    async fn commit(&self, reservation: Reservation);
+
+    /// Releases a reserved upload that failed.
+    async fn rollback(&self, reservation: Reservation);
+}
+
+/// Daily limits of a tenant, unlimited by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QuotaLimits {
+    bytes_per_day: Option<u64>,
+    items_per_day: Option<u64>,
+}
+
+impl QuotaLimits {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn bytes_per_day(mut self, bytes: u64) -> Self {
+        self.bytes_per_day = Some(bytes);
+        self
+    }
+
+    pub fn items_per_day(mut self, items: u64) -> Self {
+        self.items_per_day = Some(items);
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct Usage {
+    /// Days since the Unix epoch the usage is for, in UTC.
+    day: u64,
+    /// Id of the first reservation of the day.
+    first_id: u64,
+    bytes: u64,
+    items: u64,
+}
+
+/// A [`QuotaManager`] keeping usage in memory, over UTC days.
+///
+/// Usage is lost when it is dropped: back a [`QuotaManager`] with a database to keep it across
+/// restarts or share it between processes.
+#[derive(Debug, Default)]
+pub struct InMemoryQuota {
+    limits: HashMap<String, QuotaLimits>,
+    default_limits: QuotaLimits,
+    clock: Clock,
+    /// Usage per tenant, counting pending reservations.
+    usage: Mutex<HashMap<String, Usage>>,
+    next_id: AtomicU64,
+}
+
+impl InMemoryQuota {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Limits of `tenant`, instead of the default ones.
+    pub fn limit(mut self, tenant: &str, limits: QuotaLimits) -> Self {
+        self.limits.insert(tenant.to_owned(), limits);
+        self
+    }
+
+    /// Limits of the tenants without their own, unlimited by default.
+    pub fn default_limits(mut self, limits: QuotaLimits) -> Self {
+        self.default_limits = limits;
+        self
+    }
+
+    /// Clock telling when days start, the system's by default.
+    pub fn clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn today(&self) -> u64 {
+        let now = self.clock.now();
+        now.duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            / SECONDS_PER_DAY
+    }
+
+    /// Applies `change` to the usage of `tenant` for today, starting it over on a new day.
+    fn with_usage<T>(&self, tenant: &str, change: impl FnOnce(&mut Usage) -> T) -> T {
+        let today = self.today();
+        let mut usage = self.usage.lock().unwrap();
+        let usage = usage.entry(tenant.to_owned()).or_default();
+        if usage.day != today {
+            *usage = Usage {
+                day: today,
+                first_id: self.next_id.load(Ordering::Relaxed),
+                ..Default::default()
+            };
+        }
+        change(usage)
+    }
+}
+
+#[async_trait::async_trait]
+impl QuotaManager for InMemoryQuota {
+    async fn check_and_reserve(
+        &self,
+        tenant: &str,
+        bytes: u64,
+    ) -> Result<Reservation, QuotaExceeded> {
+        let limits = self.limits.get(tenant).unwrap_or(&self.default_limits);
+        self.with_usage(tenant, |usage| {
+            let checks = [
+                (
+                    QuotaKind::BytesPerDay,
+                    limits.bytes_per_day,
+                    usage.bytes,
+                    bytes,
+                ),
+                (QuotaKind::ItemsPerDay, limits.items_per_day, usage.items, 1),
+            ];
+            for (kind, limit, used, requested) in checks {
+                match limit {
+                    Some(limit) if used.saturating_add(requested) > limit => {
+                        return Err(QuotaExceeded {
+                            tenant: tenant.to_owned(),
+                            kind,
+                            limit,
+                            used,
+                            requested,
+                        })
+                    }
+                    _ => (),
+                }
+            }
+            usage.bytes += bytes;
+            usage.items += 1;
+            Ok(Reservation {
+                id: self.next_id.fetch_add(1, Ordering::Relaxed),
+                tenant: tenant.to_owned(),
+                bytes,
+            })
+        })
+    }
+
+    /// Reservations are already counted, committing them only keeps them so.
+    async fn commit(&self, _reservation: Reservation) {}
+
+    /// Releases the reservation, unless it was made on a previous day, whose usage is gone.
+    async fn rollback(&self, reservation: Reservation) {
+        self.with_usage(&reservation.tenant, |usage| {
+            if reservation.id >= usage.first_id {
+                usage.bytes -= reservation.bytes;
+                usage.items -= 1;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{InMemoryQuota, QuotaLimits, QuotaManager};
+    use crate::{
+        clock::MockClock,
+        error::{QuotaExceeded, QuotaKind},
+    };
+
+    #[tokio::test]
+    async fn should_enforce_daily_limits() {
+        let mock = MockClock::new(SystemTime::UNIX_EPOCH);
+        let quota = InMemoryQuota::new()
+            .limit(
+                "small",
+                QuotaLimits::new().bytes_per_day(100).items_per_day(3),
+            )
+            .limit("unlimited", QuotaLimits::new())
+            .default_limits(QuotaLimits::new().bytes_per_day(100))
+            .clock(mock.clone().into());
+
+        let first = quota.check_and_reserve("small", 60).await.unwrap();
+        assert_eq!(
+            quota.check_and_reserve("small", 50).await,
+            Err(QuotaExceeded {
+                tenant: "small".to_owned(),
+                kind: QuotaKind::BytesPerDay,
+                limit: 100,
+                used: 60,
+                requested: 50,
+            })
+        );
+        quota.rollback(first).await;
+        for _ in 0..3 {
+            let reservation = quota.check_and_reserve("small", 10).await.unwrap();
+            quota.commit(reservation).await;
+        }
+        let res = quota.check_and_reserve("small", 10).await;
+        assert!(matches!(
+            res,
+            Err(QuotaExceeded {
+                kind: QuotaKind::ItemsPerDay,
+                ..
+            })
+        ));
+        assert!(quota.check_and_reserve("unlimited", 1 << 40).await.is_ok());
+
+        let yesterday = quota.check_and_reserve("other", 10).await.unwrap();
+        mock.advance(Duration::from_secs(24 * 60 * 60));
+        let today = quota.check_and_reserve("other", 100).await.unwrap();
+        // Yesterday's usage is gone already, rolling back its reservations leaves today's alone
+        quota.rollback(yesterday).await;
+        assert!(quota.check_and_reserve("other", 1).await.is_err());
+        quota.rollback(today).await;
+        assert!(quota.check_and_reserve("other", 100).await.is_ok());
+        assert!(quota.check_and_reserve("small", 100).await.is_ok());
+    }
+}
@@ -0,0 +1,292 @@
+//! Redirects answered by nodes, followed or not according to the client's policies.
+//!
+//! The HTTP client a [`Bundlr`](crate::Bundlr) builds itself never follows redirects on its own,
+//! so that uploads can't be sent to another host behind the caller's back. A client given with
+//! [`BundlrBuilder::client`](crate::BundlrBuilder::client) should be built with
+//! `reqwest::redirect::Policy::none()` for these policies to apply.
+
+use std::sync::Arc;
+
+use reqwest::{header::LOCATION, RequestBuilder, Response, Url};
+
+use crate::{
+    clock::Clock,
+    error::BundlrError,
+    failover::Nodes,
+    retry::{self, RateLimitPolicy, RetryPolicy},
+    transport::Transport,
+};
+
+/// Redirects followed by [`RedirectPolicy::FollowSameHost`].
+pub const MAX_SAME_HOST_REDIRECTS: usize = 10;
+
+/// What to do when a node answers a request with a redirect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectPolicy {
+    /// Follows up to `max` redirects, to any host.
+    Follow { max: usize },
+    /// Fails on the first redirect.
+    Deny,
+    /// Follows up to [`MAX_SAME_HOST_REDIRECTS`] redirects to the host of the request, and fails
+    /// on a redirect to any other.
+    FollowSameHost,
+}
+
+/// Whether a request sends data for the node to keep, which is held to its own policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RequestKind {
+    Read,
+    Upload,
+}
+
+/// Redirect policies of a client, along with its transport policy, which every request and
+/// redirect is checked against, and its retry policies, which each of them is sent with.
+#[derive(Debug, Clone)]
+pub(crate) struct Redirects {
+    pub(crate) reads: RedirectPolicy,
+    pub(crate) uploads: RedirectPolicy,
+    pub(crate) transport: Transport,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) rate_limits: RateLimitPolicy,
+    /// Clock retries wait on.
+    pub(crate) clock: Clock,
+    /// Nodes requests to the client's node fail over between, if it has replicas.
+    pub(crate) nodes: Option<Arc<Nodes>>,
+}
+
+impl Default for Redirects {
+    fn default() -> Self {
+        Self {
+            reads: RedirectPolicy::FollowSameHost,
+            uploads: RedirectPolicy::Deny,
+            transport: Transport::default(),
+            retry: RetryPolicy::NONE,
+            rate_limits: RateLimitPolicy::default(),
+            clock: Clock::system(),
+            nodes: None,
+        }
+    }
+}
+
+impl Redirects {
+    /// Sends `req`, following redirects with the same request as allowed by the policy of `kind`.
+    /// Returns the final response, along with the locations it was redirected to, in order.
+    ///
+    /// Requests with a streamed body can't be sent again, use [`Redirects::send_with`] for them.
+    pub(crate) async fn send(
+        &self,
+        kind: RequestKind,
+        req: RequestBuilder,
+    ) -> Result<(Response, Vec<Url>), BundlrError> {
+        let (client, request) = req.build_split();
+        let request = request.map_err(|err| BundlrError::RequestError(err.to_string()))?;
+        let url = request.url().clone();
+        self.send_with(kind, url, |url| {
+            let mut request = request.try_clone().ok_or_else(|| {
+                BundlrError::RequestError("Can't send a streamed body again".to_owned())
+            })?;
+            *request.url_mut() = url;
+            Ok(RequestBuilder::from_parts(client.clone(), request))
+        })
+        .await
+    }
+
+    /// Same as [`Redirects::send`], with the request to `url` and to each location built by
+    /// `request`.
+    ///
+    /// Requests to a node with replicas go to the active one, and to the others in turn if it
+    /// can't be reached or fails with a `5xx` response, see [`failover`](crate::failover).
+    pub(crate) async fn send_with<F>(
+        &self,
+        kind: RequestKind,
+        url: Url,
+        request: F,
+    ) -> Result<(Response, Vec<Url>), BundlrError>
+    where
+        F: Fn(Url) -> Result<RequestBuilder, BundlrError>,
+    {
+        let candidates = match &self.nodes {
+            Some(nodes) => nodes.candidates(&url),
+            None => vec![],
+        };
+        let mut last = None;
+        for (index, candidate) in candidates {
+            let res = self.follow(kind, candidate, &request).await;
+            let answered = match &res {
+                Ok((res, _)) => !res.status().is_server_error(),
+                // Such as a network error, once retries are exhausted
+                Err(BundlrError::ResponseError(_)) => false,
+                Err(_) => true,
+            };
+            if let Some(nodes) = &self.nodes {
+                nodes.record(index, answered);
+            }
+            if answered {
+                return res;
+            }
+            last = Some(res);
+        }
+        match last {
+            Some(res) => res,
+            None => self.follow(kind, url, &request).await,
+        }
+    }
+
+    /// Sends the request built by `request` to `url`, following redirects as allowed by the
+    /// policy of `kind`.
+    async fn follow<F>(
+        &self,
+        kind: RequestKind,
+        url: Url,
+        request: &F,
+    ) -> Result<(Response, Vec<Url>), BundlrError>
+    where
+        F: Fn(Url) -> Result<RequestBuilder, BundlrError>,
+    {
+        let policy = match kind {
+            RequestKind::Read => self.reads,
+            RequestKind::Upload => self.uploads,
+        };
+        self.transport.check(&url)?;
+        let mut chain: Vec<Url> = vec![];
+        let mut current = url.clone();
+        loop {
+            let res = retry::send(&self.retry, &self.rate_limits, &self.clock, || {
+                request(current.clone())
+            })
+            .await?;
+            let location = match res.headers().get(LOCATION) {
+                Some(location) if res.status().is_redirection() => location,
+                _ => return Ok((res, chain)),
+            };
+            let location = location
+                .to_str()
+                .ok()
+                .and_then(|location| current.join(location).ok())
+                .ok_or_else(|| {
+                    BundlrError::ResponseError(format!("Invalid redirect location {:?}", location))
+                })?;
+            let allowed = match policy {
+                RedirectPolicy::Follow { max } => chain.len() < max,
+                RedirectPolicy::Deny => false,
+                RedirectPolicy::FollowSameHost => {
+                    chain.len() < MAX_SAME_HOST_REDIRECTS
+                        && location.host_str() == url.host_str()
+                        && location.port_or_known_default() == url.port_or_known_default()
+                }
+            };
+            self.transport.check(&location)?;
+            chain.push(location.clone());
+            if !allowed {
+                let location = location.to_string();
+                let chain = chain.iter().map(Url::to_string).collect();
+                return Err(match kind {
+                    RequestKind::Read => BundlrError::RedirectNotFollowed { location, chain },
+                    RequestKind::Upload => BundlrError::RedirectedUpload { location, chain },
+                });
+            }
+            current = location;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{Method::GET, MockServer};
+    use reqwest::{redirect, Url};
+
+    use super::{RedirectPolicy, Redirects, RequestKind};
+    use crate::error::BundlrError;
+
+    fn redirects(server: &MockServer, hops: &[(&str, String)]) {
+        for (from, to) in hops {
+            server.mock(|when, then| {
+                when.method(GET).path(*from);
+                then.status(302).header("location", to);
+            });
+        }
+        server.mock(|when, then| {
+            when.method(GET).path("/end");
+            then.status(200).body("done");
+        });
+    }
+
+    async fn send(
+        policy: RedirectPolicy,
+        kind: RequestKind,
+        url: String,
+    ) -> Result<(String, Vec<String>), BundlrError> {
+        let client = reqwest::Client::builder()
+            .redirect(redirect::Policy::none())
+            .build()
+            .unwrap();
+        let redirects = Redirects {
+            reads: policy,
+            uploads: policy,
+            ..Default::default()
+        };
+        let (res, chain) = redirects.send(kind, client.get(url)).await?;
+        let chain = chain.iter().map(Url::to_string).collect();
+        Ok((res.text().await.unwrap(), chain))
+    }
+
+    #[tokio::test]
+    async fn should_follow_redirects_as_allowed() {
+        let server = MockServer::start();
+        let other = MockServer::start();
+        redirects(
+            &server,
+            &[
+                ("/start", "/middle".to_owned()),
+                ("/middle", server.url("/end")),
+                ("/away", other.url("/end")),
+            ],
+        );
+        redirects(&other, &[]);
+        let read = RequestKind::Read;
+
+        let (body, chain) = send(RedirectPolicy::FollowSameHost, read, server.url("/start"))
+            .await
+            .unwrap();
+        assert_eq!(body, "done");
+        assert_eq!(chain, vec![server.url("/middle"), server.url("/end")]);
+
+        let res = send(RedirectPolicy::FollowSameHost, read, server.url("/away")).await;
+        assert!(matches!(
+            res,
+            Err(BundlrError::RedirectNotFollowed { location, chain })
+                if location == other.url("/end") && chain == vec![other.url("/end")]
+        ));
+        let (body, chain) = send(RedirectPolicy::Follow { max: 1 }, read, server.url("/away"))
+            .await
+            .unwrap();
+        assert_eq!((body.as_str(), chain), ("done", vec![other.url("/end")]));
+
+        let res = send(
+            RedirectPolicy::Follow { max: 1 },
+            read,
+            server.url("/start"),
+        )
+        .await;
+        assert!(matches!(
+            res,
+            Err(BundlrError::RedirectNotFollowed { location, chain })
+                if location == server.url("/end") && chain.len() == 2
+        ));
+        let res = send(
+            RedirectPolicy::Deny,
+            RequestKind::Upload,
+            server.url("/start"),
+        )
+        .await;
+        assert!(matches!(
+            res,
+            Err(BundlrError::RedirectedUpload { location, chain })
+                if location == server.url("/middle") && chain == vec![server.url("/middle")]
+        ));
+        let (body, chain) = send(RedirectPolicy::Deny, read, server.url("/end"))
+            .await
+            .unwrap();
+        assert_eq!((body.as_str(), chain), ("done", vec![]));
+    }
+}
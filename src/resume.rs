@@ -0,0 +1,176 @@
+//! Tokens to finish an operation in another process than the one that started it, e.g. after a
+//! deploy moved the work to another instance.
+//!
+//! # Compatibility
+//!
+//! Tokens carry a format version, [`RESUME_TOKEN_VERSION`]:
+//!
+//! - Adding an optional field or a new kind of operation keeps the version. Such tokens can't be
+//!   read by older releases, which fail on them instead of misreading them.
+//! - Any other change bumps it. A release keeps reading the tokens of every earlier version, as
+//!   checked against the fixtures of each version under `res/resume_tokens`, and fails with
+//!   [`BundlrError::InvalidResumeToken`](crate::error::BundlrError::InvalidResumeToken) on newer
+//!   ones.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{currency::CurrencyType, publish::Publish};
+
+/// Version of the tokens written by this release.
+pub const RESUME_TOKEN_VERSION: u16 = 1;
+
+/// An operation started by a client, to be finished by [`Bundlr::resume`](crate::Bundlr::resume)
+/// on a client with the same node and currency, possibly in another process.
+///
+/// It holds no secrets: the wallet comes from the resuming client's currency.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumeToken {
+    #[serde(rename = "v")]
+    pub(crate) version: u16,
+    pub(crate) url: String,
+    pub(crate) currency: CurrencyType,
+    #[serde(flatten)]
+    pub(crate) operation: ResumableOperation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub(crate) enum ResumableOperation {
+    /// A transfer sent to the node, that it did not credit yet.
+    #[serde(rename_all = "camelCase")]
+    Fund { tx_id: String },
+    /// Items staged by a publish, not committed yet.
+    Publish { staged: Vec<String> },
+}
+
+/// An operation picked up from a [`ResumeToken`].
+pub enum Resumed<'a, C> {
+    /// A fund, now credited by the node.
+    Fund { tx_id: String },
+    /// A publish, to add more items to or to commit.
+    Publish(Publish<'a, C>),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, str::FromStr};
+
+    use httpmock::{Method::POST, MockServer};
+    use reqwest::Url;
+
+    use super::{ResumableOperation, ResumeToken, Resumed, RESUME_TOKEN_VERSION};
+    use crate::{
+        bundlr::PubInfo,
+        currency::{arweave::ArweaveBuilder, CurrencyType},
+        error::BundlrError,
+        publish::Publish,
+        Bundlr, BundlrBuilder,
+    };
+
+    fn instance(url: &str) -> Bundlr<crate::currency::arweave::Arweave> {
+        let wallet = PathBuf::from_str("res/test_wallet.json").unwrap();
+        BundlrBuilder::new()
+            .url(Url::from_str(url).unwrap())
+            .currency(ArweaveBuilder::new().keypair_path(wallet).build().unwrap())
+            .pub_info(PubInfo::default())
+            .build()
+            .unwrap()
+    }
+
+    /// Token written by one instance, as read by another.
+    fn hand_over(token: ResumeToken) -> ResumeToken {
+        let json = serde_json::to_string(&token).unwrap();
+        assert!(!json.contains("\"n\""), "Keys must not be in tokens");
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_resume_operations_on_another_instance() {
+        let server = MockServer::start();
+        let staged = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx/arweave")
+                .body_contains("Staged");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("{ \"id\": \"staged-id\", \"timestamp\": 1 }");
+        });
+        let commit = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx/arweave")
+                .body_contains("{\"items\":[\"staged-id\",\"staged-id\"]}");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("{ \"id\": \"commit-id\", \"timestamp\": 1 }");
+        });
+        let credit = server.mock(|when, then| {
+            when.method(POST)
+                .path("/account/balance/arweave")
+                .json_body_partial("{ \"tx_id\": \"fund-tx\" }");
+            then.status(200).body("\"OK\"");
+        });
+
+        let first = instance(&server.url("/"));
+        let mut publish = Publish::begin(&first);
+        publish.add_item(b"item-1".to_vec(), vec![]).await.unwrap();
+        let token = hand_over(publish.resume_token().unwrap());
+        let fund_token = hand_over(first.fund_resume_token("fund-tx"));
+        drop(publish);
+        drop(first);
+
+        let second = instance(&server.url("/"));
+        let mut publish = match second.resume(&token).await.unwrap() {
+            Resumed::Publish(publish) => publish,
+            Resumed::Fund { .. } => panic!("Expected a publish"),
+        };
+        publish.add_item(b"item-2".to_vec(), vec![]).await.unwrap();
+        assert_eq!(publish.commit(vec![]).await.unwrap(), "commit-id");
+        staged.assert_hits(2);
+        commit.assert_hits(1);
+
+        assert!(matches!(
+            second.resume(&fund_token).await.unwrap(),
+            Resumed::Fund { tx_id } if tx_id == "fund-tx"
+        ));
+        credit.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn should_reject_incompatible_tokens() {
+        let bundlr = instance("https://node1.bundlr.network/");
+        let token = bundlr.fund_resume_token("fund-tx");
+        let newer = ResumeToken {
+            version: RESUME_TOKEN_VERSION + 1,
+            ..token.clone()
+        };
+        let other_node = instance("https://node2.bundlr.network/");
+        for res in [bundlr.resume(&newer).await, other_node.resume(&token).await] {
+            assert!(matches!(res, Err(BundlrError::InvalidResumeToken(_))));
+        }
+    }
+
+    #[test]
+    fn should_read_tokens_of_every_version() {
+        let fixtures = [
+            (
+                include_str!("../res/resume_tokens/v1/fund.json"),
+                ResumableOperation::Fund {
+                    tx_id: "fund-tx".to_owned(),
+                },
+            ),
+            (
+                include_str!("../res/resume_tokens/v1/publish.json"),
+                ResumableOperation::Publish {
+                    staged: vec!["item-1".to_owned(), "item-2".to_owned()],
+                },
+            ),
+        ];
+        for (fixture, operation) in fixtures {
+            let token: ResumeToken = serde_json::from_str(fixture).unwrap();
+            assert!(token.version <= RESUME_TOKEN_VERSION);
+            assert_eq!(token.url, "https://node1.bundlr.network/");
+            assert_eq!(token.currency, CurrencyType::Arweave);
+            assert_eq!(token.operation, operation);
+        }
+    }
+}
@@ -0,0 +1,357 @@
+//! Retries of requests failing for reasons that may go away, such as a dropped connection or a
+//! node briefly overloaded.
+//!
+//! A [`RetryPolicy`] set with [`BundlrBuilder::retry_policy`](crate::BundlrBuilder::retry_policy)
+//! applies to every request a client sends to its node, from fetching its info to posting
+//! items and funds. Requests are sent again after a network error or a `5xx` response only, and
+//! each redirect they follow is retried on its own.
+//!
+//! Requests the node rate limits are retried apart, after the wait it asks for, following the
+//! [`RateLimitPolicy`] set with
+//! [`BundlrBuilder::rate_limit_policy`](crate::BundlrBuilder::rate_limit_policy).
+
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode};
+
+use crate::{clock::Clock, drain::retry_after, error::BundlrError};
+
+/// How many times a request is sent again after a transient failure, and how long to wait
+/// before each retry.
+///
+/// Waits double after each retry, from [`RetryPolicy::initial_backoff`] up to
+/// [`RetryPolicy::max_backoff`]. With jitter, each wait is drawn between half of it and all of
+/// it, so that clients failing together don't retry together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+impl RetryPolicy {
+    /// Never retries, which clients do unless given a policy.
+    pub const NONE: RetryPolicy = RetryPolicy {
+        max_retries: 0,
+        initial_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+        jitter: false,
+    };
+
+    /// Up to `max_retries` retries, the first one after 200ms, waiting at most 10s, with jitter.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(10),
+            jitter: true,
+        }
+    }
+
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    pub fn jitter(mut self, enabled: bool) -> Self {
+        self.jitter = enabled;
+        self
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Wait before retry `retry`, counted from 1.
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff);
+        match self.jitter && !backoff.is_zero() {
+            true => rand::thread_rng().gen_range(backoff / 2..=backoff),
+            false => backoff,
+        }
+    }
+}
+
+/// How requests answered with `429 Too Many Requests` are retried, once the wait the node
+/// asks for with a `Retry-After` header passed.
+///
+/// By default, up to 3 retries are made, waiting 1s when the node doesn't say how long, and
+/// giving up right away when it asks for more than a minute. Requests still rate limited fail
+/// with [`BundlrError::RateLimited`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitPolicy {
+    max_retries: u32,
+    max_wait: Duration,
+    default_wait: Duration,
+}
+
+impl Default for RateLimitPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            max_wait: Duration::from_secs(60),
+            default_wait: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RateLimitPolicy {
+    /// Fails rate limited requests right away.
+    pub const NONE: RateLimitPolicy = RateLimitPolicy {
+        max_retries: 0,
+        max_wait: Duration::ZERO,
+        default_wait: Duration::ZERO,
+    };
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Longest wait to honor, giving up on requests the node asks to wait longer for.
+    pub fn max_wait(mut self, wait: Duration) -> Self {
+        self.max_wait = wait;
+        self
+    }
+
+    /// How long to wait when the node doesn't send a `Retry-After` header.
+    pub fn default_wait(mut self, wait: Duration) -> Self {
+        self.default_wait = wait;
+        self
+    }
+}
+
+/// Sends the request built by `request`, building and sending it again while it fails
+/// transiently as allowed by `retry`, or is rate limited as allowed by `rate_limits`, waiting on
+/// `clock` in between. Returns the last response, `5xx` ones included, for callers to read
+/// their errors as with any other, but fails on `429`s with [`BundlrError::RateLimited`].
+///
+/// Requests that can't be built again, such as those with a streamed body, aren't retried.
+pub(crate) async fn send<F>(
+    retry: &RetryPolicy,
+    rate_limits: &RateLimitPolicy,
+    clock: &Clock,
+    request: F,
+) -> Result<Response, BundlrError>
+where
+    F: Fn() -> Result<RequestBuilder, BundlrError>,
+{
+    let finish = |res: Result<Response, reqwest::Error>| match res {
+        Ok(res) if res.status() == StatusCode::TOO_MANY_REQUESTS => Err(BundlrError::RateLimited {
+            retry_after: retry_after(res.headers(), clock.now()),
+        }),
+        res => res.map_err(|err| BundlrError::ResponseError(err.to_string())),
+    };
+
+    let mut req = request()?;
+    let (mut retries, mut rate_limited) = (0, 0);
+    loop {
+        let res = req.send().await;
+        let wait = match &res {
+            Ok(limited) if limited.status() == StatusCode::TOO_MANY_REQUESTS => {
+                let wait =
+                    retry_after(limited.headers(), clock.now()).unwrap_or(rate_limits.default_wait);
+                if rate_limited >= rate_limits.max_retries || wait > rate_limits.max_wait {
+                    return finish(res);
+                }
+                rate_limited += 1;
+                wait
+            }
+            res if retries < retry.max_retries && is_transient(res) => {
+                retries += 1;
+                retry.backoff(retries)
+            }
+            _ => return finish(res),
+        };
+        req = match request() {
+            Ok(req) => req,
+            Err(_) => return finish(res),
+        };
+        clock.sleep(wait).await;
+    }
+}
+
+/// Whether `res` failed for a reason that may go away by itself.
+fn is_transient(res: &Result<Response, reqwest::Error>) -> bool {
+    match res {
+        Ok(res) => res.status().is_server_error(),
+        Err(err) => !err.is_builder(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use httpmock::{Method::GET, MockServer};
+
+    use super::{send, RateLimitPolicy, RetryPolicy};
+    use crate::clock::{Clock, MockClock};
+    use crate::error::BundlrError;
+
+    #[test]
+    fn should_back_off_exponentially_up_to_the_cap() {
+        let policy = RetryPolicy::new(5)
+            .initial_backoff(Duration::from_secs(1))
+            .max_backoff(Duration::from_secs(5))
+            .jitter(false);
+        let backoffs: Vec<_> = (1..=5)
+            .map(|retry| policy.backoff(retry).as_secs())
+            .collect();
+        assert_eq!(backoffs, [1, 2, 4, 5, 5]);
+
+        let policy = policy.jitter(true);
+        for retry in 1..=5 {
+            let backoff = policy.backoff(retry);
+            let full = policy.jitter(false).backoff(retry);
+            assert!(backoff >= full / 2 && backoff <= full);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_retry_transient_failures() {
+        let server = MockServer::start();
+        let mut failing = server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(503);
+        });
+        let clock = MockClock::new(SystemTime::UNIX_EPOCH);
+        let retries = Clock::from(clock.clone());
+        let client = reqwest::Client::new();
+        let url = server.url("/info");
+        let policy = RetryPolicy::new(3).jitter(false);
+        let none = RateLimitPolicy::NONE;
+
+        // Gives up after the last retry, with the node's answer
+        let drive = async {
+            loop {
+                if clock.sleepers() > 0 {
+                    clock.advance(Duration::from_secs(10));
+                }
+                tokio::task::yield_now().await;
+            }
+        };
+        let sending = send(&policy, &none, &retries, || Ok(client.get(&url)));
+        let res = tokio::select! {
+            res = sending => res,
+            _ = drive => unreachable!(),
+        };
+        assert_eq!(res.unwrap().status(), 503);
+        failing.assert_hits(4);
+
+        // Succeeds once the node recovers
+        failing.delete();
+        let mut failing = server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(502);
+        });
+        let drive = async {
+            while clock.sleepers() == 0 {
+                tokio::task::yield_now().await;
+            }
+            failing.delete();
+            server.mock(|when, then| {
+                when.method(GET).path("/info");
+                then.status(200).body("{}");
+            });
+            clock.advance(Duration::from_secs(10));
+        };
+        let (res, _) = tokio::join!(
+            send(&policy, &none, &retries, || Ok(client.get(&url))),
+            drive
+        );
+        assert_eq!(res.unwrap().status(), 200);
+
+        // Not found isn't transient
+        let missing = server.mock(|when, then| {
+            when.method(GET).path("/missing");
+            then.status(404);
+        });
+        let url = server.url("/missing");
+        let res = send(&policy, &none, &retries, || Ok(client.get(&url))).await;
+        assert_eq!(res.unwrap().status(), 404);
+        missing.assert_hits(1);
+
+        let res = send(&RetryPolicy::NONE, &none, &retries, || {
+            Ok(client.get(server.url("/info")))
+        })
+        .await;
+        assert_eq!(res.unwrap().status(), 200);
+    }
+
+    #[tokio::test]
+    async fn should_wait_as_asked_when_rate_limited() {
+        let server = MockServer::start();
+        let mut limited = server.mock(|when, then| {
+            when.method(GET).path("/price");
+            then.status(429).header("retry-after", "5");
+        });
+        let clock = MockClock::new(SystemTime::UNIX_EPOCH);
+        let retries = Clock::from(clock.clone());
+        let client = reqwest::Client::new();
+        let url = server.url("/price");
+        let policy = RateLimitPolicy::new();
+
+        let drive = async {
+            while clock.sleepers() == 0 {
+                tokio::task::yield_now().await;
+            }
+            limited.delete();
+            server.mock(|when, then| {
+                when.method(GET).path("/price");
+                then.status(200).body("1");
+            });
+            clock.advance(Duration::from_secs(5));
+        };
+        let (res, _) = tokio::join!(
+            send(&RetryPolicy::NONE, &policy, &retries, || Ok(
+                client.get(&url)
+            )),
+            drive
+        );
+        assert_eq!(res.unwrap().status(), 200);
+        assert_eq!(clock.now(), SystemTime::UNIX_EPOCH + Duration::from_secs(5));
+
+        // Asked to wait too long, or not retrying
+        let url = server.url("/slow");
+        let slow = server.mock(|when, then| {
+            when.method(GET).path("/slow");
+            then.status(429).header("retry-after", "120");
+        });
+        let res = send(&RetryPolicy::NONE, &policy, &retries, || {
+            Ok(client.get(&url))
+        })
+        .await;
+        assert!(matches!(
+            res,
+            Err(BundlrError::RateLimited { retry_after: Some(wait) }) if wait.as_secs() == 120
+        ));
+        let none = RateLimitPolicy::NONE;
+        let res = send(&RetryPolicy::new(3), &none, &retries, || {
+            Ok(client.get(&url))
+        })
+        .await;
+        assert!(matches!(res, Err(BundlrError::RateLimited { .. })));
+        slow.assert_hits(2);
+    }
+}
@@ -0,0 +1,53 @@
+use reqwest::Url;
+
+use crate::tags::Tag;
+
+/// What an item must look like to be sent through a route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteCondition {
+    /// Has a tag named `name`, with the given `value` if any.
+    Tag { name: String, value: Option<String> },
+    /// Has at least this many bytes of data.
+    MinSize(u64),
+    /// Has at most this many bytes of data.
+    MaxSize(u64),
+}
+
+impl RouteCondition {
+    fn matches(&self, tags: &[Tag], size: u64) -> bool {
+        match self {
+            RouteCondition::Tag { name, value } => tags.iter().any(|tag| {
+                tag.name == *name && value.as_ref().is_none_or(|value| tag.value == *value)
+            }),
+            RouteCondition::MinSize(min) => size >= *min,
+            RouteCondition::MaxSize(max) => size <= *max,
+        }
+    }
+}
+
+/// Sends the items matching `condition` to the node at `url`, instead of the client's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingRule {
+    pub name: String,
+    pub url: Url,
+    pub condition: RouteCondition,
+}
+
+impl RoutingRule {
+    pub fn new(name: &str, url: Url, condition: RouteCondition) -> Self {
+        Self {
+            name: name.to_owned(),
+            url,
+            condition,
+        }
+    }
+}
+
+/// First rule matching an item with `tags` and `size` bytes of data.
+pub(crate) fn select<'a>(
+    rules: &'a [RoutingRule],
+    tags: &[Tag],
+    size: u64,
+) -> Option<&'a RoutingRule> {
+    rules.iter().find(|rule| rule.condition.matches(tags, size))
+}
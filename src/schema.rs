@@ -0,0 +1,233 @@
+use std::fmt;
+
+#[cfg(feature = "client")]
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+#[cfg(feature = "client")]
+use crate::error::BundlrError;
+
+/// Type of a JSON value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonKind {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl JsonKind {
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::Null => JsonKind::Null,
+            Value::Bool(_) => JsonKind::Bool,
+            Value::Number(_) => JsonKind::Number,
+            Value::String(_) => JsonKind::String,
+            Value::Array(_) => JsonKind::Array,
+            Value::Object(_) => JsonKind::Object,
+        }
+    }
+}
+
+impl fmt::Display for JsonKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            JsonKind::Null => "null",
+            JsonKind::Bool => "bool",
+            JsonKind::Number => "number",
+            JsonKind::String => "string",
+            JsonKind::Array => "array",
+            JsonKind::Object => "object",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Field a response is expected to have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpectedField {
+    pub name: &'static str,
+    pub kind: JsonKind,
+    pub required: bool,
+}
+
+impl ExpectedField {
+    pub const fn required(name: &'static str, kind: JsonKind) -> Self {
+        Self {
+            name,
+            kind,
+            required: true,
+        }
+    }
+
+    pub const fn optional(name: &'static str, kind: JsonKind) -> Self {
+        Self {
+            name,
+            kind,
+            required: false,
+        }
+    }
+}
+
+/// Expected top-level fields of a response body, as the SDK parses it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseShape {
+    pub name: &'static str,
+    pub fields: &'static [ExpectedField],
+}
+
+/// Field present in a response with another type than expected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MistypedField {
+    pub name: &'static str,
+    pub expected: JsonKind,
+    pub found: JsonKind,
+}
+
+/// Differences between a response body and the shape the SDK expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaDiff {
+    /// Name of the expected response.
+    pub response: &'static str,
+    /// Required fields absent from the body.
+    pub missing: Vec<&'static str>,
+    pub mistyped: Vec<MistypedField>,
+    /// Fields in the body the SDK doesn't know about. These don't make a body incompatible.
+    pub extra: Vec<String>,
+}
+
+impl SchemaDiff {
+    pub fn new(shape: &ResponseShape, body: &Map<String, Value>) -> Self {
+        let mut missing = vec![];
+        let mut mistyped = vec![];
+        for field in shape.fields {
+            match body.get(field.name) {
+                None if field.required => missing.push(field.name),
+                // Optional fields may be left out or null
+                None | Some(Value::Null) if !field.required => {}
+                Some(value) if JsonKind::of(value) != field.kind => mistyped.push(MistypedField {
+                    name: field.name,
+                    expected: field.kind,
+                    found: JsonKind::of(value),
+                }),
+                _ => {}
+            }
+        }
+        let extra = body
+            .keys()
+            .filter(|key| !shape.fields.iter().any(|field| field.name == *key))
+            .cloned()
+            .collect();
+
+        Self {
+            response: shape.name,
+            missing,
+            mistyped,
+            extra,
+        }
+    }
+
+    /// Whether the body has every required field, with the expected types.
+    pub fn is_compatible(&self) -> bool {
+        self.missing.is_empty() && self.mistyped.is_empty()
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} response", self.response)?;
+        if !self.missing.is_empty() {
+            write!(f, ", missing: {}", self.missing.join(", "))?;
+        }
+        for field in &self.mistyped {
+            write!(
+                f,
+                ", {}: expected {} found {}",
+                field.name, field.expected, field.found
+            )?;
+        }
+        if !self.extra.is_empty() {
+            write!(f, ", extra: {}", self.extra.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks `body` against `shape`, failing with [`BundlrError::SchemaMismatch`] if incompatible.
+#[cfg(feature = "client")]
+pub(crate) fn check_shape(shape: &ResponseShape, body: &Value) -> Result<(), BundlrError> {
+    let object = match body {
+        Value::Object(object) => object,
+        other => {
+            return Err(BundlrError::ParseError(format!(
+                "Expected a JSON object for {} response, found {}",
+                shape.name,
+                JsonKind::of(other)
+            )))
+        }
+    };
+    let diff = SchemaDiff::new(shape, object);
+    match diff.is_compatible() {
+        true => Ok(()),
+        false => Err(BundlrError::SchemaMismatch(diff)),
+    }
+}
+
+/// Parses `body`, explaining failures with a diff against `shape` where possible.
+#[cfg(feature = "client")]
+pub(crate) fn parse_diagnosed<T>(shape: &ResponseShape, body: &[u8]) -> Result<T, BundlrError>
+where
+    T: DeserializeOwned,
+{
+    serde_json::from_slice(body).map_err(|err| {
+        let value = match serde_json::from_slice::<Value>(body) {
+            Ok(value) => value,
+            Err(_) => return BundlrError::ParseError(err.to_string()),
+        };
+        match check_shape(shape, &value) {
+            Err(diagnosed) => diagnosed,
+            // Top-level fields match, the mismatch is deeper in the body
+            Ok(()) => BundlrError::ParseError(err.to_string()),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{ExpectedField, JsonKind, MistypedField, ResponseShape, SchemaDiff};
+
+    const SHAPE: ResponseShape = ResponseShape {
+        name: "Test",
+        fields: &[
+            ExpectedField::required("id", JsonKind::String),
+            ExpectedField::optional("block", JsonKind::Number),
+        ],
+    };
+
+    #[test]
+    fn should_diff_against_shape() {
+        let body = json!({ "block": "1", "extra": true });
+        let diff = SchemaDiff::new(&SHAPE, body.as_object().unwrap());
+        assert_eq!(diff.missing, vec!["id"]);
+        assert_eq!(
+            diff.mistyped,
+            vec![MistypedField {
+                name: "block",
+                expected: JsonKind::Number,
+                found: JsonKind::String,
+            }]
+        );
+        assert_eq!(diff.extra, vec!["extra".to_owned()]);
+        assert_eq!(
+            diff.to_string(),
+            "Test response, missing: id, block: expected number found string, extra: extra"
+        );
+
+        let body = json!({ "id": "some-id", "block": null, "extra": true });
+        assert!(SchemaDiff::new(&SHAPE, body.as_object().unwrap()).is_compatible());
+    }
+}
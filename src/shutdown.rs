@@ -0,0 +1,186 @@
+use std::{future::Future, pin::pin, sync::Arc, time::Duration};
+
+use async_stream::try_stream;
+use futures::{
+    future::{self, Either},
+    Stream, TryStreamExt,
+};
+use tokio::{runtime::Handle, sync::watch};
+use tokio_util::sync::CancellationToken;
+
+use crate::{clock::Clock, error::BundlrError, task};
+
+/// Outcome of [`Bundlr::shutdown`](crate::Bundlr::shutdown).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Operations that were in flight when shutdown started, and finished before the timeout.
+    pub completed: usize,
+    /// Operations that were still in flight at the timeout, and were cancelled.
+    pub cancelled: usize,
+    /// Confirmation polls that were running when shutdown started. They are cancelled right
+    /// away, as there is nothing to wait for.
+    pub polls_cancelled: usize,
+}
+
+#[derive(Default)]
+struct State {
+    active: usize,
+    polls: usize,
+    shutting_down: bool,
+}
+
+/// Registry of the operations in flight on a client, so they can be drained on shutdown.
+#[derive(Clone)]
+pub(crate) struct InFlight {
+    state: Arc<watch::Sender<State>>,
+    cancel: CancellationToken,
+    cancel_polls: CancellationToken,
+}
+
+impl Default for InFlight {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(watch::channel(State::default()).0),
+            cancel: CancellationToken::new(),
+            cancel_polls: CancellationToken::new(),
+        }
+    }
+}
+
+/// Keeps an operation registered until dropped.
+struct Operation(Arc<watch::Sender<State>>);
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        self.0.send_modify(|state| state.active -= 1);
+    }
+}
+
+/// Keeps a confirmation poll registered until dropped.
+struct Poll(Arc<watch::Sender<State>>);
+
+impl Drop for Poll {
+    fn drop(&mut self) {
+        self.0.send_modify(|state| state.polls -= 1);
+    }
+}
+
+impl InFlight {
+    fn begin_poll(&self) -> Result<Poll, BundlrError> {
+        let mut accepted = false;
+        self.state.send_if_modified(|state| {
+            accepted = !state.shutting_down;
+            if accepted {
+                state.polls += 1;
+            }
+            accepted
+        });
+        match accepted {
+            true => Ok(Poll(self.state.clone())),
+            false => Err(BundlrError::ShuttingDown),
+        }
+    }
+
+    /// Yields the statuses of a confirmation poll, unless shutting down. The poll is dropped,
+    /// and the stream ends with `ShuttingDown`, as soon as a shutdown starts.
+    pub(crate) fn poll<'a, T, S>(
+        &'a self,
+        poll: S,
+    ) -> impl Stream<Item = Result<T, BundlrError>> + 'a
+    where
+        T: 'a,
+        S: Stream<Item = Result<T, BundlrError>> + 'a,
+    {
+        try_stream! {
+            let _poll = self.begin_poll()?;
+            let mut poll = pin!(poll);
+            let mut cancelled = pin!(self.cancel_polls.cancelled());
+            loop {
+                let next = match future::select(poll.try_next(), cancelled.as_mut()).await {
+                    Either::Left((next, _)) => next?,
+                    Either::Right(_) => Err(BundlrError::ShuttingDown)?,
+                };
+                match next {
+                    Some(status) => yield status,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    fn begin(&self) -> Result<Operation, BundlrError> {
+        let mut accepted = false;
+        self.state.send_if_modified(|state| {
+            accepted = !state.shutting_down;
+            if accepted {
+                state.active += 1;
+            }
+            accepted
+        });
+        match accepted {
+            true => Ok(Operation(self.state.clone())),
+            false => Err(BundlrError::ShuttingDown),
+        }
+    }
+
+    /// Runs `operation`, unless shutting down. It is dropped, and `ShuttingDown` returned, if
+    /// the shutdown times out before it completes.
+    pub(crate) async fn track<T, F>(&self, operation: F) -> Result<T, BundlrError>
+    where
+        F: Future<Output = Result<T, BundlrError>>,
+    {
+        let _operation = self.begin()?;
+        match future::select(pin!(operation), pin!(self.cancel.cancelled())).await {
+            Either::Left((res, _)) => res,
+            Either::Right(_) => Err(BundlrError::ShuttingDown),
+        }
+    }
+
+    /// Spawns `task` on `handle` as a task named `name`, tracked like the other operations.
+    /// It isn't spawned, and `ShuttingDown` returned, when shutting down.
+    pub(crate) fn spawn<F>(&self, name: &str, handle: &Handle, task: F) -> Result<(), BundlrError>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let operation = self.begin()?;
+        let cancel = self.cancel.clone();
+        let task = async move {
+            let _operation = operation;
+            future::select(pin!(task), pin!(cancel.cancelled())).await;
+        };
+        task::spawn_on(name, task, handle);
+        Ok(())
+    }
+
+    /// Stops accepting work, and waits up to `timeout` on `clock` for the operations in flight
+    /// to complete before cancelling the others.
+    pub(crate) async fn shutdown(&self, timeout: Duration, clock: &Clock) -> ShutdownReport {
+        let (mut pending, mut polls) = (0, 0);
+        self.state.send_modify(|state| {
+            state.shutting_down = true;
+            pending = state.active;
+            polls = state.polls;
+        });
+        self.cancel_polls.cancel();
+
+        let mut state = self.state.subscribe();
+        let drain = state.wait_for(|state| state.active == 0);
+        let drained = matches!(
+            future::select(pin!(drain), pin!(clock.sleep(timeout))).await,
+            Either::Left((Ok(_), _))
+        );
+        let cancelled = match drained {
+            true => 0,
+            false => {
+                self.cancel.cancel();
+                self.state.borrow().active
+            }
+        };
+
+        ShutdownReport {
+            completed: pending.saturating_sub(cancelled),
+            cancelled,
+            polls_cancelled: polls,
+        }
+    }
+}
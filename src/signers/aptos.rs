@@ -23,6 +23,12 @@ impl AptosSigner {
             signer: Ed25519Signer::from_base58(s)?,
         })
     }
+
+    /// Signs `message` as is, as Aptos transactions are, without the prefix items are signed
+    /// with.
+    pub(crate) fn sign_raw(&self, message: Bytes) -> Result<Bytes, BundlrError> {
+        self.signer.sign(message)
+    }
 }
 
 const SIG_TYPE: SignerMap = SignerMap::InjectedAptos;
@@ -4,7 +4,7 @@ use crate::{error::BundlrError, index::SignerMap, Verifier};
 use arweave_rs::ArweaveSigner as SdkSigner;
 use bytes::Bytes;
 
-use super::Signer;
+use super::{rsa_pss::RsaPssVerifier, Signer};
 
 pub struct ArweaveSigner {
     sdk: SdkSigner,
@@ -53,10 +53,7 @@ impl Signer for ArweaveSigner {
 
 impl Verifier for ArweaveSigner {
     fn verify(pk: Bytes, message: Bytes, signature: Bytes) -> Result<(), BundlrError> {
-        SdkSigner::verify(&pk, &message, &signature).map_err(|err| match err {
-            arweave_rs::error::Error::InvalidSignature => BundlrError::InvalidSignature,
-            _ => BundlrError::ArweaveSdkError(err),
-        })
+        RsaPssVerifier::verify(pk, message, signature)
     }
 }
 
@@ -65,25 +62,11 @@ mod tests {
     use std::{path::PathBuf, str::FromStr};
 
     use crate::{
-        deep_hash::DeepHashChunk, deep_hash_sync::deep_hash_sync, ArweaveSigner, Signer, Verifier,
+        deep_hash::DeepHashChunk, deep_hash_sync::deep_hash_sync, verify::receipt::Receipt,
+        ArweaveSigner, Signer, Verifier,
     };
     use bytes::Bytes;
     use data_encoding::BASE64URL_NOPAD;
-    use serde::{Deserialize, Serialize};
-
-    //TODO: remove this when receipt included
-    #[derive(Serialize, Deserialize)]
-    #[serde(rename_all = "camelCase")]
-    pub struct Receipt {
-        pub id: String,
-        pub timestamp: u64,
-        pub version: String,
-        pub public: String,
-        pub signature: String,
-        pub deadline_height: u64,
-        pub block: u64,
-        pub validator_signatures: Vec<String>,
-    }
 
     #[test]
     fn should_sign_and_verify() {
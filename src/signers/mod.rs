@@ -7,15 +7,20 @@ pub mod aptos;
 pub mod arweave;
 #[cfg(feature = "cosmos")]
 pub mod cosmos;
-#[cfg(any(feature = "solana", feature = "algorand", feature = "aptos"))]
+#[cfg(any(
+    feature = "solana",
+    feature = "algorand",
+    feature = "aptos",
+    feature = "near"
+))]
 pub mod ed25519;
+#[cfg(feature = "verify")]
+pub mod rsa_pss;
 #[cfg(any(feature = "ethereum", feature = "erc20"))]
 pub mod secp256k1;
 #[cfg(any(feature = "ethereum", feature = "erc20"))]
 pub mod typed_ethereum;
 
-pub trait ToPem {}
-
 pub trait Signer: Send + Sync {
     fn sign(&self, message: Bytes) -> Result<Bytes, BundlrError>;
     fn sig_type(&self) -> SignerMap;
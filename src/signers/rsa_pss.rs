@@ -0,0 +1,24 @@
+use bytes::Bytes;
+use rsa::{BigUint, PaddingScheme, PublicKey, RsaPublicKey};
+use sha2::{Digest, Sha256};
+
+use crate::{error::BundlrError, Verifier};
+
+/// Exponent of every Arweave key.
+const PUBLIC_EXPONENT: u32 = 65537;
+
+/// Verifies the RSA-PSS SHA-256 signatures of Arweave keys, given their modulus as the public
+/// key. Unlike [`ArweaveSigner`](crate::ArweaveSigner), it doesn't need the Arweave SDK, nor any
+/// networking.
+pub struct RsaPssVerifier;
+
+impl Verifier for RsaPssVerifier {
+    fn verify(pk: Bytes, message: Bytes, signature: Bytes) -> Result<(), BundlrError> {
+        let key = RsaPublicKey::new(BigUint::from_bytes_be(&pk), BigUint::from(PUBLIC_EXPONENT))
+            .map_err(|err| BundlrError::InvalidKey(err.to_string()))?;
+        let hashed = Sha256::digest(&message);
+        let padding = PaddingScheme::new_pss::<Sha256, _>(rand::thread_rng());
+        key.verify(padding, &hashed, &signature)
+            .map_err(|_| BundlrError::InvalidSignature)
+    }
+}
@@ -7,7 +7,7 @@ use secp256k1::{
     Message, PublicKey, Secp256k1, SecretKey,
 };
 use web3::{
-    signing::{keccak256, recover},
+    signing::{keccak256, recover, Key, Signature, SigningError},
     types::{Address, H256},
 };
 
@@ -47,6 +47,47 @@ impl Secp256k1Signer {
         .concat();
         keccak256(data)
     }
+
+    /// Address of the key, as used on EVM chains.
+    pub fn address(&self) -> Address {
+        let pub_key = self.pub_key.serialize_uncompressed();
+        Address::from_slice(&keccak256(&pub_key[1..])[12..])
+    }
+
+    /// Signs a 32 bytes `hash`, returning the recovery id along with the signature.
+    fn sign_hash(&self, hash: &[u8]) -> Result<(u64, H256, H256), SigningError> {
+        let msg = Message::from_slice(hash).map_err(|_| SigningError::InvalidMessage)?;
+        let (recovery_id, signature) = secp256k1::Secp256k1::signing_only()
+            .sign_ecdsa_recoverable(&msg, &self.sec_key)
+            .serialize_compact();
+        Ok((
+            recovery_id.to_i32() as u64,
+            H256::from_slice(&signature[..32]),
+            H256::from_slice(&signature[32..]),
+        ))
+    }
+}
+
+/// Signs EVM transactions, e.g. with
+/// [`Accounts::sign_transaction`](web3::api::Accounts::sign_transaction).
+impl Key for &Secp256k1Signer {
+    fn sign(&self, message: &[u8], chain_id: Option<u64>) -> Result<Signature, SigningError> {
+        let (recovery_id, r, s) = self.sign_hash(message)?;
+        let v = match chain_id {
+            Some(chain_id) => recovery_id + 35 + chain_id * 2,
+            None => recovery_id + 27,
+        };
+        Ok(Signature { v, r, s })
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Result<Signature, SigningError> {
+        let (v, r, s) = self.sign_hash(message)?;
+        Ok(Signature { v, r, s })
+    }
+
+    fn address(&self) -> Address {
+        Secp256k1Signer::address(self)
+    }
 }
 
 const SIG_TYPE: SignerMap = SignerMap::Ethereum;
@@ -116,6 +157,8 @@ mod tests {
     use bytes::Bytes;
     use secp256k1::SecretKey;
 
+    use web3::signing::{keccak256, recover, Key};
+
     use crate::{Secp256k1Signer, Signer, Verifier};
 
     #[test]
@@ -144,4 +187,21 @@ mod tests {
         let pub_key = signer.pub_key();
         assert!(Secp256k1Signer::verify(pub_key, msg, sig).is_ok());
     }
+
+    #[test]
+    fn should_sign_evm_transaction_hashes() {
+        let secret_key = SecretKey::from_slice(b"00000000000000000000000000000000").unwrap();
+        let signer = Secp256k1Signer::new(secret_key);
+        let hash = keccak256(b"transaction");
+        let signature = Key::sign(&&signer, &hash, Some(1)).unwrap();
+        assert!(signature.v == 37 || signature.v == 38);
+        let rs = [signature.r.as_bytes(), signature.s.as_bytes()].concat();
+        let recovered = recover(&hash, &rs, (signature.v - 37) as i32).unwrap();
+        assert_eq!(recovered, signer.address());
+        assert_eq!(
+            Key::sign(&&signer, &hash, None).unwrap().v,
+            signature.v - 10
+        );
+        assert!(Key::sign(&&signer, &[0; 4], None).is_err());
+    }
 }
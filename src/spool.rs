@@ -0,0 +1,134 @@
+use std::{error::Error, fs, path::PathBuf, pin::pin};
+
+use bytes::{Bytes, BytesMut};
+use futures::{stream, Stream, StreamExt};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{self, AsyncRead, AsyncReadExt, AsyncWriteExt},
+};
+
+use crate::error::BundlrError;
+
+/// Data read once from a reader, kept in a temporary file to be read again as many times as
+/// needed without holding it in memory. The file is removed when the spool is dropped.
+pub(crate) struct Spool {
+    path: PathBuf,
+    len: u64,
+}
+
+impl Spool {
+    /// Copies everything `reader` yields to a new temporary file.
+    pub(crate) async fn read_from(reader: impl AsyncRead) -> Result<Self, BundlrError> {
+        let (mut spool, mut file) = Self::create().await?;
+        spool.len = io::copy(&mut pin!(reader), &mut file).await?;
+        file.flush().await?;
+        Ok(spool)
+    }
+
+    /// Copies the chunks of `chunks` to a new temporary file, pulling each one once the previous
+    /// is written. Fails on the first error of `chunks`.
+    pub(crate) async fn collect<E>(
+        chunks: impl Stream<Item = Result<Bytes, E>>,
+    ) -> Result<Self, BundlrError>
+    where
+        E: Into<Box<dyn Error + Send + Sync>>,
+    {
+        let (mut spool, mut file) = Self::create().await?;
+        let mut chunks = pin!(chunks);
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk.map_err(|err| BundlrError::IoError(std::io::Error::other(err)))?;
+            file.write_all(&chunk).await?;
+            spool.len += chunk.len() as u64;
+        }
+        file.flush().await?;
+        Ok(spool)
+    }
+
+    async fn create() -> Result<(Self, File), BundlrError> {
+        let path =
+            std::env::temp_dir().join(format!("bundlr-spool-{:016x}", rand::random::<u64>()));
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await?;
+        // From here on, the file goes away with the spool, even on errors
+        Ok((Self { path, len: 0 }, file))
+    }
+
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// The data, read from the file anew in chunks of at most `chunk_size` bytes.
+    pub(crate) fn stream(
+        &self,
+        chunk_size: usize,
+    ) -> Result<impl Stream<Item = std::io::Result<Bytes>> + Send + Sync + 'static, BundlrError>
+    {
+        let file = File::from_std(fs::File::open(&self.path)?);
+        Ok(stream::try_unfold(file, move |mut file| async move {
+            let mut chunk = BytesMut::with_capacity(chunk_size);
+            while chunk.len() < chunk_size {
+                if file.read_buf(&mut chunk).await? == 0 {
+                    break;
+                }
+            }
+            Ok((!chunk.is_empty()).then(|| (chunk.freeze(), file)))
+        }))
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use bytes::Bytes;
+    use futures::{stream, TryStreamExt};
+
+    use super::Spool;
+    use crate::error::BundlrError;
+
+    #[tokio::test]
+    async fn should_read_the_spooled_data_again_in_chunks() {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let spool = Spool::read_from(&data[..]).await.unwrap();
+        assert_eq!(spool.len(), 1000);
+
+        for _ in 0..2 {
+            let chunks: Vec<_> = spool.stream(300).unwrap().try_collect().await.unwrap();
+            let lengths: Vec<_> = chunks.iter().map(|chunk| chunk.len()).collect();
+            assert_eq!(lengths, [300, 300, 300, 100]);
+            assert_eq!(chunks.concat(), data);
+        }
+
+        let path = spool.path.clone();
+        assert!(path.exists());
+        drop(spool);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn should_spool_streams_up_to_their_first_error() {
+        let chunks = [Bytes::from_static(b"Hello"), Bytes::from_static(b" world")];
+        let spool = Spool::collect(stream::iter(chunks.map(Ok::<_, io::Error>)))
+            .await
+            .unwrap();
+        assert_eq!(spool.len(), 11);
+        let data: Vec<_> = spool.stream(64).unwrap().try_collect().await.unwrap();
+        assert_eq!(data.concat(), b"Hello world");
+
+        let chunks = stream::iter([
+            Ok(Bytes::from_static(b"Hello")),
+            Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset")),
+        ]);
+        let res = Spool::collect(chunks).await;
+        assert!(matches!(res, Err(BundlrError::IoError(_))));
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bundlr::{fetch_pub_info, PubInfo},
+    clock::Clock,
+    redirect::Redirects,
+    shutdown::InFlight,
+};
+
+/// Snapshot of what a client learned from its node, to start new clients without fetching it
+/// again, e.g. on serverless cold starts.
+///
+/// It never contains secrets: the currency, and so the wallet, is provided separately when
+/// building a client from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientState {
+    pub url: String,
+    pub pub_info: PubInfo,
+    /// When `pub_info` was fetched from the node, `None` if it was provided by hand.
+    pub pub_info_fetched_at: Option<SystemTime>,
+}
+
+impl ClientState {
+    /// Whether `pub_info` was fetched at most `ttl` ago.
+    pub fn is_fresh(&self, ttl: Duration) -> bool {
+        is_fresh(self.pub_info_fetched_at, ttl, SystemTime::now())
+    }
+}
+
+pub(crate) fn is_fresh(fetched_at: Option<SystemTime>, ttl: Duration, now: SystemTime) -> bool {
+    fetched_at
+        .and_then(|fetched_at| now.duration_since(fetched_at).ok())
+        .is_some_and(|age| age <= ttl)
+}
+
+/// The node's public info, shared with background refreshes. Empty until fetched for clients
+/// built with [`BundlrBuilder::fetch_pub_info_lazily`](crate::BundlrBuilder::fetch_pub_info_lazily).
+#[derive(Clone, Default)]
+pub(crate) struct PubInfoCache(Arc<RwLock<Option<Fetched>>>);
+
+/// Public info, and when it was fetched if it was.
+type Fetched = (PubInfo, Option<SystemTime>);
+
+impl PubInfoCache {
+    pub(crate) fn new(pub_info: PubInfo, fetched_at: Option<SystemTime>) -> Self {
+        Self(Arc::new(RwLock::new(Some((pub_info, fetched_at)))))
+    }
+
+    /// The info, a default one if not fetched yet.
+    pub(crate) fn get(&self) -> Fetched {
+        self.loaded().unwrap_or_default()
+    }
+
+    pub(crate) fn loaded(&self) -> Option<Fetched> {
+        self.0.read().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+
+    pub(crate) fn set(&self, pub_info: PubInfo, fetched_at: SystemTime) {
+        *self.0.write().unwrap_or_else(|err| err.into_inner()) = Some((pub_info, Some(fetched_at)));
+    }
+
+    /// Fetches the public info again in the background, keeping the current one if it fails.
+    /// The refresh is tracked in `in_flight`. Does nothing outside of a Tokio runtime.
+    pub(crate) fn refresh_in_background(
+        &self,
+        client: &reqwest::Client,
+        redirects: &Redirects,
+        url: Url,
+        clock: Clock,
+        in_flight: &InFlight,
+    ) {
+        let handle = match tokio::runtime::Handle::try_current() {
+            Ok(handle) => handle,
+            Err(_) => return,
+        };
+        let (cache, client, redirects) = (self.clone(), client.clone(), redirects.clone());
+        let name = format!("bundlr::refresh_pub_info {}", url);
+        let refresh = async move {
+            if let Ok(pub_info) = fetch_pub_info(&client, &redirects, &url, false).await {
+                cache.set(pub_info, clock.now());
+            }
+        };
+        // Not refreshed when shutting down
+        in_flight.spawn(&name, &handle, refresh).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::PathBuf,
+        str::FromStr,
+        time::{Duration, SystemTime},
+    };
+
+    use httpmock::{Method::GET, MockServer};
+    use reqwest::Url;
+
+    use super::ClientState;
+    use crate::{
+        clock::MockClock, currency::arweave::ArweaveBuilder, shutdown::ShutdownReport,
+        BundlrBuilder,
+    };
+
+    const TTL: Duration = Duration::from_secs(3600);
+
+    fn info_mock(server: &MockServer) -> httpmock::Mock<'_> {
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(r#"{ "version": "0.2.0", "gateway": "arweave.net", "addresses": { "arweave": "address" } }"#);
+        })
+    }
+
+    fn builder() -> BundlrBuilder<crate::currency::arweave::Arweave> {
+        let wallet = PathBuf::from_str("res/test_wallet.json").unwrap();
+        BundlrBuilder::new().currency(ArweaveBuilder::new().keypair_path(wallet).build().unwrap())
+    }
+
+    #[tokio::test]
+    async fn should_start_from_fresh_state_without_requests() {
+        let server = MockServer::start();
+        let info = info_mock(&server);
+        let original = builder()
+            .url(Url::from_str(&server.url("/")).unwrap())
+            .fetch_pub_info()
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        let exported = serde_json::to_string(&original.export_state()).unwrap();
+        assert!(!exported.contains("\"n\""), "Keys must not be exported");
+
+        let state: ClientState = serde_json::from_str(&exported).unwrap();
+        assert!(state.is_fresh(TTL));
+        let restored = builder().with_state(state, TTL).unwrap().build().unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let state = restored.export_state();
+        assert_eq!(state.url, server.url("/"));
+        assert_eq!(state.pub_info.addresses()["arweave"], "address");
+        info.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn should_refresh_stale_state_once_in_background() {
+        let server = MockServer::start();
+        let info = info_mock(&server);
+        let mock = MockClock::new(SystemTime::UNIX_EPOCH + 10 * TTL);
+        let fetched_at = mock.now();
+        let state = ClientState {
+            url: server.url("/"),
+            pub_info: Default::default(),
+            pub_info_fetched_at: Some(fetched_at),
+        };
+        let fresh = builder()
+            .clock(mock.clone().into())
+            .with_state(state.clone(), TTL)
+            .unwrap()
+            .build()
+            .unwrap();
+        mock.advance(2 * TTL);
+        let stale = builder()
+            .clock(mock.clone().into())
+            .with_state(state, TTL)
+            .unwrap()
+            .build()
+            .unwrap();
+        // Served from the snapshot until refreshed
+        for _ in 0..50 {
+            if stale.export_state().pub_info_fetched_at != Some(fetched_at) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let state = stale.export_state();
+        assert_eq!(state.pub_info_fetched_at, Some(mock.now()));
+        assert_eq!(state.pub_info.addresses()["arweave"], "address");
+        assert_eq!(fresh.export_state().pub_info_fetched_at, Some(fetched_at));
+        info.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn should_track_background_refreshes_on_shutdown() {
+        let server = MockServer::start();
+        let info = server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200)
+                .body(r#"{ "version": "0.2.0", "gateway": "arweave.net", "addresses": {} }"#)
+                .delay(Duration::from_millis(300));
+        });
+        let state = ClientState {
+            url: server.url("/"),
+            pub_info: Default::default(),
+            pub_info_fetched_at: None,
+        };
+        let stale = builder().with_state(state, TTL).unwrap().build().unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let report = stale.shutdown(Duration::from_secs(10)).await;
+        assert_eq!(
+            report,
+            ShutdownReport {
+                completed: 1,
+                cancelled: 0,
+                polls_cancelled: 0
+            }
+        );
+        assert!(stale.export_state().pub_info_fetched_at.is_some());
+        info.assert();
+    }
+}
@@ -1,7 +1,10 @@
-use avro_rs::{from_avro_datum, to_avro_datum, Schema};
+use avro_rs::Schema;
 use bytes::Bytes;
 use lazy_static::lazy_static;
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{self, value::MapDeserializer, DeserializeOwned, IntoDeserializer, Unexpected, Visitor},
+    forward_to_deserialize_any, Deserialize, Deserializer, Serialize,
+};
 
 use crate::error::BundlrError;
 
@@ -18,6 +21,356 @@ impl Tag {
             value: value.to_string(),
         }
     }
+
+    /// Splits a value that may be longer than [`MAX_TAG_VALUE_BYTES`] over several tags, read back
+    /// with [`TagList::get_chunked`].
+    ///
+    /// The value is cut at character boundaries into `name`, `name-1`, `name-2`, ..., followed by
+    /// a `name-Chunks` tag with the number of chunks. Fails if it would take more than
+    /// [`MAX_TAGS`] tags, or if the names are too long.
+    pub fn new_chunked(name: &str, value: &str) -> Result<Vec<Self>, BundlrError> {
+        let count_name = chunk_count_name(name);
+        if count_name.len() > MAX_TAG_NAME_BYTES {
+            return Err(BundlrError::InvalidChunkedTag(format!(
+                "Name {} is too long",
+                count_name
+            )));
+        }
+
+        let mut chunks = vec![];
+        let mut rest = value;
+        loop {
+            let mut end = rest.len().min(MAX_TAG_VALUE_BYTES);
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            let (chunk, next) = rest.split_at(end);
+            chunks.push(chunk);
+            rest = next;
+            if rest.is_empty() {
+                break;
+            }
+        }
+        if chunks.len() >= MAX_TAGS {
+            return Err(BundlrError::InvalidChunkedTag(format!(
+                "Value of {} bytes needs {} tags, more than the {} allowed",
+                value.len(),
+                chunks.len() + 1,
+                MAX_TAGS
+            )));
+        }
+
+        let count = chunks.len().to_string();
+        let mut tags: Vec<Self> = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| Tag::new(&chunk_name(name, i), chunk))
+            .collect();
+        tags.push(Tag::new(&count_name, &count));
+        Ok(tags)
+    }
+
+    /// Same as [`Tag::from_serializable_with`], failing on fields holding objects or arrays.
+    pub fn from_serializable<T: Serialize>(
+        prefix: Option<&str>,
+        value: &T,
+    ) -> Result<Vec<Self>, BundlrError> {
+        Self::from_serializable_with(prefix, value, NestedFields::Reject)
+    }
+
+    /// Writes the top-level fields of a struct or map as tags, read back with
+    /// [`TagList::deserialize_into`]. Tags are named after their field, prefixed with `prefix`.
+    ///
+    /// Strings are written as is, numbers and booleans as their JSON text, and objects and arrays
+    /// according to `nested`. Fields set to `None` or `()` are left out.
+    ///
+    /// Fails, naming the field, if a name or value is too long, or if there are more fields than
+    /// an item can have tags.
+    pub fn from_serializable_with<T: Serialize>(
+        prefix: Option<&str>,
+        value: &T,
+        nested: NestedFields,
+    ) -> Result<Vec<Self>, BundlrError> {
+        let fields = match serde_json::to_value(value) {
+            Ok(serde_json::Value::Object(fields)) => fields,
+            Ok(_) => {
+                return Err(BundlrError::TagSerialization(
+                    "Only structs and maps can be written as tags".to_owned(),
+                ))
+            }
+            Err(err) => return Err(BundlrError::TagSerialization(err.to_string())),
+        };
+        let invalid = |field: &str, reason: String| {
+            BundlrError::TagSerialization(format!("Field {}: {}", field, reason))
+        };
+
+        let mut tags = vec![];
+        for (field, value) in fields {
+            let value = match value {
+                serde_json::Value::Null => continue,
+                serde_json::Value::String(value) => value,
+                serde_json::Value::Array(_) | serde_json::Value::Object(_)
+                    if nested == NestedFields::Reject =>
+                {
+                    return Err(invalid(&field, "Nested values are not allowed".to_owned()))
+                }
+                value => value.to_string(),
+            };
+            let name = format!("{}{}", prefix.unwrap_or_default(), field);
+            if name.len() > MAX_TAG_NAME_BYTES {
+                return Err(invalid(
+                    &field,
+                    format!("Name is longer than {} bytes", MAX_TAG_NAME_BYTES),
+                ));
+            }
+            if value.len() > MAX_TAG_VALUE_BYTES {
+                return Err(invalid(
+                    &field,
+                    format!(
+                        "Value is {} bytes, more than the {} a tag can hold",
+                        value.len(),
+                        MAX_TAG_VALUE_BYTES
+                    ),
+                ));
+            }
+            tags.push(Tag { name, value });
+        }
+        if tags.len() > MAX_TAGS {
+            return Err(BundlrError::TagSerialization(format!(
+                "{} fields, more than the {} tags an item can have",
+                tags.len(),
+                MAX_TAGS
+            )));
+        }
+        Ok(tags)
+    }
+}
+
+fn chunk_name(name: &str, i: usize) -> String {
+    match i {
+        0 => name.to_owned(),
+        i => format!("{}-{}", name, i),
+    }
+}
+
+fn chunk_count_name(name: &str) -> String {
+    format!("{}-Chunks", name)
+}
+
+/// Lookups over the tags of an item or of a transaction fetched from a gateway.
+pub trait TagList {
+    fn as_tags(&self) -> &[Tag];
+
+    /// Value of the first tag called `name`.
+    fn tag_value(&self, name: &str) -> Option<&str> {
+        self.as_tags()
+            .iter()
+            .find(|tag| tag.name == name)
+            .map(|tag| tag.value.as_str())
+    }
+
+    /// Reassembles a value written with [`Tag::new_chunked`], or reads a plain `name` tag if there
+    /// is no `name-Chunks` tag. `None` if there is no `name` tag.
+    ///
+    /// Fails if chunks are missing or the chunk count is invalid.
+    fn get_chunked(&self, name: &str) -> Result<Option<String>, BundlrError> {
+        let first = match self.tag_value(name) {
+            Some(first) => first,
+            None => return Ok(None),
+        };
+        let count = match self.tag_value(&chunk_count_name(name)) {
+            Some(count) => count
+                .parse::<usize>()
+                .ok()
+                .filter(|count| (1..MAX_TAGS).contains(count))
+                .ok_or_else(|| {
+                    BundlrError::InvalidChunkedTag(format!(
+                        "Invalid chunk count {:?} for {}",
+                        count, name
+                    ))
+                })?,
+            None => return Ok(Some(first.to_owned())),
+        };
+
+        let mut value = first.to_owned();
+        for i in 1..count {
+            let chunk_name = chunk_name(name, i);
+            let chunk = self.tag_value(&chunk_name).ok_or_else(|| {
+                BundlrError::InvalidChunkedTag(format!("Missing chunk {}", chunk_name))
+            })?;
+            value.push_str(chunk);
+        }
+        Ok(Some(value))
+    }
+
+    /// Reads back a value written with [`Tag::from_serializable`], from the tags whose name
+    /// starts with `prefix`, stripped of it. The first tag of each name is used.
+    ///
+    /// Tag values are parsed as the type of their field: numbers and booleans from their text,
+    /// objects and arrays from JSON. Fields without a tag must be optional.
+    fn deserialize_into<T: DeserializeOwned>(
+        &self,
+        prefix: Option<&str>,
+    ) -> Result<T, BundlrError> {
+        let prefix = prefix.unwrap_or_default();
+        let mut fields: Vec<(&str, &str)> = vec![];
+        for tag in self.as_tags() {
+            if let Some(field) = tag.name.strip_prefix(prefix) {
+                if !fields.iter().any(|(name, _)| *name == field) {
+                    fields.push((field, &tag.value));
+                }
+            }
+        }
+        let fields = MapDeserializer::new(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name, TagValue(value))),
+        );
+        T::deserialize(fields).map_err(|err| BundlrError::TagSerialization(err.to_string()))
+    }
+}
+
+impl TagList for [Tag] {
+    fn as_tags(&self) -> &[Tag] {
+        self
+    }
+}
+
+/// How [`Tag::from_serializable_with`] writes fields holding objects or arrays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NestedFields {
+    /// Fails, naming the field.
+    #[default]
+    Reject,
+    /// Writes them as compact JSON.
+    Json,
+}
+
+/// Reads a tag value as whatever type the field being deserialized expects, parsing numbers,
+/// booleans and JSON encoded objects and arrays out of it.
+struct TagValue<'a>(&'a str);
+
+macro_rules! parse_tag_value {
+    ($($method:ident => $ty:ty, $visit:ident;)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self.0.parse::<$ty>() {
+                    Ok(value) => visitor.$visit(value.into()),
+                    Err(_) => Err(de::Error::invalid_value(
+                        Unexpected::Str(self.0),
+                        &stringify!($ty),
+                    )),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for TagValue<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.0)
+    }
+
+    parse_tag_value! {
+        deserialize_bool => bool, visit_bool;
+        deserialize_i8 => i64, visit_i64;
+        deserialize_i16 => i64, visit_i64;
+        deserialize_i32 => i64, visit_i64;
+        deserialize_i64 => i64, visit_i64;
+        deserialize_i128 => i128, visit_i128;
+        deserialize_u8 => u64, visit_u64;
+        deserialize_u16 => u64, visit_u64;
+        deserialize_u32 => u64, visit_u64;
+        deserialize_u64 => u64, visit_u64;
+        deserialize_u128 => u128, visit_u128;
+        deserialize_f32 => f64, visit_f64;
+        deserialize_f64 => f64, visit_f64;
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.json()?
+            .deserialize_seq(visitor)
+            .map_err(de::Error::custom)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.json()?
+            .deserialize_tuple(len, visitor)
+            .map_err(de::Error::custom)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.json()?
+            .deserialize_tuple_struct(name, len, visitor)
+            .map_err(de::Error::custom)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.json()?
+            .deserialize_map(visitor)
+            .map_err(de::Error::custom)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.json()?
+            .deserialize_struct(name, fields, visitor)
+            .map_err(de::Error::custom)
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct identifier ignored_any
+    }
+}
+
+impl TagValue<'_> {
+    fn json(&self) -> Result<serde_json::Value, de::value::Error> {
+        serde_json::from_str(self.0).map_err(de::Error::custom)
+    }
+}
+
+impl<'de, 'a> IntoDeserializer<'de, de::value::Error> for TagValue<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
 }
 
 const SCHEMA_STR: &str = r#"{
@@ -47,21 +400,114 @@ pub trait AvroDecode {
     fn decode(&mut self) -> Result<Vec<Tag>, BundlrError>;
 }
 
+/// Writes the tags by hand, the same way as `avro_rs` with [`TAGS_SCHEMA`] would, in a single
+/// block, without going through its intermediate values.
 impl AvroEncode for Vec<Tag> {
     fn encode(&self) -> Result<Bytes, BundlrError> {
-        let v = avro_rs::to_value(self)?;
-        to_avro_datum(&TAGS_SCHEMA, v)
-            .map(|v| v.into())
-            .map_err(|_| BundlrError::NoBytesLeft)
+        fn write_long(buffer: &mut Vec<u8>, n: i64) {
+            let mut value = ((n << 1) ^ (n >> 63)) as u64;
+            while value >= 0x80 {
+                buffer.push((value as u8 & 0x7f) | 0x80);
+                value >>= 7;
+            }
+            buffer.push(value as u8);
+        }
+        fn write_string(buffer: &mut Vec<u8>, s: &str) {
+            write_long(buffer, s.len() as i64);
+            buffer.extend_from_slice(s.as_bytes());
+        }
+
+        // Lengths take at most two bytes each for tags within the limits
+        let capacity = self
+            .iter()
+            .map(|tag| tag.name.len() + tag.value.len() + 4)
+            .sum::<usize>()
+            + 11;
+        let mut buffer = Vec::with_capacity(capacity);
+        if !self.is_empty() {
+            write_long(&mut buffer, self.len() as i64);
+            for tag in self {
+                write_string(&mut buffer, &tag.name);
+                write_string(&mut buffer, &tag.value);
+            }
+        }
+        buffer.push(0);
+        Ok(buffer.into())
+    }
+}
+
+/// Most tags an item may have, per ANS-104.
+pub const MAX_TAGS: usize = 128;
+/// Longest tag name, in bytes, per ANS-104.
+pub const MAX_TAG_NAME_BYTES: usize = 1024;
+/// Longest tag value, in bytes, per ANS-104.
+pub const MAX_TAG_VALUE_BYTES: usize = 3072;
+
+/// Reads the Avro encoding of the tags schema by hand, rather than through `avro_rs`, which
+/// allocates for the number of items or bytes announced by its input before checking it.
+struct TagsReader<'a>(&'a [u8]);
+
+impl TagsReader<'_> {
+    fn read_long(&mut self) -> Result<i64, BundlrError> {
+        let mut value = 0u64;
+        for i in 0..10 {
+            let (byte, rest) = self
+                .0
+                .split_first()
+                .ok_or(BundlrError::InvalidTagEncoding)?;
+            self.0 = rest;
+            value |= ((byte & 0x7f) as u64) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok((value >> 1) as i64 ^ -((value & 1) as i64));
+            }
+        }
+        Err(BundlrError::InvalidTagEncoding)
+    }
+
+    fn read_string(&mut self, max_len: usize) -> Result<String, BundlrError> {
+        let len =
+            usize::try_from(self.read_long()?).map_err(|_| BundlrError::InvalidTagEncoding)?;
+        if len > max_len || len > self.0.len() {
+            return Err(BundlrError::InvalidTagEncoding);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        String::from_utf8(bytes.to_vec()).map_err(|_| BundlrError::InvalidTagEncoding)
     }
 }
 
 impl AvroDecode for &mut [u8] {
     fn decode(&mut self) -> Result<Vec<Tag>, BundlrError> {
-        let x = self.to_vec();
-        let v = from_avro_datum(&TAGS_SCHEMA, &mut x.as_slice(), Some(&TAGS_SCHEMA))
-            .map_err(|_| BundlrError::InvalidTagEncoding)?;
-        avro_rs::from_value(&v).map_err(|_| BundlrError::InvalidTagEncoding)
+        (&**self).decode()
+    }
+}
+
+impl AvroDecode for &[u8] {
+    fn decode(&mut self) -> Result<Vec<Tag>, BundlrError> {
+        let mut reader = TagsReader(self);
+        let mut tags = vec![];
+        loop {
+            let count = match reader.read_long()? {
+                0 => break,
+                // A negative count is followed by the block's size in bytes
+                count if count < 0 => {
+                    reader.read_long()?;
+                    count.checked_neg().ok_or(BundlrError::InvalidTagEncoding)?
+                }
+                count => count,
+            };
+            let count = usize::try_from(count).map_err(|_| BundlrError::InvalidTagEncoding)?;
+            if count > MAX_TAGS - tags.len() {
+                return Err(BundlrError::InvalidTagEncoding);
+            }
+            tags.reserve(count);
+            for _ in 0..count {
+                let name = reader.read_string(MAX_TAG_NAME_BYTES)?;
+                let value = reader.read_string(MAX_TAG_VALUE_BYTES)?;
+                tags.push(Tag { name, value });
+            }
+        }
+        Ok(tags)
     }
 }
 
@@ -76,13 +522,16 @@ mod tests {
 
     use crate::tags::{AvroDecode, AvroEncode};
 
-    use super::Tag;
+    use serde::{Deserialize, Serialize};
+
+    use super::{NestedFields, Tag, TagList, MAX_TAGS, MAX_TAG_VALUE_BYTES, TAGS_SCHEMA};
+    use crate::error::BundlrError;
 
     #[test]
     fn test_bytes() {
-        let b = &[2u8, 8, 110, 97, 109, 101, 10, 118, 97, 108, 117, 101, 0];
+        let mut b = [2u8, 8, 110, 97, 109, 101, 10, 118, 97, 108, 117, 101, 0];
 
-        let mut sli = &mut b.clone()[..];
+        let mut sli = &mut b[..];
 
         dbg!((sli).decode()).unwrap();
     }
@@ -96,4 +545,142 @@ mod tests {
 
         dbg!(tags.encode().unwrap().to_vec());
     }
+
+    #[test]
+    fn should_encode_like_avro_rs() {
+        let long = "v".repeat(3072);
+        for tags in [
+            vec![],
+            vec![Tag::new("name", "value")],
+            vec![Tag::new("", ""), Tag::new("Ünïcode", &long)],
+            (0..128)
+                .map(|i| Tag::new(&format!("name-{}", i), &i.to_string()))
+                .collect(),
+        ] {
+            let value = avro_rs::to_value(&tags).unwrap();
+            let expected = avro_rs::to_avro_datum(&TAGS_SCHEMA, value).unwrap();
+            let encoded = tags.encode().unwrap();
+            assert_eq!(encoded.to_vec(), expected);
+            assert_eq!(encoded.to_vec().as_mut_slice().decode().unwrap(), tags);
+        }
+    }
+
+    #[test]
+    fn should_chunk_long_values() {
+        let max = MAX_TAG_VALUE_BYTES;
+        for (len, chunks) in [
+            (0, 1),
+            (max, 1),
+            (max + 1, 2),
+            (2 * max, 2),
+            (127 * max, 127),
+        ] {
+            let value = "v".repeat(len);
+            let tags = Tag::new_chunked("Payload", &value).unwrap();
+            assert_eq!(tags.len(), chunks + 1, "{}", len);
+            assert!(tags.iter().all(|tag| tag.value.len() <= max));
+            assert_eq!(
+                tags.tag_value("Payload-Chunks"),
+                Some(chunks.to_string().as_str())
+            );
+            assert_eq!(tags.get_chunked("Payload").unwrap(), Some(value));
+            assert!(tags.encode().unwrap().as_ref().decode().is_ok());
+        }
+
+        // Characters are never split
+        let value = format!("v{}", "é".repeat(max));
+        let tags = Tag::new_chunked("Payload", &value).unwrap();
+        assert_eq!(tags[0].value.len(), max - 1);
+        assert_eq!(tags.get_chunked("Payload").unwrap(), Some(value));
+
+        let plain = [Tag::new("Payload", "value")];
+        assert_eq!(
+            plain.get_chunked("Payload").unwrap(),
+            Some("value".to_owned())
+        );
+        assert_eq!(plain.get_chunked("Other").unwrap(), None);
+    }
+
+    #[test]
+    fn should_reject_invalid_chunks() {
+        fn invalid<T>(res: Result<T, BundlrError>) -> bool {
+            matches!(res, Err(BundlrError::InvalidChunkedTag(_)))
+        }
+        let too_long = "v".repeat(MAX_TAGS * MAX_TAG_VALUE_BYTES);
+        assert!(invalid(Tag::new_chunked("Payload", &too_long)));
+        assert!(invalid(Tag::new_chunked(&"n".repeat(1024), "value")));
+
+        let mut tags = Tag::new_chunked("Payload", &"v".repeat(3 * MAX_TAG_VALUE_BYTES)).unwrap();
+        tags.remove(1);
+        assert!(invalid(tags.get_chunked("Payload")));
+        for count in ["0", "x", "128"] {
+            let tags = [Tag::new("Payload", "v"), Tag::new("Payload-Chunks", count)];
+            assert!(invalid(tags.get_chunked("Payload")), "{}", count);
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    struct Metadata {
+        title: String,
+        page_count: u32,
+        offset: i64,
+        rating: f64,
+        published: bool,
+        subtitle: Option<String>,
+        keywords: Vec<String>,
+    }
+
+    fn metadata(subtitle: Option<&str>) -> Metadata {
+        Metadata {
+            title: "12".to_owned(),
+            page_count: 256,
+            offset: -3,
+            rating: 4.5,
+            published: true,
+            subtitle: subtitle.map(str::to_owned),
+            keywords: vec!["a".to_owned(), "b".to_owned()],
+        }
+    }
+
+    #[test]
+    fn should_round_trip_serializable_values() {
+        for (prefix, subtitle) in [(None, None), (Some("Meta-"), Some("true"))] {
+            let value = metadata(subtitle);
+            let mut tags = vec![Tag::new("Content-Type", "text/plain")];
+            tags.extend(Tag::from_serializable_with(prefix, &value, NestedFields::Json).unwrap());
+            let name = |field: &str| format!("{}{}", prefix.unwrap_or_default(), field);
+            assert_eq!(tags.tag_value(&name("pageCount")), Some("256"));
+            assert_eq!(tags.tag_value(&name("published")), Some("true"));
+            assert_eq!(tags.tag_value(&name("keywords")), Some(r#"["a","b"]"#));
+            assert_eq!(tags.tag_value(&name("subtitle")), subtitle);
+
+            // The Content-Type tag is an unknown field, ignored
+            let read: Metadata = tags.deserialize_into(prefix).unwrap();
+            assert_eq!(read, value);
+        }
+    }
+
+    #[test]
+    fn should_reject_unserializable_values() {
+        fn invalid<T>(res: Result<T, BundlrError>, field: &str) -> bool {
+            matches!(res, Err(BundlrError::TagSerialization(msg)) if msg.contains(field))
+        }
+        let value = metadata(None);
+        assert!(invalid(Tag::from_serializable(None, &value), "keywords"));
+        let long = Metadata {
+            title: "t".repeat(MAX_TAG_VALUE_BYTES + 1),
+            ..value
+        };
+        assert!(invalid(
+            Tag::from_serializable_with(None, &long, NestedFields::Json),
+            "title"
+        ));
+        assert!(invalid(Tag::from_serializable(None, &vec![1]), "structs"));
+        let fields: std::collections::HashMap<_, _> = (0..=MAX_TAGS).map(|i| (i, i)).collect();
+        assert!(invalid(Tag::from_serializable(None, &fields), "129 fields"));
+
+        let tags = [Tag::new("title", "t"), Tag::new("pageCount", "many")];
+        assert!(invalid(tags.deserialize_into::<Metadata>(None), "many"));
+    }
 }
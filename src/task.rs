@@ -0,0 +1,177 @@
+//! Tasks spawned by clients, and spans around what they do.
+//!
+//! With the `tracing` feature, uploads and chunks run in spans linked by the ids they carry,
+//! and, when also built with `--cfg tokio_unstable`, tasks are named after what they work on
+//! for tokio-console to tell them apart.
+
+use std::future::Future;
+
+use tokio::{runtime::Handle, task::JoinHandle};
+
+/// Runs `future` in a span, only built with the `tracing` feature.
+macro_rules! in_span {
+    ($future:expr, $($span:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        let future = tracing::Instrument::instrument($future, tracing::info_span!($($span)+));
+        #[cfg(not(feature = "tracing"))]
+        let future = $future;
+        future
+    }};
+}
+pub(crate) use in_span;
+
+/// Spawns `future` on `handle`, as a task named `name`.
+pub(crate) fn spawn_on<F>(name: &str, future: F, handle: &Handle) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    #[cfg(all(feature = "tracing", tokio_unstable))]
+    return tokio::task::Builder::new()
+        .name(name)
+        .spawn_on(future, handle)
+        .expect("Tasks spawn on running runtimes");
+    #[cfg(not(all(feature = "tracing", tokio_unstable)))]
+    {
+        let _ = name;
+        handle.spawn(future)
+    }
+}
+
+/// Runs `f` on the blocking thread pool, as a task named `name`.
+pub(crate) fn spawn_blocking<F, R>(name: &str, f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    #[cfg(all(feature = "tracing", tokio_unstable))]
+    return tokio::task::Builder::new()
+        .name(name)
+        .spawn_blocking(f)
+        .expect("Tasks spawn on running runtimes");
+    #[cfg(not(all(feature = "tracing", tokio_unstable)))]
+    {
+        let _ = name;
+        tokio::task::spawn_blocking(f)
+    }
+}
+
+/// A subscriber recording the spans created, with their fields and parents.
+#[cfg(all(test, feature = "tracing"))]
+pub(crate) mod recording {
+    use std::{
+        collections::HashMap,
+        fmt::Debug,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub(crate) struct RecordedSpan {
+        pub(crate) id: u64,
+        pub(crate) name: &'static str,
+        pub(crate) parent: Option<u64>,
+        pub(crate) fields: HashMap<&'static str, String>,
+    }
+
+    impl Visit for RecordedSpan {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.fields.insert(field.name(), format!("{:?}", value));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.fields.insert(field.name(), value.to_owned());
+        }
+    }
+
+    #[derive(Default)]
+    struct State {
+        spans: Vec<RecordedSpan>,
+        /// Spans entered on each thread, innermost last.
+        entered: HashMap<std::thread::ThreadId, Vec<u64>>,
+    }
+
+    #[derive(Clone, Default)]
+    pub(crate) struct Recorder(Arc<Mutex<State>>);
+
+    impl Recorder {
+        pub(crate) fn spans(&self) -> Vec<RecordedSpan> {
+            self.0.lock().unwrap().spans.clone()
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+            let mut state = self.0.lock().unwrap();
+            let parent = match attributes.parent() {
+                Some(parent) => Some(parent.into_u64()),
+                None if attributes.is_contextual() => state
+                    .entered
+                    .get(&std::thread::current().id())
+                    .and_then(|entered| entered.last().copied()),
+                None => None,
+            };
+            let mut span = RecordedSpan {
+                id: state.spans.len() as u64 + 1,
+                name: attributes.metadata().name(),
+                parent,
+                fields: HashMap::new(),
+            };
+            attributes.record(&mut span);
+            let id = Id::from_u64(span.id);
+            state.spans.push(span);
+            id
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut state = self.0.lock().unwrap();
+            values.record(&mut state.spans[span.into_u64() as usize - 1]);
+        }
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, _event: &Event<'_>) {}
+
+        fn enter(&self, span: &Id) {
+            let mut state = self.0.lock().unwrap();
+            let thread = std::thread::current().id();
+            state
+                .entered
+                .entry(thread)
+                .or_default()
+                .push(span.into_u64());
+        }
+
+        fn exit(&self, span: &Id) {
+            let mut state = self.0.lock().unwrap();
+            let thread = std::thread::current().id();
+            let entered = state.entered.entry(thread).or_default();
+            if let Some(position) = entered.iter().rposition(|id| *id == span.into_u64()) {
+                entered.remove(position);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::runtime::Handle;
+
+    use super::{spawn_blocking, spawn_on};
+
+    #[tokio::test]
+    async fn should_spawn_named_tasks() {
+        let task = spawn_on("bundlr::test", async { 1 }, &Handle::current());
+        let blocking = spawn_blocking("bundlr::test_blocking", || 2);
+        assert_eq!((task.await.unwrap(), blocking.await.unwrap()), (1, 2));
+    }
+}
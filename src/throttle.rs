@@ -0,0 +1,165 @@
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+
+use crate::clock::Clock;
+
+/// Limits how fast a client sends the bytes of its uploads, all of them together.
+///
+/// Each send books the time its bytes take at the rate, after those booked before it, and
+/// waits for its turn. A client that sent nothing for a while may send right away, but never
+/// more than one send ahead of the rate.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Throttle(Option<Arc<Limiter>>);
+
+#[derive(Debug)]
+struct Limiter {
+    bytes_per_sec: u64,
+    clock: Clock,
+    free_at: Mutex<Option<SystemTime>>,
+}
+
+impl Throttle {
+    /// Sends at most `bytes_per_sec` bytes per second, timed with `clock`, or as fast as
+    /// possible without a rate.
+    pub(crate) fn new(bytes_per_sec: Option<u64>, clock: Clock) -> Self {
+        Self(bytes_per_sec.map(|bytes_per_sec| {
+            Arc::new(Limiter {
+                bytes_per_sec: bytes_per_sec.max(1),
+                clock,
+                free_at: Mutex::new(None),
+            })
+        }))
+    }
+
+    pub(crate) fn bytes_per_sec(&self) -> Option<u64> {
+        self.0.as_ref().map(|limiter| limiter.bytes_per_sec)
+    }
+
+    /// Waits until `bytes` more bytes may be sent.
+    pub(crate) async fn acquire(&self, bytes: usize) {
+        let Some(limiter) = &self.0 else {
+            return;
+        };
+        let wait = {
+            let now = limiter.clock.now();
+            let mut free_at = limiter
+                .free_at
+                .lock()
+                .unwrap_or_else(|err| err.into_inner());
+            let start = free_at.filter(|free_at| *free_at > now).unwrap_or(now);
+            let takes = Duration::from_secs_f64(bytes as f64 / limiter.bytes_per_sec as f64);
+            *free_at = Some(start + takes);
+            start.duration_since(now).unwrap_or_default()
+        };
+        if !wait.is_zero() {
+            limiter.clock.sleep(wait).await;
+        }
+    }
+
+    /// Hands out the chunks of `body` no faster than the rate.
+    pub(crate) fn body<S, E>(&self, body: S) -> impl Stream<Item = Result<Bytes, E>> + Send + Sync
+    where
+        S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+        E: Send + 'static,
+    {
+        let throttle = self.clone();
+        let body = body.then(move |chunk| {
+            let throttle = throttle.clone();
+            async move {
+                if let Ok(chunk) = &chunk {
+                    throttle.acquire(chunk.len()).await;
+                }
+                chunk
+            }
+        });
+        Exclusive(Mutex::new(Box::pin(body)))
+    }
+}
+
+/// A stream only ever polled through `&mut`, which makes it `Sync` as HTTP bodies must be, even
+/// though the futures it waits on need not be.
+struct Exclusive<S>(Mutex<S>);
+
+impl<S> Stream for Exclusive<S>
+where
+    S: Stream + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut()
+            .0
+            .get_mut()
+            .unwrap_or_else(|err| err.into_inner())
+            .poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use bytes::Bytes;
+    use futures::{stream, FutureExt, TryStreamExt};
+
+    use super::Throttle;
+    use crate::clock::{Clock, MockClock};
+
+    #[tokio::test]
+    async fn should_not_wait_without_a_rate() {
+        let throttle = Throttle::new(None, MockClock::new(UNIX_EPOCH).into());
+        for _ in 0..3 {
+            assert!(throttle.acquire(1 << 30).now_or_never().is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn should_space_sends_by_the_rate() {
+        let clock = MockClock::new(UNIX_EPOCH);
+        let throttle = Throttle::new(Some(1000), Clock::from(clock.clone()));
+
+        // The first send goes right away, and books the next second
+        assert!(throttle.acquire(1000).now_or_never().is_some());
+        let second = tokio::spawn({
+            let throttle = throttle.clone();
+            async move { throttle.acquire(500).await }
+        });
+        while clock.sleepers() == 0 {
+            tokio::task::yield_now().await;
+        }
+        clock.advance(Duration::from_millis(999));
+        tokio::task::yield_now().await;
+        assert!(!second.is_finished());
+        clock.advance(Duration::from_millis(1));
+        second.await.unwrap();
+
+        // Past the sends booked so far, sending is free again
+        clock.advance(Duration::from_secs(10));
+        assert!(throttle.acquire(500).now_or_never().is_some());
+    }
+
+    #[tokio::test]
+    async fn should_throttle_bodies_chunk_by_chunk() {
+        let clock = MockClock::new(UNIX_EPOCH);
+        let throttle = Throttle::new(Some(4), Clock::from(clock.clone()));
+        let chunks =
+            [b"head", b"data", b"tail"].map(|chunk| Ok::<_, ()>(Bytes::from_static(chunk)));
+        let body = tokio::spawn(throttle.body(stream::iter(chunks)).try_collect::<Vec<_>>());
+
+        for _ in 0..2 {
+            while clock.sleepers() == 0 {
+                tokio::task::yield_now().await;
+            }
+            clock.advance(Duration::from_secs(1));
+        }
+        assert_eq!(body.await.unwrap().unwrap().concat(), b"headdatatail");
+        assert_eq!(clock.now(), UNIX_EPOCH + Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,140 @@
+//! Tombstones: items by which an uploader asks gateways to stop serving one of their items.
+//!
+//! This is a convention between uploaders and the gateways that choose to honor it. Nothing is
+//! ever deleted: the tombstoned item stays on the network, and can still be served by any gateway
+//! ignoring its tombstones. A tombstone only counts when signed by the owner of its target.
+
+use data_encoding::BASE64URL_NOPAD;
+
+use crate::{error::BundlrError, tags::Tag};
+
+pub const APP_NAME: &str = "Tombstone";
+/// Tag holding the id of the item a tombstone is for.
+pub const TOMBSTONE_FOR_TAG: &str = "Tombstone-For";
+/// Tag holding the reason code of a tombstone.
+pub const REASON_TAG: &str = "Reason";
+pub const MAX_REASON_CODE_LEN: usize = 64;
+
+/// A request for gateways to stop serving an item, carried by the tags of another item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tombstone {
+    target_id: String,
+    reason_code: String,
+}
+
+impl Tombstone {
+    /// Tombstone for the item `target_id`, for `reason_code`: a short code of lowercase ASCII
+    /// letters, digits and dashes, such as `dmca`, whose meaning is up to the gateways.
+    ///
+    /// Fails if `target_id` is not an item id, or if `reason_code` is not such a code.
+    pub fn new(target_id: &str, reason_code: &str) -> Result<Self, BundlrError> {
+        match BASE64URL_NOPAD.decode(target_id.as_bytes()) {
+            Ok(bytes) if bytes.len() == 32 => (),
+            _ => {
+                return Err(BundlrError::InvalidTombstone(format!(
+                    "Invalid target id {:?}",
+                    target_id
+                )))
+            }
+        }
+        let valid_code = !reason_code.is_empty()
+            && reason_code.len() <= MAX_REASON_CODE_LEN
+            && reason_code
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-');
+        if !valid_code {
+            return Err(BundlrError::InvalidTombstone(format!(
+                "Invalid reason code {:?}",
+                reason_code
+            )));
+        }
+        Ok(Self {
+            target_id: target_id.to_owned(),
+            reason_code: reason_code.to_owned(),
+        })
+    }
+
+    /// Reads the tombstone carried by an item's tags.
+    pub fn from_tags(tags: &[Tag]) -> Result<Self, BundlrError> {
+        let get = |name: &str| {
+            tags.iter()
+                .find(|tag| tag.name == name)
+                .map(|tag| tag.value.as_str())
+                .ok_or_else(|| BundlrError::InvalidTombstone(format!("No {} tag", name)))
+        };
+        if get("App-Name")? != APP_NAME {
+            return Err(BundlrError::InvalidTombstone("Not a tombstone".to_owned()));
+        }
+        Self::new(get(TOMBSTONE_FOR_TAG)?, get(REASON_TAG)?)
+    }
+
+    pub fn target_id(&self) -> &str {
+        &self.target_id
+    }
+
+    pub fn reason_code(&self) -> &str {
+        &self.reason_code
+    }
+
+    pub fn tags(&self) -> Vec<Tag> {
+        vec![
+            Tag::new("App-Name", APP_NAME),
+            Tag::new(TOMBSTONE_FOR_TAG, &self.target_id),
+            Tag::new(REASON_TAG, &self.reason_code),
+        ]
+    }
+}
+
+/// A tombstone found on the network, signed by the owner of its target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishedTombstone {
+    /// Id of the tombstone item itself.
+    pub id: String,
+    /// Address of the owner of both the tombstone and its target.
+    pub owner: String,
+    /// Milliseconds since the Unix epoch, according to the node.
+    pub timestamp: u64,
+    pub tombstone: Tombstone,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tombstone;
+    use crate::{error::BundlrError, tags::Tag};
+
+    const TARGET: &str = "KT45jaf8n9UwgkEareWxPgLJk4oMWpI5NODgYVIF1fY";
+
+    #[test]
+    fn should_tag_tombstones() {
+        let tombstone = Tombstone::new(TARGET, "dmca-2024").unwrap();
+        let tags = tombstone.tags();
+        assert_eq!(
+            tags,
+            vec![
+                Tag::new("App-Name", "Tombstone"),
+                Tag::new("Tombstone-For", TARGET),
+                Tag::new("Reason", "dmca-2024"),
+            ]
+        );
+        assert_eq!(Tombstone::from_tags(&tags).unwrap(), tombstone);
+    }
+
+    #[test]
+    fn should_reject_invalid_tombstones() {
+        fn invalid<T>(res: Result<T, BundlrError>) -> bool {
+            matches!(res, Err(BundlrError::InvalidTombstone(_)))
+        }
+        for id in ["", "not-an-id", &TARGET[1..]] {
+            assert!(invalid(Tombstone::new(id, "dmca")), "{}", id);
+        }
+        let long = "x".repeat(65);
+        for code in ["", "DMCA", "dmca request", "dmca_1", &long] {
+            assert!(invalid(Tombstone::new(TARGET, code)), "{}", code);
+        }
+        assert!(invalid(Tombstone::from_tags(&[
+            Tag::new("App-Name", "Other"),
+            Tag::new("Tombstone-For", TARGET),
+            Tag::new("Reason", "dmca"),
+        ])));
+    }
+}
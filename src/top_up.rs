@@ -0,0 +1,351 @@
+//! Keeping the balance of a client's wallet topped up, for long-running services not to stall
+//! on an empty balance.
+//!
+//! A [`TopUp`] checks the balance every [`TopUpOptions::interval`], and funds it back to its
+//! target once it falls below a threshold. Being borrowed from its client, it runs in the task
+//! of choice, e.g. spawned with a shared client:
+//!
+//! ```ignore
+//! let bundlr = Arc::new(bundlr);
+//! tokio::spawn({
+//!     let bundlr = bundlr.clone();
+//!     async move { bundlr.top_up(TopUpOptions::new(1_000_000_000_000)).run().await }
+//! });
+//! ```
+
+use std::{
+    pin::pin,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use futures::future;
+use num::BigUint;
+use num_traits::ToPrimitive;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    bundlr::{shortfall, FundOptions},
+    currency,
+    error::BundlrError,
+    Bundlr,
+};
+
+/// How a [`TopUp`] keeps the balance funded, amounts in the currency's base units.
+///
+/// When the balance is below the threshold, which is the target unless set, the difference to
+/// the target is funded, raised to [`TopUpOptions::min_amount`] and capped to
+/// [`TopUpOptions::max_amount`] if set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TopUpOptions {
+    target: u64,
+    threshold: Option<u64>,
+    interval: Duration,
+    min_amount: u64,
+    max_amount: Option<u64>,
+    fund_options: FundOptions,
+}
+
+impl TopUpOptions {
+    /// Keeps the balance at `target`, checking it every minute.
+    pub fn new(target: u64) -> Self {
+        Self {
+            target,
+            threshold: None,
+            interval: Duration::from_secs(60),
+            min_amount: 0,
+            max_amount: None,
+            fund_options: FundOptions::default(),
+        }
+    }
+
+    /// Only funds once the balance is below `threshold`, to fund less often but more at a time.
+    pub fn threshold(mut self, threshold: u64) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Smallest amount funded at a time, not to pay a transfer fee for a tiny amount.
+    pub fn min_amount(mut self, amount: u64) -> Self {
+        self.min_amount = amount;
+        self
+    }
+
+    /// Largest amount funded at a time, bounding what a single check can spend.
+    pub fn max_amount(mut self, amount: u64) -> Self {
+        self.max_amount = Some(amount);
+        self
+    }
+
+    /// Options of the funds, as with [`Bundlr::fund_with`].
+    pub fn fund_options(mut self, options: FundOptions) -> Self {
+        self.fund_options = options;
+        self
+    }
+
+    /// Amount to fund for `balance`, zero if it isn't below the threshold.
+    fn amount(&self, balance: &BigUint) -> u64 {
+        if *balance >= BigUint::from(self.threshold.unwrap_or(self.target)) {
+            return 0;
+        }
+        let missing = shortfall(&BigUint::from(self.target), balance)
+            .to_u64()
+            .unwrap_or(u64::MAX);
+        if missing == 0 {
+            return 0;
+        }
+        let amount = missing.max(self.min_amount);
+        match self.max_amount {
+            Some(max) => amount.min(max),
+            None => amount,
+        }
+    }
+}
+
+/// Outcome of a check of the balance by a [`TopUp`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopUpCheck {
+    /// Balance before funding.
+    pub balance: BigUint,
+    /// Amount funded, zero if the balance didn't need it.
+    pub funded: u64,
+}
+
+/// What a [`TopUp`] did so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopUpMetrics {
+    pub checks: u64,
+    /// Number of funds made.
+    pub funds: u64,
+    /// Total amount funded.
+    pub funded: u128,
+    /// Number of checks that failed, from fetching the balance to funding it.
+    pub failures: u64,
+    /// When the last check was made, and its error if it failed.
+    pub last_check: Option<SystemTime>,
+    pub last_error: Option<String>,
+}
+
+/// Task keeping the balance of a client's wallet funded, see [`Bundlr::top_up`].
+pub struct TopUp<'a, Currency> {
+    bundlr: &'a Bundlr<Currency>,
+    options: TopUpOptions,
+    metrics: Mutex<TopUpMetrics>,
+    stopped: CancellationToken,
+}
+
+/// Whether `err` is the client shutting down, possibly once a transfer was sent.
+fn is_shutdown(err: &BundlrError) -> bool {
+    match err {
+        BundlrError::ShuttingDown => true,
+        BundlrError::FundingFailed { source, .. } | BundlrError::FundNotCredited { source, .. } => {
+            is_shutdown(source)
+        }
+        _ => false,
+    }
+}
+
+impl<'a, Currency> TopUp<'a, Currency>
+where
+    Currency: currency::Currency,
+{
+    pub(crate) fn new(bundlr: &'a Bundlr<Currency>, options: TopUpOptions) -> Self {
+        Self {
+            bundlr,
+            options,
+            metrics: Mutex::new(TopUpMetrics::default()),
+            stopped: CancellationToken::new(),
+        }
+    }
+
+    /// Checks the balance now, funding it if below the threshold.
+    pub async fn check(&self) -> Result<TopUpCheck, BundlrError> {
+        let balance = self.bundlr.wallet_balance().await?;
+        let funded = self.options.amount(&balance);
+        if funded > 0 {
+            self.bundlr
+                .fund_with(funded, self.options.fund_options)
+                .await?;
+        }
+        Ok(TopUpCheck { balance, funded })
+    }
+
+    /// Checks the balance every [`TopUpOptions::interval`], starting right away, until stopped
+    /// with [`TopUp::stop`] or the client shuts down. Failed checks are counted in the
+    /// [`TopUp::metrics`] and don't stop the next ones.
+    pub async fn run(&self) {
+        let clock = self.bundlr.clock();
+        while !self.stopped.is_cancelled() {
+            let res = self.check().await;
+            {
+                let mut metrics = self.metrics.lock().unwrap();
+                metrics.checks += 1;
+                metrics.last_check = Some(clock.now());
+                match &res {
+                    Ok(check) => {
+                        metrics.funds += (check.funded > 0) as u64;
+                        metrics.funded += check.funded as u128;
+                        metrics.last_error = None;
+                    }
+                    Err(err) => {
+                        metrics.failures += 1;
+                        metrics.last_error = Some(err.to_string());
+                    }
+                }
+            }
+            if matches!(&res, Err(err) if is_shutdown(err)) {
+                return;
+            }
+            let sleep = clock.sleep(self.options.interval);
+            future::select(pin!(sleep), pin!(self.stopped.cancelled())).await;
+        }
+    }
+
+    /// Has [`TopUp::run`] return, once done with its current check if one is under way.
+    pub fn stop(&self) {
+        self.stopped.cancel();
+    }
+
+    pub fn metrics(&self) -> TopUpMetrics {
+        self.metrics.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc, time::Duration};
+
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use num::BigUint;
+    use reqwest::Url;
+
+    use super::{TopUpCheck, TopUpOptions};
+    use crate::{
+        bundlr::PubInfo,
+        clock::{Clock, MockClock},
+        currency::mock::MockCurrency,
+        Bundlr, BundlrBuilder,
+    };
+
+    fn bundlr(
+        server: &MockServer,
+        currency: &Arc<MockCurrency>,
+        clock: Clock,
+    ) -> Bundlr<Arc<MockCurrency>> {
+        BundlrBuilder::new()
+            .url(Url::from_str(&server.url("/")).unwrap())
+            .currency(currency.clone())
+            .clock(clock)
+            .pub_info(
+                serde_json::from_str::<PubInfo>(
+                    r#"{ "version": "0.2.0", "gateway": "arweave.net", "addresses": { "arweave": "node-address" } }"#,
+                )
+                .unwrap(),
+            )
+            .build()
+            .unwrap()
+    }
+
+    fn balance_mock<'a>(server: &'a MockServer, balance: &str) -> httpmock::Mock<'a> {
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/account/balance/arweave")
+                .query_param("address", "wallet");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(format!("{{ \"balance\": \"{}\" }}", balance));
+        })
+    }
+
+    #[test]
+    fn should_fund_up_to_the_target_within_bounds() {
+        let amount = |options: TopUpOptions, balance: u64| options.amount(&balance.into());
+        let options = TopUpOptions::new(1000);
+        assert_eq!(amount(options, 1000), 0);
+        assert_eq!(amount(options, 999), 1);
+        assert_eq!(amount(options, 0), 1000);
+
+        let options = options.threshold(400);
+        assert_eq!(amount(options, 400), 0);
+        assert_eq!(amount(options, 399), 601);
+
+        let options = TopUpOptions::new(1000).min_amount(100).max_amount(500);
+        assert_eq!(amount(options, 990), 100);
+        assert_eq!(amount(options, 200), 500);
+        assert_eq!(amount(options, 1200), 0);
+
+        // A threshold above the target never funds past it
+        assert_eq!(amount(TopUpOptions::new(1000).threshold(2000), 1500), 0);
+    }
+
+    #[tokio::test]
+    async fn should_top_up_the_balance() {
+        let server = MockServer::start();
+        let credit = server.mock(|when, then| {
+            when.method(POST).path("/account/balance/arweave");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("\"OK\"");
+        });
+        let mut low = balance_mock(&server, "250");
+        let currency = MockCurrency::new(true, Some(5));
+        let bundlr = bundlr(&server, &currency, Clock::system());
+        let top_up = bundlr.top_up(TopUpOptions::new(1000).threshold(500));
+
+        let check = top_up.check().await.unwrap();
+        assert_eq!(
+            check,
+            TopUpCheck {
+                balance: BigUint::from(250u32),
+                funded: 750
+            }
+        );
+        assert_eq!(currency.amounts(), vec![750]);
+        credit.assert_hits(1);
+
+        low.delete();
+        balance_mock(&server, "700");
+        assert_eq!(top_up.check().await.unwrap().funded, 0);
+        assert_eq!(currency.amounts(), vec![750]);
+    }
+
+    #[tokio::test]
+    async fn should_check_every_interval_until_stopped() {
+        let server = MockServer::start();
+        let balance = balance_mock(&server, "5000");
+        let currency = MockCurrency::new(true, Some(5));
+        let clock = MockClock::new(std::time::SystemTime::UNIX_EPOCH);
+        let bundlr = bundlr(&server, &currency, clock.clone().into());
+        let top_up = bundlr.top_up(TopUpOptions::new(1000).interval(Duration::from_secs(30)));
+
+        let drive = async {
+            for checks in 1..=3 {
+                while top_up.metrics().checks < checks || clock.sleepers() == 0 {
+                    tokio::task::yield_now().await;
+                }
+                match checks {
+                    3 => top_up.stop(),
+                    _ => clock.advance(Duration::from_secs(30)),
+                }
+            }
+        };
+        tokio::join!(top_up.run(), drive);
+
+        let metrics = top_up.metrics();
+        assert_eq!((metrics.checks, metrics.funds, metrics.failures), (3, 0, 0));
+        assert_eq!(
+            metrics.last_check,
+            Some(std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(60))
+        );
+        balance.assert_hits(3);
+        assert!(currency.sent().is_empty());
+    }
+}
@@ -17,10 +17,30 @@ use crate::utils::read_offset;
 
 enum Data {
     None,
-    Bytes(Vec<u8>),
+    Bytes(Bytes),
     Stream(Pin<Box<dyn Stream<Item = anyhow::Result<Bytes>> + Send>>),
 }
 
+/// A data item, serialized following [ANS-104](https://github.com/ArweaveTeam/arweave-standards/blob/master/ans/ANS-104.md).
+///
+/// The layout is, with all integers little endian:
+///
+/// | Field             | Size                                        |
+/// |-------------------|---------------------------------------------|
+/// | signature type    | 2 bytes                                     |
+/// | signature         | depends on the signature type               |
+/// | owner             | depends on the signature type               |
+/// | target            | presence byte (0 or 1), then 32 bytes if 1  |
+/// | anchor            | presence byte (0 or 1), then 32 bytes if 1  |
+/// | number of tags    | 8 bytes                                     |
+/// | tags length       | 8 bytes                                     |
+/// | tags              | Avro encoded array of `{ name, value }`     |
+/// | data              | rest of the item                            |
+///
+/// Tags are encoded in the order they are given, and are never sorted nor deduplicated: the
+/// same tags in a different order produce a different item and a different id. The id is the
+/// SHA-256 of the signature. Outputs are checked byte for byte against the vectors in
+/// `res/golden`, all of them generated by the reference JS implementation.
 pub struct BundlrTx {
     signature_type: SignerMap,
     signature: Vec<u8>,
@@ -33,6 +53,43 @@ pub struct BundlrTx {
 
 impl BundlrTx {
     pub fn new(target: Vec<u8>, data: Vec<u8>, tags: Vec<Tag>) -> Result<Self, BundlrError> {
+        Self::with_data(target, Data::Bytes(data.into()), tags)
+    }
+
+    /// Same as [`BundlrTx::new`], for data read from `data` when signing. The item can then only
+    /// be sent by parts, its header from [`BundlrTx::header`] followed by the data read again.
+    #[cfg(feature = "client")]
+    pub(crate) fn from_stream(
+        target: Vec<u8>,
+        data: impl Stream<Item = anyhow::Result<Bytes>> + Send + 'static,
+        tags: Vec<Tag>,
+    ) -> Result<Self, BundlrError> {
+        Self::with_data(target, Data::Stream(Box::pin(data)), tags)
+    }
+
+    /// Signed item made of its parts, e.g. read back from a node, to verify it.
+    #[cfg(feature = "client")]
+    pub(crate) fn from_parts(
+        signature_type: SignerMap,
+        signature: Vec<u8>,
+        owner: Vec<u8>,
+        target: Vec<u8>,
+        anchor: Vec<u8>,
+        tags: Vec<Tag>,
+        data: Vec<u8>,
+    ) -> Self {
+        BundlrTx {
+            signature_type,
+            signature,
+            owner,
+            target,
+            anchor,
+            tags,
+            data: Data::Bytes(data.into()),
+        }
+    }
+
+    fn with_data(target: Vec<u8>, data: Data, tags: Vec<Tag>) -> Result<Self, BundlrError> {
         let mut randoms: [u8; 32] = [0; 32];
         let sr = ring::rand::SystemRandom::new();
         match sr.fill(&mut randoms) {
@@ -48,12 +105,26 @@ impl BundlrTx {
             target,
             anchor,
             tags,
-            data: Data::Bytes(data),
+            data,
         })
     }
 
     fn from_info_bytes(buffer: &[u8]) -> Result<(Self, usize), BundlrError> {
-        let sig_type_b = &buffer[0..2];
+        // Every length read from the buffer is checked before use, so that hostile or truncated
+        // input fails with an error instead of panicking or allocating what it asks for.
+        let slice = |start: usize, len: usize| {
+            start
+                .checked_add(len)
+                .and_then(|end| buffer.get(start..end))
+                .ok_or(BundlrError::NoBytesLeft)
+        };
+        let read_u64 = |start: usize| -> Result<u64, BundlrError> {
+            let bytes = <[u8; 8]>::try_from(slice(start, 8)?)
+                .map_err(|err| BundlrError::BytesError(err.to_string()))?;
+            Ok(u64::from_le_bytes(bytes))
+        };
+
+        let sig_type_b = slice(0, 2)?;
         let signature_type = u16::from_le_bytes(
             <[u8; 2]>::try_from(sig_type_b)
                 .map_err(|err| BundlrError::BytesError(err.to_string()))?,
@@ -64,48 +135,36 @@ impl BundlrTx {
             pub_length,
             sig_length,
             ..
-        } = signer.get_config();
+        } = signer
+            .try_get_config()
+            .ok_or(BundlrError::InvalidSignerType)?;
 
-        let signature = &buffer[2..2 + sig_length];
-        let owner = &buffer[2 + sig_length..2 + sig_length + pub_length];
+        let signature = slice(2, sig_length)?;
+        let owner = slice(2 + sig_length, pub_length)?;
 
         let target_start = 2 + sig_length + pub_length;
-        let target_present = u8::from_le_bytes(
-            <[u8; 1]>::try_from(&buffer[target_start..target_start + 1])
-                .map_err(|err| BundlrError::BytesError(err.to_string()))?,
-        );
+        let target_present = slice(target_start, 1)?[0];
         let target = match target_present {
             0 => &[],
-            1 => &buffer[target_start + 1..target_start + 33],
+            1 => slice(target_start + 1, 32)?,
             b => return Err(BundlrError::InvalidPresenceByte(b.to_string())),
         };
         let anchor_start = target_start + 1 + target.len();
-        let anchor_present = u8::from_le_bytes(
-            <[u8; 1]>::try_from(&buffer[anchor_start..anchor_start + 1])
-                .map_err(|err| BundlrError::BytesError(err.to_string()))?,
-        );
+        let anchor_present = slice(anchor_start, 1)?[0];
         let anchor = match anchor_present {
             0 => &[],
-            1 => &buffer[anchor_start + 1..anchor_start + 33],
+            1 => slice(anchor_start + 1, 32)?,
             b => return Err(BundlrError::InvalidPresenceByte(b.to_string())),
         };
 
         let tags_start = anchor_start + 1 + anchor.len();
-        let number_of_tags = u64::from_le_bytes(
-            <[u8; 8]>::try_from(&buffer[tags_start..tags_start + 8])
-                .map_err(|err| BundlrError::BytesError(err.to_string()))?,
-        );
-
-        let number_of_tags_bytes = u64::from_le_bytes(
-            <[u8; 8]>::try_from(&buffer[tags_start + 8..tags_start + 16])
-                .map_err(|err| BundlrError::BytesError(err.to_string()))?,
-        );
-
-        let mut b = buffer.to_vec();
-        let mut tags_bytes =
-            &mut b[tags_start + 16..tags_start + 16 + number_of_tags_bytes as usize];
+        let number_of_tags = read_u64(tags_start)?;
+        let number_of_tags_bytes =
+            usize::try_from(read_u64(tags_start + 8)?).map_err(|_| BundlrError::NoBytesLeft)?;
+        let tags_bytes = slice(tags_start + 16, number_of_tags_bytes)?;
 
         let tags = if number_of_tags_bytes > 0 {
+            let mut tags_bytes = tags_bytes;
             tags_bytes.decode()?
         } else {
             vec![]
@@ -125,15 +184,15 @@ impl BundlrTx {
             data: Data::None,
         };
 
-        Ok((bundlr_tx, tags_start + 16 + number_of_tags_bytes as usize))
+        Ok((bundlr_tx, tags_start + 16 + number_of_tags_bytes))
     }
 
     pub fn from_bytes(buffer: Vec<u8>) -> Result<Self, BundlrError> {
         let (bundlr_tx, data_start) = BundlrTx::from_info_bytes(&buffer)?;
-        let data = &buffer[data_start..buffer.len()];
+        let data = Bytes::from(buffer).slice(data_start..);
 
         Ok(BundlrTx {
-            data: Data::Bytes(data.to_vec()),
+            data: Data::Bytes(data),
             ..bundlr_tx
         })
     }
@@ -148,7 +207,9 @@ impl BundlrTx {
         let (bundlr_tx, data_start) = BundlrTx::from_info_bytes(&buffer)?;
 
         let data_start = data_start as u64;
-        let data_size = size - data_start;
+        let data_size = size
+            .checked_sub(data_start)
+            .ok_or(BundlrError::NoBytesLeft)?;
         let mut file_clone = file.try_clone()?;
         let file_stream = try_stream! {
             let chunk_size = CHUNK_SIZE;
@@ -171,14 +232,31 @@ impl BundlrTx {
     }
 
     pub fn as_bytes(self) -> Result<Vec<u8>, BundlrError> {
-        if !self.is_signed() {
-            return Err(BundlrError::NoSignature);
-        }
-        let data = match &self.data {
+        let (header, data) = self.into_parts()?;
+        let mut b = Vec::with_capacity(header.len() + data.len());
+        b.put(header);
+        b.put(data);
+        Ok(b)
+    }
+
+    /// Splits a signed item into its serialized header (everything preceding the data) and its
+    /// data. The data is handed back as is, without being copied, so callers can send both
+    /// parts without ever holding a second full copy of the payload.
+    pub(crate) fn into_parts(self) -> Result<(Bytes, Bytes), BundlrError> {
+        let header = self.header()?;
+        let data = match self.data {
             Data::Stream(_) => return Err(BundlrError::InvalidDataType),
             Data::None => return Err(BundlrError::InvalidDataType),
             Data::Bytes(data) => data,
         };
+        Ok((header, data))
+    }
+
+    /// The serialized header of a signed item, everything preceding its data.
+    pub(crate) fn header(&self) -> Result<Bytes, BundlrError> {
+        if !self.is_signed() {
+            return Err(BundlrError::NoSignature);
+        }
 
         let encoded_tags = if !self.tags.is_empty() {
             self.tags.encode()?
@@ -191,15 +269,14 @@ impl BundlrTx {
             + config.pub_length as u64
             + 34
             + 16
-            + encoded_tags.len() as u64
-            + data.len() as u64;
+            + encoded_tags.len() as u64;
 
         let mut b = Vec::with_capacity(
             TryInto::<usize>::try_into(length)
                 .map_err(|err| BundlrError::TypeParseError(err.to_string()))?,
         );
 
-        let sig_type: [u8; 2] = (self.signature_type as u16).to_le_bytes();
+        let sig_type: [u8; 2] = (self.signature_type.clone() as u16).to_le_bytes();
         let target_presence_byte = if self.target.is_empty() {
             &[0u8]
         } else {
@@ -225,8 +302,7 @@ impl BundlrTx {
             b.put(encoded_tags);
         }
 
-        b.put(&data[..]);
-        Ok(b)
+        Ok(b.into())
     }
 
     pub fn as_byte_stream(
@@ -245,7 +321,7 @@ impl BundlrTx {
         match &mut self.data {
             Data::None => Ok(Bytes::new()),
             Data::Bytes(data) => {
-                let data_chunk = DeepHashChunk::Chunk(data.clone().into());
+                let data_chunk = DeepHashChunk::Chunk(data.clone());
                 let sig_type = &self.signature_type;
                 let sig_type_bytes = sig_type.as_u16().to_string().as_bytes().to_vec();
                 deep_hash_sync(DeepHashChunk::Chunks(vec![
@@ -287,6 +363,15 @@ impl BundlrTx {
         Ok(())
     }
 
+    /// Same as [`BundlrTx::sign`], for items whose data is held in memory.
+    pub fn sign_sync(&mut self, signer: &dyn Signer) -> Result<(), BundlrError> {
+        if let Data::Stream(_) = self.data {
+            return Err(BundlrError::InvalidDataType);
+        }
+        // Hashing in-memory data never waits, so this completes on the first poll
+        futures::executor::block_on(self.sign(signer))
+    }
+
     pub async fn verify(&mut self) -> Result<(), BundlrError> {
         let message = self.get_message().await?;
         let pub_key = &self.owner;
@@ -296,22 +381,107 @@ impl BundlrTx {
         verifier.verify(pub_key, &message, signature)
     }
 
+    /// Same as [`BundlrTx::verify`], for items whose data is held in memory.
+    pub fn verify_sync(&mut self) -> Result<(), BundlrError> {
+        if let Data::Stream(_) = self.data {
+            return Err(BundlrError::InvalidDataType);
+        }
+        futures::executor::block_on(self.verify())
+    }
+
     pub fn get_signarure(&self) -> Vec<u8> {
         self.signature.clone()
     }
+
+    pub fn get_owner(&self) -> Vec<u8> {
+        self.owner.clone()
+    }
+
+    pub fn get_tags(&self) -> &[Tag] {
+        &self.tags
+    }
+
+    pub fn get_signature_type(&self) -> SignerMap {
+        self.signature_type.clone()
+    }
+
+    pub fn get_target(&self) -> Vec<u8> {
+        self.target.clone()
+    }
+
+    pub fn get_anchor(&self) -> Vec<u8> {
+        self.anchor.clone()
+    }
+
+    /// The item's data, if it is held in memory, as for items read with [`BundlrTx::from_bytes`].
+    pub fn get_data(&self) -> Option<&[u8]> {
+        match &self.data {
+            Data::Bytes(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Id of the signed item, the base64url SHA-256 of its signature.
+    #[cfg(feature = "client")]
+    pub(crate) fn id(&self) -> String {
+        use data_encoding::BASE64URL_NOPAD;
+        use sha2::{Digest, Sha256};
+        BASE64URL_NOPAD.encode(&Sha256::digest(&self.signature))
+    }
+
+    /// Address of the item's owner, as the chain of its signature type writes it: base64url
+    /// SHA-256 of the key for Arweave, base58 key for ed25519 and Solana, hex for Ethereum and
+    /// Aptos. Owners of other types, or without a key yet, are their base64url key.
+    pub fn owner_address(&self) -> String {
+        use data_encoding::BASE64URL_NOPAD;
+        use sha2::{Digest, Sha256};
+        let owner = &self.owner;
+        match self.signature_type {
+            SignerMap::Arweave => BASE64URL_NOPAD.encode(&Sha256::digest(owner)),
+            SignerMap::ED25519 | SignerMap::Solana if !owner.is_empty() => {
+                bs58::encode(owner).into_string()
+            }
+            #[cfg(any(feature = "ethereum", feature = "erc20"))]
+            SignerMap::Ethereum if owner.len() > 1 => {
+                let hash = web3::signing::keccak256(&owner[1..]);
+                format!("0x{}", data_encoding::HEXLOWER.encode(&hash[12..]))
+            }
+            #[cfg(all(feature = "aptos", feature = "client"))]
+            SignerMap::InjectedAptos if !owner.is_empty() => {
+                crate::currency::aptos::address_of(owner)
+            }
+            _ => BASE64URL_NOPAD.encode(owner),
+        }
+    }
+
+    /// Size of the data, if it is held in memory.
+    pub(crate) fn data_len(&self) -> Option<usize> {
+        match &self.data {
+            Data::Bytes(data) => Some(data.len()),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::error::BundlrError;
+    use crate::index::SignerMap;
     use crate::tags::Tag;
     #[cfg(feature = "solana")]
     use crate::transaction::bundlr::BundlrTx;
-    use crate::{ArweaveSigner, Ed25519Signer, Secp256k1Signer};
+    use crate::{AptosSigner, ArweaveSigner, Ed25519Signer, Secp256k1Signer, Signer};
+    use bytes::Bytes;
+    use data_encoding::BASE64URL_NOPAD;
     use secp256k1::SecretKey;
+    use serde::Deserialize;
+    use sha2::{Digest, Sha256};
     use std::path::PathBuf;
     use std::str::FromStr;
     use std::{fs, fs::File, io::Write};
 
+    use super::Data;
+
     #[allow(unused)]
     macro_rules! aw {
         ($e:expr) => {
@@ -414,6 +584,130 @@ mod tests {
         let buffer = fs::read(path).expect("Could not read file");
         let data_item_2 = BundlrTx::from_bytes(buffer).expect("Invalid bytes");
         assert!(&data_item_2.is_signed());
+        let address = format!("{:?}", signer.address());
+        assert_eq!(data_item_2.owner_address(), address);
         assert_eq!(data_item_1_bytes, data_item_2.as_bytes().unwrap());
     }
+
+    /// Input of a golden vector, stored next to its expected bytes in `res/golden`.
+    #[derive(Deserialize)]
+    struct GoldenSpec {
+        signature_type: u16,
+        /// Key to sign with, for vectors whose signature is deterministic.
+        key: Option<GoldenKey>,
+        owner: String,
+        target: String,
+        anchor: String,
+        tags: Vec<Tag>,
+        data: String,
+        signature: String,
+        id: String,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(tag = "kind", content = "secret", rename_all = "snake_case")]
+    enum GoldenKey {
+        Ed25519(String),
+        Solana(String),
+        Aptos(String),
+    }
+
+    /// Same as `Ed25519Signer`, with the Solana signature type.
+    struct SolanaTypeSigner(Ed25519Signer);
+
+    impl Signer for SolanaTypeSigner {
+        fn sign(&self, message: Bytes) -> Result<Bytes, BundlrError> {
+            self.0.sign(message)
+        }
+        fn pub_key(&self) -> Bytes {
+            self.0.pub_key()
+        }
+        fn sig_type(&self) -> SignerMap {
+            SignerMap::Solana
+        }
+        fn get_sig_length(&self) -> u16 {
+            self.0.get_sig_length()
+        }
+        fn get_pub_length(&self) -> u16 {
+            self.0.get_pub_length()
+        }
+    }
+
+    fn decode(s: &str) -> Vec<u8> {
+        BASE64URL_NOPAD.decode(s.as_bytes()).unwrap()
+    }
+
+    async fn check_golden(name: &str) {
+        let spec: GoldenSpec =
+            serde_json::from_slice(&fs::read(format!("res/golden/{}.json", name)).unwrap())
+                .unwrap();
+        let expected = fs::read(format!("res/golden/{}.bin", name)).unwrap();
+        let unsigned = || BundlrTx {
+            signature_type: SignerMap::None,
+            signature: vec![],
+            owner: vec![],
+            target: decode(&spec.target),
+            anchor: decode(&spec.anchor),
+            tags: spec.tags.clone(),
+            data: Data::Bytes(decode(&spec.data).into()),
+        };
+
+        // Serializing the inputs with the reference signature gives back the exact same bytes
+        let tx = BundlrTx {
+            signature_type: SignerMap::from(spec.signature_type),
+            signature: decode(&spec.signature),
+            owner: decode(&spec.owner),
+            ..unsigned()
+        };
+        assert!(tx.as_bytes().unwrap() == expected, "{}: bytes differ", name);
+
+        // Signing the inputs gives the same item, when the signature is deterministic
+        if let Some(key) = &spec.key {
+            let signer: Box<dyn Signer> = match key {
+                GoldenKey::Ed25519(s) => Box::new(Ed25519Signer::from_base58(s).unwrap()),
+                GoldenKey::Solana(s) => {
+                    Box::new(SolanaTypeSigner(Ed25519Signer::from_base58(s).unwrap()))
+                }
+                GoldenKey::Aptos(s) => Box::new(AptosSigner::from_base58(s).unwrap()),
+            };
+            let mut tx = unsigned();
+            tx.sign(signer.as_ref()).await.unwrap();
+            assert!(
+                tx.as_bytes().unwrap() == expected,
+                "{}: signed bytes differ",
+                name
+            );
+        }
+
+        // Parsing the expected bytes gives back the inputs, tags in order, and a valid item
+        let mut parsed = BundlrTx::from_bytes(expected.clone()).unwrap();
+        assert_eq!(
+            parsed.signature_type.as_u16(),
+            spec.signature_type,
+            "{}",
+            name
+        );
+        assert_eq!(parsed.owner, decode(&spec.owner), "{}", name);
+        assert_eq!(parsed.tags, spec.tags, "{}", name);
+        parsed.verify().await.unwrap();
+
+        let id = Sha256::digest(&parsed.signature);
+        assert_eq!(BASE64URL_NOPAD.encode(&id), spec.id, "{}: id differs", name);
+    }
+
+    #[tokio::test]
+    async fn should_match_golden_vectors() {
+        // Every case there, so that the ones `npm run generate-golden` adds are checked too
+        let mut names: Vec<String> = fs::read_dir("res/golden")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .map(|path| path.file_stem().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert!(names.len() >= 5, "golden vectors missing: {:?}", names);
+        for name in &names {
+            check_golden(name).await;
+        }
+    }
 }
@@ -1,13 +1,16 @@
 pub mod bundlr;
+#[cfg(feature = "client")]
 pub mod poll;
 
-#[derive(Debug)]
+#[cfg(feature = "client")]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TxStatus {
     pub confirmations: u64,
     pub height: u128,
     pub block_hash: String,
 }
 
+#[cfg(feature = "client")]
 pub struct Tx {
     pub id: String,
     pub from: String,
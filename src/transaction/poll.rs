@@ -1,27 +1,362 @@
-use std::{thread::sleep, time::Duration};
+use std::{pin::pin, time::Duration};
+
+use async_stream::try_stream;
+use futures::{Stream, TryStreamExt};
 
 use crate::{
+    clock::Clock,
     consts::{CONFIRMATIONS_NEEDED, RETRY_SLEEP},
     currency::Currency,
+    error::BundlrError,
+    transaction::TxStatus,
 };
 
+/// How long [`ConfirmationPoll`] waits for a transaction, polling every [`RETRY_SLEEP`] seconds
+/// for up to an hour by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollOptions {
+    interval: Duration,
+    max_attempts: Option<u32>,
+    timeout: Option<Duration>,
+    confirmations: u64,
+    dropped_after: u32,
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(RETRY_SLEEP),
+            max_attempts: None,
+            timeout: Some(Duration::from_secs(3600)),
+            confirmations: CONFIRMATIONS_NEEDED,
+            dropped_after: 3,
+        }
+    }
+}
+
+impl PollOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Gives up after `attempts` polls, failed ones included.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    /// Gives up once `timeout` passed since the first poll. `None` polls until confirmed or out
+    /// of attempts.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Confirmations to wait for, [`CONFIRMATIONS_NEEDED`] by default.
+    pub fn confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    /// Considers the transaction dropped once neither in a block nor found by
+    /// [`Currency::get_tx`] for `polls` polls in a row, 3 by default, leaving it time to reach
+    /// the nodes polled after being broadcast. Zero never does.
+    pub fn dropped_after(mut self, polls: u32) -> Self {
+        self.dropped_after = polls;
+        self
+    }
+}
+
 pub struct ConfirmationPoll();
 
-#[allow(unused)]
 impl ConfirmationPoll {
-    pub async fn await_confirmation(tx_id: &String, currency: &dyn Currency) {
-        let mut confirmations = 0;
-        while confirmations < CONFIRMATIONS_NEEDED {
-            let (status, tx_status) = match currency.get_tx_status(tx_id.to_string()).await {
-                Ok(ok) => ok,
-                Err(err) => continue,
-            };
-
-            if let Some(tx_status) = tx_status {
-                confirmations = tx_status.confirmations
+    /// Polls the status of `tx_id` until it is confirmed, with the default [`PollOptions`].
+    pub async fn await_confirmation(
+        tx_id: &str,
+        currency: &dyn Currency,
+    ) -> Result<TxStatus, BundlrError> {
+        Self::await_confirmation_with(tx_id, currency, &PollOptions::default()).await
+    }
+
+    pub async fn await_confirmation_with(
+        tx_id: &str,
+        currency: &dyn Currency,
+        options: &PollOptions,
+    ) -> Result<TxStatus, BundlrError> {
+        Self::await_confirmation_with_clock(tx_id, currency, options, &Clock::system()).await
+    }
+
+    /// Polls the status of `tx_id` until it has enough confirmations, waiting on `clock` between
+    /// polls, including after failed ones.
+    ///
+    /// Fails with [`BundlrError::ConfirmationTimeout`] once out of attempts or time, or with
+    /// [`BundlrError::TxDropped`] if the transaction left the mempool or was reorged out, for it
+    /// to be broadcast again.
+    pub async fn await_confirmation_with_clock(
+        tx_id: &str,
+        currency: &dyn Currency,
+        options: &PollOptions,
+        clock: &Clock,
+    ) -> Result<TxStatus, BundlrError> {
+        let updates = Self::stream_with_clock(tx_id, currency, *options, clock.clone());
+        let mut updates = pin!(updates);
+        let mut last = None;
+        while let Some(status) = updates.try_next().await? {
+            last = Some(status);
+        }
+        // The stream only ends by itself once confirmed, after a status
+        last.ok_or(BundlrError::TxStatusNotConfirmed)
+    }
+
+    /// Polls the status of `tx_id` as [`ConfirmationPoll::await_confirmation_with`] does,
+    /// yielding it each time it changes, e.g. to show its confirmations as they come.
+    ///
+    /// The stream ends after the status with enough confirmations, or with the error the poll
+    /// failed with.
+    pub fn stream<'a>(
+        tx_id: &'a str,
+        currency: &'a dyn Currency,
+        options: PollOptions,
+    ) -> impl Stream<Item = Result<TxStatus, BundlrError>> + 'a {
+        Self::stream_with_clock(tx_id, currency, options, Clock::system())
+    }
+
+    pub fn stream_with_clock<'a>(
+        tx_id: &'a str,
+        currency: &'a dyn Currency,
+        options: PollOptions,
+        clock: Clock,
+    ) -> impl Stream<Item = Result<TxStatus, BundlrError>> + 'a {
+        try_stream! {
+            let started = clock.now();
+            let mut attempts = 0;
+            let mut last: Option<TxStatus> = None;
+            let mut missing = 0;
+            loop {
+                attempts += 1;
+                match currency.get_tx_status(tx_id.to_string()).await {
+                    Ok((_, Some(tx_status))) => {
+                        missing = 0;
+                        let confirmed = tx_status.confirmations >= options.confirmations;
+                        if last.as_ref() != Some(&tx_status) {
+                            last = Some(tx_status.clone());
+                            yield tx_status;
+                        }
+                        if confirmed {
+                            return;
+                        }
+                    }
+                    // Out of a block, which may be pending or gone
+                    Ok((_, None)) => match currency.get_tx(tx_id.to_string()).await {
+                        Err(BundlrError::TxNotFound) => missing += 1,
+                        Ok(_) => missing = 0,
+                        Err(_) => {}
+                    },
+                    Err(_) => {}
+                }
+                let confirmations = last.as_ref().map(|status| status.confirmations);
+                if options.dropped_after > 0 && missing >= options.dropped_after {
+                    Err(BundlrError::TxDropped {
+                        tx_id: tx_id.to_owned(),
+                        confirmations,
+                    })?;
+                }
+
+                let elapsed = clock.now().duration_since(started).unwrap_or_default();
+                let remaining = options
+                    .timeout
+                    .map(|timeout| timeout.saturating_sub(elapsed));
+                let out_of_attempts = options.max_attempts.is_some_and(|max| attempts >= max);
+                if out_of_attempts || remaining == Some(Duration::ZERO) {
+                    Err(BundlrError::ConfirmationTimeout {
+                        tx_id: tx_id.to_owned(),
+                        attempts,
+                        confirmations,
+                    })?;
+                }
+                let pause = match remaining {
+                    Some(remaining) => options.interval.min(remaining),
+                    None => options.interval,
+                };
+                clock.sleep(pause).await;
             }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant, SystemTime};
 
-            sleep(Duration::from_secs(RETRY_SLEEP));
+    use futures::TryStreamExt;
+
+    use super::{ConfirmationPoll, PollOptions};
+    use crate::{
+        clock::MockClock,
+        consts::{CONFIRMATIONS_NEEDED, RETRY_SLEEP},
+        currency::mock::MockCurrency,
+        error::BundlrError,
+    };
+
+    /// Polls with `options` on a mock clock, advanced by `step` after each poll, returning the
+    /// outcome and when it came.
+    async fn poll(
+        currency: &MockCurrency,
+        options: PollOptions,
+        step: Duration,
+    ) -> (Result<u64, BundlrError>, SystemTime) {
+        let mock = MockClock::new(SystemTime::UNIX_EPOCH);
+        let poll = async {
+            let res = ConfirmationPoll::await_confirmation_with_clock(
+                "tx",
+                currency,
+                &options,
+                &mock.clone().into(),
+            )
+            .await;
+            (res.map(|status| status.confirmations), mock.now())
+        };
+        let drive = async {
+            // Advance once per sleep, each following a different poll
+            let mut advanced_after = 0;
+            loop {
+                if mock.sleepers() > 0 && advanced_after != currency.polls() {
+                    advanced_after = currency.polls();
+                    mock.advance(step);
+                }
+                tokio::task::yield_now().await;
+            }
+        };
+        tokio::select! {
+            res = poll => res,
+            _ = drive => unreachable!(),
         }
     }
+
+    #[tokio::test]
+    async fn should_back_off_between_polls() {
+        let currency = MockCurrency::new(false, Some(0));
+        let started = Instant::now();
+
+        let step = Duration::from_secs(RETRY_SLEEP);
+        let (res, finished_at) = poll(&currency, PollOptions::new(), step).await;
+
+        // A failed poll, then one per confirmation
+        assert_eq!(res.unwrap(), CONFIRMATIONS_NEEDED);
+        assert_eq!(currency.polls(), CONFIRMATIONS_NEEDED + 1);
+        assert_eq!(
+            finished_at,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(RETRY_SLEEP * CONFIRMATIONS_NEEDED)
+        );
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn should_give_up_out_of_attempts_or_time() {
+        let currency = MockCurrency::new(false, Some(0));
+        let options = PollOptions::new()
+            .interval(Duration::from_secs(1))
+            .max_attempts(3);
+        let (res, _) = poll(&currency, options, Duration::from_secs(1)).await;
+        assert!(matches!(
+            res,
+            Err(BundlrError::ConfirmationTimeout {
+                attempts: 3,
+                confirmations: Some(2),
+                ..
+            })
+        ));
+
+        let currency = MockCurrency::new(false, Some(0));
+        let options = PollOptions::new()
+            .interval(Duration::from_secs(4))
+            .timeout(Some(Duration::from_secs(10)));
+        let (res, finished_at) = poll(&currency, options, Duration::from_secs(4)).await;
+        // Polls at 0, 4 and 8, then once more after a sleep cut short by the deadline
+        assert!(matches!(
+            res,
+            Err(BundlrError::ConfirmationTimeout { attempts: 4, .. })
+        ));
+        assert!(finished_at >= SystemTime::UNIX_EPOCH + Duration::from_secs(10));
+
+        let currency = MockCurrency::new(false, Some(0));
+        let options = PollOptions::new().confirmations(1).timeout(None);
+        let (res, _) = poll(&currency, options, Duration::from_secs(RETRY_SLEEP)).await;
+        assert_eq!(res.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn should_stream_status_updates() {
+        let currency = MockCurrency::new(false, Some(0));
+        let mock = MockClock::new(SystemTime::UNIX_EPOCH);
+        let updates = ConfirmationPoll::stream_with_clock(
+            "tx",
+            currency.as_ref(),
+            PollOptions::new().confirmations(3),
+            mock.clone().into(),
+        )
+        .map_ok(|status| status.confirmations)
+        .try_collect::<Vec<_>>();
+        let drive = async {
+            loop {
+                if mock.sleepers() > 0 {
+                    mock.advance(Duration::from_secs(RETRY_SLEEP));
+                }
+                tokio::task::yield_now().await;
+            }
+        };
+        let updates = tokio::select! {
+            updates = updates => updates,
+            _ = drive => unreachable!(),
+        };
+        // Nothing for the failed poll, then every confirmation up to the last one needed
+        assert_eq!(updates.unwrap(), [1, 2, 3]);
+        assert_eq!(currency.polls(), 4);
+
+        let currency = MockCurrency::dropping(0);
+        let options = PollOptions::new().dropped_after(1);
+        let updates = ConfirmationPoll::stream("tx", currency.as_ref(), options)
+            .try_collect::<Vec<_>>()
+            .await;
+        assert!(matches!(updates, Err(BundlrError::TxDropped { .. })));
+    }
+
+    #[tokio::test]
+    async fn should_detect_dropped_transactions() {
+        let step = Duration::from_secs(RETRY_SLEEP);
+        // Reorged out with 2 confirmations, then missing for 3 polls
+        let currency = MockCurrency::dropping(3);
+        let (res, _) = poll(&currency, PollOptions::new(), step).await;
+        assert!(matches!(
+            res,
+            Err(BundlrError::TxDropped {
+                confirmations: Some(2),
+                ..
+            })
+        ));
+        assert_eq!(currency.polls(), 6);
+
+        let currency = MockCurrency::dropping(0);
+        let (res, _) = poll(&currency, PollOptions::new().dropped_after(1), step).await;
+        assert!(matches!(
+            res,
+            Err(BundlrError::TxDropped {
+                confirmations: None,
+                ..
+            })
+        ));
+
+        let currency = MockCurrency::dropping(0);
+        let options = PollOptions::new().dropped_after(0).max_attempts(5);
+        let (res, _) = poll(&currency, options, step).await;
+        assert!(matches!(
+            res,
+            Err(BundlrError::ConfirmationTimeout { attempts: 5, .. })
+        ));
+    }
 }
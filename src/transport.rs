@@ -0,0 +1,297 @@
+//! Whether clients may talk to nodes, gateways and currency RPCs without TLS, through which
+//! proxy, and the known networks to set clients up for.
+
+use std::fmt;
+
+use reqwest::{NoProxy, Proxy, Url};
+
+use crate::{
+    consts::BUNDLR_DEFAULT_URL,
+    currency::Currency,
+    error::{BuilderError, BundlrError},
+};
+
+pub const NODE2_URL: &str = "https://node2.bundlr.network/";
+pub const DEVNET_URL: &str = "https://devnet.bundlr.network/";
+pub const ARWEAVE_GATEWAY_URL: &str = "https://arweave.net/";
+pub const WEAVEVM_TESTNET_RPC_URL: &str = "https://testnet-rpc.wvm.dev/";
+
+/// Which URLs a client may send requests to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransportPolicy {
+    /// Any URL, with or without TLS.
+    #[default]
+    AllowPlaintext,
+    /// Only `https` URLs, except for the hosts allowed with
+    /// [`BundlrBuilder::allow_plaintext_for`](crate::BundlrBuilder::allow_plaintext_for).
+    /// Other URLs are refused with [`BundlrError::InsecureTransport`], whether given when
+    /// building the client or reached later, e.g. through redirects.
+    RequireTls,
+}
+
+/// Bundlr networks, to set up a client for with
+/// [`BundlrBuilder::network`](crate::BundlrBuilder::network) or
+/// [`Bundlr::for_network`](crate::Bundlr::for_network).
+///
+/// Mainnet nodes take payments in mainnet tokens and settle their items on Arweave. Devnet
+/// takes testnet tokens, and keeps items for a while only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Network {
+    /// Mainnet node1, at [`BUNDLR_DEFAULT_URL`]. Requires TLS.
+    Mainnet,
+    /// Mainnet node2, at [`NODE2_URL`]. Requires TLS.
+    Node2,
+    /// Devnet, at [`DEVNET_URL`]. Allows plaintext, to test against local nodes.
+    Devnet,
+    /// Devnet, paid for in WeaveVM testnet tokens through [`WEAVEVM_TESTNET_RPC_URL`]. Allows
+    /// plaintext as [`Network::Devnet`] does.
+    WeaveVmTestnet,
+}
+
+impl Network {
+    /// URL of the network's node.
+    pub fn url(&self) -> Url {
+        match self {
+            Network::Mainnet => Url::parse(BUNDLR_DEFAULT_URL),
+            Network::Node2 => Url::parse(NODE2_URL),
+            Network::Devnet | Network::WeaveVmTestnet => Url::parse(DEVNET_URL),
+        }
+        .expect("Network URLs are valid")
+    }
+
+    /// Gateway serving the items uploaded to the network, `None` on devnets, whose items are
+    /// served by the node only.
+    pub fn gateway_url(&self) -> Option<Url> {
+        match self.is_testnet() {
+            true => None,
+            false => Some(Url::parse(ARWEAVE_GATEWAY_URL).expect("Gateway URL is valid")),
+        }
+    }
+
+    /// RPC of the currency network to pay with, if the network is bound to one.
+    pub fn rpc_url(&self) -> Option<Url> {
+        match self {
+            Network::WeaveVmTestnet => {
+                Some(Url::parse(WEAVEVM_TESTNET_RPC_URL).expect("RPC URL is valid"))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the network takes testnet tokens.
+    pub fn is_testnet(&self) -> bool {
+        matches!(self, Network::Devnet | Network::WeaveVmTestnet)
+    }
+
+    pub fn transport_policy(&self) -> TransportPolicy {
+        match self.is_testnet() {
+            true => TransportPolicy::AllowPlaintext,
+            false => TransportPolicy::RequireTls,
+        }
+    }
+
+    /// Fails with [`BundlrError::NetworkMismatch`] if `currency` sends its transactions to a
+    /// known RPC of another network: a testnet one for mainnet nodes, or another one than
+    /// [`Network::rpc_url`] for networks bound to one.
+    pub fn check_currency(&self, currency: &dyn Currency) -> Result<(), BundlrError> {
+        let rpc_url = match currency.rpc_url() {
+            Some(rpc_url) => rpc_url,
+            None => return Ok(()),
+        };
+        let testnet = rpc_url.as_str() == WEAVEVM_TESTNET_RPC_URL;
+        let mismatch = match self.rpc_url() {
+            Some(expected) => expected != rpc_url,
+            None => testnet && !self.is_testnet(),
+        };
+        match mismatch {
+            true => Err(BundlrError::NetworkMismatch {
+                network: format!("{:?}", self),
+                rpc_url: rpc_url.to_string(),
+            }),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Proxy a client sends its requests through, set with
+/// [`BundlrBuilder::proxy`](crate::BundlrBuilder::proxy).
+///
+/// Without one, clients use the proxies of the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY`
+/// environment variables, if any.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ProxyOptions {
+    url: Url,
+    credentials: Option<(String, String)>,
+    no_proxy: Vec<String>,
+}
+
+impl ProxyOptions {
+    /// Proxy at `url`, with an `http` or `https` scheme, or `socks5` or `socks5h` (resolving
+    /// host names through the proxy) when built with the `socks` feature.
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            credentials: None,
+            no_proxy: vec![],
+        }
+    }
+
+    /// Authenticates to the proxy with `username` and `password`.
+    pub fn basic_auth(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_owned(), password.to_owned()));
+        self
+    }
+
+    /// Reaches `host` directly: a host name, a domain and its subdomains such as
+    /// `.example.com`, an IP address or a range such as `10.0.0.0/8`.
+    pub fn no_proxy(mut self, host: &str) -> Self {
+        self.no_proxy.push(host.to_owned());
+        self
+    }
+
+    pub(crate) fn build(&self) -> Result<Proxy, BuilderError> {
+        match self.url.scheme() {
+            "http" | "https" => {}
+            "socks5" | "socks5h" if cfg!(feature = "socks") => {}
+            scheme => {
+                return Err(BuilderError::BundlrError(format!(
+                    "Unsupported proxy scheme {}",
+                    scheme
+                )))
+            }
+        }
+        let mut proxy = Proxy::all(self.url.as_str())
+            .map_err(|err| BuilderError::BundlrError(format!("Invalid proxy: {}", err)))?;
+        if let Some((username, password)) = &self.credentials {
+            proxy = proxy.basic_auth(username, password);
+        }
+        Ok(proxy.no_proxy(NoProxy::from_string(&self.no_proxy.join(","))))
+    }
+}
+
+impl fmt::Debug for ProxyOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyOptions")
+            .field("url", &self.url.as_str())
+            .field(
+                "username",
+                &self.credentials.as_ref().map(|(username, _)| username),
+            )
+            .field("no_proxy", &self.no_proxy)
+            .finish()
+    }
+}
+
+/// TLS implementation of a client's requests, among those enabled with the `rustls` and
+/// `native-tls` features, set with
+/// [`BundlrBuilder::tls_backend`](crate::BundlrBuilder::tls_backend). Clients built with both use
+/// the native one unless told otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsBackend {
+    /// rustls, with the Mozilla root certificates built in.
+    #[cfg(feature = "rustls")]
+    Rustls,
+    /// The platform's TLS library and trust store: OpenSSL on Linux, Secure Transport on macOS
+    /// and SChannel on Windows.
+    #[cfg(feature = "native-tls")]
+    NativeTls,
+}
+
+/// Transport policy of a client, with its exempted hosts.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Transport {
+    pub(crate) policy: TransportPolicy,
+    pub(crate) plaintext_hosts: Vec<String>,
+}
+
+impl Transport {
+    /// Fails if the policy doesn't allow sending requests to `url`.
+    pub(crate) fn check(&self, url: &Url) -> Result<(), BundlrError> {
+        let allowed = match self.policy {
+            TransportPolicy::AllowPlaintext => true,
+            TransportPolicy::RequireTls => {
+                url.scheme() == "https"
+                    || url.host_str().is_some_and(|host| {
+                        self.plaintext_hosts
+                            .iter()
+                            .any(|allowed| allowed.eq_ignore_ascii_case(host))
+                    })
+            }
+        };
+        match allowed {
+            true => Ok(()),
+            false => Err(BundlrError::InsecureTransport {
+                url: url.to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Url;
+
+    use super::{Network, Transport, TransportPolicy, WEAVEVM_TESTNET_RPC_URL};
+    use crate::{currency::mock::MockCurrency, error::BundlrError};
+
+    #[test]
+    fn should_require_tls_except_for_allowed_hosts() {
+        let transport = Transport {
+            policy: TransportPolicy::RequireTls,
+            plaintext_hosts: vec!["localhost".to_owned()],
+        };
+        let check = |url: &str| transport.check(&Url::parse(url).unwrap());
+        assert!(check("https://node1.bundlr.network/").is_ok());
+        assert!(check("http://localhost:1984/").is_ok());
+        assert!(check("http://LOCALHOST/").is_ok());
+        for url in [
+            "http://node1.bundlr.network/",
+            "http://127.0.0.1/",
+            "ws://node.example/",
+        ] {
+            assert!(
+                matches!(check(url), Err(BundlrError::InsecureTransport { url: refused }) if refused == url),
+                "{}",
+                url
+            );
+        }
+        assert!(Transport::default()
+            .check(&Url::parse("http://node1.bundlr.network/").unwrap())
+            .is_ok());
+    }
+
+    #[test]
+    fn should_refuse_currencies_of_other_networks() {
+        assert_eq!(
+            Network::Node2.url().as_str(),
+            "https://node2.bundlr.network/"
+        );
+        assert_eq!(
+            Network::WeaveVmTestnet.url(),
+            Network::Devnet.url(),
+            "WeaveVM testnet tokens are taken by devnet"
+        );
+        assert!(Network::Mainnet.gateway_url().is_some());
+        assert!(Network::Devnet.gateway_url().is_none());
+
+        let testnet = MockCurrency::with_rpc_url(Url::parse(WEAVEVM_TESTNET_RPC_URL).unwrap());
+        let other = MockCurrency::with_rpc_url(Url::parse("https://rpc.example/").unwrap());
+        let offline = MockCurrency::new(false, None);
+        for network in [Network::Mainnet, Network::Node2] {
+            assert!(matches!(
+                network.check_currency(testnet.as_ref()),
+                Err(BundlrError::NetworkMismatch { rpc_url, .. })
+                    if rpc_url == WEAVEVM_TESTNET_RPC_URL
+            ));
+            assert!(network.check_currency(other.as_ref()).is_ok());
+        }
+        assert!(Network::Devnet.check_currency(testnet.as_ref()).is_ok());
+        assert!(Network::WeaveVmTestnet
+            .check_currency(testnet.as_ref())
+            .is_ok());
+        assert!(Network::WeaveVmTestnet
+            .check_currency(other.as_ref())
+            .is_err());
+        assert!(Network::Mainnet.check_currency(offline.as_ref()).is_ok());
+    }
+}
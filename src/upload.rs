@@ -1,12 +1,36 @@
-use std::{str::FromStr, thread::sleep, time::Duration};
+use std::{
+    cmp,
+    collections::BTreeSet,
+    fs,
+    io::ErrorKind,
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
-use reqwest::{header::ACCEPT, Url};
+use bytes::Bytes;
+use data_encoding::BASE64URL_NOPAD;
+use futures::{future, stream, StreamExt, TryStreamExt};
+use reqwest::{
+    header::{ACCEPT, CONTENT_TYPE},
+    Url,
+};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 
 use crate::{
-    consts::{BUNDLR_DEFAULT_URL, CHUNKS_RETRIES, CHUNKS_RETRY_SLEEP, CHUNK_SIZE},
+    clock::Clock,
+    consts::{
+        BUNDLR_DEFAULT_URL, CHUNKS_BUFFER_FACTOR, CHUNKS_RETRIES, CHUNKS_RETRY_SLEEP, CHUNK_SIZE,
+    },
     currency::CurrencyType,
     error::BundlrError,
+    progress::ProgressReporter,
+    task::in_span,
+    throttle::Throttle,
+    utils::read_json,
 };
 
 #[derive(Serialize, Deserialize)]
@@ -16,12 +40,198 @@ struct IdRes {
     min: u64,
 }
 
+/// How [`Bundlr::upload_chunked`](crate::Bundlr::upload_chunked) splits an item, and how its
+/// chunks are posted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkedUploadOptions {
+    pub(crate) chunk_size: u64,
+    pub(crate) concurrency: usize,
+    pub(crate) retries: u16,
+    pub(crate) retry_delay: Duration,
+}
+
+impl Default for ChunkedUploadOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: CHUNK_SIZE,
+            concurrency: CHUNKS_BUFFER_FACTOR,
+            retries: CHUNKS_RETRIES,
+            retry_delay: Duration::from_secs(CHUNKS_RETRY_SLEEP),
+        }
+    }
+}
+
+impl ChunkedUploadOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Size of the chunks in bytes, [`CHUNK_SIZE`] by default. The node rejects sizes outside of
+    /// its range with [`BundlrError::ChunkSizeOutOfRange`].
+    pub fn chunk_size(mut self, chunk_size: u64) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Number of chunks posted at the same time, [`CHUNKS_BUFFER_FACTOR`] by default, at least 1.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Number of times a chunk is posted again after failing, [`CHUNKS_RETRIES`] by default,
+    /// before the upload fails. Other chunks keep being posted in the meantime.
+    pub fn retries(mut self, retries: u16) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Wait before posting a failed chunk again, [`CHUNKS_RETRY_SLEEP`] seconds by default.
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+}
+
+/// A chunked upload whose progress is saved to a state file, to resume it after an interruption
+/// with [`Bundlr::upload_chunked_with_session`](crate::Bundlr::upload_chunked_with_session).
+///
+/// The file records the upload id, the item being uploaded and the offsets of the chunks the node
+/// accepted, which are not posted again. It holds no data: resuming takes the same signed item.
+/// It is removed once the upload is finalized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadSession {
+    path: PathBuf,
+    state: SessionState,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionState {
+    upload_id: Option<String>,
+    item: Option<SessionItem>,
+    uploaded: BTreeSet<usize>,
+}
+
+/// The item a session uploads, and how it is split.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionItem {
+    /// SHA-256 of the item's header, which holds its signature.
+    digest: String,
+    length: usize,
+    chunk_size: u64,
+}
+
+impl SessionItem {
+    fn new(header: &Bytes, length: usize, chunk_size: u64) -> Self {
+        Self {
+            digest: BASE64URL_NOPAD.encode(&Sha256::digest(header)),
+            length,
+            chunk_size,
+        }
+    }
+}
+
+impl UploadSession {
+    /// The session saved at `path`, or a new one saving its progress there if there is none.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, BundlrError> {
+        let path = path.into();
+        let state = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|err| BundlrError::InvalidUploadSession(err.to_string()))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => SessionState::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self { path, state })
+    }
+
+    /// The session saved at `path` for upload `upload_id`, or a new one resuming it if there is
+    /// none, e.g. when the upload was started elsewhere. Without a saved state, all chunks are
+    /// posted again.
+    pub fn resume(path: impl Into<PathBuf>, upload_id: &str) -> Result<Self, BundlrError> {
+        let mut session = Self::open(path)?;
+        match session.state.upload_id.as_deref() {
+            None => session.state.upload_id = Some(upload_id.to_owned()),
+            Some(id) if id == upload_id => {}
+            Some(id) => {
+                return Err(BundlrError::InvalidUploadSession(format!(
+                    "{} is saved for upload {}, not {}",
+                    session.path.display(),
+                    id,
+                    upload_id
+                )))
+            }
+        }
+        Ok(session)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The node's id for the upload, once it started.
+    pub fn upload_id(&self) -> Option<&str> {
+        self.state.upload_id.as_deref()
+    }
+
+    /// Offsets of the chunks the node accepted.
+    pub fn uploaded(&self) -> impl Iterator<Item = usize> + '_ {
+        self.state.uploaded.iter().copied()
+    }
+
+    /// Records that `item` is uploaded as `upload_id`, failing if the session is for another
+    /// item.
+    fn begin(&mut self, upload_id: String, item: SessionItem) -> Result<(), BundlrError> {
+        if let Some(known) = &self.state.item {
+            if known.digest != item.digest || known.length != item.length {
+                return Err(BundlrError::InvalidUploadSession(format!(
+                    "{} is saved for another item",
+                    self.path.display()
+                )));
+            }
+        }
+        self.state.upload_id = Some(upload_id);
+        self.state.item = Some(item);
+        self.save()
+    }
+
+    fn record(&mut self, offset: usize) -> Result<(), BundlrError> {
+        self.state.uploaded.insert(offset);
+        self.save()
+    }
+
+    fn finish(&self) -> Result<(), BundlrError> {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes the state next to the file before moving it in place, so that an interruption
+    /// never leaves it half written.
+    fn save(&self) -> Result<(), BundlrError> {
+        let bytes = serde_json::to_vec(&self.state)
+            .map_err(|err| BundlrError::InvalidUploadSession(err.to_string()))?;
+        let mut partial = self.path.clone().into_os_string();
+        partial.push(".partial");
+        fs::write(&partial, bytes)?;
+        fs::rename(&partial, &self.path)?;
+        Ok(())
+    }
+}
+
 pub struct Uploader {
     url: Url,
     client: reqwest::Client,
     pub upload_id: Option<String>,
-    currency: CurrencyType,
+    currency: String,
     chunk_size: u64,
+    retries: u16,
+    retry_delay: Duration,
+    clock: Clock,
+    progress: Option<ProgressReporter>,
+    throttle: Throttle,
 }
 
 impl Default for Uploader {
@@ -32,8 +242,13 @@ impl Default for Uploader {
             url,
             client,
             upload_id: None,
-            currency: CurrencyType::Arweave,
+            currency: CurrencyType::Arweave.to_string(),
             chunk_size: CHUNK_SIZE,
+            retries: CHUNKS_RETRIES,
+            retry_delay: Duration::from_secs(CHUNKS_RETRY_SLEEP),
+            clock: Clock::default(),
+            progress: None,
+            throttle: Throttle::default(),
         }
     }
 }
@@ -44,12 +259,133 @@ impl Uploader {
             url,
             client,
             upload_id: None,
-            currency,
+            currency: currency.to_string(),
             chunk_size: CHUNK_SIZE,
+            retries: CHUNKS_RETRIES,
+            retry_delay: Duration::from_secs(CHUNKS_RETRY_SLEEP),
+            clock: Clock::default(),
+            progress: None,
+            throttle: Throttle::default(),
         }
     }
 
+    /// Sends chunks for the currency the node names `name`, see [`Currency::name`].
+    pub(crate) fn with_currency_name(mut self, name: String) -> Self {
+        self.currency = name;
+        self
+    }
+
+    /// Waits between retries with `clock`.
+    pub(crate) fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Splits items in chunks of `chunk_size` bytes.
+    pub(crate) fn with_chunk_size(mut self, chunk_size: u64) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Posts failed chunks again up to `retries` times, waiting `delay` before each.
+    pub(crate) fn with_retries(mut self, retries: u16, delay: Duration) -> Self {
+        self.retries = retries;
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Posts chunks no faster than `throttle` lets them.
+    pub(crate) fn with_throttle(mut self, throttle: Throttle) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    /// Reports the progress of items to `progress`.
+    pub(crate) fn with_progress(mut self, progress: ProgressReporter) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
     pub async fn upload(&mut self, _data: Vec<u8>) -> Result<(), BundlrError> {
+        self.start().await
+    }
+
+    /// Uploads the signed item made of `header` followed by `data`, posting `concurrency` of its
+    /// chunks at a time, then has the node assemble them and returns its receipt.
+    ///
+    /// With a `session`, its upload is resumed, in chunks of the size it started with, and the
+    /// progress is saved to it.
+    pub(crate) async fn upload_item(
+        &mut self,
+        header: Bytes,
+        data: Bytes,
+        concurrency: usize,
+        mut session: Option<&mut UploadSession>,
+    ) -> Result<Value, BundlrError> {
+        let length = header.len() + data.len();
+        if let Some(session) = &session {
+            if let Some(upload_id) = session.upload_id() {
+                self.upload_id = Some(upload_id.to_owned());
+            }
+            if let Some(item) = &session.state.item {
+                self.chunk_size = item.chunk_size;
+            }
+        }
+        self.start().await?;
+
+        let chunk_size = self.chunk_size as usize;
+        let mut offsets: Vec<usize> = (0..length).step_by(chunk_size).collect();
+        if let Some(session) = &mut session {
+            let upload_id = self.upload_id.clone().unwrap_or_default();
+            session.begin(
+                upload_id,
+                SessionItem::new(&header, length, self.chunk_size),
+            )?;
+            offsets.retain(|offset| !session.state.uploaded.contains(offset));
+        }
+        let chunk_len = |offset: usize| cmp::min(offset + chunk_size, length) - offset;
+        if let Some(progress) = &self.progress {
+            let total = length.div_ceil(chunk_size);
+            let pending: usize = offsets.iter().map(|offset| chunk_len(*offset)).sum();
+            progress.split(
+                total as u64,
+                (length - pending) as u64,
+                (total - offsets.len()) as u64,
+            );
+        }
+
+        let uploader = &*self;
+        stream::iter(offsets)
+            .map(|offset| {
+                let range = offset..cmp::min(offset + chunk_size, length);
+                let chunk = slice_item(&header, &data, range);
+                uploader.post_chunk_with_retries(chunk, offset, vec![])
+            })
+            .buffer_unordered(cmp::max(concurrency, 1))
+            .try_for_each(|offset| {
+                if let Some(progress) = &uploader.progress {
+                    progress.sent(chunk_len(offset) as u64, 1);
+                }
+                future::ready(match &mut session {
+                    Some(session) => session.record(offset),
+                    None => Ok(()),
+                })
+            })
+            .await?;
+
+        let receipt = self.finalize().await?;
+        if let Some(progress) = &self.progress {
+            progress.accepted();
+        }
+        if let Some(session) = &session {
+            session.finish()?;
+        }
+        Ok(receipt)
+    }
+
+    /// Gets an upload id for the node, or resumes the known one, and checks that chunks of
+    /// `chunk_size` are accepted.
+    async fn start(&mut self) -> Result<(), BundlrError> {
         let (max, min) = if let Some(upload_id) = self.upload_id.clone() {
             let url = self
                 .url
@@ -95,22 +431,23 @@ impl Uploader {
         Ok(())
     }
 
-    /*
-    fn upload_transaction_chunks_stream<'a>(
-        uploader: &'a Uploader,
-        chunks: Vec<Vec<u8>>,
-        buffer: usize,
-    ) -> impl Stream<Item = Result<usize, BundlrError>> + 'a {
-        stream::iter(0..chunks.len())
-            .map(move |i| {
-                let chunk = chunks[i].clone();
-                uploader.post_chunk_with_retries(chunk, 0, vec![])
-            })
-            .buffer_unordered(buffer)
+    pub async fn post_chunk_with_retries(
+        &self,
+        chunk: Vec<u8>,
+        offset: usize,
+        headers: Vec<(String, String)>,
+    ) -> Result<usize, BundlrError> {
+        let post = self.post_chunk_retrying(chunk, offset, headers);
+        in_span!(
+            post,
+            "bundlr.chunk",
+            upload_id = self.upload_id.as_deref().unwrap_or_default(),
+            offset
+        )
+        .await
     }
-    */
 
-    pub async fn post_chunk_with_retries(
+    async fn post_chunk_retrying(
         &self,
         chunk: Vec<u8>,
         offset: usize,
@@ -119,13 +456,16 @@ impl Uploader {
         let mut retries = 0;
         let mut resp = self.post_chunk(&chunk, offset, headers.clone()).await;
 
-        while retries < CHUNKS_RETRIES {
+        while retries < self.retries {
             match resp {
                 Ok(offset) => return Ok(offset),
                 Err(e) => {
                     dbg!("post_chunk_with_retries: {:?}", e);
-                    sleep(Duration::from_secs(CHUNKS_RETRY_SLEEP));
+                    self.clock.sleep(self.retry_delay).await;
                     retries += 1;
+                    if let Some(progress) = &self.progress {
+                        progress.retried();
+                    }
                     resp = self.post_chunk(&chunk, offset, headers.clone()).await;
                 }
             }
@@ -154,12 +494,14 @@ impl Uploader {
         let mut req = self
             .client
             .post(url)
-            .json(&chunk)
-            .header(&ACCEPT, "application/json");
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .header(&ACCEPT, "application/json")
+            .body(chunk.to_vec());
         for (header, value) in headers {
             req = req.header(header, value);
         }
 
+        self.throttle.acquire(chunk.len()).await;
         let res = req
             .send()
             .await
@@ -170,4 +512,474 @@ impl Uploader {
             err => Err(BundlrError::RequestError(err.to_string())),
         }
     }
+
+    /// Has the node assemble the chunks posted under the upload id into the item, returning its
+    /// receipt.
+    pub async fn finalize(&self) -> Result<Value, BundlrError> {
+        let upload_id = match &self.upload_id {
+            Some(id) => id,
+            None => return Err(BundlrError::UploadError("No upload id".to_string())),
+        };
+        let url = self
+            .url
+            .join(&format!("/chunks/{}/{}/-1", self.currency, upload_id))
+            .map_err(|err| BundlrError::ParseError(err.to_string()))?;
+
+        let res = self
+            .client
+            .post(url)
+            .header("x-chunking-version", "2")
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .header(&ACCEPT, "application/json")
+            .send()
+            .await;
+        read_json(res).await
+    }
+}
+
+/// Bytes `range` of the item made of `header` followed by `data`.
+fn slice_item(header: &Bytes, data: &Bytes, range: Range<usize>) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(range.len());
+    let split = header.len();
+    if range.start < split {
+        chunk.extend_from_slice(&header[range.start..cmp::min(range.end, split)]);
+    }
+    if range.end > split {
+        chunk.extend_from_slice(&data[range.start.saturating_sub(split)..range.end - split]);
+    }
+    chunk
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        path::PathBuf,
+        time::{Duration, Instant, SystemTime},
+    };
+
+    use bytes::Bytes;
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use reqwest::Url;
+    use serde_json::json;
+
+    use tokio::sync::broadcast;
+
+    use super::{slice_item, SessionItem, SessionState, UploadSession, Uploader};
+    use crate::{
+        clock::MockClock, currency::CurrencyType, error::BundlrError, progress::ProgressReporter,
+        throttle::Throttle,
+    };
+
+    fn uploader(server: &MockServer) -> Uploader {
+        Uploader::new(
+            Url::parse(&server.url("/")).unwrap(),
+            reqwest::Client::new(),
+            CurrencyType::Arweave,
+        )
+    }
+
+    /// Path of a state file for `test`, removed if left by an earlier run.
+    fn session_path(test: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "bundlr-upload-session-{}-{}.json",
+            test,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    /// A session for upload `upload-id` of the item of `head` followed by 7 bytes of data, in
+    /// chunks of 5 bytes, having uploaded the first one.
+    fn interrupted_session(path: PathBuf, header: &'static [u8]) -> UploadSession {
+        let session = UploadSession {
+            path,
+            state: SessionState {
+                upload_id: Some("upload-id".to_owned()),
+                item: Some(SessionItem::new(&Bytes::from_static(header), 11, 5)),
+                uploaded: BTreeSet::from([0]),
+            },
+        };
+        session.save().unwrap();
+        UploadSession::open(session.path).unwrap()
+    }
+
+    #[test]
+    fn should_slice_items_across_header_and_data() {
+        let header = Bytes::from_static(b"head");
+        let data = Bytes::from_static(b"payload");
+
+        assert_eq!(slice_item(&header, &data, 0..3), b"hea");
+        assert_eq!(slice_item(&header, &data, 2..6), b"adpa");
+        assert_eq!(slice_item(&header, &data, 4..11), b"payload");
+        assert_eq!(slice_item(&header, &data, 9..11), b"ad");
+    }
+
+    #[tokio::test]
+    async fn should_upload_items_in_chunks() {
+        let server = MockServer::start();
+        let id = server.mock(|when, then| {
+            when.method(GET)
+                .path("/chunks/arweave/-1/-1")
+                .header("x-chunking-version", "2");
+            then.status(200)
+                .json_body(json!({ "id": "upload-id", "min": 1, "max": 10 }));
+        });
+        let chunks = [(0, "headp"), (5, "ayloa"), (10, "d")].map(|(offset, body)| {
+            server.mock(|when, then| {
+                when.method(POST)
+                    .path(format!("/chunks/arweave/upload-id/{}", offset))
+                    .header("content-type", "application/octet-stream")
+                    .body(body);
+                then.status(200);
+            })
+        });
+        let finalize = server.mock(|when, then| {
+            when.method(POST).path("/chunks/arweave/upload-id/-1");
+            then.status(201).json_body(json!({ "id": "item-id" }));
+        });
+
+        let mut uploader = uploader(&server).with_chunk_size(5);
+        let receipt = uploader
+            .upload_item(
+                Bytes::from_static(b"head"),
+                Bytes::from_static(b"payload"),
+                2,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(receipt, json!({ "id": "item-id" }));
+        id.assert();
+        for chunk in chunks {
+            chunk.assert();
+        }
+        finalize.assert();
+    }
+
+    #[tokio::test]
+    async fn should_post_chunks_concurrently() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/chunks/arweave/-1/-1");
+            then.status(200)
+                .json_body(json!({ "id": "upload-id", "min": 1, "max": 10 }));
+        });
+        let chunks = [0, 5, 10, 15].map(|offset| {
+            server.mock(|when, then| {
+                when.method(POST)
+                    .path(format!("/chunks/arweave/upload-id/{}", offset));
+                then.status(200).delay(Duration::from_millis(400));
+            })
+        });
+        server.mock(|when, then| {
+            when.method(POST).path("/chunks/arweave/upload-id/-1");
+            then.status(200).json_body(json!({ "id": "item-id" }));
+        });
+
+        let started = Instant::now();
+        uploader(&server)
+            .with_chunk_size(5)
+            .upload_item(
+                Bytes::from_static(b"head"),
+                Bytes::from_static(b"sixteen byte dat"),
+                4,
+                None,
+            )
+            .await
+            .unwrap();
+
+        // One after the other, they would take 1.6s
+        assert!(started.elapsed() < Duration::from_millis(1200));
+        for chunk in chunks {
+            chunk.assert();
+        }
+    }
+
+    #[tokio::test]
+    async fn should_hold_chunks_back_to_the_upload_rate() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/chunks/arweave/-1/-1");
+            then.status(200)
+                .json_body(json!({ "id": "upload-id", "min": 1, "max": 10 }));
+        });
+        let chunks = [0, 5, 10, 15].map(|offset| {
+            server.mock(|when, then| {
+                when.method(POST)
+                    .path(format!("/chunks/arweave/upload-id/{}", offset));
+                then.status(200);
+            })
+        });
+        server.mock(|when, then| {
+            when.method(POST).path("/chunks/arweave/upload-id/-1");
+            then.status(200).json_body(json!({ "id": "item-id" }));
+        });
+
+        let clock = MockClock::new(SystemTime::UNIX_EPOCH);
+        let mut uploader = uploader(&server)
+            .with_chunk_size(5)
+            .with_throttle(Throttle::new(Some(5), clock.clone().into()));
+        let upload = tokio::spawn(async move {
+            uploader
+                .upload_item(
+                    Bytes::from_static(b"head"),
+                    Bytes::from_static(b"sixteen byte dat"),
+                    4,
+                    None,
+                )
+                .await
+        });
+
+        // At 5 bytes per second, all chunks but the first wait for their second
+        while clock.sleepers() < 3 {
+            tokio::task::yield_now().await;
+        }
+        assert!(!upload.is_finished());
+        clock.advance(Duration::from_secs(3));
+        upload.await.unwrap().unwrap();
+        for chunk in chunks {
+            chunk.assert();
+        }
+    }
+
+    #[tokio::test]
+    async fn should_give_up_on_chunks_after_their_retries() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/chunks/arweave/-1/-1");
+            then.status(200)
+                .json_body(json!({ "id": "upload-id", "min": 1, "max": 10 }));
+        });
+        let failing = server.mock(|when, then| {
+            when.method(POST).path("/chunks/arweave/upload-id/0");
+            then.status(500);
+        });
+        let finalize = server.mock(|when, then| {
+            when.method(POST).path("/chunks/arweave/upload-id/-1");
+            then.status(200).json_body(json!({ "id": "item-id" }));
+        });
+
+        let res = uploader(&server)
+            .with_chunk_size(5)
+            .with_retries(2, Duration::ZERO)
+            .upload_item(Bytes::from_static(b"head"), Bytes::new(), 1, None)
+            .await;
+
+        assert!(matches!(res, Err(BundlrError::RequestError(_))));
+        failing.assert_hits(3);
+        finalize.assert_hits(0);
+    }
+
+    #[tokio::test]
+    async fn should_reject_chunk_sizes_out_of_the_node_range() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/chunks/arweave/-1/-1");
+            then.status(200)
+                .json_body(json!({ "id": "upload-id", "min": 1000, "max": 2000 }));
+        });
+        let chunks = server.mock(|when, then| {
+            when.method(POST);
+            then.status(200);
+        });
+
+        let mut uploader = uploader(&server).with_chunk_size(5);
+        let res = uploader
+            .upload_item(Bytes::from_static(b"head"), Bytes::new(), 2, None)
+            .await;
+
+        assert!(matches!(
+            res,
+            Err(BundlrError::ChunkSizeOutOfRange(1000, 2000))
+        ));
+        chunks.assert_hits(0);
+    }
+
+    #[tokio::test]
+    async fn should_resume_sessions_with_the_chunks_left() {
+        let server = MockServer::start();
+        let id = server.mock(|when, then| {
+            when.method(GET).path("/chunks/arweave/upload-id/-1");
+            then.status(200)
+                .json_body(json!({ "id": "upload-id", "min": 1, "max": 10 }));
+        });
+        let uploaded = server.mock(|when, then| {
+            when.method(POST).path("/chunks/arweave/upload-id/0");
+            then.status(200);
+        });
+        let chunks = [(5, "ayloa"), (10, "d")].map(|(offset, body)| {
+            server.mock(|when, then| {
+                when.method(POST)
+                    .path(format!("/chunks/arweave/upload-id/{}", offset))
+                    .body(body);
+                then.status(200);
+            })
+        });
+        server.mock(|when, then| {
+            when.method(POST).path("/chunks/arweave/upload-id/-1");
+            then.status(200).json_body(json!({ "id": "item-id" }));
+        });
+
+        let path = session_path("resume");
+        let mut session = interrupted_session(path.clone(), b"head");
+        let (sender, mut updates) = broadcast::channel(16);
+        let progress = ProgressReporter::new(&sender, "item-id".to_owned(), 11, 0);
+        // The session's chunk size wins over the uploader's
+        let mut uploader = uploader(&server)
+            .with_chunk_size(8)
+            .with_progress(progress.clone());
+        let receipt = uploader
+            .upload_item(
+                Bytes::from_static(b"head"),
+                Bytes::from_static(b"payload"),
+                2,
+                Some(&mut session),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(receipt, json!({ "id": "item-id" }));
+        id.assert();
+        uploaded.assert_hits(0);
+        for chunk in chunks {
+            chunk.assert();
+        }
+        assert_eq!(session.uploaded().collect::<Vec<_>>(), [0, 5, 10]);
+        assert!(!path.exists());
+
+        // The chunk uploaded before counts from the start
+        let first = updates.try_recv().unwrap();
+        assert_eq!((first.bytes_sent, first.chunks_completed), (5, 1));
+        let last = progress.get();
+        assert_eq!((last.bytes_sent, last.total_bytes), (11, 11));
+        assert_eq!((last.chunks_completed, last.total_chunks), (3, 3));
+        assert!(last.accepted);
+    }
+
+    #[tokio::test]
+    async fn should_save_the_progress_of_sessions() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/chunks/arweave/-1/-1");
+            then.status(200)
+                .json_body(json!({ "id": "upload-id", "min": 1, "max": 10 }));
+        });
+        server.mock(|when, then| {
+            when.method(POST).path("/chunks/arweave/upload-id/0");
+            then.status(200);
+        });
+        server.mock(|when, then| {
+            when.method(POST).path("/chunks/arweave/upload-id/5");
+            then.status(500);
+        });
+
+        let path = session_path("save");
+        let mut session = UploadSession::open(&path).unwrap();
+        let clock = MockClock::new(SystemTime::UNIX_EPOCH);
+        let mut uploader = uploader(&server)
+            .with_chunk_size(5)
+            .with_clock(clock.clone().into());
+        let upload = uploader.upload_item(
+            Bytes::from_static(b"head"),
+            Bytes::from_static(b"pay"),
+            1,
+            Some(&mut session),
+        );
+        // The upload is interrupted while waiting to post the second chunk again
+        tokio::select! {
+            _ = upload => panic!("the upload should wait to retry"),
+            _ = async {
+                while clock.sleepers() == 0 {
+                    tokio::task::yield_now().await;
+                }
+            } => {}
+        }
+
+        let saved = UploadSession::open(&path).unwrap();
+        assert_eq!(saved.upload_id(), Some("upload-id"));
+        assert_eq!(saved.uploaded().collect::<Vec<_>>(), [0]);
+        assert_eq!(saved, session);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_reject_sessions_of_another_item() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/chunks/arweave/upload-id/-1");
+            then.status(200)
+                .json_body(json!({ "id": "upload-id", "min": 1, "max": 10 }));
+        });
+        let chunks = server.mock(|when, then| {
+            when.method(POST);
+            then.status(200);
+        });
+
+        let path = session_path("other-item");
+        let mut session = interrupted_session(path.clone(), b"head");
+        let res = uploader(&server)
+            .upload_item(
+                Bytes::from_static(b"HEAD"),
+                Bytes::from_static(b"payload"),
+                2,
+                Some(&mut session),
+            )
+            .await;
+
+        assert!(matches!(res, Err(BundlrError::InvalidUploadSession(_))));
+        chunks.assert_hits(0);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_resume_sessions_by_upload_id() {
+        let path = session_path("by-id");
+        let session = UploadSession::resume(&path, "upload-id").unwrap();
+        assert_eq!(session.upload_id(), Some("upload-id"));
+        assert_eq!(session.uploaded().count(), 0);
+
+        interrupted_session(path.clone(), b"head");
+        let session = UploadSession::resume(&path, "upload-id").unwrap();
+        assert_eq!(session.uploaded().collect::<Vec<_>>(), [0]);
+        assert!(matches!(
+            UploadSession::resume(&path, "another-id"),
+            Err(BundlrError::InvalidUploadSession(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn should_span_chunks_with_their_upload_id() {
+        use crate::task::recording::Recorder;
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/chunks/arweave/upload-id/1024");
+            then.status(200);
+        });
+        let mut uploader = uploader(&server);
+        uploader.upload_id = Some("upload-id".to_owned());
+
+        let recorder = Recorder::default();
+        let _guard = tracing::subscriber::set_default(recorder.clone());
+        let offset = uploader
+            .post_chunk_with_retries(vec![1, 2, 3], 1024, vec![])
+            .await;
+        assert_eq!(offset.unwrap(), 1024);
+
+        let spans = recorder.spans();
+        let chunk = spans
+            .iter()
+            .find(|span| span.name == "bundlr.chunk")
+            .unwrap();
+        assert_eq!(chunk.fields["upload_id"], "upload-id");
+        assert_eq!(chunk.fields["offset"], "1024");
+    }
 }
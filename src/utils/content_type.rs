@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+
+use crate::error::BundlrError;
+
+/// Number of bytes at the start of a file enough to recognize its type from them.
+pub(crate) const SNIFF_LENGTH: usize = 8192;
+
+/// MIME type of the file at `path` starting with `head`, guessed from its extension, or else
+/// from the magic bytes of `head`.
+///
+/// The extension comes first as it tells apart more types, e.g. documents that are ZIP archives
+/// underneath, and text formats that have no magic bytes at all.
+pub(crate) fn detect(path: &Path, head: &[u8]) -> Option<String> {
+    mime_guess::from_path(path)
+        .first()
+        .map(|mime| mime.to_string())
+        .or_else(|| infer::get(head).map(|kind| kind.mime_type().to_owned()))
+}
+
+/// Opens the file at `path`, along with its MIME type if it can be told.
+pub(crate) async fn open(path: &Path) -> Result<(File, Option<String>), BundlrError> {
+    let mut file = File::open(path).await?;
+    let mut head = Vec::with_capacity(SNIFF_LENGTH);
+    (&mut file)
+        .take(SNIFF_LENGTH as u64)
+        .read_to_end(&mut head)
+        .await?;
+    file.rewind().await?;
+    Ok((file, detect(path, &head)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::detect;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[test]
+    fn should_prefer_the_extension() {
+        assert_eq!(
+            detect(Path::new("page.html"), b"<!doctype html>").as_deref(),
+            Some("text/html")
+        );
+        assert_eq!(
+            detect(Path::new("report.docx"), b"PK\x03\x04").as_deref(),
+            Some("application/vnd.openxmlformats-officedocument.wordprocessingml.document")
+        );
+    }
+
+    #[test]
+    fn should_fall_back_to_magic_bytes() {
+        assert_eq!(
+            detect(Path::new("image"), PNG).as_deref(),
+            Some("image/png")
+        );
+        assert_eq!(
+            detect(Path::new("image.unknown-extension"), PNG).as_deref(),
+            Some("image/png")
+        );
+        assert_eq!(detect(Path::new("notes"), b"just some text"), None);
+    }
+}
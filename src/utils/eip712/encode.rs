@@ -107,12 +107,12 @@ fn encode_data(
             // check if the type definition actually matches
             // the length of items to be encoded
             if length.is_some() && Some(values.len() as u64) != *length {
-                let array_type = format!("{}[{}]", inner.to_string(), length.unwrap());
+                let array_type = format!("{}[{}]", inner, length.unwrap());
                 return Err(Eip712Error::UnequalArrayItems(
                     length.unwrap(),
                     array_type,
                     values.len() as u64,
-                ))?;
+                ));
             }
 
             for item in values {
@@ -178,7 +178,7 @@ fn encode_data(
         Type::Address => {
             let addr = value.as_str().ok_or(serde_error("string", field_name))?;
             if addr.len() != 42 {
-                return Err(Eip712Error::InvalidAddressLength(addr.len()))?;
+                return Err(Eip712Error::InvalidAddressLength(addr.len()));
             }
             let address = EthAddress::from_str(&addr[2..])
                 .map_err(|err| Eip712Error::HexParseError(format!("{}", err)))?;
@@ -245,7 +245,7 @@ mod tests {
     use rustc_hex::ToHex;
     use serde_json::from_str;
 
-    const JSON: &'static str = r#"{
+    const JSON: &str = r#"{
 		"primaryType": "Mail",
 		"domain": {
 			"name": "Ether Mail",
@@ -340,7 +340,7 @@ mod tests {
         let mail = &String::from("Mail");
         assert_eq!(
             "Mail(Person from,Person to,string contents)Person(string name,address wallet)",
-            encode_type(&mail, &value).expect("alas error!")
+            encode_type(mail, &value).expect("alas error!")
         )
     }
 
@@ -366,7 +366,7 @@ mod tests {
 
         let value = from_str::<MessageTypes>(string).expect("alas error!");
         let mail = &String::from("Mail");
-        let hash = (type_hash(&mail, &value).expect("alas error!").0).to_hex::<String>();
+        let hash = (type_hash(mail, &value).expect("alas error!").0).to_hex::<String>();
         assert_eq!(
             hash,
             "a0cedeb2dc280ba39b857546d74f5549c3a1d7bdc2dd96bf881f76108e23dac2"
@@ -385,7 +385,7 @@ mod tests {
 
     #[test]
     fn test_unequal_array_lengths() {
-        const TEST: &'static str = r#"{
+        const TEST: &str = r#"{
 		"primaryType": "Mail",
 		"domain": {
 			"name": "Ether Mail",
@@ -64,9 +64,8 @@ impl Validate for EIP712 {
         }
         for field_types in self.types.values() {
             for field_type in field_types {
-                field_type.validate().map_err(|err| {
+                field_type.validate().inspect_err(|err| {
                     dbg!(err.to_string());
-                    err
                 })?;
             }
         }
@@ -98,7 +97,7 @@ mod tests {
             "Person[0]",
         ];
         for case in test_cases {
-            assert_eq!(TYPE_REGEX.is_match(case), false)
+            assert!(!TYPE_REGEX.is_match(case))
         }
 
         let test_cases = vec![
@@ -110,7 +109,7 @@ mod tests {
             "contents",
         ];
         for case in test_cases {
-            assert_eq!(TYPE_REGEX.is_match(case), true)
+            assert!(TYPE_REGEX.is_match(case))
         }
     }
 
@@ -197,7 +196,7 @@ mod tests {
 			}
 		}"#;
         let data = from_str::<EIP712>(string).unwrap();
-        assert_eq!(data.validate().is_err(), true);
+        assert!(data.validate().is_err());
     }
 
     #[test]
@@ -228,7 +227,7 @@ mod tests {
 			}
 		}"#;
         let data = from_str::<EIP712>(string).unwrap();
-        assert_eq!(data.validate().is_err(), false);
+        assert!(data.validate().is_ok());
     }
 
     #[test]
@@ -252,7 +251,7 @@ mod tests {
 			}
 		}"#;
         let data = from_str::<EIP712>(string).unwrap();
-        assert_eq!(data.validate().is_err(), true);
+        assert!(data.validate().is_err());
     }
 }
 
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use logos::Logos;
 
 use crate::utils::eip712::{error::Eip712Error, lexer::Token};
@@ -18,24 +20,21 @@ pub enum Type {
     },
 }
 
-impl ToString for Type {
-    fn to_string(&self) -> String {
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Type::Address => "address".to_owned(),
-            Type::Uint => "uint".to_owned(),
-            Type::Int => "int".to_owned(),
-            Type::String => "string".to_owned(),
-            Type::Bool => "bool".to_owned(),
-            Type::Bytes => "bytes".to_owned(),
-            Type::Byte(len) => format!("bytes{}", len),
-            Type::Custom(custom) => custom.to_string(),
-            Type::Array { inner, length } => {
-                let inner: String = (*inner).to_string();
-                match length {
-                    None => format!("{}[]", inner),
-                    Some(length) => format!("{}[{}]", inner, length),
-                }
-            }
+            Type::Address => write!(f, "address"),
+            Type::Uint => write!(f, "uint"),
+            Type::Int => write!(f, "int"),
+            Type::String => write!(f, "string"),
+            Type::Bool => write!(f, "bool"),
+            Type::Bytes => write!(f, "bytes"),
+            Type::Byte(len) => write!(f, "bytes{}", len),
+            Type::Custom(custom) => write!(f, "{}", custom),
+            Type::Array { inner, length } => match length {
+                None => write!(f, "{}[]", inner),
+                Some(length) => write!(f, "{}[{}]", inner, length),
+            },
         }
     }
 }
@@ -78,22 +77,21 @@ pub fn parse_type(field_type: &str) -> Result<Type, Eip712Error> {
                     state = State::Open;
                     continue;
                 }
+                Token::BracketClose if array_depth < 10 && state == State::Open => {
+                    let length = current_array_length.take();
+                    state = State::Close;
+                    token = Some(Type::Array {
+                        inner: Box::new(token.expect("if statement checks for some; qed")),
+                        length,
+                    });
+                    array_depth += 1;
+                    continue;
+                }
                 Token::BracketClose if array_depth < 10 => {
-                    if state == State::Open {
-                        let length = current_array_length.take();
-                        state = State::Close;
-                        token = Some(Type::Array {
-                            inner: Box::new(token.expect("if statement checks for some; qed")),
-                            length,
-                        });
-                        array_depth += 1;
-                        continue;
-                    } else {
-                        return Err(Eip712Error::UnexpectedToken(
-                            lexer.slice().to_owned(),
-                            field_type.to_owned(),
-                        ))?;
-                    }
+                    return Err(Eip712Error::UnexpectedToken(
+                        lexer.slice().to_owned(),
+                        field_type.to_owned(),
+                    ))?;
                 }
                 Token::BracketClose if array_depth == 10 => {
                     return Err(Eip712Error::UnsupportedArrayDepth)?;
@@ -0,0 +1,167 @@
+use std::time::Instant;
+
+use bytes::Bytes;
+use reqwest::{
+    header::{HeaderMap, DATE},
+    Response, StatusCode, Url,
+};
+use serde::Deserialize;
+
+use super::NodeClock;
+use crate::{
+    error::{BundlrError, ResponseFormatKind},
+    schema::{parse_diagnosed, ResponseShape},
+};
+
+pub async fn check_and_return<T>(res: Result<Response, reqwest::Error>) -> Result<T, BundlrError>
+where
+    T: for<'de> Deserialize<'de> + Default,
+{
+    let body = read_body(res).await?;
+    Ok(serde_json::from_slice::<T>(&body).unwrap_or_default())
+}
+
+/// Like [`check_and_return`], but fails on bodies that can't be parsed, with a diff against
+/// `shape` when the mismatch is in its fields.
+pub(crate) async fn check_and_diagnose<T>(
+    res: Result<Response, reqwest::Error>,
+    shape: &ResponseShape,
+) -> Result<T, BundlrError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let body = read_body(res).await?;
+    parse_diagnosed(shape, &body)
+}
+
+/// The body of `res` parsed as `T`, failing on error statuses and on bodies that can't be
+/// parsed, for answers that have no default.
+pub(crate) async fn read_json<T>(res: Result<Response, reqwest::Error>) -> Result<T, BundlrError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let body = read_body(res).await?;
+    serde_json::from_slice::<T>(&body).map_err(|err| BundlrError::ResponseError(err.to_string()))
+}
+
+/// Reads the body of a response, failing on error statuses and on bodies that are obviously not
+/// JSON.
+async fn read_body(res: Result<Response, reqwest::Error>) -> Result<Bytes, BundlrError> {
+    let r = res.map_err(|err| BundlrError::ResponseError(err.to_string()))?;
+    let status = r.status();
+    let body = r
+        .bytes()
+        .await
+        .map_err(|err| BundlrError::ResponseError(err.to_string()))?;
+    check_body(status, body)
+}
+
+/// Same as [`read_body`], for a body already read.
+pub(crate) fn check_body(status: StatusCode, body: Bytes) -> Result<Bytes, BundlrError> {
+    if let Some(err) = unexpected_format(status, &body) {
+        return Err(err);
+    }
+    if !status.is_success() {
+        let text = String::from_utf8_lossy(&body).replace('\"', "");
+        let msg = format!("Status: {}:{:?}", status, text);
+        return Err(BundlrError::ResponseError(msg));
+    };
+    Ok(body)
+}
+
+/// Recognizes bodies that can't be a JSON answer from the node, such as error pages served by a
+/// proxy or CDN in front of it. The body is sniffed, regardless of its `Content-Type`.
+///
+/// Empty bodies are only unexpected on success, an error status tells enough on its own.
+pub(crate) fn unexpected_format(status: StatusCode, body: &[u8]) -> Option<BundlrError> {
+    let text = String::from_utf8_lossy(body);
+    let text = text.trim_start();
+    let kind = if text.is_empty() {
+        match status.is_success() {
+            true => ResponseFormatKind::Empty,
+            false => return None,
+        }
+    } else if text.starts_with('<') {
+        let challenge = [
+            "challenge-platform",
+            "cf_chl_",
+            "cf-challenge",
+            "Just a moment...",
+        ];
+        match challenge.iter().any(|marker| text.contains(marker)) {
+            true => ResponseFormatKind::CloudflareChallenge,
+            false => ResponseFormatKind::Html,
+        }
+    } else {
+        return None;
+    };
+
+    Some(BundlrError::UnexpectedResponseFormat {
+        kind,
+        status: status.as_u16(),
+        snippet: html_snippet(text),
+    })
+}
+
+/// Short, single line and tag free summary of an HTML page: its title if it has one.
+fn html_snippet(html: &str) -> String {
+    const MAX_LEN: usize = 80;
+
+    let lower = html.to_ascii_lowercase();
+    let content = match (lower.find("<title>"), lower.find("</title>")) {
+        (Some(start), Some(end)) if start + 7 <= end => &html[start + 7..end],
+        _ => html,
+    };
+
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in content.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if in_tag => {}
+            c if c.is_control() || c.is_whitespace() => text.push(' '),
+            c => text.push(c),
+        }
+    }
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(MAX_LEN) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
+
+impl NodeClock {
+    /// Records the node's time from a response's headers, if it sent a valid `Date`.
+    pub(crate) fn observe(&self, headers: &HeaderMap) {
+        let date = headers
+            .get(DATE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| httpdate::parse_http_date(value).ok());
+        if let Some(date) = date {
+            *self.last.lock().unwrap_or_else(|err| err.into_inner()) = Some((date, Instant::now()));
+        }
+    }
+}
+
+pub async fn get_nonce(
+    client: &reqwest::Client,
+    url: &Url,
+    address: String,
+    currency: String,
+) -> Result<u64, BundlrError> {
+    let res = client
+        .get(
+            url.join(&format!(
+                "/account/withdrawals/{}?address={}",
+                currency, address
+            ))
+            .map_err(|err| BundlrError::ParseError(err.to_string()))?,
+        )
+        .send()
+        .await;
+    check_and_return::<u64>(res).await
+}
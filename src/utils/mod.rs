@@ -1,62 +1,47 @@
 #[cfg(any(feature = "ethereum", feature = "erc20"))]
 mod eip712;
 
-pub(crate) use eip712::hash_structured_data;
-pub(crate) use eip712::Eip712Error;
-pub(crate) use eip712::EIP712;
+#[cfg(feature = "client")]
+pub(crate) mod content_type;
+#[cfg(feature = "client")]
+mod http;
+#[cfg(feature = "client")]
+mod numeric;
+
+#[cfg(any(feature = "ethereum", feature = "erc20"))]
+pub(crate) use eip712::{hash_structured_data, Eip712Error, EIP712};
+#[cfg(feature = "client")]
+pub(crate) use http::{check_and_diagnose, check_body, read_json, unexpected_format};
+#[cfg(feature = "client")]
+pub use http::{check_and_return, get_nonce};
+#[cfg(feature = "client")]
+pub(crate) use numeric::RawNumber;
 
 use std::{
     fs::File,
     io::{Read, Seek, SeekFrom},
+    sync::{Arc, Mutex},
+    time::{Instant, SystemTime},
 };
 
 use bytes::Bytes;
-use reqwest::{Response, Url};
-use serde::Deserialize;
-
-use crate::error::BundlrError;
 
-pub async fn check_and_return<T: for<'de> Deserialize<'de>>(
-    res: Result<Response, reqwest::Error>,
-) -> Result<T, BundlrError>
-where
-    T: Default,
-{
-    match res {
-        Ok(r) => {
-            if !r.status().is_success() {
-                let status = r.status();
-                let text = r
-                    .text()
-                    .await
-                    .map_err(|err| BundlrError::ParseError(err.to_string()))?
-                    .replace('\"', "");
-                let msg = format!("Status: {}:{:?}", status, text);
-                return Err(BundlrError::ResponseError(msg));
-            };
-            Ok(r.json::<T>().await.unwrap_or_default())
-        }
-        Err(err) => Err(BundlrError::ResponseError(err.to_string())),
-    }
+/// Current time according to the node, based on the `Date` header of its latest response.
+///
+/// The header only has a one second resolution, so this is only accurate to about a second.
+#[derive(Debug, Clone, Default)]
+pub struct NodeClock {
+    last: Arc<Mutex<Option<(SystemTime, Instant)>>>,
 }
 
-pub async fn get_nonce(
-    client: &reqwest::Client,
-    url: &Url,
-    address: String,
-    currency: String,
-) -> Result<u64, BundlrError> {
-    let res = client
-        .get(
-            url.join(&format!(
-                "/account/withdrawals/{}?address={}",
-                currency, address
-            ))
-            .map_err(|err| BundlrError::ParseError(err.to_string()))?,
-        )
-        .send()
-        .await;
-    check_and_return::<u64>(res).await
+impl NodeClock {
+    /// The node's current time, or `None` if no response carried a `Date` header yet.
+    pub fn now(&self) -> Option<SystemTime> {
+        self.last
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .map(|(date, observed_at)| date + observed_at.elapsed())
+    }
 }
 
 // Reads `length` bytes at `offset` within `file`
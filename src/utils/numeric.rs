@@ -0,0 +1,186 @@
+use num::{BigUint, Zero};
+use serde::{Deserialize, Deserializer};
+use serde_json::value::RawValue;
+
+use crate::error::BundlrError;
+
+/// Most digits an amount may expand to, far more than any balance or price needs, so that an
+/// exponent can't make us allocate at will.
+const MAX_DIGITS: usize = 256;
+
+/// A number as the node wrote it, whether as a JSON string or number, kept verbatim since
+/// `serde_json` rounds large numbers to `f64`. Parsed with [`RawNumber::parse_atomic`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct RawNumber(String);
+
+impl<'de> Deserialize<'de> for RawNumber {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+        let text = match serde_json::from_str::<String>(raw.get()) {
+            Ok(text) => text,
+            Err(_) => raw.get().to_owned(),
+        };
+        Ok(Self(text))
+    }
+}
+
+impl RawNumber {
+    /// Parses an amount of atomic units: a decimal integer, possibly in scientific notation such
+    /// as `1e18` or `1.5E3`, which must expand to a whole number.
+    ///
+    /// `field` names the value in the error returned for anything else.
+    pub(crate) fn parse_atomic(&self, field: &str) -> Result<BigUint, BundlrError> {
+        parse_atomic(self.0.trim()).ok_or_else(|| BundlrError::NumericParse {
+            field: field.to_owned(),
+            raw: self.0.clone(),
+        })
+    }
+}
+
+fn parse_atomic(text: &str) -> Option<BigUint> {
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(i) => {
+            let exponent = &text[i + 1..];
+            let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            if !is_digits(digits) {
+                return None;
+            }
+            (&text[..i], exponent.parse::<i64>().ok()?)
+        }
+        None => (text, 0),
+    };
+    let (int, frac) = match mantissa.split_once('.') {
+        Some((int, frac)) if is_digits(frac) => (int, frac),
+        Some(_) => return None,
+        None => (mantissa, ""),
+    };
+    if !is_digits(int) {
+        return None;
+    }
+
+    let mut digits = format!("{}{}", int, frac);
+    let shift = exponent.checked_sub(i64::try_from(frac.len()).ok()?)?;
+    if shift < 0 {
+        // Only whole numbers of atomic units are valid
+        let cut = digits.len().checked_sub(usize::try_from(-shift).ok()?);
+        match cut {
+            Some(cut) if digits[cut..].bytes().all(|b| b == b'0') => digits.truncate(cut),
+            _ if digits.bytes().all(|b| b == b'0') => return Some(BigUint::zero()),
+            _ => return None,
+        }
+    } else {
+        let shift = usize::try_from(shift).ok()?;
+        let significant = digits.trim_start_matches('0').len();
+        if significant > 0 && significant + shift > MAX_DIGITS {
+            return None;
+        }
+        if significant > 0 {
+            digits.extend(std::iter::repeat_n('0', shift));
+        }
+    }
+    match digits.is_empty() {
+        true => Some(BigUint::zero()),
+        false => digits.parse().ok(),
+    }
+}
+
+fn is_digits(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use num::BigUint;
+    use proptest::prelude::*;
+
+    use super::RawNumber;
+    use crate::error::BundlrError;
+
+    fn parse(json: &str) -> Result<BigUint, BundlrError> {
+        serde_json::from_str::<RawNumber>(json)
+            .unwrap()
+            .parse_atomic("amount")
+    }
+
+    fn big(n: &str) -> BigUint {
+        BigUint::from_str(n).unwrap()
+    }
+
+    #[test]
+    fn should_parse_node_amounts() {
+        let quintillion = big("1000000000000000000");
+        let trillion = big("1000000000000");
+        let cases = [
+            ("1e18", &quintillion),
+            ("1E+18", &quintillion),
+            ("\"1e18\"", &quintillion),
+            ("0.000001e24", &quintillion),
+            ("\"1000000000000\"", &trillion),
+            ("1000000000000", &trillion),
+            ("1.0e12", &trillion),
+            ("10000000000000e-1", &trillion),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(&parse(json).unwrap(), expected, "{}", json);
+        }
+        assert_eq!(
+            parse("1.2345678901234567890123e22").unwrap(),
+            big("12345678901234567890123")
+        );
+        assert_eq!(parse("0").unwrap(), big("0"));
+        assert_eq!(parse("0e-5").unwrap(), big("0"));
+        assert_eq!(parse("0e99999999999").unwrap(), big("0"));
+    }
+
+    #[test]
+    fn should_reject_invalid_amounts() {
+        for json in [
+            "1.5", "15e-1", "-1", "\"-1\"", "\"\"", "\"abc\"", "\"1e\"", "\"e5\"", "\".5\"",
+            "\"1.\"", "null", "true", "[1]", "\"0x10\"", "\" 1 2\"", "1e99999",
+        ] {
+            match parse(json) {
+                Err(BundlrError::NumericParse { field, raw }) => {
+                    assert_eq!(field, "amount");
+                    assert_eq!(
+                        raw,
+                        serde_json::from_str::<String>(json).unwrap_or(json.to_owned())
+                    );
+                }
+                res => panic!("{} parsed as {:?}", json, res),
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn should_parse_any_representation(n in any::<u128>(), exponent in 0usize..30) {
+            let expected = BigUint::from(n) * BigUint::from(10u8).pow(exponent as u32);
+            let digits = n.to_string();
+            let point = digits.len().min(1 + exponent % digits.len());
+            let (int, frac) = digits.split_at(point);
+            let frac = match frac {
+                "" => String::new(),
+                frac => format!(".{}", frac),
+            };
+            let sci = format!("{}{}e{}", int, frac, exponent + digits.len() - point);
+            for json in [
+                expected.to_string(),
+                format!("\"{}\"", expected),
+                format!("{}e{}", n, exponent),
+                format!("\"{}E+{}\"", n, exponent),
+                sci,
+            ] {
+                prop_assert_eq!(&parse(&json).unwrap(), &expected, "{}", json);
+            }
+        }
+
+        #[test]
+        fn should_never_panic(text in "[-+0-9.eE\"]{0,24}") {
+            if let Ok(raw) = serde_json::from_str::<RawNumber>(&text) {
+                let _ = raw.parse_atomic("amount");
+            }
+        }
+    }
+}
@@ -1,10 +1,10 @@
-use super::types::{Header, Item};
+use super::types::Item;
+use crate::bundle::{parse_header, ParsedHeader};
 use crate::error::BundlrError;
 use crate::utils::read_offset;
 use crate::BundlrTx;
 use data_encoding::BASE64URL;
-use primitive_types::U256;
-use std::{cmp, fs::File};
+use std::fs::File;
 
 impl From<std::io::Error> for BundlrError {
     fn from(e: std::io::Error) -> Self {
@@ -15,43 +15,34 @@ impl From<std::io::Error> for BundlrError {
 pub async fn verify_file_bundle(filename: String) -> Result<Vec<Item>, BundlrError> {
     let mut file = File::open(&filename)?;
 
-    let bundle_length = U256::from_little_endian(&read_offset(&mut file, 0, 32)?).as_u64();
+    let file_length = file.metadata()?.len();
+    let malformed = |msg: &str| BundlrError::MalformedBundle(format!("{}: {}", filename, msg));
 
-    // NOTE THIS IS UNSAFE BEYOND USIZE LIMIT
-    let header_bytes = read_offset(&mut file, 32, bundle_length as usize * 64)?;
-    // This will use ~100 bytes per header. So 1 GB is 1e+7 headers
-    let mut headers = Vec::with_capacity(cmp::min(bundle_length as usize, 1000));
-
-    for i in (0..(64
-        * usize::try_from(bundle_length)
-            .map_err(|err| BundlrError::TypeParseError(err.to_string()))?))
-        .step_by(64)
-    {
-        let h = Header(
-            U256::from_little_endian(&header_bytes[i..i + 32]).as_u64(),
-            BASE64URL.encode(&header_bytes[i + 32..i + 64]),
-        );
-        headers.push(h);
-    }
-
-    let mut offset = 32 + (64 * bundle_length);
-    let mut items = Vec::with_capacity(cmp::min(bundle_length as usize, 1000));
+    // The item count first, then as many headers as it announces
+    let mut header_bytes = read_offset(&mut file, 0, 32)?;
+    let headers = loop {
+        match parse_header(&header_bytes, Some(file_length)).map_err(malformed)? {
+            ParsedHeader::Items(headers) => break headers,
+            ParsedHeader::Incomplete(length) => header_bytes = read_offset(&mut file, 0, length)?,
+        }
+    };
+    drop(header_bytes);
 
-    for Header(size, id) in headers {
+    let mut items = Vec::with_capacity(headers.len());
+    for header in headers {
         // Read 4 KiB - max data-less Bundlr tx
         // We do it all at once to improve performance - by lowering fs ops and doing ops in memory
-        let mut tx = BundlrTx::from_file_position(&mut file, size, offset, 4096)?;
+        let mut tx = BundlrTx::from_file_position(&mut file, header.size, header.offset, 4096)?;
 
         match tx.verify().await {
             Err(err) => return Err(err),
             Ok(_) => {
                 let sig = tx.get_signarure();
                 let item = Item {
-                    tx_id: id,
+                    tx_id: BASE64URL.encode(&header.id),
                     signature: sig,
                 };
                 items.push(item);
-                offset += size;
             }
         }
     }
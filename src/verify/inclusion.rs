@@ -0,0 +1,166 @@
+use bytes::{Bytes, BytesMut};
+use data_encoding::BASE64URL_NOPAD;
+use futures::{Stream, StreamExt};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    bundle::{parse_header, ParsedHeader},
+    error::BundlrError,
+    tags::Tag,
+    BundlrTx,
+};
+
+/// Evidence that a data item is part of a bundle settled on Arweave.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionProof {
+    /// Id of the Arweave transaction carrying the bundle.
+    pub parent_id: String,
+    pub item_id: String,
+    /// Position of the item in the bundle's data, in bytes.
+    pub offset: u64,
+    /// Size of the item, in bytes.
+    pub size: u64,
+    /// SHA-256 of the item, as serialized in the bundle.
+    pub digest: [u8; 32],
+}
+
+/// Checks the Arweave transaction's tags, base64url encoded as returned by gateways, describe an
+/// ANS-104 bundle.
+pub(crate) fn check_bundle_tags(encoded: &[Tag]) -> Result<(), BundlrError> {
+    let decode = |s: &str| {
+        BASE64URL_NOPAD
+            .decode(s.as_bytes())
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .map_err(|err| BundlrError::MalformedBundle(err.to_string()))
+    };
+    let mut format = None;
+    let mut version = None;
+    for tag in encoded {
+        match decode(&tag.name)?.as_str() {
+            "Bundle-Format" => format = Some(decode(&tag.value)?),
+            "Bundle-Version" => version = Some(decode(&tag.value)?),
+            _ => {}
+        }
+    }
+
+    match (format.as_deref(), version.as_deref()) {
+        (Some("binary"), Some("2.0.0")) => Ok(()),
+        (format, version) => Err(BundlrError::MalformedBundle(format!(
+            "Unsupported Bundle-Format {:?} and Bundle-Version {:?}",
+            format, version
+        ))),
+    }
+}
+
+/// Reads from a byte stream only as far as needed.
+struct StreamReader<S> {
+    stream: S,
+    buffer: BytesMut,
+    /// Position in the stream of the start of `buffer`.
+    position: u64,
+}
+
+impl<S> StreamReader<S>
+where
+    S: Stream<Item = Result<Bytes, BundlrError>> + Unpin,
+{
+    /// Reads the next `len` bytes, or `None` if the stream ends before.
+    async fn read(&mut self, len: usize) -> Result<Option<Bytes>, BundlrError> {
+        while self.buffer.len() < len {
+            match self.stream.next().await {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk?),
+                None => return Ok(None),
+            }
+        }
+        self.position += len as u64;
+        Ok(Some(self.buffer.split_to(len).freeze()))
+    }
+
+    /// Skips `len` bytes, without keeping them around.
+    async fn skip(&mut self, len: u64) -> Result<bool, BundlrError> {
+        let mut left = len;
+        while left > self.buffer.len() as u64 {
+            left -= self.buffer.len() as u64;
+            self.position += self.buffer.len() as u64;
+            self.buffer.clear();
+            match self.stream.next().await {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk?),
+                None => return Ok(false),
+            }
+        }
+        self.position += left;
+        let _ = self.buffer.split_to(left as usize);
+        Ok(true)
+    }
+}
+
+/// Scans a bundle's header for `item_id`, then reads that item and verifies its signature.
+///
+/// Only the start of the bundle, up to the end of the item, is read from `bundle`.
+pub(crate) async fn find_item<S>(
+    bundle: S,
+    parent_id: &str,
+    item_id: &str,
+) -> Result<InclusionProof, BundlrError>
+where
+    S: Stream<Item = Result<Bytes, BundlrError>> + Unpin,
+{
+    let malformed = |msg: &str| BundlrError::MalformedBundle(format!("{}: {}", parent_id, msg));
+    let not_found = || BundlrError::ItemNotInBundle {
+        item_id: item_id.to_owned(),
+        bundle_id: parent_id.to_owned(),
+    };
+    let raw_id = BASE64URL_NOPAD
+        .decode(item_id.as_bytes())
+        .map_err(|_| not_found())?;
+
+    let mut reader = StreamReader {
+        stream: bundle,
+        buffer: BytesMut::new(),
+        position: 0,
+    };
+    let mut header_bytes = reader
+        .read(32)
+        .await?
+        .ok_or_else(|| malformed("Missing item count"))?
+        .to_vec();
+    let headers = loop {
+        match parse_header(&header_bytes, None).map_err(malformed)? {
+            ParsedHeader::Items(headers) => break headers,
+            ParsedHeader::Incomplete(length) => {
+                let rest = reader
+                    .read(length - header_bytes.len())
+                    .await?
+                    .ok_or_else(|| malformed("Truncated header"))?;
+                header_bytes.extend_from_slice(&rest);
+            }
+        }
+    };
+    let item = headers
+        .iter()
+        .find(|header| header.id[..] == raw_id[..])
+        .ok_or_else(not_found)?;
+    let size = item.size;
+
+    let truncated = || malformed("Truncated item");
+    if !reader.skip(item.offset - header_bytes.len() as u64).await? {
+        return Err(truncated());
+    }
+    let size_usize = usize::try_from(size).map_err(|_| truncated())?;
+    let position = reader.position;
+    let bytes = reader.read(size_usize).await?.ok_or_else(truncated)?;
+
+    let mut tx = BundlrTx::from_bytes(bytes.to_vec())?;
+    tx.verify().await?;
+    if Sha256::digest(tx.get_signarure())[..] != raw_id[..] {
+        return Err(BundlrError::InvalidSignature);
+    }
+
+    Ok(InclusionProof {
+        parent_id: parent_id.to_owned(),
+        item_id: item_id.to_owned(),
+        offset: position,
+        size,
+        digest: Sha256::digest(&bytes).into(),
+    })
+}
@@ -3,8 +3,19 @@ use bytes::Bytes;
 use crate::error::BundlrError;
 
 pub mod file;
+#[cfg(feature = "client")]
+pub mod inclusion;
+#[cfg(feature = "verify")]
+pub mod receipt;
+#[cfg(feature = "client")]
+pub mod response;
+mod standalone;
 pub mod types;
 
+pub use standalone::{verify_data_item, ItemSummary};
+#[cfg(feature = "verify")]
+pub use standalone::{verify_receipt, PubKey, ReceiptVerification};
+
 pub trait Verifier
 where
     Self: Sized,
@@ -0,0 +1,225 @@
+use std::{
+    ops::RangeInclusive,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use data_encoding::BASE64URL_NOPAD;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    deep_hash::DeepHashChunk, deep_hash_sync::deep_hash_sync, error::BundlrError, utils::NodeClock,
+    RsaPssVerifier, Verifier,
+};
+
+use super::PubKey;
+
+/// Receipt returned by the node when uploading a transaction.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+    pub id: String,
+    /// Milliseconds since the Unix epoch, according to the node.
+    pub timestamp: u64,
+    pub version: String,
+    pub public: String,
+    pub signature: String,
+    pub deadline_height: u64,
+    pub block: u64,
+    pub validator_signatures: Vec<String>,
+}
+
+/// How far the client's idea of the current time may be from the node's, either way.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSkewTolerance(pub Duration);
+
+/// Where to take the current time from when checking timestamps.
+#[derive(Debug, Default, Clone)]
+pub enum TimeSource {
+    /// The local system clock.
+    #[default]
+    Local,
+    /// The node's clock, as reported by the `Date` header of its responses. Falls back to the
+    /// local clock until a response carried one.
+    Node(NodeClock),
+}
+
+/// Options for [`Receipt::verify`].
+#[derive(Debug, Clone)]
+pub struct ReceiptVerification {
+    max_age: Duration,
+    tolerance: ClockSkewTolerance,
+    time_source: TimeSource,
+}
+
+impl ReceiptVerification {
+    /// Accepts receipts issued at most `max_age` ago, with no tolerance for clock skew.
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            max_age,
+            tolerance: ClockSkewTolerance::default(),
+            time_source: TimeSource::default(),
+        }
+    }
+
+    pub fn clock_skew_tolerance(mut self, tolerance: ClockSkewTolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn time_source(mut self, time_source: TimeSource) -> Self {
+        self.time_source = time_source;
+        self
+    }
+
+    /// Timestamps accepted at `now`, in milliseconds since the Unix epoch.
+    fn allowed_window(&self, now: SystemTime) -> RangeInclusive<u64> {
+        let now = now
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let tolerance = self.tolerance.0.as_millis() as u64;
+        let oldest = now.saturating_sub(self.max_age.as_millis() as u64 + tolerance);
+        oldest..=now.saturating_add(tolerance)
+    }
+}
+
+impl Receipt {
+    /// Checks the node's signature over the receipt.
+    pub fn verify_signature(&self) -> Result<(), BundlrError> {
+        let fields = DeepHashChunk::Chunks(vec![
+            DeepHashChunk::Chunk("Bundlr".into()),
+            DeepHashChunk::Chunk(self.version.clone().into()),
+            DeepHashChunk::Chunk(self.id.clone().into()),
+            DeepHashChunk::Chunk(self.deadline_height.to_string().into()),
+            DeepHashChunk::Chunk(self.timestamp.to_string().into()),
+        ]);
+
+        let decode = |s: &str| {
+            BASE64URL_NOPAD
+                .decode(s.as_bytes())
+                .map_err(|err| BundlrError::ParseError(err.to_string()))
+        };
+        let public = decode(&self.public)?;
+        let signature = decode(&self.signature)?;
+        let message = deep_hash_sync(fields)?;
+
+        RsaPssVerifier::verify(public.into(), message, signature.into())
+    }
+
+    /// Checks the receipt was signed with `node_key`, such as the key from the node's `/public`
+    /// endpoint, rather than only with the key it carries.
+    ///
+    /// Fails with [`BundlrError::UntrustedSigner`] if it carries another key.
+    pub fn verify_signed_by(&self, node_key: &PubKey) -> Result<(), BundlrError> {
+        let signer = PubKey::from_base64url(&self.public)
+            .map_err(|err| BundlrError::ParseError(err.to_string()))?;
+        if signer != *node_key {
+            return Err(BundlrError::UntrustedSigner {
+                signer: self.public.clone(),
+            });
+        }
+        self.verify_signature()
+    }
+
+    /// Checks the node's signature, then that the receipt was issued recently enough according
+    /// to `options`.
+    ///
+    /// Fails with [`BundlrError::InvalidSignature`] if the signature doesn't match, or
+    /// [`BundlrError::TimestampOutOfRange`] if the receipt is too old or in the future.
+    pub fn verify(&self, options: &ReceiptVerification) -> Result<(), BundlrError> {
+        self.verify_at(options, SystemTime::now())
+    }
+
+    fn verify_at(
+        &self,
+        options: &ReceiptVerification,
+        local_now: SystemTime,
+    ) -> Result<(), BundlrError> {
+        self.verify_signature()?;
+
+        let now = match &options.time_source {
+            TimeSource::Local => local_now,
+            TimeSource::Node(clock) => clock.now().unwrap_or(local_now),
+        };
+        let allowed_window = options.allowed_window(now);
+        if allowed_window.contains(&self.timestamp) {
+            Ok(())
+        } else {
+            Err(BundlrError::TimestampOutOfRange {
+                timestamp: self.timestamp,
+                allowed_window,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use reqwest::header::{HeaderMap, HeaderValue, DATE};
+
+    use super::{ClockSkewTolerance, Receipt, ReceiptVerification, TimeSource};
+    use crate::{error::BundlrError, utils::NodeClock};
+
+    fn test_receipt() -> Receipt {
+        let data = std::fs::read_to_string("res/test_receipt.json").expect("Unable to read file");
+        serde_json::from_str(&data).expect("Unable to parse json file")
+    }
+
+    fn issued_at(receipt: &Receipt) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(receipt.timestamp)
+    }
+
+    #[test]
+    fn should_tolerate_skewed_local_clock() {
+        let receipt = test_receipt();
+        let options = ReceiptVerification::new(Duration::from_secs(60));
+        // Local clock 2 minutes ahead, and 2 minutes behind
+        let ahead = issued_at(&receipt) + Duration::from_secs(120);
+        let behind = issued_at(&receipt) - Duration::from_secs(120);
+
+        for now in [ahead, behind] {
+            let res = receipt.verify_at(&options, now);
+            assert!(matches!(
+                res,
+                Err(BundlrError::TimestampOutOfRange { timestamp, .. }) if timestamp == receipt.timestamp
+            ));
+        }
+
+        let tolerant = options.clock_skew_tolerance(ClockSkewTolerance(Duration::from_secs(180)));
+        for now in [ahead, behind] {
+            assert!(receipt.verify_at(&tolerant, now).is_ok());
+        }
+    }
+
+    #[test]
+    fn should_use_node_time() {
+        let receipt = test_receipt();
+        let clock = NodeClock::default();
+        let node_now = issued_at(&receipt) + Duration::from_secs(10);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            DATE,
+            HeaderValue::from_str(&httpdate::fmt_http_date(node_now)).unwrap(),
+        );
+        clock.observe(&headers);
+
+        let options = ReceiptVerification::new(Duration::from_secs(60))
+            .clock_skew_tolerance(ClockSkewTolerance(Duration::from_secs(2)));
+        let skewed_local = issued_at(&receipt) + Duration::from_secs(3600);
+        assert!(receipt.verify_at(&options, skewed_local).is_err());
+
+        let options = options.time_source(TimeSource::Node(clock));
+        assert!(receipt.verify_at(&options, skewed_local).is_ok());
+    }
+
+    #[test]
+    fn should_distinguish_invalid_signatures() {
+        let mut receipt = test_receipt();
+        receipt.deadline_height += 1;
+        let options = ReceiptVerification::new(Duration::from_secs(60));
+        let res = receipt.verify_at(&options, issued_at(&receipt));
+        assert!(matches!(res, Err(BundlrError::InvalidSignature)));
+    }
+}
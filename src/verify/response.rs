@@ -0,0 +1,239 @@
+use bytes::Bytes;
+use data_encoding::BASE64URL_NOPAD;
+use reqwest::RequestBuilder;
+use ring::rand::SecureRandom;
+use serde::Deserialize;
+
+use crate::{
+    deep_hash::DeepHashChunk,
+    deep_hash_sync::deep_hash_sync,
+    error::BundlrError,
+    redirect::{Redirects, RequestKind},
+    utils::{check_and_return, unexpected_format, NodeClock},
+    ArweaveSigner, Verifier,
+};
+
+/// Header carrying the client's random challenge, base64url encoded.
+pub const NONCE_HEADER: &str = "x-bundlr-nonce";
+/// Header carrying the node's signature over the challenge and the response body.
+pub const SIGNATURE_HEADER: &str = "x-bundlr-signature";
+
+/// What to do with read responses depending on whether the node signed them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseVerificationPolicy {
+    /// Reject any response that is unsigned or carries an invalid signature.
+    Require,
+    /// Verify signed responses, accept unsigned ones from nodes that don't support it.
+    IfAvailable,
+    /// Don't send a challenge and don't check responses.
+    #[default]
+    Off,
+}
+
+/// Integrity check for balance and price reads.
+///
+/// When enabled, a random nonce is sent along with the request, and the node is expected to
+/// answer with a signature over `deep_hash(["Bundlr", nonce, body])`, made with the same key it
+/// uses for receipts.
+#[derive(Debug, Clone)]
+pub struct ResponseVerification {
+    public_key: Bytes,
+    policy: ResponseVerificationPolicy,
+}
+
+impl ResponseVerification {
+    pub fn new(public_key: Bytes, policy: ResponseVerificationPolicy) -> Self {
+        Self { public_key, policy }
+    }
+
+    pub fn policy(&self) -> ResponseVerificationPolicy {
+        self.policy
+    }
+
+    /// Checks a response body against the node's signature, if any, according to the policy.
+    pub fn verify(
+        &self,
+        nonce: &[u8],
+        body: &[u8],
+        signature: Option<&[u8]>,
+    ) -> Result<(), BundlrError> {
+        let signature = match (self.policy, signature) {
+            (ResponseVerificationPolicy::Off, _) => return Ok(()),
+            (ResponseVerificationPolicy::IfAvailable, None) => return Ok(()),
+            (ResponseVerificationPolicy::Require, None) => {
+                return Err(BundlrError::UnverifiedResponse(
+                    "No signature present".to_owned(),
+                ))
+            }
+            (_, Some(signature)) => signature,
+        };
+
+        let message = deep_hash_sync(DeepHashChunk::Chunks(vec![
+            DeepHashChunk::Chunk("Bundlr".into()),
+            DeepHashChunk::Chunk(Bytes::copy_from_slice(nonce)),
+            DeepHashChunk::Chunk(Bytes::copy_from_slice(body)),
+        ]))?;
+
+        ArweaveSigner::verify(
+            self.public_key.clone(),
+            message,
+            Bytes::copy_from_slice(signature),
+        )
+        .map_err(|err| BundlrError::UnverifiedResponse(err.to_string()))
+    }
+}
+
+/// Sends a read request, challenging the node to sign its response when `verification` asks
+/// for it, and following redirects as `redirects` allow. The node's time is recorded in `clock`.
+pub(crate) async fn send_verified<T>(
+    req: RequestBuilder,
+    verification: Option<&ResponseVerification>,
+    clock: &NodeClock,
+    redirects: &Redirects,
+) -> Result<T, BundlrError>
+where
+    T: for<'de> Deserialize<'de> + Default,
+{
+    let verification = match verification {
+        Some(v) if v.policy() != ResponseVerificationPolicy::Off => v,
+        _ => {
+            let (res, _) = redirects.send(RequestKind::Read, req).await?;
+            clock.observe(res.headers());
+            return check_and_return::<T>(Ok(res)).await;
+        }
+    };
+
+    let mut nonce = [0u8; 32];
+    ring::rand::SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|err| BundlrError::Unknown(err.to_string()))?;
+
+    let req = req.header(NONCE_HEADER, BASE64URL_NOPAD.encode(&nonce));
+    let (res, _) = redirects.send(RequestKind::Read, req).await?;
+    clock.observe(res.headers());
+
+    if !res.status().is_success() {
+        return check_and_return::<T>(Ok(res)).await;
+    }
+    let status = res.status();
+
+    let signature = match res.headers().get(SIGNATURE_HEADER) {
+        Some(value) => Some(
+            BASE64URL_NOPAD
+                .decode(value.as_bytes())
+                .map_err(|err| BundlrError::UnverifiedResponse(err.to_string()))?,
+        ),
+        None => None,
+    };
+    let body = res
+        .bytes()
+        .await
+        .map_err(|err| BundlrError::ResponseError(err.to_string()))?;
+    if let Some(err) = unexpected_format(status, &body) {
+        return Err(err);
+    }
+
+    verification.verify(&nonce, &body, signature.as_deref())?;
+
+    serde_json::from_slice::<T>(&body).map_err(|err| BundlrError::ParseError(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, str::FromStr};
+
+    use bytes::Bytes;
+    use httpmock::{Method::GET, MockServer};
+
+    use super::{send_verified, ResponseVerification, ResponseVerificationPolicy, NONCE_HEADER};
+    use crate::{
+        deep_hash::DeepHashChunk, deep_hash_sync::deep_hash_sync, error::BundlrError,
+        redirect::Redirects, utils::NodeClock, ArweaveSigner, Signer,
+    };
+
+    const NONCE: &[u8] = b"nonce";
+    const BODY: &[u8] = b"{ \"balance\": \"10\" }";
+
+    fn node_signer() -> ArweaveSigner {
+        let path = PathBuf::from_str("res/test_wallet.json").unwrap();
+        ArweaveSigner::from_keypair_path(path).unwrap()
+    }
+
+    fn sign(signer: &ArweaveSigner, nonce: &[u8], body: &[u8]) -> Bytes {
+        let message = deep_hash_sync(DeepHashChunk::Chunks(vec![
+            DeepHashChunk::Chunk("Bundlr".into()),
+            DeepHashChunk::Chunk(Bytes::copy_from_slice(nonce)),
+            DeepHashChunk::Chunk(Bytes::copy_from_slice(body)),
+        ]))
+        .unwrap();
+        signer.sign(message).unwrap()
+    }
+
+    #[test]
+    fn should_check_responses_according_to_policy() {
+        let signer = node_signer();
+        let signature = sign(&signer, NONCE, BODY);
+        let tampered = b"{ \"balance\": \"99999\" }";
+
+        for (policy, signed, tampered_ok, absent_ok) in [
+            (ResponseVerificationPolicy::Require, true, false, false),
+            (ResponseVerificationPolicy::IfAvailable, true, false, true),
+            (ResponseVerificationPolicy::Off, true, true, true),
+        ] {
+            let verification = ResponseVerification::new(signer.pub_key(), policy);
+            assert_eq!(
+                verification.verify(NONCE, BODY, Some(&signature)).is_ok(),
+                signed
+            );
+            assert_eq!(
+                verification
+                    .verify(NONCE, tampered, Some(&signature))
+                    .is_ok(),
+                tampered_ok
+            );
+            assert_eq!(verification.verify(NONCE, BODY, None).is_ok(), absent_ok);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_reject_unsigned_response_when_required() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/price/arweave/1")
+                .header_exists(NONCE_HEADER);
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("321");
+        });
+        let client = reqwest::Client::new();
+        let url = server.url("/price/arweave/1");
+
+        let required =
+            ResponseVerification::new(node_signer().pub_key(), ResponseVerificationPolicy::Require);
+        let clock = NodeClock::default();
+        let res = send_verified::<u64>(
+            client.get(&url),
+            Some(&required),
+            &clock,
+            &Redirects::default(),
+        )
+        .await;
+        assert!(matches!(res, Err(BundlrError::UnverifiedResponse(_))));
+
+        let if_available = ResponseVerification::new(
+            node_signer().pub_key(),
+            ResponseVerificationPolicy::IfAvailable,
+        );
+        let res = send_verified::<u64>(
+            client.get(&url),
+            Some(&if_available),
+            &clock,
+            &Redirects::default(),
+        )
+        .await;
+        assert_eq!(res.unwrap(), 321);
+
+        mock.assert_hits(2);
+    }
+}
@@ -0,0 +1,102 @@
+//! Verification for third parties holding receipts or items, without a currency, a node url nor
+//! any networking. Available with `--no-default-features --features verify`.
+
+use data_encoding::BASE64URL_NOPAD;
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "verify")]
+use super::receipt::Receipt;
+use crate::{error::VerifyError, index::SignerMap, tags::Tag, BundlrTx};
+
+/// Public key of a node: the modulus of its Arweave key.
+#[cfg(feature = "verify")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PubKey(pub Vec<u8>);
+
+#[cfg(feature = "verify")]
+impl PubKey {
+    /// Decodes a key as found in receipts and in the node's `/public` endpoint.
+    pub fn from_base64url(key: &str) -> Result<Self, VerifyError> {
+        BASE64URL_NOPAD
+            .decode(key.as_bytes())
+            .map(Self)
+            .map_err(|err| VerifyError::Malformed(err.to_string()))
+    }
+}
+
+/// A receipt whose signature was checked by [`verify_receipt`].
+///
+/// Unlike [`receipt::ReceiptVerification`](super::receipt::ReceiptVerification), which holds the
+/// options of [`Receipt::verify`], this is the outcome of a verification.
+#[cfg(feature = "verify")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceiptVerification {
+    pub id: String,
+    /// Milliseconds since the Unix epoch, according to the node.
+    pub timestamp: u64,
+    pub deadline_height: u64,
+    pub block: u64,
+    /// Which of the trusted keys signed the receipt.
+    pub signer: PubKey,
+}
+
+/// Checks a receipt, as returned by the node when uploading, was signed by one of
+/// `node_pubkeys`.
+///
+/// Only the signature is checked: see [`Receipt::verify`] to also check its timestamp.
+#[cfg(feature = "verify")]
+pub fn verify_receipt(
+    receipt_json: &str,
+    node_pubkeys: &[PubKey],
+) -> Result<ReceiptVerification, VerifyError> {
+    let receipt: Receipt = serde_json::from_str(receipt_json)
+        .map_err(|err| VerifyError::Malformed(err.to_string()))?;
+    let signer = PubKey::from_base64url(&receipt.public)?;
+    if !node_pubkeys.contains(&signer) {
+        return Err(VerifyError::UntrustedSigner);
+    }
+    receipt.verify_signature()?;
+
+    Ok(ReceiptVerification {
+        id: receipt.id,
+        timestamp: receipt.timestamp,
+        deadline_height: receipt.deadline_height,
+        block: receipt.block,
+        signer,
+    })
+}
+
+/// What a data item checked by [`verify_data_item`] contains. Binary fields are base64url
+/// encoded, empty when absent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemSummary {
+    /// SHA-256 of the signature.
+    pub id: String,
+    pub signature_type: SignerMap,
+    pub owner: String,
+    pub target: String,
+    pub anchor: String,
+    pub tags: Vec<Tag>,
+    /// Size of the data, in bytes.
+    pub data_size: u64,
+}
+
+/// Parses a serialized data item and checks its signature.
+///
+/// Only the signature types whose feature is enabled can be verified, Arweave's with `verify`;
+/// the others fail with [`VerifyError::UnsupportedSigner`].
+pub fn verify_data_item(bytes: &[u8]) -> Result<ItemSummary, VerifyError> {
+    let mut tx = BundlrTx::from_bytes(bytes.to_vec())?;
+    tx.verify_sync()?;
+
+    let encode = |bytes: &[u8]| BASE64URL_NOPAD.encode(bytes);
+    Ok(ItemSummary {
+        id: encode(&Sha256::digest(tx.get_signarure())),
+        signature_type: tx.get_signature_type(),
+        owner: encode(&tx.get_owner()),
+        target: encode(&tx.get_target()),
+        anchor: encode(&tx.get_anchor()),
+        tags: tx.get_tags().to_vec(),
+        data_size: tx.data_len().unwrap_or_default() as u64,
+    })
+}
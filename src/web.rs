@@ -0,0 +1,103 @@
+//! Uploads of signed items without the client, for builds where it isn't available such as
+//! `wasm32-unknown-unknown`, in browsers. Requests go through reqwest, which sends them with the
+//! browser's `fetch` there.
+//!
+//! Items are signed beforehand, e.g. with [`Ed25519Signer`](crate::Ed25519Signer) for Solana
+//! keys, and funding the node is left to the dapp's wallet.
+
+use reqwest::{header::CONTENT_TYPE, StatusCode, Url};
+use serde_json::Value;
+
+use crate::{error::BundlrError, BundlrTx};
+
+/// Posts the signed `item` to the node at `node`, for the balance of its signer in `currency`,
+/// e.g. `solana`, and returns what the node answered, the item's receipt.
+///
+/// Fails with [`BundlrError::NoSignature`] for unsigned items, and with
+/// [`BundlrError::InsufficientBalance`] if the signer's balance doesn't cover the upload.
+pub async fn upload(node: &Url, currency: &str, item: BundlrTx) -> Result<Value, BundlrError> {
+    upload_with(&reqwest::Client::new(), node, currency, item).await
+}
+
+/// Same as [`upload`], sending the request with `client`.
+pub async fn upload_with(
+    client: &reqwest::Client,
+    node: &Url,
+    currency: &str,
+    item: BundlrTx,
+) -> Result<Value, BundlrError> {
+    let url = node
+        .join(&format!("tx/{}", currency))
+        .map_err(|err| BundlrError::ParseError(err.to_string()))?;
+    let res = client
+        .post(url)
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .body(item.as_bytes()?)
+        .send()
+        .await
+        .map_err(|err| BundlrError::UploadError(err.to_string()))?;
+    let status = res.status();
+    let body = res
+        .bytes()
+        .await
+        .map_err(|err| BundlrError::ResponseError(err.to_string()))?;
+    let text = || String::from_utf8_lossy(&body).replace('\"', "");
+    if status == StatusCode::PAYMENT_REQUIRED {
+        return Err(BundlrError::InsufficientBalance(text()));
+    }
+    if !status.is_success() {
+        let msg = format!("Status: {}:{:?}", status, text());
+        return Err(BundlrError::ResponseError(msg));
+    }
+    serde_json::from_slice(&body).map_err(|err| BundlrError::ResponseError(err.to_string()))
+}
+
+#[cfg(all(test, feature = "client", feature = "solana"))]
+mod tests {
+    use std::str::FromStr;
+
+    use httpmock::{Method::POST, MockServer};
+    use reqwest::Url;
+
+    use super::upload;
+    use crate::{error::BundlrError, tags::Tag, BundlrTx, Ed25519Signer};
+
+    #[tokio::test]
+    async fn should_upload_signed_items() {
+        let signer = Ed25519Signer::from_base58(
+            "kNykCXNxgePDjFbDWjPNvXQRa8U12Ywc19dFVaQ7tebUj3m7H4sF4KKdJwM7yxxb3rqxchdjezX9Szh8bLcQAjb",
+        )
+        .unwrap();
+        let mut item = BundlrTx::new(vec![], b"Hello".to_vec(), vec![Tag::new("a", "b")]).unwrap();
+        item.sign_sync(&signer).unwrap();
+
+        let server = MockServer::start();
+        let uploaded = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx/solana")
+                .header("content-type", "application/octet-stream")
+                .body_contains("Hello");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("{ \"id\": \"some-id\" }");
+        });
+        let node = Url::from_str(&server.url("/")).unwrap();
+        let res = upload(&node, "solana", item).await.unwrap();
+        assert_eq!(res["id"], "some-id");
+        uploaded.assert();
+
+        let unsigned = BundlrTx::new(vec![], b"Hello".to_vec(), vec![]).unwrap();
+        let res = upload(&node, "solana", unsigned).await;
+        assert!(matches!(res, Err(BundlrError::NoSignature)));
+
+        let broke = server.mock(|when, then| {
+            when.method(POST).path("/tx/broke");
+            then.status(402).body("Not enough funds to send data");
+        });
+        let mut item = BundlrTx::new(vec![], b"Hello".to_vec(), vec![]).unwrap();
+        item.sign_sync(&signer).unwrap();
+        let res = upload(&node, "broke", item).await;
+        assert!(matches!(res, Err(BundlrError::InsufficientBalance(_))));
+        broke.assert();
+    }
+}
@@ -0,0 +1,232 @@
+//! Past withdrawals of an address, and the status of their transfers, to reconcile balances on
+//! the node with the funds received.
+
+use num::BigUint;
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::{
+    consts::CONFIRMATIONS_NEEDED,
+    error::BundlrError,
+    pagination::{Cursor, Page, Paginated},
+    redirect::{Redirects, RequestKind},
+    transaction::TxStatus,
+    utils::{read_json, RawNumber},
+};
+
+/// A withdrawal listed by a [`WithdrawalHistory`], amounts in the currency's base units.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WithdrawalRecord {
+    /// Id of the transfer sending the funds back, to check with
+    /// [`Bundlr::withdrawal_status`](crate::Bundlr::withdrawal_status).
+    pub tx_id: String,
+    pub requested: BigUint,
+    pub fee: BigUint,
+    /// Milliseconds since the Unix epoch, according to the node.
+    pub timestamp: u64,
+}
+
+/// Where the transfer of a withdrawal is on its chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WithdrawalStatus {
+    /// Not seen on chain yet, e.g. still in the mempool.
+    Unseen,
+    /// In a block, with fewer than [`CONFIRMATIONS_NEEDED`] confirmations.
+    Pending { confirmations: u64 },
+    /// With enough confirmations to be considered final.
+    Confirmed {
+        confirmations: u64,
+        height: u128,
+        block_hash: String,
+    },
+}
+
+impl WithdrawalStatus {
+    pub(crate) fn from_tx(status: Option<TxStatus>) -> Self {
+        match status {
+            None => WithdrawalStatus::Unseen,
+            Some(status) if status.confirmations < CONFIRMATIONS_NEEDED => {
+                WithdrawalStatus::Pending {
+                    confirmations: status.confirmations,
+                }
+            }
+            Some(status) => WithdrawalStatus::Confirmed {
+                confirmations: status.confirmations,
+                height: status.height,
+                block_hash: status.block_hash,
+            },
+        }
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        matches!(self, WithdrawalStatus::Confirmed { .. })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordData {
+    tx_id: String,
+    requested: RawNumber,
+    fee: RawNumber,
+    timestamp: u64,
+}
+
+#[derive(Deserialize)]
+struct PageData {
+    withdrawals: Vec<RecordData>,
+    #[serde(default)]
+    cursor: Option<String>,
+}
+
+/// Listing of the withdrawals of an address in a currency, newest first, from the node's
+/// `account/withdrawals/{currency}/history` endpoint.
+pub struct WithdrawalHistory {
+    client: reqwest::Client,
+    url: Url,
+    currency: String,
+    address: String,
+    redirects: Redirects,
+}
+
+impl WithdrawalHistory {
+    pub(crate) fn new(
+        client: reqwest::Client,
+        url: Url,
+        currency: String,
+        address: String,
+        redirects: Redirects,
+    ) -> Self {
+        Self {
+            client,
+            url,
+            currency,
+            address,
+            redirects,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Paginated for WithdrawalHistory {
+    type Item = WithdrawalRecord;
+
+    async fn fetch_page(
+        &self,
+        cursor: Option<&Cursor>,
+    ) -> Result<Page<WithdrawalRecord>, BundlrError> {
+        let url = self
+            .url
+            .join(&format!("account/withdrawals/{}/history", self.currency))
+            .map_err(|err| BundlrError::ParseError(err.to_string()))?;
+        let mut req = self
+            .client
+            .get(url)
+            .query(&[("address", self.address.as_str())]);
+        if let Some(cursor) = cursor {
+            req = req.query(&[("cursor", cursor.as_str())]);
+        }
+        let (res, _) = self.redirects.send(RequestKind::Read, req).await?;
+        let page: PageData = read_json(Ok(res)).await?;
+
+        let items = page
+            .withdrawals
+            .into_iter()
+            .map(|record| {
+                Ok(WithdrawalRecord {
+                    requested: record.requested.parse_atomic("requested")?,
+                    fee: record.fee.parse_atomic("fee")?,
+                    tx_id: record.tx_id,
+                    timestamp: record.timestamp,
+                })
+            })
+            .collect::<Result<_, BundlrError>>()?;
+        Ok(Page {
+            items,
+            next_cursor: page.cursor.map(Cursor::from),
+            total: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use httpmock::{Method::GET, MockServer};
+    use num::BigUint;
+    use reqwest::Url;
+
+    use super::{WithdrawalHistory, WithdrawalRecord, WithdrawalStatus};
+    use crate::{
+        consts::CONFIRMATIONS_NEEDED, pagination::Paginated, redirect::Redirects,
+        transaction::TxStatus,
+    };
+
+    #[tokio::test]
+    async fn should_list_withdrawals_page_by_page() {
+        let server = MockServer::start();
+        let second = server.mock(|when, then| {
+            when.method(GET)
+                .path("/account/withdrawals/arweave/history")
+                .query_param("address", "wallet")
+                .query_param("cursor", "next");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(r#"{ "withdrawals": [{ "txId": "tx-1", "requested": 50, "fee": 1, "timestamp": 1 }] }"#);
+        });
+        let first = server.mock(|when, then| {
+            when.method(GET)
+                .path("/account/withdrawals/arweave/history")
+                .query_param("address", "wallet");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(r#"{ "withdrawals": [{ "txId": "tx-2", "requested": "1000", "fee": "25", "timestamp": 2 }], "cursor": "next" }"#);
+        });
+        let history = WithdrawalHistory::new(
+            reqwest::Client::new(),
+            Url::parse(&server.url("/")).unwrap(),
+            "arweave".to_owned(),
+            "wallet".to_owned(),
+            Redirects::default(),
+        );
+
+        let records: Vec<_> = history.into_stream(None).try_collect().await.unwrap();
+        assert_eq!(
+            records,
+            [
+                WithdrawalRecord {
+                    tx_id: "tx-2".to_owned(),
+                    requested: BigUint::from(1000u32),
+                    fee: BigUint::from(25u32),
+                    timestamp: 2,
+                },
+                WithdrawalRecord {
+                    tx_id: "tx-1".to_owned(),
+                    requested: BigUint::from(50u32),
+                    fee: BigUint::from(1u32),
+                    timestamp: 1,
+                },
+            ]
+        );
+        first.assert_hits(1);
+        second.assert_hits(1);
+    }
+
+    #[test]
+    fn should_tell_confirmed_withdrawals() {
+        let status = |confirmations| TxStatus {
+            confirmations,
+            height: 100,
+            block_hash: "block".to_owned(),
+        };
+        assert_eq!(WithdrawalStatus::from_tx(None), WithdrawalStatus::Unseen);
+        assert_eq!(
+            WithdrawalStatus::from_tx(Some(status(CONFIRMATIONS_NEEDED - 1))),
+            WithdrawalStatus::Pending {
+                confirmations: CONFIRMATIONS_NEEDED - 1
+            }
+        );
+        let confirmed = WithdrawalStatus::from_tx(Some(status(CONFIRMATIONS_NEEDED)));
+        assert!(confirmed.is_confirmed());
+    }
+}
@@ -0,0 +1,210 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fs,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use bundlr_sdk::{tags::AvroDecode, verify::file::verify_file_bundle, BundlrTx};
+use proptest::prelude::*;
+
+/// Tracks the bytes currently allocated and the highest value reached since the last reset.
+struct TrackingAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+/// Allocations are measured process wide, so parses must not overlap.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+/// Room for the parser's own bookkeeping, independent of the input size.
+const ALLOWED_OVERHEAD: usize = 64 * 1024;
+const ALLOWED_TIME: Duration = Duration::from_secs(1);
+
+/// Runs `parse` on `input`, checking it neither allocates nor takes more than its input warrants.
+/// Panics inside `parse` fail the test case.
+fn bounded<T>(input: &[u8], parse: impl FnOnce(Vec<u8>) -> T) -> T {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let input = input.to_vec();
+    let len = input.len();
+
+    let baseline = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let start = Instant::now();
+    let res = parse(input);
+    let elapsed = start.elapsed();
+    let peak = PEAK.load(Ordering::SeqCst);
+
+    assert!(
+        peak - baseline <= 4 * len + ALLOWED_OVERHEAD,
+        "parsing {} bytes allocated {} bytes",
+        len,
+        peak - baseline
+    );
+    assert!(
+        elapsed < ALLOWED_TIME,
+        "parsing {} bytes took {:?}",
+        len,
+        elapsed
+    );
+    res
+}
+
+fn golden_items() -> Vec<Vec<u8>> {
+    let mut items: Vec<_> = fs::read_dir("res/golden")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "bin"))
+        .map(|path| fs::read(path).unwrap())
+        .collect();
+    items.sort();
+    items
+}
+
+fn golden_item() -> impl Strategy<Value = Vec<u8>> {
+    proptest::sample::select(golden_items())
+}
+
+fn zigzag(n: i64) -> Vec<u8> {
+    let mut value = ((n << 1) ^ (n >> 63)) as u64;
+    let mut out = vec![];
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return out;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Header of an ED25519 item without target nor anchor, up to its tags.
+fn ed25519_header(number_of_tags: u64, tags_length: u64) -> Vec<u8> {
+    let mut item = 2u16.to_le_bytes().to_vec();
+    item.extend_from_slice(&[0; 64 + 32]);
+    item.extend_from_slice(&[0, 0]);
+    item.extend_from_slice(&number_of_tags.to_le_bytes());
+    item.extend_from_slice(&tags_length.to_le_bytes());
+    item
+}
+
+/// Avro tags claiming `count` items, or a block of `count` items if negative, each with a name
+/// and value of the given lengths, followed by whatever bytes are actually there.
+fn avro_tags() -> impl Strategy<Value = Vec<u8>> {
+    (
+        prop_oneof![Just(i64::MIN), Just(i64::MAX), any::<i64>(), -200i64..200],
+        prop_oneof![Just(i64::MAX), Just(-1i64), any::<i64>(), 0i64..5000],
+        prop_oneof![Just(i64::MAX), any::<i64>(), 0i64..5000],
+        proptest::collection::vec(any::<u8>(), 0..64),
+    )
+        .prop_map(|(count, name_len, value_len, body)| {
+            let mut tags = zigzag(count);
+            if count < 0 {
+                tags.extend(zigzag(body.len() as i64));
+            }
+            tags.extend(zigzag(name_len));
+            tags.extend_from_slice(&body);
+            tags.extend(zigzag(value_len));
+            tags.extend_from_slice(&body);
+            tags
+        })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn random_bytes_never_panic(bytes in proptest::collection::vec(any::<u8>(), 0..4096)) {
+        bounded(&bytes, |bytes| BundlrTx::from_bytes(bytes).map(|_| ()))
+            .ok();
+    }
+
+    #[test]
+    fn truncated_items_never_panic(item in golden_item(), cut in any::<prop::sample::Index>()) {
+        let cut = cut.index(item.len());
+        bounded(&item[..cut], |bytes| BundlrTx::from_bytes(bytes).map(|_| ()))
+            .ok();
+    }
+
+    #[test]
+    fn mutated_items_never_panic(
+        item in golden_item(),
+        position in any::<prop::sample::Index>(),
+        flip in 1u8..,
+    ) {
+        let mut item = item;
+        let position = position.index(item.len());
+        item[position] ^= flip;
+        bounded(&item, |bytes| BundlrTx::from_bytes(bytes).map(|_| ()))
+            .ok();
+    }
+
+    #[test]
+    fn pathological_tags_never_panic(
+        number_of_tags in prop_oneof![Just(u64::MAX), any::<u64>(), 0u64..200],
+        tags_length in prop_oneof![Just(u64::MAX), any::<u64>(), 0u64..200],
+        tags in avro_tags(),
+    ) {
+        let mut item = ed25519_header(number_of_tags, tags_length);
+        item.extend_from_slice(&tags);
+        bounded(&item, |bytes| BundlrTx::from_bytes(bytes).map(|_| ()))
+            .ok();
+        bounded(&tags, |mut bytes| bytes.as_mut_slice().decode().map(|_| ()))
+            .ok();
+    }
+
+    #[test]
+    fn huge_declared_bundle_sizes_never_panic(
+        count in prop_oneof![Just(u64::MAX), any::<u64>(), 0u64..8],
+        size in prop_oneof![Just(u64::MAX), any::<u64>(), 0u64..4096],
+        high in any::<bool>(),
+        body in proptest::collection::vec(any::<u8>(), 0..256),
+    ) {
+        // The bundle's item count, then a single header, then a tiny body
+        let mut bundle = count.to_le_bytes().to_vec();
+        bundle.extend_from_slice(&[if high { 0xff } else { 0 }; 24]);
+        bundle.extend_from_slice(&size.to_le_bytes());
+        bundle.extend_from_slice(&[0; 24 + 32]);
+        bundle.extend_from_slice(&body);
+
+        let path = std::env::temp_dir().join(format!(
+            "bundlr-sdk-adversarial-{}",
+            std::process::id()
+        ));
+        fs::write(&path, &bundle).unwrap();
+        let filename = path.to_string_lossy().into_owned();
+        bounded(&bundle, |_| futures::executor::block_on(verify_file_bundle(filename)).map(|_| ()))
+            .ok();
+        fs::remove_file(path).ok();
+    }
+}
+
+#[test]
+fn golden_items_still_parse() {
+    for item in golden_items() {
+        bounded(&item, BundlrTx::from_bytes).unwrap();
+    }
+}
@@ -0,0 +1,57 @@
+#![cfg(all(feature = "client", feature = "arweave"))]
+#![allow(deprecated)]
+
+use std::{path::PathBuf, str::FromStr};
+
+use bundlr_sdk::{compat::Bundlr, currency::arweave::ArweaveBuilder, tags::Tag};
+use httpmock::{Method::GET, Method::POST, MockServer};
+use reqwest::Url;
+
+fn info(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET).path("/info");
+        then.status(200).body(
+            "{ \"version\": \"0.2.0\", \"gateway\": \"arweave.net\", \"addresses\": { \"arweave\": \"address\" } }",
+        );
+    });
+}
+
+#[tokio::test]
+async fn should_upload_like_the_upstream_crate() {
+    let server = MockServer::start();
+    info(&server);
+    let upload = server.mock(|when, then| {
+        when.method(POST).path("/tx/arweave");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body("{ \"id\": \"some-id\", \"timestamp\": 1 }");
+    });
+
+    // The upstream crate's README example, with the node mocked
+    let url = Url::parse(&server.url("/")).unwrap();
+    let wallet = PathBuf::from_str("res/test_wallet.json").unwrap();
+    let currency = ArweaveBuilder::new().keypair_path(wallet).build().unwrap();
+    let bundlr = Bundlr::new(url, &currency).await;
+    let mut tx =
+        bundlr.create_transaction_with_tags(Vec::from("Hello"), vec![Tag::new("name", "value")]);
+    assert!(bundlr.sign_transaction(&mut tx).await.is_ok());
+    let result = bundlr.send_transaction(tx).await.unwrap();
+    assert_eq!(result["id"], "some-id");
+    upload.assert();
+
+    // Funding keeps its signature, only checked to build as funding needs a real network
+    drop(bundlr.fund(1000, Some(1.2)));
+
+    let bundlr = bundlr.into_inner();
+    assert!(bundlr.create_transaction(vec![], vec![]).is_ok());
+}
+
+#[tokio::test]
+#[should_panic(expected = "Could not create a client")]
+async fn should_panic_without_pub_info() {
+    let server = MockServer::start();
+    let url = Url::parse(&server.url("/")).unwrap();
+    let wallet = PathBuf::from_str("res/test_wallet.json").unwrap();
+    let currency = ArweaveBuilder::new().keypair_path(wallet).build().unwrap();
+    Bundlr::new(url, &currency).await;
+}
@@ -0,0 +1,93 @@
+#![cfg(all(feature = "ffi", unix))]
+
+use std::{env, path::PathBuf, process::Command};
+
+use httpmock::{
+    Method::{GET, POST},
+    MockServer,
+};
+
+/// Builds the crate as a shared library, compiles `tests/ffi/smoke.c` against it and the
+/// generated header, then runs it against a mocked node.
+#[test]
+fn c_program_should_upload_and_get_balance() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/info");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"{ "version": "0.2.0", "gateway": "arweave.net", "addresses": {} }"#);
+    });
+    let upload = server.mock(|when, then| {
+        when.method(POST).path("/tx/arweave");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"{ "id": "some-id", "timestamp": 1 }"#);
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/account/balance/arweave")
+            .query_param("address", "some-address");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"{ "balance": "42" }"#);
+    });
+
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let tmp = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    // In a target directory of its own, not to wait on the lock of the one running the tests
+    let target_dir = tmp.join("ffi-target");
+    let status = Command::new(env!("CARGO"))
+        .args([
+            "rustc",
+            "--lib",
+            "--features",
+            "ffi",
+            "--crate-type",
+            "cdylib",
+        ])
+        .arg("--manifest-path")
+        .arg(root.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "Could not build the shared library");
+    // Where cargo copies the final artifacts, named without their hash
+    let lib_dir = target_dir.join("debug");
+    let exe = tmp.join("ffi_smoke");
+
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+    let status = Command::new(cc)
+        .arg(root.join("tests/ffi/smoke.c"))
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lbundlr_sdk")
+        .arg("-o")
+        .arg(&exe)
+        .status()
+        .expect("A C compiler is needed to run this test");
+    assert!(status.success(), "Could not compile the C program");
+
+    // Cargo points the loader at its own artifacts when running tests, ahead of the rpath
+    let output = Command::new(&exe)
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .env("DYLD_LIBRARY_PATH", &lib_dir)
+        .arg(server.url("/"))
+        .arg(root.join("res/test_wallet.json"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "C program failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "id=some-id\nbalance=42\n"
+    );
+    upload.assert();
+}
@@ -0,0 +1,80 @@
+/* Uploads a small item and reads a balance through the C interface.
+ * Usage: smoke <node url> <arweave wallet path> */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "bundlr.h"
+
+static int fail(const char *what, BundlrStatus status, const BundlrHandle *handle) {
+    const char *err = bundlr_last_error(handle);
+    fprintf(stderr, "%s failed with %d: %s\n", what, status, err ? err : "(no error)");
+    return 1;
+}
+
+/* Reads the whole file at `path`, to release with `free`. */
+static uint8_t *read_file(const char *path, size_t *len) {
+    FILE *file = fopen(path, "rb");
+    if (file == NULL) {
+        return NULL;
+    }
+    uint8_t *buf = NULL;
+    long size = -1;
+    if (fseek(file, 0, SEEK_END) == 0 && (size = ftell(file)) > 0 &&
+        fseek(file, 0, SEEK_SET) == 0) {
+        buf = malloc((size_t)size);
+    }
+    if (buf != NULL && fread(buf, 1, (size_t)size, file) != (size_t)size) {
+        free(buf);
+        buf = NULL;
+    }
+    fclose(file);
+    *len = (size_t)size;
+    return buf;
+}
+
+int main(int argc, char **argv) {
+    if (argc != 3) {
+        fprintf(stderr, "usage: %s <url> <wallet>\n", argv[0]);
+        return 2;
+    }
+
+    size_t jwk_len = 0;
+    uint8_t *jwk = read_file(argv[2], &jwk_len);
+    if (jwk == NULL) {
+        fprintf(stderr, "could not read %s\n", argv[2]);
+        return 2;
+    }
+    BundlrHandle *handle = NULL;
+    BundlrStatus status = bundlr_client_new(argv[1], 1, jwk, jwk_len, &handle);
+    free(jwk);
+    if (status != BUNDLR_STATUS_OK) {
+        return fail("bundlr_client_new", status, NULL);
+    }
+
+    const char *data = "Hello from C";
+    char *id = NULL;
+    status = bundlr_upload(handle, (const uint8_t *)data, strlen(data),
+                           "[{\"name\": \"Content-Type\", \"value\": \"text/plain\"}]", &id);
+    if (status != BUNDLR_STATUS_OK) {
+        return fail("bundlr_upload", status, handle);
+    }
+    printf("id=%s\n", id);
+    bundlr_string_free(id);
+
+    char *balance = NULL;
+    status = bundlr_get_balance(handle, "some-address", &balance);
+    if (status != BUNDLR_STATUS_OK) {
+        return fail("bundlr_get_balance", status, handle);
+    }
+    printf("balance=%s\n", balance);
+    bundlr_string_free(balance);
+
+    status = bundlr_get_balance(handle, NULL, &balance);
+    if (status != BUNDLR_STATUS_INVALID_ARGUMENT || bundlr_last_error(handle) == NULL) {
+        return fail("bundlr_get_balance with a null address", status, handle);
+    }
+
+    bundlr_client_free(handle);
+    return 0;
+}
@@ -0,0 +1,110 @@
+#![cfg(all(feature = "client", feature = "arweave"))]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    io::{Read, Write},
+    net::TcpListener,
+    path::PathBuf,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use bundlr_sdk::{bundlr::PubInfo, currency::arweave::ArweaveBuilder, BundlrBuilder};
+use reqwest::Url;
+
+/// Tracks the bytes currently allocated and the highest value reached since the last reset.
+struct TrackingAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+const DATA_SIZE: usize = 16 * 1024 * 1024;
+/// Room for the HTTP client's own buffers and connection state, independent of the item size.
+const ALLOWED_OVERHEAD: usize = 4 * 1024 * 1024;
+
+/// Minimal HTTP server that reads and discards one request body, so it doesn't allocate
+/// proportionally to the payload itself.
+fn spawn_discarding_server() -> Url {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0u8; 64 * 1024];
+        let mut head = Vec::new();
+        let body_start = loop {
+            let n = stream.read(&mut buf).unwrap();
+            head.extend_from_slice(&buf[..n]);
+            if let Some(pos) = head.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+        };
+        let headers = String::from_utf8_lossy(&head[..body_start]).to_lowercase();
+        let content_length: usize = headers
+            .lines()
+            .find_map(|l| l.strip_prefix("content-length:"))
+            .expect("Request should carry a content length")
+            .trim()
+            .parse()
+            .unwrap();
+        let mut read = head.len() - body_start;
+        while read < content_length {
+            read += stream.read(&mut buf).unwrap();
+        }
+        stream
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}",
+            )
+            .unwrap();
+    });
+    Url::parse(&format!("http://{}/", addr)).unwrap()
+}
+
+#[tokio::test]
+async fn send_transaction_should_not_copy_data() {
+    let url = spawn_discarding_server();
+    let wallet = PathBuf::from_str("res/test_wallet.json").unwrap();
+    let currency = ArweaveBuilder::new().keypair_path(wallet).build().unwrap();
+    let bundlr = BundlrBuilder::new()
+        .url(url)
+        .currency(currency)
+        .pub_info(PubInfo::default())
+        .build()
+        .unwrap();
+
+    let mut tx = bundlr
+        .create_transaction(vec![7u8; DATA_SIZE], vec![])
+        .unwrap();
+    bundlr.sign_transaction(&mut tx).await.unwrap();
+
+    let baseline = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    bundlr.send_transaction(tx).await.unwrap();
+    let peak = PEAK.load(Ordering::SeqCst);
+
+    assert!(
+        peak - baseline < ALLOWED_OVERHEAD,
+        "sending allocated {} bytes on top of the {} byte item",
+        peak - baseline,
+        DATA_SIZE
+    );
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "verify")]
+
+use std::fs;
+
+use bundlr_sdk::{
+    error::VerifyError,
+    index::SignerMap,
+    verify::{verify_data_item, verify_receipt, PubKey},
+};
+
+const RECEIPT: &str = "res/test_receipt.json";
+
+fn node_key() -> PubKey {
+    let receipt: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(RECEIPT).unwrap()).unwrap();
+    PubKey::from_base64url(receipt["public"].as_str().unwrap()).unwrap()
+}
+
+#[test]
+fn should_verify_receipt_from_trusted_node() {
+    let receipt = fs::read_to_string(RECEIPT).unwrap();
+    let verified = verify_receipt(&receipt, &[node_key()]).unwrap();
+    assert_eq!(verified.id, "juLVTu4DrmE7hC9izySHX95gRApRoqSC7SKM75seUR4");
+    assert_eq!(verified.timestamp, 1683731921178);
+    assert_eq!(verified.signer, node_key());
+}
+
+#[test]
+fn should_reject_receipt_from_untrusted_node() {
+    let receipt = fs::read_to_string(RECEIPT).unwrap();
+    let other = PubKey(vec![1; 512]);
+    assert!(matches!(
+        verify_receipt(&receipt, &[other]),
+        Err(VerifyError::UntrustedSigner)
+    ));
+}
+
+#[test]
+fn should_reject_tampered_receipt() {
+    let receipt = fs::read_to_string(RECEIPT)
+        .unwrap()
+        .replace("1683731921178", "1683731921179");
+    assert!(matches!(
+        verify_receipt(&receipt, &[node_key()]),
+        Err(VerifyError::InvalidSignature)
+    ));
+    assert!(matches!(
+        verify_receipt("{}", &[node_key()]),
+        Err(VerifyError::Malformed(_))
+    ));
+}
+
+#[test]
+fn should_verify_arweave_data_item() {
+    let item = fs::read("res/golden/arweave.bin").unwrap();
+    let summary = verify_data_item(&item).unwrap();
+    assert_eq!(summary.signature_type, SignerMap::Arweave);
+    assert!(summary.data_size > 0);
+    assert!(!summary.tags.is_empty());
+
+    let mut tampered = item;
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(matches!(
+        verify_data_item(&tampered),
+        Err(VerifyError::InvalidSignature)
+    ));
+    assert!(matches!(
+        verify_data_item(&[1, 0, 0]),
+        Err(VerifyError::Malformed(_))
+    ));
+}
+
+#[cfg(not(feature = "algorand"))]
+#[test]
+fn should_not_verify_disabled_signers() {
+    let item = fs::read("res/golden/ed25519.bin").unwrap();
+    assert!(matches!(
+        verify_data_item(&item),
+        Err(VerifyError::UnsupportedSigner)
+    ));
+}